@@ -1,50 +1,81 @@
-use crate::crud::executor::{DataMeta, ExecutionResult, execute_query};
-use crate::database::connections::{Connection, load_connections, save_connections};
+use crate::cli::{Cli, DEFAULT_MAX_ROWS};
+use crate::crud::executor::{
+    DataMeta, ExecutionResult, ScriptError, delete_row, execute_script, fetch_page, insert_row,
+    update_cell,
+};
+use crate::crud::result_set::ResultSet;
+use crate::crud::row_edit::{CellUpdate, RowDelete, RowInsert};
+use crate::database::connections::{
+    Connection, delete_keyring_password, group_connections, load_connections, save_connections,
+    store_keyring_password,
+};
 use crate::database::fetch::{
-    Database, TableMetadata, fetch_databases, fetch_table_details, fetch_tables,
-    metadata_to_tree_items,
+    Database, Table, TableMetadata, count_rows, fetch_databases, fetch_server_version,
+    fetch_table_ddl, fetch_table_details, fetch_tables, filter_databases, metadata_to_tree_items,
+    table_node_id,
 };
-use crate::database::pool::DbPool;
+use crate::database::pool::{
+    ConnectError, DEFAULT_CONNECT_TIMEOUT_SECS, DbPool, DbSession, is_connection_lost,
+};
+use crate::database::ssh_tunnel::{SshTunnel, close_tunnels, forward};
 use crate::database::{
-    connector::{ConnectionDetails, DatabaseType},
+    connector::{ConnectionDetails, DatabaseType, SslMode, database_url_from_env, quote_ident},
     pool::pool,
 };
 use crate::layout::query_editor::QueryEditor;
-use crate::layout::{data_table::DataTable, sidebar::SideBar};
-use crate::state::{get_history, get_query_stats, load_history, save_history};
-use color_eyre::eyre::Result;
-use crossterm::execute;
+use crate::layout::{
+    data_table::{DEFAULT_MAX_CELL_LENGTH, DataTable, ServerPage},
+    sidebar::SideBar,
+};
+use crate::state::{
+    ColumnWidths, PaneLayout, QueryHistoryEntry, Snippet, get_history, get_query_stats,
+    load_buffer, load_column_widths, load_history, load_last_connection, load_line_numbers,
+    load_max_cell_length, load_pane_layout, load_snippets, save_buffer, save_column_widths,
+    save_history, save_last_connection, save_line_numbers, save_max_cell_length, save_pane_layout,
+    save_snippets, set_history_limit, upsert_snippet,
+};
+use color_eyre::eyre::{Result, eyre};
 use crossterm::{
-    ExecutableCommand, cursor,
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEvent},
-    style::Print,
-    terminal::{Clear, ClearType},
+    ExecutableCommand,
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
 };
-use inquire::{Confirm, Password, Select, Text};
+use inquire::{Confirm, InquireError, Password, Select, Text, validator::Validation};
 use ratatui::{
     DefaultTerminal, Frame,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
+    layout::{Constraint, Direction, Layout, Position, Rect},
+    style::{Modifier, Style, palette::tailwind},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, ScrollbarState},
 };
 use std::collections::HashMap;
-use std::io::Write;
-use std::sync::{
-    Arc,
-    atomic::{AtomicBool, Ordering},
-};
-use std::{io::stdout, time::Duration};
+use std::io::stdout;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use tokio::task::JoinHandle;
-use tokio::time::sleep;
 
 use tui_tree_widget::TreeItem;
 
 use crate::command::Command;
+use crate::components::command_palette::CommandPalette;
+use crate::components::insert_form::InsertForm;
+use crate::components::json_tree::JsonTree;
 use crate::components::popup::Popup;
+use crate::key_bindings::load_key_bindings;
 use crate::key_maps::{DefaultKeyMapper, KeyMapper};
-use crate::layout::key_map_guide::get_key_map_guide;
-use crate::style::theme::{COLOR_UNFOCUSED, COLOR_WHITE};
+use crate::layout::key_map_guide::{KeyMapView, get_key_map_guide};
+use crate::style::theme;
+use crate::utils::clipboard::copy_text;
+use crate::utils::copy_format::{CopyFormat, NOT_SINGLE_TABLE, format_rows, json_objects};
+use crate::utils::query_type::{
+    explain_sql, is_ddl, is_destructive, is_read_only, leading_keyword, plain_table_select,
+    split_table_name, whole_table_select,
+};
+use crate::utils::spinner::{FRAMES, Spinner};
+use crate::utils::sql_format::format_sql;
+use crate::utils::statement_splitter::split_statements;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Focus {
@@ -53,6 +84,17 @@ pub enum Focus {
     Table,
 }
 
+/// Whether the server of the current connection answers, as last checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionHealth {
+    Connected,
+    Lost,
+    Reconnecting { since: Instant },
+}
+
+/// How often the server is pinged to notice a dropped connection before the next query.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
 impl Focus {
     fn next(self) -> Self {
         match self {
@@ -74,46 +116,593 @@ pub struct App<'a> {
     pub connection_name: Option<String>,
     key_mapper: DefaultKeyMapper,
     pub show_key_map: bool,
-    pub key_map_scroll: u16,
-    key_map_scroll_state: ScrollbarState,
+    /// Filter and collapsed categories of the key map popup.
+    pub key_map_view: KeyMapView,
+    /// Pane proportions, adjusted with `Ctrl` and the arrow keys.
+    pub pane_layout: PaneLayout,
+    /// Whether only the focused pane is drawn, filling the screen.
+    pub zoomed: bool,
+    /// Where each pane was drawn on the last frame, for mouse clicks.
+    pane_areas: Vec<(Focus, Rect)>,
+    /// Column name and full contents of the cell being inspected, if any.
+    pub cell_inspect: Option<(String, String)>,
+    /// Tree view of the inspected cell, when it holds a JSON object or array.
+    json_tree: Option<JsonTree>,
+    pub popup_scroll: u16,
+    popup_scroll_state: ScrollbarState,
     connections: Vec<Connection>,
     databases: Vec<Database>,
     current_connection: Option<Connection>,
+    /// Version reported by the server of the current connection, once fetched.
+    server_version: Option<String>,
     table_details_cache: HashMap<String, TableMetadata>,
+    /// Pools of the databases expanded in the sidebar, by name. `pool` connects to the
+    /// server's default database.
+    database_pools: HashMap<String, DbPool>,
+    /// Database editor queries run on, the one last opened or closed in the sidebar, or
+    /// `None` for the server's default one.
+    database: Option<String>,
+    /// Database the results shown were fetched from, whose rows edits change.
+    results_database: Option<String>,
+    query_task: Option<JoinHandle<()>>,
+    /// Whether the running query is an `EXPLAIN` whose plan should open in a popup.
+    explain_pending: bool,
+    /// Editor position the running query starts at, for placing the cursor on an error.
+    query_origin: (usize, usize),
+    /// Connection editor queries run on, so session settings and temporary tables persist.
+    /// It is lent to the query task while a query runs.
+    session: Option<DbSession>,
+    /// Database the session connection was opened on.
+    session_database: Option<String>,
+    /// Whether the session has a transaction open, including while a query holds it.
+    in_transaction: bool,
+    /// Whether a statement changed the schema, so loaded table lists need reloading once
+    /// no transaction hides the change from other connections.
+    schema_changed: bool,
+    /// Row cap applied to every fetch; 0 means unlimited.
+    max_rows: usize,
+    /// Whether destructive statements must be confirmed before they run.
+    confirm_destructive: bool,
+    /// How long to wait for the server when opening a connection pool.
+    connect_timeout: Duration,
+    health: ConnectionHealth,
+    /// When the server was last pinged; `None` to ping it right away.
+    health_checked_at: Option<Instant>,
+    /// Whether a ping or a reconnect is under way.
+    health_check_running: bool,
+    /// Whether statements that write are refused before reaching the database.
+    read_only: bool,
+    /// Whether `SELECT * FROM table` is fetched a page at a time instead of all at once.
+    server_paging: bool,
+    /// The page the running query fetches, when it is a server page.
+    pending_page: Option<ServerPage>,
+    /// A result cell being edited in the edit popup.
+    cell_edit: Option<CellUpdate>,
+    /// Form for a new row of a table, present while it is open.
+    insert_form: Option<InsertForm>,
+    /// Database of the table the insert form adds a row to.
+    insert_database: Option<String>,
+    /// A row deletion waiting for confirmation.
+    pending_delete: Option<RowDelete>,
+    /// The change to a result row the running query writes back, when it is one.
+    pending_edit: Option<RowEdit>,
+    /// A destructive query waiting for confirmation, with its `explain` flag.
+    pending_query: Option<(String, bool)>,
+    snippets: Vec<Snippet>,
+    /// Hand-adjusted column widths of every connection, as stored on disk.
+    column_widths: ColumnWidths,
+    /// Selected row of the snippet picker, present while the picker is open.
+    snippet_picker: Option<usize>,
+    /// SQL opened with `--edit`, shown in the editor instead of the saved buffer.
+    initial_query: Option<String>,
+    /// Whether the editor contents replace the saved buffer on exit. Not when a file was
+    /// opened with `--edit`, which would otherwise lose the buffer to the file's contents.
+    keep_buffer: bool,
+    /// Snippet name being typed, mirrored from the key mapper for rendering.
+    snippet_name_input: Option<String>,
+    /// Selected row of the connection switcher, present while it is open.
+    connection_switcher: Option<usize>,
+    /// Connection chosen in the switcher that has no saved password, and the password
+    /// typed for it so far.
+    password_prompt: Option<(Connection, String)>,
+    command_palette: Option<CommandPalette>,
+    query_result_tx: UnboundedSender<QueryResult>,
+    query_result_rx: UnboundedReceiver<QueryResult>,
+    sidebar_tx: UnboundedSender<SidebarUpdate>,
+    sidebar_rx: UnboundedReceiver<SidebarUpdate>,
+}
+
+/// What to do with a result row once its table's primary key is known.
+#[derive(Debug, Clone, Copy)]
+enum RowAction {
+    EditCell,
+    DeleteRow,
+}
+
+/// A change to a table row being written back to its table.
+#[derive(Clone)]
+enum RowEdit {
+    Update(CellUpdate),
+    Delete(RowDelete),
+    Insert(RowInsert),
+}
+
+impl RowEdit {
+    /// The statement shown while the change runs, with placeholders for its values.
+    fn sql(&self, db_type: DatabaseType) -> String {
+        match self {
+            RowEdit::Update(update) => update.to_sql(db_type).0,
+            RowEdit::Delete(delete) => delete.to_sql(db_type).0,
+            RowEdit::Insert(insert) => insert.to_sql(db_type).0,
+        }
+    }
+}
+
+/// The outcome of a background query, handing back the session it ran in.
+type QueryResult = (Result<ExecutionResult, ScriptError>, Option<DbSession>);
+
+/// Sidebar metadata fetched by a background task.
+enum SidebarUpdate {
+    Tables {
+        db_name: String,
+        result: Result<(DbPool, Vec<Table>)>,
+    },
+    TableDetails {
+        db_name: String,
+        table_id: String,
+        result: Result<Box<TableMetadata>>,
+    },
+    RowCount {
+        db_name: String,
+        table_id: String,
+        result: Result<i64>,
+    },
+    Ddl {
+        table_name: String,
+        result: Result<String>,
+    },
+    /// The details of a table whose result row is about to be changed, for its primary key.
+    EditTarget {
+        table: String,
+        position: (usize, usize),
+        action: RowAction,
+        result: Result<Box<TableMetadata>>,
+    },
+    /// The details of a table to insert a row into, for its columns.
+    InsertTarget {
+        db_name: String,
+        table: Box<Table>,
+        result: Result<Box<TableMetadata>>,
+    },
+    /// The version reported by the server of the connection named `connection_name`.
+    ServerVersion {
+        connection_name: String,
+        result: Result<String>,
+    },
+    /// Whether the server of the connection named `connection_name` answered a ping.
+    HealthCheck {
+        connection_name: String,
+        result: Result<()>,
+    },
+    /// The pools opened again for the connection named `connection_name` after its server
+    /// stopped answering: the default database's and those of the expanded databases.
+    Reconnected {
+        connection_name: String,
+        result: Result<(DbPool, HashMap<String, DbPool>)>,
+    },
+    /// A connection opened from the switcher, with its databases and query history.
+    Connected {
+        connection: Box<Connection>,
+        result: Result<(DbPool, Vec<String>, Vec<QueryHistoryEntry>)>,
+    },
+}
+
+/// Turns database names into collapsed sidebar entries whose tables load on demand.
+fn databases_from_names(names: Vec<String>) -> Vec<Database> {
+    names
+        .into_iter()
+        .map(|name| Database {
+            name,
+            tables: vec![],
+            loading: false,
+        })
+        .collect()
+}
+
+/// Why read-only mode refuses `query`, if any of its statements would write.
+fn read_only_refusal(query: &str, db_type: DatabaseType) -> Option<String> {
+    let statement = split_statements(query, db_type)
+        .into_iter()
+        .find(|statement| !is_read_only(statement, db_type))?;
+    Some(format!(
+        "Read-only mode: refused to run {} statement. Only row-returning queries are allowed.",
+        leading_keyword(statement).unwrap_or_default()
+    ))
+}
+
+/// Describes a connection for the connection info popup. SQLite connections only have a
+/// file, so the network details are left out for them.
+fn connection_info_text(
+    connection: &Connection,
+    database: Option<&str>,
+    server_version: &str,
+) -> String {
+    let mut lines = vec![
+        format!("Name: {}", connection.label()),
+        format!("Type: {}", connection.db_type),
+    ];
+    if connection.db_type == DatabaseType::SQLite {
+        lines.push(format!("File: {}", connection.host));
+    } else {
+        match connection.port {
+            Some(port) => lines.push(format!("Host: {}:{}", connection.host, port)),
+            None => lines.push(format!("Host: {}", connection.host)),
+        }
+        lines.push(format!("User: {}", connection.user));
+        lines.push(format!(
+            "Database: {}",
+            database.unwrap_or("(server default)")
+        ));
+        if let Some(tunnel) = &connection.ssh {
+            lines.push(format!(
+                "SSH tunnel: {}@{}:{}",
+                tunnel.user,
+                tunnel.host,
+                tunnel.port.unwrap_or(22)
+            ));
+        }
+    }
+    lines.push(format!("Server version: {}", server_version));
+    lines.join("\n")
+}
+
+/// Lists saved connections, marking the one in use and highlighting the selected one.
+fn connection_switcher_text(
+    connections: &[Connection],
+    current: Option<&str>,
+    selected: usize,
+) -> ratatui::text::Text<'static> {
+    if connections.is_empty() {
+        return "No saved connections. Start lazydata without arguments to create one.".into();
+    }
+
+    connections
+        .iter()
+        .enumerate()
+        .map(|(i, connection)| {
+            let style = if i == selected {
+                Style::default()
+                    .fg(theme::current().border_focused)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let marker = if current == Some(connection.name.as_str()) {
+                "● "
+            } else {
+                "  "
+            };
+            Line::from(vec![
+                Span::styled(format!("{}{}  ", marker, connection.label()), style),
+                Span::styled(
+                    format!("{} {}", connection.db_type, connection.host),
+                    Style::default().fg(theme::current().null),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Entries of the connection menu listed after the saved connections.
+const CREATE_CONNECTION: &str = "Create new connection";
+const EDIT_CONNECTION: &str = "Edit a connection";
+const MOVE_CONNECTION: &str = "Move a connection";
+const DELETE_CONNECTION: &str = "Delete a connection";
+
+/// Choices offered when a new connection fails to open.
+const REENTER_CONNECTION: &str = "Re-enter the details";
+const SAVE_CONNECTION_ANYWAY: &str = "Save it anyway";
+
+/// Longest a new connection is tried for before it is saved.
+const CONNECTION_TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Opens a pool for `connection`, through its SSH tunnel if it has one.
+async fn open_pool(
+    connection: &Connection,
+    details: ConnectionDetails,
+    db_name: Option<&str>,
+    timeout: Duration,
+    read_only: bool,
+) -> Result<DbPool, ConnectError> {
+    let details = match &connection.ssh {
+        Some(tunnel) if connection.db_type != DatabaseType::SQLite => {
+            forward(tunnel, connection.db_type, details, timeout).await?
+        }
+        _ => details,
+    };
+    let db_name = db_name.or(connection.database.as_deref());
+    pool(connection.db_type, &details, db_name, timeout, read_only).await
+}
+
+/// Remembers `name` as the connection to offer first next time. This happens before the
+/// UI starts, so a failure is only printed.
+fn remember_last_connection(name: &str) {
+    if let Err(e) = save_last_connection(name) {
+        eprintln!("Could not remember the last connection: {}", e);
+    }
+}
+
+/// Asks whether to connect through an SSH host and, if so, how to log in to it,
+/// offering the settings of `existing`.
+fn prompt_ssh_tunnel(existing: Option<&SshTunnel>) -> Result<Option<SshTunnel>> {
+    let use_tunnel = Confirm::new("Connect through an SSH tunnel?")
+        .with_default(existing.is_some())
+        .with_help_message("For databases only reachable from a bastion host")
+        .prompt()?;
+    if !use_tunnel {
+        return Ok(None);
+    }
+    let host = Text::new("SSH host:")
+        .with_initial_value(existing.map_or("", |t| t.host.as_str()))
+        .prompt()?;
+    let current_port = existing
+        .and_then(|t| t.port)
+        .map(|port| port.to_string())
+        .unwrap_or_default();
+    let port = Text::new("SSH port:")
+        .with_initial_value(&current_port)
+        .with_help_message("Leave empty for 22")
+        .with_validator(|input: &str| {
+            if input.trim().is_empty() || input.trim().parse::<u16>().is_ok() {
+                Ok(Validation::Valid)
+            } else {
+                Ok(Validation::Invalid(
+                    "Port must be a number between 0 and 65535".into(),
+                ))
+            }
+        })
+        .prompt()?
+        .trim()
+        .parse::<u16>()
+        .ok();
+    let user = Text::new("SSH user:")
+        .with_initial_value(existing.map_or("", |t| t.user.as_str()))
+        .prompt()?;
+    let key_path = Text::new("SSH private key path:")
+        .with_initial_value(
+            existing
+                .and_then(|t| t.key_path.as_deref())
+                .unwrap_or_default(),
+        )
+        .with_help_message("Leave empty to use ssh-agent and ~/.ssh/config")
+        .prompt()?;
+    let key_path = Some(key_path.trim().to_string()).filter(|p| !p.is_empty());
+    Ok(Some(SshTunnel {
+        host: host.trim().to_string(),
+        port,
+        user: user.trim().to_string(),
+        key_path,
+    }))
+}
+
+/// Prints a failed connection attempt, returning whether `result` was one.
+fn report_connect_error(result: &Result<()>) -> bool {
+    match result {
+        Err(e) if e.downcast_ref::<ConnectError>().is_some() => {
+            eprintln!("❌ {}", e);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Lists saved snippets with their first line of SQL, highlighting the selected one.
+fn snippet_picker_text(snippets: &[Snippet], selected: usize) -> ratatui::text::Text<'static> {
+    if snippets.is_empty() {
+        return "No saved snippets. Press F8 to save the editor contents as one.".into();
+    }
+
+    snippets
+        .iter()
+        .enumerate()
+        .map(|(i, snippet)| {
+            let preview = snippet.sql.lines().next().unwrap_or_default().to_string();
+            let style = if i == selected {
+                Style::default()
+                    .fg(theme::current().border_focused)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!("{}  ", snippet.name), style),
+                Span::styled(preview, Style::default().fg(theme::current().null)),
+            ])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Renders an `EXPLAIN` result as plain text, one plan row per line.
+fn plan_text(result_set: &ResultSet) -> String {
+    let lines = result_set
+        .rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" | ")
+        })
+        .collect::<Vec<_>>();
+
+    if result_set.headers.len() > 1 {
+        // Tabular plans (MySQL, SQLite) need their headers to be readable.
+        std::iter::once(result_set.headers.join(" | "))
+            .chain(lines)
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        lines.join("\n")
+    }
 }
 
 impl App<'_> {
     pub fn default() -> Self {
+        let (query_result_tx, query_result_rx) = mpsc::unbounded_channel();
+        let (sidebar_tx, sidebar_rx) = mpsc::unbounded_channel();
         Self {
             focus: Focus::Sidebar,
             query: String::new(),
             exit: false,
-            data_table: DataTable::new(vec![], vec![], vec![]),
+            data_table: DataTable::new(ResultSet::default(), vec![]),
             query_editor: QueryEditor::new(),
             sidebar: SideBar::new(vec![], Focus::Sidebar),
             pool: None,
             connection_name: None,
             key_mapper: DefaultKeyMapper::new(),
             show_key_map: false,
-            key_map_scroll: 0,
-            key_map_scroll_state: ScrollbarState::default(),
+            key_map_view: KeyMapView::default(),
+            pane_layout: PaneLayout::default(),
+            zoomed: false,
+            pane_areas: Vec::new(),
+            cell_inspect: None,
+            json_tree: None,
+            popup_scroll: 0,
+            popup_scroll_state: ScrollbarState::default(),
             connections: Vec::new(),
             databases: Vec::new(),
             current_connection: None,
+            server_version: None,
             table_details_cache: HashMap::new(),
+            database_pools: HashMap::new(),
+            database: None,
+            results_database: None,
+            query_task: None,
+            explain_pending: false,
+            query_origin: (0, 0),
+            session: None,
+            session_database: None,
+            in_transaction: false,
+            schema_changed: false,
+            max_rows: DEFAULT_MAX_ROWS,
+            confirm_destructive: true,
+            read_only: false,
+            server_paging: false,
+            pending_page: None,
+            cell_edit: None,
+            insert_form: None,
+            insert_database: None,
+            pending_delete: None,
+            pending_edit: None,
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            health: ConnectionHealth::Connected,
+            health_checked_at: Some(Instant::now()),
+            health_check_running: false,
+            pending_query: None,
+            snippets: Vec::new(),
+            column_widths: ColumnWidths::new(),
+            snippet_picker: None,
+            initial_query: None,
+            keep_buffer: true,
+            snippet_name_input: None,
+            connection_switcher: None,
+            password_prompt: None,
+            command_palette: None,
+            query_result_tx,
+            query_result_rx,
+            sidebar_tx,
+            sidebar_rx,
         }
     }
 
-    pub async fn init(&mut self) -> Result<()> {
+    pub async fn init(&mut self, cli: Cli) -> Result<()> {
         self.connections = load_connections()?;
+        self.max_rows = cli.max_rows;
+        self.confirm_destructive = !cli.no_confirm;
+        self.read_only = cli.read_only;
+        self.server_paging = cli.server_paging;
+        self.data_table.show_timings = cli.timings;
+        self.data_table.max_cell_length = match cli.max_cell_length {
+            Some(max_cell_length) => {
+                if let Err(e) = save_max_cell_length(max_cell_length) {
+                    self.data_table
+                        .set_status(format!("Failed to save the cell length limit: {}", e));
+                }
+                max_cell_length
+            }
+            None => load_max_cell_length().unwrap_or(DEFAULT_MAX_CELL_LENGTH),
+        };
+        self.data_table.copy_format = cli.copy_format;
+        self.connect_timeout = Duration::from_secs(cli.connect_timeout);
+        set_history_limit(cli.history_limit);
+        self.key_mapper = DefaultKeyMapper::with_bindings(load_key_bindings());
+        self.query_editor.mode = self.key_mapper.editor_mode();
+        self.query_editor.line_numbers = load_line_numbers();
+        self.pane_layout = load_pane_layout();
+
+        let script = match (&cli.query, &cli.file) {
+            (Some(sql), _) if sql == "-" => Some(std::io::read_to_string(std::io::stdin())?),
+            (Some(sql), _) => Some(sql.clone()),
+            (None, Some(path)) => Some(
+                std::fs::read_to_string(path)
+                    .map_err(|e| eyre!("Failed to read {}: {}", path.display(), e))?,
+            ),
+            (None, None) => None,
+        };
+        match script {
+            Some(sql) if cli.edit => {
+                self.initial_query = Some(sql);
+                self.keep_buffer = false;
+            }
+            Some(sql) => return self.run_headless(&cli, &sql).await,
+            None => {}
+        }
+
+        if let Some(url) = cli.url {
+            return self.connect_url(&url).await;
+        }
+
+        if let Some(name) = cli.connection {
+            let Some(connection) = self.connections.iter().find(|c| c.name == name).cloned() else {
+                eprintln!("❌ Connection '{}' not found.", name);
+                if self.connections.is_empty() {
+                    eprintln!("No saved connections found.");
+                } else {
+                    eprintln!("Available connections:");
+                    for connection in &self.connections {
+                        eprintln!("  {}", connection.name);
+                    }
+                }
+                std::process::exit(1);
+            };
+            return self.connect_saved(connection).await;
+        }
+
+        if cli.last {
+            let last = load_last_connection()
+                .and_then(|name| self.connections.iter().find(|c| c.name == name).cloned());
+            match last {
+                Some(connection) => return self.connect_saved(connection).await,
+                None => println!("No previous connection found."),
+            }
+        }
 
         if self.connections.is_empty() {
+            if let Some(url) = database_url_from_env() {
+                return self.connect_url(&url).await;
+            }
             println!("No saved connections found.");
             let confirm_create = Confirm::new("Would you like to create a new connection?")
                 .with_default(true)
                 .prompt()?;
             if confirm_create {
-                self.create_new_connection().await?;
+                let result = self.create_new_connection().await;
+                if !report_connect_error(&result) {
+                    return result;
+                }
+                self.select_connection().await?;
             } else {
                 println!("\n👋 Bye");
             }
@@ -125,98 +714,553 @@ impl App<'_> {
     }
 
     async fn create_new_connection(&mut self) -> Result<()> {
-        let db_type = Select::new(
-            "Select database type:",
-            vec![
-                DatabaseType::PostgreSQL,
-                DatabaseType::MySQL,
-                DatabaseType::SQLite,
-            ],
-        )
-        .prompt()?;
-
-        let name = Text::new("Connection Name:").prompt()?;
-        let host = Text::new("Host:").prompt()?;
-        let user = Text::new("User:").prompt()?;
-        let password = Password::new("Password:").prompt()?;
-        let save_password = Confirm::new("Save password?")
-            .with_default(false)
+        let (mut new_connection, mut password) = self.prompt_connection(None)?;
+        // Try the connection before saving it, so that a typo isn't kept and retried on
+        // every launch.
+        while let Err(e) = self.test_connection(&new_connection, &password).await {
+            eprintln!("❌ {}", e);
+            let choice = Select::new(
+                "The connection could not be opened:",
+                vec![REENTER_CONNECTION, SAVE_CONNECTION_ANYWAY],
+            )
             .prompt()?;
-
-        let new_connection = Connection {
-            name,
-            host,
-            user,
-            password: if save_password { Some(password) } else { None },
-            db_type,
-        };
+            if choice == SAVE_CONNECTION_ANYWAY {
+                break;
+            }
+            (new_connection, password) = self.prompt_connection(Some(&new_connection))?;
+        }
 
         self.connections.push(new_connection.clone());
+        group_connections(&mut self.connections);
         save_connections(&self.connections)?;
+        remember_last_connection(&new_connection.name);
+        // The typed password is used for this session even when it isn't saved.
+        new_connection.password = Some(password);
         self.current_connection = Some(new_connection.clone());
 
         self.setup_and_run_app(new_connection).await?;
         Ok(())
     }
 
-    async fn select_connection(&mut self) -> Result<()> {
-        let mut options = self
+    /// Opens and drops a pool for `connection`, giving up sooner than a real connect.
+    async fn test_connection(&self, connection: &Connection, password: &str) -> Result<()> {
+        println!("Testing connection to {}...", connection.host);
+        let mut details = connection.details(None);
+        details.password = Some(password.to_string());
+        let timeout = self.connect_timeout.min(CONNECTION_TEST_TIMEOUT);
+        open_pool(connection, details, None, timeout, self.read_only).await?;
+        Ok(())
+    }
+
+    /// Asks for the details of a connection, offering those of `existing` when editing
+    /// one, and returns it along with the password to connect with. An edited connection
+    /// keeps its saved password unless a new one is typed.
+    fn prompt_connection(&self, existing: Option<&Connection>) -> Result<(Connection, String)> {
+        let db_types = vec![
+            DatabaseType::PostgreSQL,
+            DatabaseType::MySQL,
+            DatabaseType::SQLite,
+        ];
+        let db_type = Select::new("Select database type:", db_types.clone())
+            .with_starting_cursor(
+                existing
+                    .and_then(|c| db_types.iter().position(|t| *t == c.db_type))
+                    .unwrap_or(0),
+            )
+            .prompt()?;
+
+        let taken_names: Vec<String> = self
             .connections
             .iter()
             .map(|c| c.name.clone())
-            .collect::<Vec<_>>() as Vec<String>;
-        options.push("Create new connection".to_string());
+            .filter(|name| existing.is_none_or(|c| c.name != *name))
+            .collect();
+        let name = Text::new("Connection Name:")
+            .with_initial_value(existing.map_or("", |c| c.name.as_str()))
+            .with_validator(move |input: &str| {
+                if input.trim().is_empty() {
+                    Ok(Validation::Invalid("Name the connection".into()))
+                } else if taken_names.iter().any(|name| name == input) {
+                    Ok(Validation::Invalid(
+                        "Another connection already has this name".into(),
+                    ))
+                } else {
+                    Ok(Validation::Valid)
+                }
+            })
+            .prompt()?;
+        let host = Text::new("Host:")
+            .with_initial_value(existing.map_or("", |c| c.host.as_str()))
+            .prompt()?;
+        let port = if db_type == DatabaseType::SQLite {
+            None
+        } else {
+            let current_port = existing
+                .and_then(|c| c.port)
+                .map(|port| port.to_string())
+                .unwrap_or_default();
+            Text::new("Port:")
+                .with_initial_value(&current_port)
+                .with_help_message("Leave empty to use the default port")
+                .with_validator(|input: &str| {
+                    if input.trim().is_empty() || input.trim().parse::<u16>().is_ok() {
+                        Ok(Validation::Valid)
+                    } else {
+                        Ok(Validation::Invalid(
+                            "Port must be a number between 0 and 65535".into(),
+                        ))
+                    }
+                })
+                .prompt()?
+                .trim()
+                .parse::<u16>()
+                .ok()
+        };
+        let user = Text::new("User:")
+            .with_initial_value(existing.map_or("", |c| c.user.as_str()))
+            .prompt()?;
 
-        let selected = Select::new("Select a connection:", options).prompt()?;
+        let group = Text::new("Group:")
+            .with_initial_value(
+                existing
+                    .and_then(|c| c.group.as_deref())
+                    .unwrap_or_default(),
+            )
+            .with_help_message("Optional, e.g. prod, staging or local")
+            .prompt()?;
+        let group = Some(group.trim().to_string()).filter(|g| !g.is_empty());
+        let current_tags = existing.map(|c| c.tags.join(", ")).unwrap_or_default();
+        let tags = Text::new("Tags:")
+            .with_initial_value(&current_tags)
+            .with_help_message("Optional, separated by commas")
+            .prompt()?
+            .split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect::<Vec<_>>();
 
-        if selected == "Create new connection" {
-            self.create_new_connection().await?;
+        let ssh = if db_type == DatabaseType::SQLite {
+            None
         } else {
-            let mut connection = self
+            prompt_ssh_tunnel(existing.and_then(|c| c.ssh.as_ref()))?
+        };
+
+        let saved_password = existing.and_then(|c| c.password.clone());
+        let mut password_prompt = Password::new("Password:");
+        if saved_password.is_some() {
+            password_prompt =
+                password_prompt.with_help_message("Leave empty to keep the saved password");
+        }
+        let password = password_prompt.prompt()?;
+        let keep_password = password.is_empty() && saved_password.is_some();
+        let save_password = !keep_password
+            && Confirm::new("Save password?")
+                .with_default(false)
+                .prompt()?;
+
+        let (sslmode, ca_cert_path) = if db_type == DatabaseType::SQLite {
+            (None, None)
+        } else {
+            let sslmode = Select::new("SSL mode:", SslMode::ALL.to_vec())
+                .with_starting_cursor(
+                    existing
+                        .and_then(|c| c.sslmode)
+                        .and_then(|mode| SslMode::ALL.iter().position(|m| *m == mode))
+                        .unwrap_or(1),
+                )
+                .prompt()?;
+            let ca_cert_path = Text::new("CA certificate path:")
+                .with_initial_value(
+                    existing
+                        .and_then(|c| c.ca_cert_path.as_deref())
+                        .unwrap_or_default(),
+                )
+                .with_help_message(if sslmode.verifies_certificate() {
+                    "Needed to verify the server certificate"
+                } else {
+                    "Leave empty unless the server uses a private CA"
+                })
+                .prompt()?;
+            let ca_cert_path = Some(ca_cert_path.trim().to_string()).filter(|p| !p.is_empty());
+            (Some(sslmode), ca_cert_path)
+        };
+
+        if keep_password && let Some(existing) = existing {
+            let mut keyring_entry = existing.keyring_entry.clone();
+            // The keyring entry is named after the connection, so it follows a rename.
+            if let (Some(entry), Some(password)) = (&keyring_entry, &saved_password)
+                && *entry != name
+                && store_keyring_password(&name, password).is_ok()
+            {
+                delete_keyring_password(entry);
+                keyring_entry = Some(name.clone());
+            }
+            let password = saved_password.clone().unwrap_or_default();
+            let connection = Connection {
+                name,
+                host,
+                port,
+                user,
+                password: saved_password,
+                db_type,
+                sslmode,
+                ca_cert_path,
+                keyring_entry,
+                group,
+                tags,
+                database: existing.database.clone(),
+                ssh,
+            };
+            return Ok((connection, password));
+        }
+
+        let mut keyring_entry = None;
+        let mut store_password = false;
+        if save_password {
+            match store_keyring_password(&name, &password) {
+                Ok(()) => keyring_entry = Some(name.clone()),
+                Err(e) => {
+                    store_password = Confirm::new(&format!(
+                        "{:#}. Save the password in plain text instead?",
+                        e
+                    ))
+                    .with_default(false)
+                    .prompt()?;
+                }
+            }
+        }
+        if let Some(entry) = existing.and_then(|c| c.keyring_entry.as_ref())
+            && keyring_entry.as_ref() != Some(entry)
+        {
+            delete_keyring_password(entry);
+        }
+
+        let connection = Connection {
+            name,
+            host,
+            port,
+            user,
+            password: (keyring_entry.is_some() || store_password).then(|| password.clone()),
+            db_type,
+            sslmode,
+            ca_cert_path,
+            keyring_entry,
+            group,
+            tags,
+            database: existing.and_then(|c| c.database.clone()),
+            ssh,
+        };
+        Ok((connection, password))
+    }
+
+    /// Lets the user pick a saved connection for `action`, or `None` when they back out.
+    fn pick_connection(&self, action: &str) -> Result<Option<usize>> {
+        let labels = self
+            .connections
+            .iter()
+            .map(Connection::label)
+            .collect::<Vec<_>>();
+        match Select::new(&format!("Connection to {}:", action), labels).raw_prompt() {
+            Ok(choice) => Ok(Some(choice.index)),
+            Err(InquireError::OperationCanceled) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn edit_connection(&mut self, index: usize) -> Result<()> {
+        let old = self.connections[index].clone();
+        let (edited, _) = self.prompt_connection(Some(&old))?;
+        if load_last_connection().as_deref() == Some(old.name.as_str()) {
+            remember_last_connection(&edited.name);
+        }
+        println!("Saved connection {}.", edited.name);
+        self.connections[index] = edited;
+        group_connections(&mut self.connections);
+        save_connections(&self.connections)
+    }
+
+    /// Moves the connection at `index` in front of another one, or to the end. Groups
+    /// are kept together, so moving a connection first in its group moves the group.
+    fn move_connection(&mut self, index: usize) -> Result<()> {
+        let moving = self.connections.remove(index);
+        let mut targets = self
+            .connections
+            .iter()
+            .map(|c| format!("Before {}", c.label()))
+            .collect::<Vec<_>>();
+        targets.push("At the end".to_string());
+        let target = Select::new(&format!("Move {} to:", moving.name), targets)
+            .with_starting_cursor(index)
+            .raw_prompt();
+        let target = match target {
+            Ok(target) => target.index,
+            Err(e) => {
+                self.connections.insert(index, moving);
+                return Err(e.into());
+            }
+        };
+        self.connections.insert(target, moving);
+        group_connections(&mut self.connections);
+        save_connections(&self.connections)
+    }
+
+    fn delete_connection(&mut self, index: usize) -> Result<()> {
+        let name = self.connections[index].name.clone();
+        if !Confirm::new(&format!("Delete connection {}?", name))
+            .with_default(false)
+            .prompt()?
+        {
+            return Ok(());
+        }
+        let removed = self.connections.remove(index);
+        if let Some(entry) = &removed.keyring_entry {
+            delete_keyring_password(entry);
+        }
+        println!("Deleted connection {}.", name);
+        save_connections(&self.connections)
+    }
+
+    async fn select_connection(&mut self) -> Result<()> {
+        loop {
+            let mut options = self
                 .connections
                 .iter()
-                .find(|c| c.name == selected)
-                .cloned()
-                .unwrap();
+                .map(Connection::label)
+                .collect::<Vec<_>>();
+            let starting_cursor = load_last_connection()
+                .and_then(|name| self.connections.iter().position(|c| c.name == name))
+                .unwrap_or(0);
+            options.push(CREATE_CONNECTION.to_string());
+            if !self.connections.is_empty() {
+                options.push(EDIT_CONNECTION.to_string());
+                options.push(MOVE_CONNECTION.to_string());
+                options.push(DELETE_CONNECTION.to_string());
+            }
+
+            let selected = Select::new("Select a connection:", options)
+                .with_starting_cursor(starting_cursor)
+                .raw_prompt()?;
+            // Options after the saved connections are the menu actions.
+            let action = (selected.index >= self.connections.len()).then_some(selected.value);
 
-            if connection.password.is_none() {
-                connection.password = Some(Password::new("Password:").prompt()?);
+            let result = if action.as_deref() == Some(CREATE_CONNECTION) {
+                self.create_new_connection().await
+            } else if let Some(action) = action {
+                let verb = match action.as_str() {
+                    EDIT_CONNECTION => "edit",
+                    MOVE_CONNECTION => "move",
+                    _ => "delete",
+                };
+                if let Some(index) = self.pick_connection(verb)? {
+                    let outcome = match action.as_str() {
+                        EDIT_CONNECTION => self.edit_connection(index),
+                        MOVE_CONNECTION => self.move_connection(index),
+                        _ => self.delete_connection(index),
+                    };
+                    // Backing out of the questions returns to the menu unchanged.
+                    if let Err(e) = outcome
+                        && !matches!(
+                            e.downcast_ref::<InquireError>(),
+                            Some(InquireError::OperationCanceled)
+                        )
+                    {
+                        return Err(e);
+                    }
+                }
+                continue;
+            } else {
+                let connection = self.connections[selected.index].clone();
+                self.connect_saved(connection).await
+            };
+
+            // A failed connection goes back to the menu; anything else ends the session.
+            if !report_connect_error(&result) {
+                return result;
+            }
+        }
+    }
+
+    /// Runs `sql` on the connection picked by the command line and prints the results to
+    /// stdout, without starting the UI. Exits with status 1 if it can't connect or the
+    /// query fails.
+    async fn run_headless(&self, cli: &Cli, sql: &str) -> Result<()> {
+        let connection = self.headless_connection(cli)?;
+        if self.read_only
+            && let Some(refusal) = read_only_refusal(sql, connection.db_type)
+        {
+            return Err(eyre!(refusal));
+        }
+        let result = async {
+            let database = cli.database.clone();
+            let details = connection.details(database.clone());
+            let pool = open_pool(
+                &connection,
+                details,
+                database.as_deref(),
+                self.connect_timeout,
+                self.read_only,
+            )
+            .await?;
+            let connection_name = Some(connection.name.clone());
+            let result = execute_script(&pool, &mut None, sql, connection_name, self.max_rows)
+                .await
+                .map_err(|e| eyre!(e.message))?;
+            Ok::<_, color_eyre::Report>((pool.get_type(), result))
+        }
+        .await;
+        close_tunnels().await;
+
+        match result? {
+            (db_type, ExecutionResult::Data { result_set, meta }) => {
+                let output = match cli.format {
+                    // A list even for one row, so that scripts read every result alike.
+                    CopyFormat::Json => serde_json::to_string_pretty(&json_objects(
+                        &result_set.headers,
+                        &result_set.rows,
+                    ))?,
+                    format => {
+                        let table = match split_statements(sql, db_type).as_slice() {
+                            [statement] => plain_table_select(statement, db_type),
+                            _ => None,
+                        };
+                        if format == CopyFormat::Insert && table.is_none() {
+                            return Err(eyre!(NOT_SINGLE_TABLE));
+                        }
+                        format_rows(
+                            format,
+                            &result_set.headers,
+                            &result_set.rows,
+                            table,
+                            db_type,
+                        )
+                    }
+                };
+                // Scripts report each statement; a single query just prints its rows.
+                if split_statements(sql, db_type).len() > 1 {
+                    eprintln!("{}", meta.message);
+                }
+                println!("{}", output);
+                if result_set.truncated {
+                    eprintln!(
+                        "Stopped at {} rows; pass --max-rows 0 to fetch everything.",
+                        meta.rows
+                    );
+                }
+                Ok(())
+            }
+            (_, ExecutionResult::Affected { message, .. }) => {
+                eprintln!("{}", message);
+                Ok(())
             }
-            self.current_connection = Some(connection.clone());
-            self.setup_and_run_app(connection).await?;
         }
-        Ok(())
     }
 
+    /// The connection `--query` runs on, chosen the same way as when starting the UI.
+    fn headless_connection(&self, cli: &Cli) -> Result<Connection> {
+        let url = cli.url.clone().or_else(|| {
+            (cli.connection.is_none() && !cli.last)
+                .then(database_url_from_env)
+                .flatten()
+        });
+        if let Some(url) = url {
+            return Connection::from_url(&url);
+        }
+        let name = match &cli.connection {
+            Some(name) => name.clone(),
+            None if cli.last => {
+                load_last_connection().ok_or_else(|| eyre!("No previous connection found."))?
+            }
+            None => {
+                return Err(eyre!(
+                    "--query needs a connection: pass --connection, --url or --last."
+                ));
+            }
+        };
+        let mut connection = self
+            .connections
+            .iter()
+            .find(|c| c.name == name)
+            .cloned()
+            .ok_or_else(|| eyre!("Connection '{}' not found.", name))?;
+        if connection.password.is_none() {
+            connection.password = Some(Password::new("Password:").prompt()?);
+        }
+        Ok(connection)
+    }
+
+    async fn connect_url(&mut self, url: &str) -> Result<()> {
+        let connection = Connection::from_url(url)?;
+        self.current_connection = Some(connection.clone());
+        self.setup_and_run_app(connection).await
+    }
+
+    async fn connect_saved(&mut self, mut connection: Connection) -> Result<()> {
+        if connection.password.is_none() {
+            connection.password = Some(Password::new("Password:").prompt()?);
+        }
+        remember_last_connection(&connection.name);
+        self.current_connection = Some(connection.clone());
+        self.setup_and_run_app(connection).await
+    }
+
+    /// Table and column names loaded so far, offered by editor completion.
+    fn schema_words(&self) -> Vec<String> {
+        let tables = self.databases.iter().flat_map(|db| &db.tables);
+        let columns = tables
+            .clone()
+            .filter_map(|table| table.metadata.as_ref())
+            .chain(self.table_details_cache.values())
+            .flat_map(|metadata| &metadata.columns)
+            .map(|column| column.name.clone());
+        let mut words: Vec<String> = tables
+            .map(|table| table.name.clone())
+            .chain(columns)
+            .collect();
+        words.sort();
+        words.dedup();
+        words
+    }
+
+    /// The selected editor text if there is a selection, otherwise the whole buffer.
     fn current_query(&self) -> String {
-        self.query_editor.textarea_content()
+        self.query_editor
+            .selected_text()
+            .unwrap_or_else(|| self.query_editor.textarea_content())
     }
 
     async fn setup_and_run_app(&mut self, connection: Connection) -> Result<()> {
-        let details = ConnectionDetails {
-            host: Some(connection.host.clone()),
-            user: Some(connection.user.clone()),
-            password: connection.password.clone(),
-            database: None,
-        };
+        let details = connection.details(None);
         self.connection_name = Some(connection.name.clone());
         load_history().await?;
         self.data_table.query_history = get_history(self.connection_name.clone()).await;
-        let pool_instance = pool(connection.db_type, &details, None).await?;
-        self.pool = Some(pool_instance.clone());
-
-        let (spinner_handle, loading) = self.loading().await;
-        let databases = fetch_databases(&pool_instance).await?;
-        let mut db_vec = Vec::new();
-        for db_name in &databases {
-            db_vec.push(Database {
-                name: db_name.clone(),
-                tables: vec![],
-            });
+        self.column_widths = load_column_widths();
+        self.data_table.saved_column_widths = self
+            .column_widths
+            .get(&connection.name)
+            .cloned()
+            .unwrap_or_default();
+
+        let spinner = Spinner::start(format!("Connecting to {}...", connection.name));
+        let result = async {
+            let pool_instance = open_pool(
+                &connection,
+                details,
+                None,
+                self.connect_timeout,
+                self.read_only,
+            )
+            .await?;
+            spinner.set_message("Fetching databases...");
+            let databases = fetch_databases(&pool_instance).await?;
+            Ok::<_, color_eyre::Report>((pool_instance, databases))
         }
-        self.databases = db_vec;
-        loading.store(false, Ordering::SeqCst);
-        spinner_handle.await.unwrap();
+        .await;
+        spinner.stop().await;
+        let (pool_instance, databases) = result?;
+        self.pool = Some(pool_instance);
+        self.database = None;
+        self.databases = databases_from_names(databases);
+        self.load_server_version();
 
         if self.databases.is_empty() {
             println!("❌ No databases found on the server.");
@@ -235,141 +1279,1297 @@ impl App<'_> {
         Ok(())
     }
 
-    pub async fn loading(&mut self) -> (JoinHandle<()>, Arc<AtomicBool>) {
-        let loading = Arc::new(AtomicBool::new(true));
-        let spinner_flag = loading.clone();
+    async fn setup_ui(&mut self, sidebar_items: Vec<TreeItem<'static, String>>) -> Result<()> {
+        self.focus = Focus::Sidebar;
+        self.sidebar.update_items(sidebar_items);
+        self.sidebar.update_focus(self.focus.clone());
+
+        let buffer = self.initial_query.take().unwrap_or_else(load_buffer);
+        if !buffer.is_empty() {
+            self.query_editor.set_textarea_content(
+                buffer,
+                &self.focus,
+                self.connection_name.clone(),
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        while !self.exit {
+            terminal.draw(|f| self.render_ui(f))?;
+            self.poll_query_result().await;
+            self.poll_sidebar_updates();
+            self.check_connection_health();
+            let _ = self.handle_events().await;
+        }
+        save_history().await?;
+        if self.keep_buffer {
+            save_buffer(&self.query_editor.textarea_content())?;
+        }
+        Ok(())
+    }
+
+    async fn handle_events(&mut self) -> Result<()> {
+        if !event::poll(Duration::from_millis(100))? {
+            return Ok(());
+        }
+        match event::read()? {
+            Event::Key(key_event) => self.handle_key_event(key_event).await,
+            Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
+        let running_query_command = if self.query_task.is_some() {
+            self.key_mapper.map_running_query_key(key_event)
+        } else {
+            None
+        };
+
+        let command = if running_query_command.is_some() {
+            running_query_command
+        } else if self.pending_query.is_some() || self.pending_delete.is_some() {
+            self.key_mapper.map_confirm_key(key_event)
+        } else if self.snippet_picker.is_some() {
+            self.key_mapper.map_snippet_picker_key(key_event)
+        } else if self.password_prompt.is_some() {
+            self.key_mapper.map_password_prompt_key(key_event)
+        } else if self.cell_edit.is_some() {
+            self.key_mapper.map_cell_edit_key(key_event)
+        } else if self.insert_form.is_some() {
+            self.key_mapper.map_insert_form_key(key_event)
+        } else if self.connection_switcher.is_some() {
+            self.key_mapper.map_connection_switcher_key(key_event)
+        } else if self.command_palette.is_some() {
+            self.key_mapper.map_command_palette_key(key_event)
+        } else if self.show_key_map {
+            self.key_mapper.map_key_map_key(key_event)
+        } else if self.json_tree.is_some() {
+            self.key_mapper.map_json_tree_key(key_event)
+        } else if self.cell_inspect.is_some() {
+            self.key_mapper.map_popup_key(key_event)
+        } else if let Some(command) = self
+            .query_editor
+            .completion
+            .as_ref()
+            .and_then(|_| self.key_mapper.map_completion_key(key_event))
+        {
+            Some(command)
+        } else {
+            // Any key the completion list does not use dismisses it.
+            self.query_editor.completion = None;
+            self.key_mapper
+                .map_key_to_command(key_event, &self.focus, self.data_table.tabs.index)
+        };
+
+        let command = match command {
+            Some(Command::PaletteSubmit) => self
+                .command_palette
+                .take()
+                .and_then(|palette| palette.selected_command()),
+            command => command,
+        };
+
+        if let Some(command) = command {
+            self.handle_command(command, key_event).await?;
+            self.query_editor.mode = self.key_mapper.editor_mode();
+            self.data_table.input = self.key_mapper.table_input();
+            self.snippet_name_input = self.key_mapper.snippet_name();
+            self.query_editor.command_line = self.key_mapper.editor_command_line();
+            self.sidebar.filter_editing = self.key_mapper.sidebar_filter().is_some();
+            self.key_map_view.filter_editing = self.key_mapper.key_map_filter().is_some();
+        }
+        Ok(())
+    }
+
+    /// Clicking focuses the pane under the mouse before acting on it; the wheel scrolls the
+    /// focused pane. The mouse is ignored while a popup or prompt is open.
+    async fn handle_mouse_event(&mut self, mouse_event: MouseEvent) -> Result<()> {
+        if self.pending_query.is_some()
+            || self.pending_delete.is_some()
+            || self.snippet_picker.is_some()
+            || self.password_prompt.is_some()
+            || self.cell_edit.is_some()
+            || self.insert_form.is_some()
+            || self.command_palette.is_some()
+            || self.connection_switcher.is_some()
+            || self.show_key_map
+            || self.cell_inspect.is_some()
+        {
+            return Ok(());
+        }
+
+        if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) {
+            let position = Position::new(mouse_event.column, mouse_event.row);
+            let Some((focus, _)) = self
+                .pane_areas
+                .iter()
+                .find(|(_, area)| area.contains(position))
+                .cloned()
+            else {
+                return Ok(());
+            };
+            self.query_editor.completion = None;
+            self.set_focus(focus);
+        }
+
+        if let Some(command) =
+            self.key_mapper
+                .map_mouse_event(mouse_event, &self.focus, self.data_table.tabs.index)
+        {
+            self.handle_command(command, KeyEvent::from(KeyCode::Null))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Opens the switcher, unless a query or transaction still needs the current connection.
+    fn open_connection_switcher(&mut self) {
+        if self.query_task.is_some() {
+            self.data_table
+                .set_status("A query is already running.".to_string());
+            return;
+        }
+        if self.in_transaction {
+            self.data_table.set_status(
+                "Commit or roll back the open transaction before switching connections."
+                    .to_string(),
+            );
+            return;
+        }
+        let current = self.connection_name.as_deref();
+        let selected = self
+            .connections
+            .iter()
+            .position(|connection| Some(connection.name.as_str()) == current)
+            .unwrap_or(0);
+        self.connection_switcher = Some(selected);
+        self.popup_scroll = selected.saturating_sub(10) as u16;
+    }
+
+    /// Connects to `connection` in the background; the sidebar is rebuilt once it succeeds.
+    fn switch_connection(&mut self, connection: Connection) {
+        self.data_table
+            .set_status(format!("Connecting to {}...", connection.name));
+        let tx = self.sidebar_tx.clone();
+        let timeout = self.connect_timeout;
+        let read_only = self.read_only;
+        tokio::spawn(async move {
+            let details = connection.details(None);
+            let result = async {
+                let pool = open_pool(&connection, details, None, timeout, read_only).await?;
+                let databases = fetch_databases(&pool).await?;
+                let history = get_history(Some(connection.name.clone())).await;
+                Ok((pool, databases, history))
+            }
+            .await;
+            let _ = tx.send(SidebarUpdate::Connected {
+                connection: Box::new(connection),
+                result,
+            });
+        });
+    }
+
+    /// Replaces the current connection, dropping its pool, session and sidebar contents.
+    fn finish_connection_switch(
+        &mut self,
+        connection: Connection,
+        pool: DbPool,
+        databases: Vec<String>,
+        history: Vec<QueryHistoryEntry>,
+    ) {
+        // Anything started while connecting ran on the old connection.
+        self.cancel_running_query();
+        if std::mem::take(&mut self.in_transaction) {
+            self.data_table
+                .set_status("The open transaction was rolled back.".to_string());
+        }
+        self.pool = Some(pool);
+        self.database_pools.clear();
+        self.database = None;
+        self.results_database = None;
+        self.session = None;
+        self.session_database = None;
+        self.schema_changed = false;
+        self.databases = databases_from_names(databases);
+        self.table_details_cache.clear();
+        self.refresh_sidebar();
+
+        self.connection_name = Some(connection.name.clone());
+        self.data_table.query_history = history;
+        self.data_table.saved_column_widths = self
+            .column_widths
+            .get(&connection.name)
+            .cloned()
+            .unwrap_or_default();
+        let message = match save_last_connection(&connection.name) {
+            Ok(()) => format!("Connected to {}.", connection.name),
+            Err(e) => format!(
+                "Connected to {}, but failed to remember it: {}",
+                connection.name, e
+            ),
+        };
+        self.data_table.set_status(message);
+        self.current_connection = Some(connection);
+        self.server_version = None;
+        self.load_server_version();
+        self.health = ConnectionHealth::Connected;
+        self.health_checked_at = Some(Instant::now());
+    }
+
+    /// Pings the server every `HEALTH_CHECK_INTERVAL` in the background, so a connection
+    /// dropped while idle is noticed and reopened before the next query needs it.
+    fn check_connection_health(&mut self) {
+        if self.health_check_running
+            || self
+                .health_checked_at
+                .is_some_and(|checked| checked.elapsed() < HEALTH_CHECK_INTERVAL)
+        {
+            return;
+        }
+        let (Some(pool), Some(connection_name)) = (self.pool.clone(), self.connection_name.clone())
+        else {
+            return;
+        };
+        self.health_check_running = true;
+        let tx = self.sidebar_tx.clone();
+        tokio::spawn(async move {
+            let result = pool.ping().await.map_err(Into::into);
+            let _ = tx.send(SidebarUpdate::HealthCheck {
+                connection_name,
+                result,
+            });
+        });
+    }
+
+    /// Opens the pool of the current connection again, through a new SSH tunnel if its
+    /// old one died with the connection.
+    fn reconnect(&mut self) {
+        let Some(connection) = self.current_connection.clone() else {
+            return;
+        };
+        self.health = ConnectionHealth::Reconnecting {
+            since: Instant::now(),
+        };
+        self.health_check_running = true;
+        let databases: Vec<String> = self.database_pools.keys().cloned().collect();
+        let timeout = self.connect_timeout;
+        let read_only = self.read_only;
+        let tx = self.sidebar_tx.clone();
+        tokio::spawn(async move {
+            let result = async {
+                let details = connection.details(None);
+                let pool = open_pool(&connection, details, None, timeout, read_only).await?;
+                let mut database_pools = HashMap::new();
+                for db_name in databases {
+                    let details = connection.details(Some(db_name.clone()));
+                    let db_pool =
+                        open_pool(&connection, details, Some(&db_name), timeout, read_only).await?;
+                    database_pools.insert(db_name, db_pool);
+                }
+                Ok((pool, database_pools))
+            }
+            .await;
+            let _ = tx.send(SidebarUpdate::Reconnected {
+                connection_name: connection.name,
+                result,
+            });
+        });
+    }
+
+    /// Uses the pools opened by `reconnect`, starting a new session for the next query.
+    fn finish_reconnect(
+        &mut self,
+        connection_name: &str,
+        pool: DbPool,
+        database_pools: HashMap<String, DbPool>,
+    ) {
+        self.pool = Some(pool);
+        self.database_pools = database_pools;
+        self.health = ConnectionHealth::Connected;
+        let mut message = format!("Reconnected to {}.", connection_name);
+        // A running query holds the session, and hands it back when it fails.
+        if self.query_task.is_none() {
+            self.session = None;
+            if std::mem::take(&mut self.in_transaction) {
+                message.push_str(" The open transaction was lost with the old connection.");
+            }
+        }
+        self.data_table.set_status(message);
+    }
+
+    /// Fetches the server version in the background, for the connection info popup.
+    fn load_server_version(&mut self) {
+        let (Some(pool), Some(connection_name)) = (self.pool.clone(), self.connection_name.clone())
+        else {
+            return;
+        };
+        let tx = self.sidebar_tx.clone();
+        tokio::spawn(async move {
+            let result = fetch_server_version(&pool).await;
+            let _ = tx.send(SidebarUpdate::ServerVersion {
+                connection_name,
+                result,
+            });
+        });
+    }
+
+    /// Shows where the app is connected to and what the server is running.
+    fn show_connection_info(&mut self) {
+        let Some(connection) = &self.current_connection else {
+            self.data_table
+                .set_status("Not connected to a database.".to_string());
+            return;
+        };
+        let server_version = match &self.server_version {
+            Some(version) => version.as_str(),
+            None => "fetching...",
+        };
+        let content = connection_info_text(connection, self.database.as_deref(), server_version);
+        self.cell_inspect = Some(("Connection".to_string(), content));
+        self.json_tree = None;
+        self.popup_scroll = 0;
+    }
+
+    /// Fetches a database's tables in the background the first time it is expanded.
+    fn load_tables(&mut self, db_name: String) {
+        let Some(connection) = self.current_connection.clone() else {
+            return;
+        };
+        let Some(db) = self.databases.iter_mut().find(|db| db.name == db_name) else {
+            return;
+        };
+        if !db.tables.is_empty() || db.loading {
+            return;
+        }
+
+        db.loading = true;
+        self.refresh_sidebar();
+
+        let tx = self.sidebar_tx.clone();
+        let timeout = self.connect_timeout;
+        let read_only = self.read_only;
+        let opened = self.database_pools.get(&db_name).cloned();
+        tokio::spawn(async move {
+            let pool = match opened {
+                Some(pool) => Ok(pool),
+                None => {
+                    let details = connection.details(Some(db_name.clone()));
+                    open_pool(&connection, details, Some(&db_name), timeout, read_only).await
+                }
+            };
+            let result = match pool {
+                Ok(pool) => fetch_tables(&pool).await.map(|tables| (pool, tables)),
+                Err(e) => Err(e.into()),
+            };
+            let _ = tx.send(SidebarUpdate::Tables { db_name, result });
+        });
+    }
+
+    fn find_table_mut(&mut self, db_name: &str, table_id: &str) -> Option<&mut Table> {
+        self.databases
+            .iter_mut()
+            .find(|db| db.name == db_name)?
+            .tables
+            .iter_mut()
+            .find(|table| table_node_id(db_name, table) == table_id)
+    }
+
+    /// Shows a table's details, fetching them in the background unless cached.
+    fn load_table_details(&mut self, db_name: String, table_id: String) {
+        let cached = self.table_details_cache.get(&table_id).cloned();
+        let pool = self.pool_for(Some(&db_name));
+        let tx = self.sidebar_tx.clone();
+
+        let Some(table) = self.find_table_mut(&db_name, &table_id) else {
+            return;
+        };
+
+        if let Some(metadata) = cached {
+            table.metadata = Some(metadata);
+        } else if let Some(pool) = pool
+            && !table.loading
+        {
+            table.loading = true;
+            let schema = table.schema.clone();
+            let table_name = table.name.clone();
+            tokio::spawn(async move {
+                let result = fetch_table_details(&pool, schema.as_deref(), &table_name)
+                    .await
+                    .map(Box::new);
+                let _ = tx.send(SidebarUpdate::TableDetails {
+                    db_name,
+                    table_id,
+                    result,
+                });
+            });
+        }
+        self.refresh_sidebar();
+    }
+
+    /// The pool of `database`, or of the server's default database for `None`, once open.
+    fn pool_for(&self, database: Option<&str>) -> Option<DbPool> {
+        match database {
+            Some(db_name) => self.database_pools.get(db_name).cloned(),
+            None => self.pool.clone(),
+        }
+    }
+
+    /// Forgets a database's tables and their cached details, so they are fetched again
+    /// the next time it is expanded. Returns `false` if they are still loading.
+    fn forget_tables(&mut self, db_name: &str) -> bool {
+        let Some(db) = self.databases.iter_mut().find(|db| db.name == db_name) else {
+            return false;
+        };
+        if db.loading {
+            return false;
+        }
+
+        for table in std::mem::take(&mut db.tables) {
+            self.table_details_cache
+                .remove(&table_node_id(db_name, &table));
+        }
+        // A selected table may be gone after the reload.
+        let db_node = format!("db_{}", db_name);
+        if self.sidebar.state.selected().first() == Some(&db_node) {
+            self.sidebar.state.select(vec![db_node]);
+        }
+        self.refresh_sidebar();
+        true
+    }
+
+    /// Fetches a database's tables again, so ones created or dropped since show up.
+    fn reload_tables(&mut self, db_name: String) {
+        if self.forget_tables(&db_name) {
+            self.load_tables(db_name);
+        }
+    }
+
+    fn refresh_selected_database(&mut self) {
+        let path = self.sidebar.state.selected();
+        let Some(db_name) = path.first().and_then(|id| id.strip_prefix("db_")) else {
+            self.data_table
+                .set_status("Select a database to refresh.".to_string());
+            return;
+        };
+        let db_name = db_name.to_string();
+        self.data_table
+            .set_status(format!("Reloading tables of {}...", db_name));
+        self.reload_tables(db_name);
+    }
+
+    /// Brings the table lists a schema change may have touched up to date. The session's
+    /// database is reloaded right away; on the server's default database any of them may
+    /// have changed, so all are forgotten until next expanded.
+    fn reload_changed_tables(&mut self) {
+        match self.session_database.clone() {
+            Some(db_name) => self.reload_tables(db_name),
+            None => {
+                let loaded: Vec<String> = self
+                    .databases
+                    .iter()
+                    .filter(|db| !db.tables.is_empty())
+                    .map(|db| db.name.clone())
+                    .collect();
+                for db_name in loaded {
+                    self.forget_tables(&db_name);
+                }
+            }
+        }
+    }
+
+    /// The database name and node id of the table selected in the sidebar, if any.
+    fn selected_table_node(&self) -> Option<(String, String)> {
+        let path = self.sidebar.state.selected();
+        let db_name = path.first()?.strip_prefix("db_")?;
+        let table_id = path.last().filter(|id| id.starts_with("tbl_"))?;
+        Some((db_name.to_string(), table_id.clone()))
+    }
+
+    /// Runs an exact `COUNT(*)` for a table in the background.
+    fn count_table_rows(&mut self, db_name: String, table_id: String) {
+        let Some(pool) = self.pool_for(Some(&db_name)) else {
+            return;
+        };
+        let tx = self.sidebar_tx.clone();
+
+        let Some(table) = self.find_table_mut(&db_name, &table_id) else {
+            return;
+        };
+        if table.counting {
+            return;
+        }
+
+        table.counting = true;
+        let schema = table.schema.clone();
+        let table_name = table.name.clone();
+        tokio::spawn(async move {
+            let result = count_rows(&pool, schema.as_deref(), &table_name).await;
+            let _ = tx.send(SidebarUpdate::RowCount {
+                db_name,
+                table_id,
+                result,
+            });
+        });
+        self.refresh_sidebar();
+    }
+
+    /// Fetches a table's `CREATE` statement in the background to show it in a popup.
+    fn show_table_ddl(&mut self, db_name: &str, table_id: &str) {
+        let Some(pool) = self.pool_for(Some(db_name)) else {
+            return;
+        };
+        let tx = self.sidebar_tx.clone();
+        let Some(table) = self
+            .find_table_mut(db_name, table_id)
+            .map(|table| table.clone())
+        else {
+            return;
+        };
+
+        self.data_table
+            .set_status(format!("Fetching DDL for {}...", table.name));
+        tokio::spawn(async move {
+            let result = fetch_table_ddl(&pool, &table).await;
+            let _ = tx.send(SidebarUpdate::Ddl {
+                table_name: table.name,
+                result,
+            });
+        });
+    }
+
+    /// Applies metadata fetched by background sidebar tasks.
+    fn poll_sidebar_updates(&mut self) {
+        while let Ok(update) = self.sidebar_rx.try_recv() {
+            match update {
+                SidebarUpdate::Tables { db_name, result } => {
+                    // Tables requested before a connection switch belong to the old server.
+                    let Some(db) = self
+                        .databases
+                        .iter_mut()
+                        .find(|db| db.name == db_name && db.loading)
+                    else {
+                        continue;
+                    };
+                    db.loading = false;
+                    match result {
+                        Ok((pool, tables)) => {
+                            db.tables = tables;
+                            self.database_pools.insert(db_name, pool);
+                        }
+                        Err(e) => {
+                            self.data_table.set_status(format!(
+                                "Failed to load tables for {}: {}",
+                                db_name, e
+                            ));
+                        }
+                    }
+                }
+                SidebarUpdate::EditTarget {
+                    table,
+                    position,
+                    action,
+                    result,
+                } => self.open_row_action(&table, position, action, result),
+                SidebarUpdate::InsertTarget {
+                    db_name,
+                    table,
+                    result,
+                } => match result {
+                    Ok(metadata) => self.open_insert_form(&db_name, &table, *metadata),
+                    Err(e) => self.data_table.set_status(format!(
+                        "Failed to fetch the columns of {}: {}",
+                        table.name, e
+                    )),
+                },
+                SidebarUpdate::ServerVersion {
+                    connection_name,
+                    result,
+                } => {
+                    // A version fetched before a connection switch belongs to the old server.
+                    if self.connection_name.as_deref() != Some(connection_name.as_str()) {
+                        continue;
+                    }
+                    match result {
+                        Ok(version) => self.server_version = Some(version),
+                        Err(e) => {
+                            self.server_version = Some(format!("unknown ({})", e));
+                        }
+                    }
+                }
+                SidebarUpdate::HealthCheck {
+                    connection_name,
+                    result,
+                } => {
+                    self.health_check_running = false;
+                    // A ping sent before a connection switch checked the old server.
+                    if self.connection_name.as_deref() != Some(connection_name.as_str()) {
+                        continue;
+                    }
+                    self.health_checked_at = Some(Instant::now());
+                    match result {
+                        Ok(()) => self.health = ConnectionHealth::Connected,
+                        Err(e) => {
+                            self.data_table.set_status(format!(
+                                "Lost the connection to {}: {}. Reconnecting...",
+                                connection_name, e
+                            ));
+                            self.reconnect();
+                        }
+                    }
+                }
+                SidebarUpdate::Reconnected {
+                    connection_name,
+                    result,
+                } => {
+                    self.health_check_running = false;
+                    if self.connection_name.as_deref() != Some(connection_name.as_str()) {
+                        continue;
+                    }
+                    self.health_checked_at = Some(Instant::now());
+                    match result {
+                        Ok((pool, database_pools)) => {
+                            self.finish_reconnect(&connection_name, pool, database_pools)
+                        }
+                        Err(e) => {
+                            self.health = ConnectionHealth::Lost;
+                            self.data_table.set_status(format!(
+                                "Could not reconnect to {}: {}. Retrying in {} seconds.",
+                                connection_name,
+                                e,
+                                HEALTH_CHECK_INTERVAL.as_secs()
+                            ));
+                        }
+                    }
+                }
+                SidebarUpdate::Connected { connection, result } => match result {
+                    Ok((pool, databases, history)) => {
+                        self.finish_connection_switch(*connection, pool, databases, history);
+                    }
+                    Err(e) => {
+                        self.data_table
+                            .set_status(format!("Could not switch to {}: {}", connection.name, e));
+                    }
+                },
+                SidebarUpdate::TableDetails {
+                    db_name,
+                    table_id,
+                    result,
+                } => match result {
+                    Ok(metadata) => {
+                        self.table_details_cache
+                            .insert(table_id.clone(), (*metadata).clone());
+                        if let Some(table) = self.find_table_mut(&db_name, &table_id) {
+                            table.loading = false;
+                            table.metadata = Some(*metadata);
+                        }
+                    }
+                    Err(e) => {
+                        if let Some(table) = self.find_table_mut(&db_name, &table_id) {
+                            table.loading = false;
+                            let message = format!("Failed to load {}: {}", table.name, e);
+                            self.data_table.set_status(message);
+                        }
+                    }
+                },
+                SidebarUpdate::RowCount {
+                    db_name,
+                    table_id,
+                    result,
+                } => {
+                    let Some(table) = self.find_table_mut(&db_name, &table_id) else {
+                        continue;
+                    };
+                    table.counting = false;
+                    match result {
+                        Ok(rows) => table.exact_row_count = Some(rows),
+                        Err(e) => {
+                            let message = format!("Failed to count rows of {}: {}", table.name, e);
+                            self.data_table.set_status(message);
+                        }
+                    }
+                }
+                SidebarUpdate::Ddl { table_name, result } => match result {
+                    Ok(ddl) => {
+                        self.data_table.status_message = None;
+                        self.cell_inspect = Some((format!("DDL: {}", table_name), ddl));
+                        self.json_tree = None;
+                        self.popup_scroll = 0;
+                    }
+                    Err(e) => {
+                        self.data_table
+                            .set_status(format!("Failed to fetch DDL for {}: {}", table_name, e));
+                    }
+                },
+            }
+            self.refresh_sidebar();
+        }
+    }
+
+    fn refresh_sidebar(&mut self) {
+        let items =
+            metadata_to_tree_items(&filter_databases(&self.databases, &self.sidebar.filter));
+        self.sidebar.update_items(items);
+    }
+
+    fn execute_current_query(&mut self) {
+        let query = self.current_query();
+        if query.is_empty() || self.refuse_writes(&query) {
+            return;
+        }
+
+        if self.server_paging && whole_table_select(&query).is_some() {
+            self.spawn_page(query, 0);
+            return;
+        }
+
+        let db_type = self.db_type();
+        if self.confirm_destructive
+            && split_statements(&query, db_type)
+                .into_iter()
+                .any(|statement| is_destructive(statement, db_type))
+        {
+            self.pending_query = Some((query, false));
+            self.popup_scroll = 0;
+            return;
+        }
+
+        self.spawn_query(query, false);
+    }
+
+    fn explain_current_query(&mut self, analyze: bool) {
+        let query = self.current_query();
+        let statements = split_statements(&query, self.db_type());
+        let [statement] = statements.as_slice() else {
+            self.data_table
+                .set_status("Select a single statement to explain.".to_string());
+            return;
+        };
+
+        let Some(db_type) = self.pool.as_ref().map(DbPool::get_type) else {
+            self.data_table
+                .set_error_state("Database connection pool not available.".to_string());
+            return;
+        };
+
+        let explain = explain_sql(statement, db_type, analyze);
+        if self.refuse_writes(&explain) {
+            return;
+        }
+        // EXPLAIN ANALYZE really executes the statement.
+        if analyze && self.confirm_destructive && is_destructive(statement, db_type) {
+            self.pending_query = Some((explain, true));
+            self.popup_scroll = 0;
+            return;
+        }
+
+        self.spawn_query(explain, true);
+    }
+
+    /// The dialect queries are written in: the connected database's, or that of the
+    /// connection being opened. Before any, Postgres's standard strings are assumed.
+    fn db_type(&self) -> DatabaseType {
+        self.pool
+            .as_ref()
+            .map(DbPool::get_type)
+            .or(self.current_connection.as_ref().map(|c| c.db_type))
+            .unwrap_or(DatabaseType::PostgreSQL)
+    }
+
+    /// In read-only mode, reports and returns `true` if any statement would write.
+    fn refuse_writes(&mut self, query: &str) -> bool {
+        if !self.read_only {
+            return false;
+        }
+        let Some(refusal) = read_only_refusal(query, self.db_type()) else {
+            return false;
+        };
+        self.data_table.set_status(refusal);
+        true
+    }
+
+    fn spawn_query(&mut self, query: String, explain: bool) {
+        let connection_name = self.connection_name.clone();
+        let max_rows = self.max_rows;
+        let script = query.clone();
+        let database = self.database.clone();
+        self.spawn_task(
+            database,
+            query,
+            explain,
+            move |pool, mut session| async move {
+                let result =
+                    execute_script(&pool, &mut session, &script, connection_name, max_rows).await;
+                (result, session)
+            },
+        );
+    }
+
+    /// Fetches the page of a `SELECT * FROM table` query starting `offset` rows in.
+    fn spawn_page(&mut self, query: String, offset: usize) {
+        let connection_name = self.connection_name.clone();
+        let page_size = self.data_table.page_size();
+        let page = ServerPage {
+            query: query.clone(),
+            offset,
+            has_more: false,
+        };
+        let pending = page.clone();
+        let database = self.database.clone();
+        let started = self.spawn_task(
+            database,
+            query,
+            false,
+            move |pool, mut session| async move {
+                let result = fetch_page(
+                    &pool,
+                    &mut session,
+                    &page.query,
+                    page.offset,
+                    page_size,
+                    connection_name,
+                )
+                .await;
+                (result, session)
+            },
+        );
+        if started {
+            self.pending_page = Some(pending);
+        }
+    }
+
+    /// Runs `run` in the background with the session on `database`, reporting `query` as
+    /// the running statement. Returns whether it was started.
+    fn spawn_task<F, Fut>(
+        &mut self,
+        database: Option<String>,
+        query: String,
+        explain: bool,
+        run: F,
+    ) -> bool
+    where
+        F: FnOnce(DbPool, Option<DbSession>) -> Fut + Send + 'static,
+        Fut: Future<Output = QueryResult> + Send + 'static,
+    {
+        if self.query_task.is_some() {
+            self.data_table
+                .set_status("A query is already running.".to_string());
+            return false;
+        }
+
+        // A session stays on the database it was opened on until its transaction ends.
+        let database = if self.in_transaction {
+            self.session_database.clone()
+        } else {
+            database
+        };
+        let Some(pool) = self.pool_for(database.as_deref()) else {
+            let message = match &database {
+                Some(db_name) => format!(
+                    "Not connected to {} yet; expand it in the sidebar.",
+                    db_name
+                ),
+                // Handle the case where the pool is not available (e.g., not connected to a DB)
+                None => "Database connection pool not available.".to_string(),
+            };
+            self.data_table.set_error_state(message);
+            return false;
+        };
+
+        self.query = query.clone();
+        self.explain_pending = explain;
+        self.pending_page = None;
+        self.pending_edit = None;
+        self.query_origin = self.query_editor.query_start();
+        self.data_table.start_loading();
+
+        let result_tx = self.query_result_tx.clone();
+        if self.session_database != database {
+            self.session = None;
+        }
+        if self.session.is_none() {
+            self.session_database = database;
+        }
+        let session = self.session.take();
+        self.query_task = Some(tokio::spawn(async move {
+            let _ = result_tx.send(run(pool, session).await);
+        }));
+        true
+    }
+
+    /// Looks up the primary key of the table the results come from, to edit the selected
+    /// cell once it is known.
+    fn start_row_action(&mut self, action: RowAction) {
+        let Some((table, position)) = self.row_action_target() else {
+            return;
+        };
+        let Some(pool) = self.pool_for(self.results_database.as_deref()) else {
+            self.data_table
+                .set_error_state("Database connection pool not available.".to_string());
+            return;
+        };
+
+        let tx = self.sidebar_tx.clone();
+        tokio::spawn(async move {
+            let (schema, table_name) = split_table_name(&table);
+            let result = fetch_table_details(&pool, schema.as_deref(), &table_name)
+                .await
+                .map(Box::new);
+            let _ = tx.send(SidebarUpdate::EditTarget {
+                table,
+                position,
+                action,
+                result,
+            });
+        });
+    }
+
+    /// The table of the shown results and the selected cell to change, or `None` after
+    /// reporting why the results can't be changed. The table is the one recorded when
+    /// the results arrived, not that of a later query that was cancelled or failed.
+    fn row_action_target(&mut self) -> Option<(String, (usize, usize))> {
+        if self.read_only {
+            self.data_table
+                .set_status("Read-only mode: rows can't be changed.".to_string());
+            return None;
+        }
+        let Some(table) = self.data_table.source_table.clone() else {
+            self.data_table.set_status(
+                "Only results of a SELECT of plain columns, without aliases or expressions, from a single table can be edited."
+                    .to_string(),
+            );
+            return None;
+        };
+        let Some(position) = self.data_table.selected_position() else {
+            self.data_table
+                .set_status("Select a cell to edit.".to_string());
+            return None;
+        };
+        Some((table, position))
+    }
+
+    /// Opens the edit popup for the cell at `position`, or asks to confirm deleting its
+    /// row, once its table's details arrive, provided the results include the table's
+    /// primary key.
+    fn open_row_action(
+        &mut self,
+        table: &str,
+        position: (usize, usize),
+        action: RowAction,
+        result: Result<Box<TableMetadata>>,
+    ) {
+        let metadata = match result {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                self.data_table.set_status(format!(
+                    "Failed to look up the primary key of {}: {}",
+                    table, e
+                ));
+                return;
+            }
+        };
+        let Some(key) = metadata.primary_key() else {
+            self.data_table.set_status(format!(
+                "{} has no primary key, so its rows can't be edited.",
+                table
+            ));
+            return;
+        };
+        let opened = match action {
+            RowAction::EditCell => self
+                .data_table
+                .cell_update(table, position, &key)
+                .map(|edit| self.cell_edit = Some(edit)),
+            RowAction::DeleteRow => self
+                .data_table
+                .row_delete(table, position.0, &key)
+                .map(|delete| self.pending_delete = Some(delete)),
+        };
+        match opened {
+            Some(()) => self.popup_scroll = 0,
+            None => self.data_table.set_status(format!(
+                "Select the primary key ({}) of {} to edit its rows.",
+                key.join(", "),
+                table
+            )),
+        }
+    }
+
+    /// Writes a change to a row of a table in `database` back in the background.
+    fn spawn_row_edit(&mut self, edit: RowEdit, database: Option<String>) {
+        let Some(db_type) = self.pool.as_ref().map(DbPool::get_type) else {
+            self.data_table
+                .set_error_state("Database connection pool not available.".to_string());
+            return;
+        };
+        if self.in_transaction && self.session_database != database {
+            self.data_table.set_status(format!(
+                "A transaction is open on {}; commit or roll it back before changing rows of {}.",
+                self.session_database
+                    .as_deref()
+                    .unwrap_or("the default database"),
+                database.as_deref().unwrap_or("the default database")
+            ));
+            return;
+        }
 
-        let spinner_handle = tokio::spawn(async move {
-            let spinner = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
-            let mut i = 0;
-            let mut stdout = stdout();
+        let sql = edit.sql(db_type);
+        let connection_name = self.connection_name.clone();
+        let pending = edit.clone();
+        // The results shown still come from the query that was run before.
+        let source_query = self.query.clone();
+        let started = self.spawn_task(database, sql, false, move |pool, mut session| async move {
+            let result = match &edit {
+                RowEdit::Update(update) => {
+                    update_cell(&pool, &mut session, update, connection_name).await
+                }
+                RowEdit::Delete(delete) => {
+                    delete_row(&pool, &mut session, delete, connection_name).await
+                }
+                RowEdit::Insert(insert) => {
+                    insert_row(&pool, &mut session, insert, connection_name).await
+                }
+            };
+            (result, session)
+        });
+        self.query = source_query;
+        if started {
+            self.pending_edit = Some(pending);
+        }
+    }
 
-            while spinner_flag.load(Ordering::SeqCst) {
-                execute!(
-                    stdout,
-                    cursor::MoveToColumn(0),
-                    Clear(ClearType::CurrentLine),
-                    Print(format!(
-                        "🔄 Fetching databases... {}",
-                        spinner[i % spinner.len()]
-                    )),
-                )
-                .unwrap();
-                stdout.flush().unwrap();
-                sleep(Duration::from_millis(100)).await;
-                i += 1;
-            }
+    /// Opens the insert form for a table once its columns are known, fetching them in
+    /// the background unless cached.
+    fn start_row_insert(&mut self, db_name: &str, table_id: &str) {
+        if self.read_only {
+            self.data_table
+                .set_status("Read-only mode: rows can't be changed.".to_string());
+            return;
+        }
+        let Some(table) = self
+            .find_table_mut(db_name, table_id)
+            .map(|table| table.clone())
+        else {
+            return;
+        };
+        if let Some(metadata) = self.table_details_cache.get(table_id).cloned() {
+            self.open_insert_form(db_name, &table, metadata);
+            return;
+        }
+        let Some(pool) = self.pool_for(Some(db_name)) else {
+            return;
+        };
+        let db_name = db_name.to_string();
 
-            execute!(
-                stdout,
-                cursor::MoveToColumn(0),
-                Clear(ClearType::CurrentLine),
-            )
-            .unwrap();
+        self.data_table
+            .set_status(format!("Fetching the columns of {}...", table.name));
+        let tx = self.sidebar_tx.clone();
+        tokio::spawn(async move {
+            let result = fetch_table_details(&pool, table.schema.as_deref(), &table.name)
+                .await
+                .map(Box::new);
+            let _ = tx.send(SidebarUpdate::InsertTarget {
+                db_name,
+                table: Box::new(table),
+                result,
+            });
         });
-        (spinner_handle, loading)
     }
 
-    async fn setup_ui(&mut self, sidebar_items: Vec<TreeItem<'static, String>>) -> Result<()> {
-        self.focus = Focus::Sidebar;
-        self.sidebar.update_items(sidebar_items);
-        self.sidebar.update_focus(self.focus.clone());
+    fn open_insert_form(&mut self, db_name: &str, table: &Table, metadata: TableMetadata) {
+        let Some(db_type) = self.pool.as_ref().map(DbPool::get_type) else {
+            return;
+        };
+        let (name, target) = match &table.schema {
+            Some(schema) => (
+                format!("{}.{}", schema, table.name),
+                format!(
+                    "{}.{}",
+                    quote_ident(db_type, schema),
+                    quote_ident(db_type, &table.name)
+                ),
+            ),
+            None => (table.name.clone(), quote_ident(db_type, &table.name)),
+        };
+        self.insert_form = Some(InsertForm::new(name, target, metadata.columns));
+        self.insert_database = Some(db_name.to_string());
+        self.data_table.status_message = None;
+    }
 
-        Ok(())
+    /// Fetches the page after or before the one shown while browsing a table on the server.
+    fn turn_server_page(&mut self, forward: bool) {
+        let Some(page) = self.data_table.server_page.clone() else {
+            return;
+        };
+        let page_size = self.data_table.page_size();
+        let offset = match forward {
+            true if page.has_more => page.offset + page_size,
+            false if page.offset > 0 => page.offset.saturating_sub(page_size),
+            _ => return,
+        };
+        self.spawn_page(page.query, offset);
     }
 
-    fn draw_once(&mut self, terminal: &mut DefaultTerminal) {
-        let _ = terminal.draw(|f| self.render_ui(f));
+    fn cancel_running_query(&mut self) {
+        if let Some(task) = self.query_task.take() {
+            // Dropping the query future closes its connection, which stops the query.
+            task.abort();
+            self.explain_pending = false;
+            self.pending_page = None;
+            self.pending_edit = None;
+            while self.query_result_rx.try_recv().is_ok() {}
+            self.data_table.cancel_loading(&self.query);
+            // The aborted task closed the session connection, rolling back its transaction.
+            let reset = if std::mem::take(&mut self.in_transaction) {
+                "The open transaction was rolled back and a new session will be started."
+            } else {
+                "Session settings and temporary tables were reset."
+            };
+            self.data_table.set_status(reset.to_string());
+        }
     }
 
-    pub async fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        while !self.exit {
-            terminal.draw(|f| self.render_ui(f))?;
-            let _ = self.handle_events(&mut terminal).await;
+    /// Commits or rolls back the open transaction by running `statement` in it.
+    fn end_transaction(&mut self, statement: &str) {
+        if !self.in_transaction {
+            self.data_table
+                .set_status("No transaction is open.".to_string());
+            return;
         }
-        save_history().await?;
-        Ok(())
+        self.spawn_query(statement.to_string(), false);
     }
 
-    async fn handle_events(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
-        if event::poll(Duration::from_millis(100))?
-            && let Event::Key(key_event) = event::read()? {
-                let command = if self.show_key_map {
-                    self.key_mapper.map_popup_key(key_event)
+    /// Applies the result of a finished background query, if one has arrived.
+    async fn poll_query_result(&mut self) {
+        let Ok((result, session)) = self.query_result_rx.try_recv() else {
+            if self
+                .query_task
+                .as_ref()
+                .is_some_and(JoinHandle::is_finished)
+            {
+                self.query_task = None;
+                self.in_transaction = false;
+                self.data_table
+                    .set_error_state("Query task ended unexpectedly.".to_string());
+            }
+            return;
+        };
+        self.query_task = None;
+        self.in_transaction = session.as_ref().is_some_and(DbSession::in_transaction);
+        self.session = session;
+        let explain = std::mem::take(&mut self.explain_pending);
+        let page = self.pending_page.take();
+        let edit = self.pending_edit.take();
+
+        if result.is_ok()
+            && split_statements(&self.query, self.db_type())
+                .into_iter()
+                .any(is_ddl)
+        {
+            self.schema_changed = true;
+        }
+        // Other connections, including the sidebar's, only see a change once it is committed.
+        if self.schema_changed && !self.in_transaction {
+            self.schema_changed = false;
+            self.reload_changed_tables();
+        }
+
+        match result {
+            Ok(ExecutionResult::Data {
+                mut result_set,
+                meta: DataMeta { rows, message },
+            }) => {
+                let elapsed_duration = if let Some(stats) = get_query_stats().await {
+                    stats.elapsed
                 } else {
-                    self.key_mapper.map_key_to_command(
-                        key_event,
-                        &self.focus,
-                        self.data_table.tabs.index,
-                    )
+                    Duration::ZERO
                 };
-
-                if let Some(command) = command {
-                    self.handle_command(command, key_event, terminal).await?;
-                    self.query_editor.mode = self.key_mapper.editor_mode();
+                self.data_table.query_history = get_history(self.connection_name.clone()).await;
+                if explain {
+                    self.cell_inspect = Some(("Query Plan".to_string(), plan_text(&result_set)));
+                    self.json_tree = None;
+                    self.popup_scroll = 0;
                 }
-            }
-        Ok(())
-    }
-
-    async fn execute_current_query(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
-        let query = self.current_query();
-        if !query.is_empty() {
-            self.query = query.clone();
-
-            self.data_table.start_loading();
-            self.draw_once(terminal);
-
-            if let Some(pool) = &self.pool {
-                match execute_query(pool, &query, self.connection_name.clone()).await {
-                    Ok(ExecutionResult::Data {
-                        headers,
-                        rows,
-                        meta: DataMeta { rows: _, message },
-                    }) => {
-                        let elapsed_duration = if let Some(stats) = get_query_stats().await {
-                            stats.elapsed
-                        } else {
-                            Duration::ZERO
-                        };
-                        self.data_table.query_history =
-                            get_history(self.connection_name.clone()).await;
-                        self.data_table
-                            .finish_loading(headers, rows, elapsed_duration);
-                        self.data_table.status_message = Some(message);
-                    }
-                    Ok(ExecutionResult::Affected { rows: _, message }) => {
-                        let elapsed_duration = if let Some(stats) = get_query_stats().await {
-                            stats.elapsed
-                        } else {
-                            Duration::ZERO
-                        };
-                        self.data_table.query_history =
-                            get_history(self.connection_name.clone()).await;
-                        self.data_table
-                            .finish_loading(Vec::new(), Vec::new(), elapsed_duration);
-                        self.data_table.status_message = Some(message);
+                // On a server page, the extra row fetched only tells that more follow.
+                let page = page.map(|page| ServerPage {
+                    has_more: std::mem::take(&mut result_set.truncated),
+                    ..page
+                });
+                self.data_table.finish_loading(result_set, elapsed_duration);
+                self.results_database = self.session_database.clone();
+                let db_type = self.db_type();
+                self.data_table.source_table =
+                    match split_statements(&self.query, db_type).as_slice() {
+                        [statement] => plain_table_select(statement, db_type).map(str::to_string),
+                        _ => None,
+                    };
+                self.data_table.db_type = self.pool.as_ref().map(DbPool::get_type);
+                match page {
+                    Some(page) => {
+                        self.data_table.set_query_status(
+                            &self.query,
+                            elapsed_duration,
+                            format!(
+                                "Fetched rows {}-{} from the server in {} ms.",
+                                page.offset + 1.min(rows),
+                                page.offset + rows,
+                                elapsed_duration.as_millis()
+                            ),
+                        );
+                        self.data_table.server_page = Some(page);
                     }
-                    Err(err) => {
+                    None => {
                         self.data_table
-                            .set_error_state(format!("❌ Error: {}", err));
+                            .set_query_status(&self.query, elapsed_duration, message)
                     }
                 }
-            } else {
-                // Handle the case where the pool is not available (e.g., not connected to a DB)
+            }
+            Ok(ExecutionResult::Affected { rows, .. }) if let Some(edit) = edit => {
+                self.data_table.query_history = get_history(self.connection_name.clone()).await;
+                match edit {
+                    RowEdit::Update(update) => self.data_table.finish_cell_update(&update, rows),
+                    RowEdit::Delete(delete) => self.data_table.finish_row_delete(&delete, rows),
+                    RowEdit::Insert(insert) => self.data_table.finish_row_insert(&insert, rows),
+                }
+            }
+            Ok(ExecutionResult::Affected { message, .. }) => {
+                let elapsed_duration = if let Some(stats) = get_query_stats().await {
+                    stats.elapsed
+                } else {
+                    Duration::ZERO
+                };
+                self.data_table.query_history = get_history(self.connection_name.clone()).await;
                 self.data_table
-                    .set_error_state("Database connection pool not available.".to_string());
+                    .finish_loading(ResultSet::default(), elapsed_duration);
+                self.data_table
+                    .set_query_status(&self.query, elapsed_duration, message);
+            }
+            Err(err) => {
+                self.data_table.query_history = get_history(self.connection_name.clone()).await;
+                self.data_table
+                    .set_query_error(&self.query, err.message.clone());
+                if is_connection_lost(&err.error) {
+                    // The session can't be used again, so the next query opens another, and
+                    // the server is checked now rather than at the next interval.
+                    self.session = None;
+                    self.in_transaction = false;
+                    self.health_checked_at = None;
+                }
+                // EXPLAIN, server pages and row edits run SQL rewritten from or unrelated
+                // to the editor text, so their positions don't match it.
+                let rewritten = explain || page.is_some() || edit.is_some();
+                if !rewritten && let Some(position) = err.position() {
+                    self.query_editor
+                        .move_to_offset(self.query_origin, position);
+                }
             }
         }
-        Ok(())
     }
 
-    async fn handle_command(
-        &mut self,
-        command: Command,
-        key_event: KeyEvent,
-        terminal: &mut DefaultTerminal,
-    ) -> Result<()> {
+    async fn handle_command(&mut self, command: Command, key_event: KeyEvent) -> Result<()> {
         match command {
             // Global Commands
             Command::Quit => {
@@ -377,22 +2577,318 @@ impl App<'_> {
             }
             Command::ShowKeyMap => {
                 self.show_key_map = true;
-                self.key_map_scroll = 0; // Reset scroll when showing
+                self.key_map_view.filter.clear();
+                self.popup_scroll = 0; // Reset scroll when showing
             }
             Command::ClosePopup => {
                 self.show_key_map = false;
+                self.cell_inspect = None;
+                self.json_tree = None;
+                self.pending_query = None;
+                self.pending_delete = None;
+                self.snippet_picker = None;
+                self.connection_switcher = None;
+                self.password_prompt = None;
+                self.cell_edit = None;
+                self.insert_form = None;
+                self.command_palette = None;
+            }
+            Command::CopyPopupContent => {
+                if let Some((_, content)) = &self.cell_inspect {
+                    let message = copy_text(content)
+                        .map_or_else(|message| message, |()| "Copied to clipboard.".to_string());
+                    self.data_table.show_toast(message);
+                }
+            }
+            Command::JsonTreeUp => {
+                if let Some(tree) = &mut self.json_tree {
+                    tree.state.key_up();
+                }
+            }
+            Command::JsonTreeDown => {
+                if let Some(tree) = &mut self.json_tree {
+                    tree.state.key_down();
+                }
+            }
+            Command::JsonTreeCollapse => {
+                if let Some(tree) = &mut self.json_tree {
+                    tree.state.key_left();
+                }
+            }
+            Command::JsonTreeExpand => {
+                if let Some(tree) = &mut self.json_tree {
+                    tree.state.key_right();
+                }
+            }
+            Command::JsonTreeToggle => {
+                if let Some(tree) = &mut self.json_tree {
+                    tree.state.toggle_selected();
+                }
             }
             Command::KeyMapScrollUp => {
-                self.key_map_scroll = self.key_map_scroll.saturating_sub(1);
+                self.popup_scroll = self.popup_scroll.saturating_sub(1);
             }
             Command::KeyMapScrollDown => {
-                self.key_map_scroll = self.key_map_scroll.saturating_add(1);
+                self.popup_scroll = self.popup_scroll.saturating_add(1);
+            }
+            Command::KeyMapFilter(filter) => {
+                self.key_map_view.filter = filter;
+                self.popup_scroll = 0;
             }
+            Command::KeyMapNextCategory => self.key_map_view.next_category(),
+            Command::KeyMapPreviousCategory => self.key_map_view.previous_category(),
+            Command::KeyMapToggleCategory => self.key_map_view.toggle_selected_category(),
             Command::ToggleFocus => {
                 self.toggle_focus();
             }
+            Command::FocusPane(focus) => {
+                self.set_focus(focus);
+            }
             Command::ExecuteQuery => {
-                self.execute_current_query(terminal).await?;
+                self.execute_current_query();
+            }
+            Command::CancelQuery => {
+                self.cancel_running_query();
+            }
+            Command::CommitTransaction => {
+                self.end_transaction("COMMIT");
+            }
+            Command::RollbackTransaction => {
+                self.end_transaction("ROLLBACK");
+            }
+            Command::ConfirmQuery => {
+                if let Some((query, explain)) = self.pending_query.take() {
+                    self.spawn_query(query, explain);
+                } else if let Some(delete) = self.pending_delete.take() {
+                    let database = self.results_database.clone();
+                    self.spawn_row_edit(RowEdit::Delete(delete), database);
+                }
+            }
+            Command::OpenConnectionSwitcher => {
+                self.open_connection_switcher();
+            }
+            Command::ShowConnectionInfo => {
+                self.show_connection_info();
+            }
+            Command::ConnectionSwitcherNext | Command::ConnectionSwitcherPrevious => {
+                if let Some(selected) = self.connection_switcher
+                    && !self.connections.is_empty()
+                {
+                    let len = self.connections.len();
+                    let next = if command == Command::ConnectionSwitcherNext {
+                        (selected + 1) % len
+                    } else {
+                        (selected + len - 1) % len
+                    };
+                    self.connection_switcher = Some(next);
+                    self.popup_scroll = next.saturating_sub(10) as u16;
+                }
+            }
+            Command::ConnectionSwitcherSelect => {
+                if let Some(connection) = self
+                    .connection_switcher
+                    .and_then(|i| self.connections.get(i))
+                    .cloned()
+                {
+                    self.connection_switcher = None;
+                    // The password typed at startup is only kept on the current connection.
+                    let connection = match &self.current_connection {
+                        Some(current)
+                            if current.name == connection.name && connection.password.is_none() =>
+                        {
+                            current.clone()
+                        }
+                        _ => connection,
+                    };
+                    if connection.password.is_none() {
+                        self.password_prompt = Some((connection, String::new()));
+                    } else {
+                        self.switch_connection(connection);
+                    }
+                }
+            }
+            Command::PasswordPromptInput(c) => {
+                if let Some((_, password)) = &mut self.password_prompt {
+                    password.push(c);
+                }
+            }
+            Command::PasswordPromptBackspace => {
+                if let Some((_, password)) = &mut self.password_prompt {
+                    password.pop();
+                }
+            }
+            Command::PasswordPromptSubmit => {
+                if let Some((mut connection, password)) = self.password_prompt.take() {
+                    connection.password = Some(password);
+                    self.switch_connection(connection);
+                }
+            }
+            Command::DataTableEditCell => self.start_row_action(RowAction::EditCell),
+            Command::DataTableDeleteRow => self.start_row_action(RowAction::DeleteRow),
+            Command::CellEditInput(c) => {
+                if let Some(edit) = &mut self.cell_edit {
+                    edit.value.get_or_insert_with(String::new).push(c);
+                }
+            }
+            Command::CellEditBackspace => {
+                if let Some(edit) = &mut self.cell_edit
+                    && let Some(value) = &mut edit.value
+                {
+                    value.pop();
+                }
+            }
+            Command::CellEditToggleNull => {
+                if let Some(edit) = &mut self.cell_edit {
+                    edit.value = match edit.value {
+                        Some(_) => None,
+                        None => Some(String::new()),
+                    };
+                }
+            }
+            Command::CellEditSubmit => {
+                if let Some(edit) = self.cell_edit.take() {
+                    let original = (!edit.original.is_null()).then(|| edit.original.to_string());
+                    if original == edit.value {
+                        self.data_table.set_status("Nothing changed.".to_string());
+                    } else {
+                        let database = self.results_database.clone();
+                        self.spawn_row_edit(RowEdit::Update(edit), database);
+                    }
+                }
+            }
+            Command::InsertFormInput(c) => {
+                if let Some(form) = &mut self.insert_form {
+                    form.input(c);
+                }
+            }
+            Command::InsertFormBackspace => {
+                if let Some(form) = &mut self.insert_form {
+                    form.backspace();
+                }
+            }
+            Command::InsertFormNextField => {
+                if let Some(form) = &mut self.insert_form {
+                    form.next();
+                }
+            }
+            Command::InsertFormPreviousField => {
+                if let Some(form) = &mut self.insert_form {
+                    form.previous();
+                }
+            }
+            Command::InsertFormToggleNull => {
+                if let Some(form) = &mut self.insert_form {
+                    form.toggle_null();
+                }
+            }
+            Command::InsertFormSubmit => {
+                if let Some(form) = &mut self.insert_form {
+                    match form.submit() {
+                        Ok(insert) => {
+                            self.insert_form = None;
+                            let database = self.insert_database.take();
+                            self.spawn_row_edit(RowEdit::Insert(insert), database);
+                        }
+                        Err(problem) => form.error = Some(problem),
+                    }
+                }
+            }
+            Command::OpenCommandPalette => {
+                self.command_palette = Some(CommandPalette::default());
+            }
+            Command::PaletteInput(c) => {
+                if let Some(palette) = &mut self.command_palette {
+                    palette.input(c);
+                }
+            }
+            Command::PaletteBackspace => {
+                if let Some(palette) = &mut self.command_palette {
+                    palette.backspace();
+                }
+            }
+            Command::PaletteNext => {
+                if let Some(palette) = &mut self.command_palette {
+                    palette.next();
+                }
+            }
+            Command::PalettePrevious => {
+                if let Some(palette) = &mut self.command_palette {
+                    palette.previous();
+                }
+            }
+            // Key handling runs the selected command in its place; nothing is left to do.
+            Command::PaletteSubmit => {
+                self.command_palette = None;
+            }
+            Command::OpenSnippetPicker => {
+                self.snippets = load_snippets();
+                self.snippet_picker = Some(0);
+                self.popup_scroll = 0;
+            }
+            Command::SnippetPickerNext | Command::SnippetPickerPrevious => {
+                if let Some(selected) = self.snippet_picker
+                    && !self.snippets.is_empty()
+                {
+                    let len = self.snippets.len();
+                    let next = if command == Command::SnippetPickerNext {
+                        (selected + 1) % len
+                    } else {
+                        (selected + len - 1) % len
+                    };
+                    self.snippet_picker = Some(next);
+                    self.popup_scroll = next.saturating_sub(10) as u16;
+                }
+            }
+            Command::SnippetPickerLoad => {
+                if let Some(snippet) = self.snippet_picker.and_then(|i| self.snippets.get(i)) {
+                    self.query_editor.set_textarea_content(
+                        snippet.sql.clone(),
+                        &self.focus,
+                        self.connection_name.clone(),
+                    );
+                    self.data_table
+                        .set_status(format!("Loaded snippet \"{}\".", snippet.name));
+                    self.snippet_picker = None;
+                }
+            }
+            Command::SnippetPickerDelete => {
+                if let Some(selected) = self.snippet_picker
+                    && selected < self.snippets.len()
+                {
+                    let removed = self.snippets.remove(selected);
+                    self.snippet_picker = Some(selected.min(self.snippets.len().saturating_sub(1)));
+                    self.data_table
+                        .set_status(match save_snippets(&self.snippets) {
+                            Ok(()) => format!("Deleted snippet \"{}\".", removed.name),
+                            Err(e) => format!("Failed to save snippets: {}", e),
+                        });
+                }
+            }
+            Command::SaveSnippet(name) => {
+                let sql = self.current_query();
+                if sql.trim().is_empty() {
+                    self.data_table
+                        .set_status("Nothing to save: the editor is empty.".to_string());
+                } else {
+                    let mut snippets = load_snippets();
+                    upsert_snippet(
+                        &mut snippets,
+                        Snippet {
+                            name: name.clone(),
+                            sql,
+                        },
+                    );
+                    self.data_table.set_status(match save_snippets(&snippets) {
+                        Ok(()) => format!("Saved snippet \"{}\".", name),
+                        Err(e) => format!("Failed to save snippet: {}", e),
+                    });
+                }
+            }
+            Command::ExplainQuery => {
+                self.explain_current_query(false);
+            }
+            Command::ExplainAnalyzeQuery => {
+                self.explain_current_query(true);
             }
 
             Command::DataTablePreviousTab
@@ -401,23 +2897,82 @@ impl App<'_> {
             | Command::DataTablePreviousRow
             | Command::DataTableNextHistoryRow
             | Command::DataTablePreviousHistoryRow
+            | Command::DataTableMessagesScrollDown
+            | Command::DataTableMessagesScrollUp
             | Command::DataTableScrollRight
             | Command::DataTableScrollLeft
             | Command::DataTableNextColor
             | Command::DataTablePreviousColor
-            | Command::DataTableNextPage
-            | Command::DataTablePreviousPage
             | Command::DataTableJumpToFirstRow
             | Command::DataTableJumpToLastRow
             | Command::DataTableNextColumn
             | Command::DataTablePreviousColumn
-            | Command::DataTableAdjustColumnWidthIncrease
-            | Command::DataTableAdjustColumnWidthDecrease
+            | Command::DataTableAutoFitColumns
             | Command::DataTableCopySelectedCell
             | Command::DataTableCopySelectedRow
-            | Command::DataTableCopyQueryToEditor => {
+            | Command::DataTableCopyColumn
+            | Command::DataTableCopyAll
+            | Command::DataTableCopyAllAsInserts
+            | Command::DataTableCycleCopyFormat
+            | Command::DataTableCopyQueryToEditor
+            | Command::DataTableSearch(_)
+            | Command::DataTableFilterHistory(_)
+            | Command::DataTableSearchSubmit
+            | Command::DataTableSearchCancel
+            | Command::DataTableSearchNext
+            | Command::DataTableSearchPrevious
+            | Command::DataTableExportJson(_)
+            | Command::DataTableToggleColumnInfo
+            | Command::DataTableToggleRowDetail
+            | Command::DataTableToggleTimings
+            | Command::DataTableRowDetailScrollDown
+            | Command::DataTableRowDetailScrollUp
+            | Command::DataTableSortAscending
+            | Command::DataTableSortDescending
+            | Command::DataTableClick(_) => {
                 self.data_table.handle_command(command);
             }
+            Command::DataTableNextPage | Command::DataTablePreviousPage
+                if self.data_table.server_page.is_some() =>
+            {
+                self.turn_server_page(command == Command::DataTableNextPage);
+            }
+            Command::DataTableNextPage
+            | Command::DataTablePreviousPage
+            | Command::DataTableSetPageSize(_)
+            | Command::DataTableIncreasePageSize
+            | Command::DataTableDecreasePageSize => {
+                let page_size = self.data_table.page_size();
+                self.data_table.handle_command(command);
+                // A server page is fetched again at the new size, from the page holding
+                // its first row.
+                let new_size = self.data_table.page_size();
+                if new_size != page_size
+                    && let Some(page) = self.data_table.server_page.clone()
+                {
+                    self.spawn_page(page.query, page.offset / new_size * new_size);
+                }
+            }
+            Command::DataTableAdjustColumnWidthIncrease
+            | Command::DataTableAdjustColumnWidthDecrease
+            | Command::DataTableResetColumnWidths => {
+                self.data_table.handle_command(command);
+                if let Some(connection_name) = self.connection_name.clone() {
+                    self.column_widths
+                        .insert(connection_name, self.data_table.saved_column_widths.clone());
+                    if let Err(e) = save_column_widths(&self.column_widths) {
+                        self.data_table
+                            .set_status(format!("Failed to save column widths: {}", e));
+                    }
+                }
+            }
+            Command::DataTableInspectCell => {
+                if let Some((column, content)) = self.data_table.selected_cell_preview() {
+                    self.json_tree = JsonTree::parse(column.clone(), &content);
+                    self.cell_inspect = Some((column, content));
+                    self.popup_scroll = 0;
+                }
+            }
             Command::DataTableRunSelectedHistoryQuery => {
                 if let Some(query) = self.data_table.get_selected_history_query() {
                     self.query_editor.set_textarea_content(
@@ -425,7 +2980,7 @@ impl App<'_> {
                         &self.focus,
                         self.connection_name.clone(),
                     );
-                    self.execute_current_query(terminal).await?;
+                    self.execute_current_query();
                 }
             }
             Command::DataTableSetTabIndex(idx) => {
@@ -434,57 +2989,50 @@ impl App<'_> {
                 }
             }
 
-            Command::SidebarToggleSelected => {
-                if let Some(identifier) = self.sidebar.handle_command(command) {
-                    if identifier.starts_with("db_") {
-                        let db_name = identifier.strip_prefix("db_").unwrap().to_string();
-                        if let Some(db) = self.databases.iter_mut().find(|db| db.name == db_name)
-                            && db.tables.is_empty() {
-                                // Only fetch if not already fetched
-                                if let Some(connection) = &self.current_connection {
-                                    let details = ConnectionDetails {
-                                        host: Some(connection.host.clone()),
-                                        user: Some(connection.user.clone()),
-                                        password: connection.password.clone(),
-                                        database: Some(db_name.clone()),
-                                    };
-                                    let pool =
-                                        pool(connection.db_type, &details, Some(&db_name)).await?;
-                                    self.pool = Some(pool.clone());
-                                    let tables = fetch_tables(&pool).await?;
-                                    db.tables = tables;
-                                    let items = metadata_to_tree_items(&self.databases);
-                                    self.sidebar.update_items(items);
-                                }
-                            }
-                    } else if identifier.starts_with("tbl_") {
-                        let parts: Vec<&str> = identifier.split('_').collect();
-                        let db_name = parts[1].to_string();
-                        let table_name = parts[2].to_string();
-
-                        let cache_key = format!("{}/{}", db_name, table_name);
-
-                        if let Some(metadata) = self.table_details_cache.get(&cache_key) {
-                            if let Some(db) =
-                                self.databases.iter_mut().find(|db| db.name == db_name)
-                                && let Some(table) =
-                                    db.tables.iter_mut().find(|t| t.name == table_name)
-                                {
-                                    table.metadata = Some(metadata.clone());
-                                }
-                        } else if let Some(pool) = &self.pool {
-                            let metadata = fetch_table_details(pool, &table_name).await?;
-                            self.table_details_cache.insert(cache_key, metadata.clone());
-                            if let Some(db) =
-                                self.databases.iter_mut().find(|db| db.name == db_name)
-                                && let Some(table) =
-                                    db.tables.iter_mut().find(|t| t.name == table_name)
-                                {
-                                    table.metadata = Some(metadata);
-                                }
+            Command::SidebarToggleSelected | Command::SidebarClick(_) => {
+                if let Some(path) = self.sidebar.handle_command(command)
+                    && let Some(db_name) = path.first().and_then(|id| id.strip_prefix("db_"))
+                {
+                    let db_name = db_name.to_string();
+                    // Editor queries run on the database last toggled in the sidebar.
+                    self.database = Some(db_name.clone());
+                    match path.last() {
+                        Some(id) if id.starts_with("tbl_") => {
+                            let table_id = id.clone();
+                            self.load_table_details(db_name, table_id);
                         }
-                        let items = metadata_to_tree_items(&self.databases);
-                        self.sidebar.update_items(items);
+                        _ if path.len() == 1 => self.load_tables(db_name),
+                        _ => {}
+                    }
+                }
+            }
+
+            Command::SidebarCountRows => {
+                if let Some((db_name, table_id)) = self.selected_table_node() {
+                    self.count_table_rows(db_name, table_id);
+                }
+            }
+            Command::SidebarInsertRow => {
+                if let Some((db_name, table_id)) = self.selected_table_node() {
+                    self.start_row_insert(&db_name, &table_id);
+                }
+            }
+            Command::SidebarShowDdl => {
+                if let Some((db_name, table_id)) = self.selected_table_node() {
+                    self.show_table_ddl(&db_name, &table_id);
+                }
+            }
+            Command::SidebarRefresh => {
+                self.refresh_selected_database();
+            }
+            Command::SidebarFilter(filter) => {
+                self.sidebar.filter = filter;
+                self.refresh_sidebar();
+                if !self.sidebar.filter.is_empty() {
+                    self.sidebar.open_all_groups();
+                    // The previous selection may be hidden now.
+                    if let Some(first) = self.sidebar.items.first() {
+                        self.sidebar.state.select(vec![first.identifier().clone()]);
                     }
                 }
             }
@@ -509,17 +3057,72 @@ impl App<'_> {
             | Command::EditorDeleteLineByEnd
             | Command::EditorCancelSelection
             | Command::EditorPaste
+            | Command::EditorPasteSystem
             | Command::EditorUndo
             | Command::EditorRedo
             | Command::EditorDeleteNextChar
+            | Command::EditorDeleteWordBack
             | Command::EditorSetMode(_)
             | Command::EditorScrollRelative(_, _)
             | Command::EditorScroll(_)
             | Command::EditorStartSelection
-            | Command::EditorCopySelection
+            | Command::EditorRepeat(_, _)
+            | Command::EditorFindChar(_, _)
+            | Command::EditorJumpToMatchingBracket
+            | Command::EditorCompletionNext
+            | Command::EditorCompletionPrevious
+            | Command::EditorCompletionAccept
+            | Command::EditorCompletionCancel => {
+                self.query_editor.handle_command(command, key_event);
+            }
+            Command::EditorFormatSql => {
+                let sql = self.query_editor.textarea_content();
+                if !sql.trim().is_empty() {
+                    self.query_editor.set_textarea_content(
+                        format_sql(&sql),
+                        &self.focus,
+                        self.connection_name.clone(),
+                    );
+                }
+            }
+            Command::EditorToggleLineNumbers => {
+                self.query_editor.line_numbers = !self.query_editor.line_numbers;
+                if let Err(e) = save_line_numbers(self.query_editor.line_numbers) {
+                    self.data_table
+                        .set_status(format!("Failed to save line numbers setting: {}", e));
+                }
+            }
+            Command::ToggleZoom => {
+                self.zoomed = !self.zoomed;
+            }
+            Command::ResizeSidebar(delta) => {
+                self.pane_layout.resize_sidebar(delta);
+                self.save_pane_layout();
+            }
+            Command::ResizeEditor(delta) => {
+                self.pane_layout.resize_editor(delta);
+                self.save_pane_layout();
+            }
+            Command::EditorComplete => {
+                let words = self.schema_words();
+                self.query_editor.open_completion(&words);
+            }
+            Command::EditorCopySelection
             | Command::EditorCutSelection
-            | Command::EditorPerformPendingOperator => {
+            | Command::EditorApplyOperator(_, _, _)
+            | Command::EditorApplyLineOperator(_, _) => {
+                let yank = matches!(
+                    command,
+                    Command::EditorCopySelection
+                        | Command::EditorApplyOperator('y', _, _)
+                        | Command::EditorApplyLineOperator('y', _)
+                );
                 self.query_editor.handle_command(command, key_event);
+                // Mirror a yank to the system clipboard so it can be pasted elsewhere. Text
+                // that is deleted or changed only goes to the editor's register.
+                if yank && let Err(message) = copy_text(&self.query_editor.yank_text()) {
+                    self.data_table.show_toast(message);
+                }
             }
             Command::NoOp => { /* No operation, do nothing */ }
         }
@@ -532,70 +3135,291 @@ impl App<'_> {
             .constraints([Constraint::Min(0), Constraint::Length(2)])
             .split(f.area());
 
-        let top_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
-            .split(outer_chunks[0]);
+        if self.zoomed {
+            let area = outer_chunks[0];
+            self.pane_areas = vec![(self.focus.clone(), area)];
+            match self.focus {
+                Focus::Sidebar => self.sidebar.render(f, area),
+                Focus::Editor => self.query_editor.draw(
+                    f,
+                    area,
+                    self.focus.clone(),
+                    self.connection_name.clone(),
+                ),
+                Focus::Table => self.data_table.draw(f, area, &self.focus.clone()),
+            }
+        } else {
+            let top_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(self.pane_layout.sidebar_percent),
+                    Constraint::Percentage(100 - self.pane_layout.sidebar_percent),
+                ])
+                .split(outer_chunks[0]);
+
+            self.sidebar.render(f, top_chunks[0]);
 
-        self.sidebar.render(f, top_chunks[0]);
+            let right_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Percentage(self.pane_layout.editor_percent),
+                    Constraint::Percentage(100 - self.pane_layout.editor_percent),
+                ])
+                .split(top_chunks[1]);
+            self.pane_areas = vec![
+                (Focus::Sidebar, top_chunks[0]),
+                (Focus::Editor, right_chunks[0]),
+                (Focus::Table, right_chunks[1]),
+            ];
 
-        let right_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(top_chunks[1]);
-
-        self.query_editor.draw(
-            f,
-            right_chunks[0],
-            self.focus.clone(),
-            self.connection_name.clone(),
-        );
+            self.query_editor.draw(
+                f,
+                right_chunks[0],
+                self.focus.clone(),
+                self.connection_name.clone(),
+            );
 
-        self.data_table
-            .draw(f, right_chunks[1], &self.focus.clone());
+            self.data_table
+                .draw(f, right_chunks[1], &self.focus.clone());
+        }
 
         let focus_text = Line::from(vec![
             /* Span::styled(
                 format!(" Focus: {} ", self.focus.as_str()),
                 Style::default()
-                    .bg(COLOR_HIGHLIGHT_BG)
-                    .fg(COLOR_BLACK)
+                    .bg(theme::current().selection_bg)
+                    .fg(theme::current().selection_fg)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(" (Tab to change) "), */
             Span::styled(
                 " q: Quit ",
-                Style::default().bg(COLOR_UNFOCUSED).fg(COLOR_WHITE),
+                Style::default()
+                    .bg(theme::current().border_unfocused)
+                    .fg(theme::current().text),
             ),
             Span::styled(
                 " F5: Execute Query ",
-                Style::default().bg(COLOR_UNFOCUSED).fg(COLOR_WHITE),
+                Style::default()
+                    .bg(theme::current().border_unfocused)
+                    .fg(theme::current().text),
             ),
             Span::styled(
                 " ?: Key Maps ",
-                Style::default().bg(COLOR_UNFOCUSED).fg(COLOR_WHITE),
+                Style::default()
+                    .bg(theme::current().border_unfocused)
+                    .fg(theme::current().text),
             ),
         ]);
 
+        let mut focus_text = focus_text;
+        if self.pool.is_some() {
+            let (indicator, color) = match self.health {
+                ConnectionHealth::Connected => ("● connected".to_string(), tailwind::GREEN.c400),
+                ConnectionHealth::Lost => ("● disconnected".to_string(), tailwind::RED.c400),
+                ConnectionHealth::Reconnecting { since } => {
+                    let frame = (since.elapsed().as_millis() / 100) as usize % FRAMES.len();
+                    (
+                        format!("{} reconnecting", FRAMES[frame]),
+                        tailwind::YELLOW.c300,
+                    )
+                }
+            };
+            focus_text.spans.insert(
+                0,
+                Span::styled(
+                    format!(" {} ", indicator),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+            );
+        }
+        if self.in_transaction {
+            focus_text.spans.insert(
+                0,
+                Span::styled(
+                    " IN TRANSACTION: F10 commit, F12 roll back ",
+                    Style::default()
+                        .bg(theme::current().selection_bg)
+                        .fg(theme::current().selection_fg)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+        }
+
+        let focus_text = match (&self.snippet_name_input, &self.password_prompt) {
+            (Some(name), _) => Line::from(vec![
+                Span::styled(
+                    " Snippet name: ",
+                    Style::default().fg(theme::current().border_focused),
+                ),
+                Span::raw(name.clone()),
+                Span::styled("█", Style::default().fg(theme::current().border_focused)),
+            ]),
+            (None, Some((connection, password))) => Line::from(vec![
+                Span::styled(
+                    format!(" Password for {}: ", connection.name),
+                    Style::default().fg(theme::current().border_focused),
+                ),
+                Span::raw("*".repeat(password.chars().count())),
+                Span::styled("█", Style::default().fg(theme::current().border_focused)),
+            ]),
+            (None, None) => focus_text,
+        };
+
         let status_block = Paragraph::new(focus_text)
             .block(Block::default().borders(Borders::TOP))
-            .style(Style::default().fg(COLOR_WHITE).bg(Color::Black));
+            .style(
+                Style::default()
+                    .fg(theme::current().text)
+                    .bg(theme::current().background),
+            );
 
         f.render_widget(status_block, outer_chunks[1]);
 
-        if self.show_key_map {
+        if let Some((query, _)) = &self.pending_query {
+            let content = format!(
+                "This will run a statement that can drop or overwrite data:\n\n{}\n\nRun it anyway? (y/n)",
+                query
+            );
+            let popup = Popup::new(
+                "Confirm",
+                content.as_str().into(),
+                self.popup_scroll,
+                &mut self.popup_scroll_state,
+            )
+            .wrap(true);
+            f.render_widget(popup, f.area());
+        } else if let Some(delete) = &self.pending_delete {
+            let content = format!(
+                "Delete the {} row where {}?\n\nThis can't be undone outside a transaction. (y/n)",
+                delete.table,
+                delete.describe_row()
+            );
+            let popup = Popup::new(
+                "Delete row",
+                content.as_str().into(),
+                self.popup_scroll,
+                &mut self.popup_scroll_state,
+            )
+            .wrap(true);
+            f.render_widget(popup, f.area());
+        } else if let Some(form) = &self.insert_form {
+            f.render_widget(form, f.area());
+        } else if let Some(edit) = &self.cell_edit {
+            let title = format!("Edit {}.{}", edit.table, edit.column);
+            let content = format!(
+                "{}\n\nRow: {}\n\nEnter runs the UPDATE, Ctrl+N switches between NULL and a value, Esc cancels.",
+                match &edit.value {
+                    Some(value) => format!("{}█", value),
+                    None => "NULL".to_string(),
+                },
+                edit.describe_row()
+            );
+            let popup = Popup::new(
+                &title,
+                content.as_str().into(),
+                self.popup_scroll,
+                &mut self.popup_scroll_state,
+            )
+            .wrap(true);
+            f.render_widget(popup, f.area());
+        } else if let Some(selected) = self.connection_switcher {
+            let content = connection_switcher_text(
+                &self.connections,
+                self.connection_name.as_deref(),
+                selected,
+            );
+            let popup = Popup::new(
+                "Connections",
+                content,
+                self.popup_scroll,
+                &mut self.popup_scroll_state,
+            );
+            f.render_widget(popup, f.area());
+        } else if let Some(selected) = self.snippet_picker {
+            let content = snippet_picker_text(&self.snippets, selected);
+            let popup = Popup::new(
+                "Snippets",
+                content,
+                self.popup_scroll,
+                &mut self.popup_scroll_state,
+            );
+            f.render_widget(popup, f.area());
+        } else if let Some(palette) = &self.command_palette {
+            f.render_widget(palette, f.area());
+        } else if self.show_key_map {
+            let title = self.key_map_view.title();
             let popup = Popup::new(
-                "Key Maps",
-                get_key_map_guide(),
-                self.key_map_scroll,
-                &mut self.key_map_scroll_state,
+                &title,
+                get_key_map_guide(&self.key_map_view, self.key_mapper.bindings()),
+                self.popup_scroll,
+                &mut self.popup_scroll_state,
             );
             f.render_widget(popup, f.area());
+        } else if let Some(tree) = &mut self.json_tree {
+            tree.render(f, f.area());
+        } else if let Some((column, content)) = &self.cell_inspect {
+            let popup = Popup::new(
+                column,
+                content.as_str().into(),
+                self.popup_scroll,
+                &mut self.popup_scroll_state,
+            )
+            .wrap(true);
+            f.render_widget(popup, f.area());
+        }
+    }
+
+    fn save_pane_layout(&mut self) {
+        if let Err(e) = save_pane_layout(self.pane_layout) {
+            self.data_table
+                .set_status(format!("Failed to save pane layout: {}", e));
         }
     }
 
     fn toggle_focus(&mut self) {
-        self.focus = self.focus.clone().next();
+        self.set_focus(self.focus.clone().next());
+    }
+
+    fn set_focus(&mut self, focus: Focus) {
+        self.focus = focus;
         self.sidebar.update_focus(self.focus.clone());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crud::result_set::CellValue;
+
+    #[tokio::test]
+    async fn test_rows_are_changed_in_the_table_of_the_shown_results() {
+        let mut app = App::default();
+        app.query = "SELECT * FROM users".to_string();
+        app.data_table.finish_loading(
+            ResultSet::new(
+                vec!["id".to_string(), "name".to_string()],
+                vec![vec![
+                    CellValue::Text("1".to_string()),
+                    CellValue::Text("ann".to_string()),
+                ]],
+            ),
+            Duration::ZERO,
+        );
+        app.data_table.source_table = Some("users".to_string());
+        app.data_table.search_in_table("ann");
+
+        // A query of another table that is cancelled leaves the results shown.
+        app.query = "SELECT * FROM orders".to_string();
+        app.data_table.start_loading();
+        app.query_task = Some(tokio::spawn(std::future::pending()));
+        app.cancel_running_query();
+        assert_eq!(app.row_action_target(), Some(("users".to_string(), (0, 1))));
+
+        app.query = "SELECT * FROM orders WHERE".to_string();
+        app.data_table.start_loading();
+        app.data_table
+            .set_query_error(&app.query, "syntax error".to_string());
+        assert_eq!(app.row_action_target(), Some(("users".to_string(), (0, 1))));
+    }
+}
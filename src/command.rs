@@ -1,15 +1,67 @@
-use crate::layout::query_editor::Mode;
+use crate::app::Focus;
+use crate::layout::query_editor::{CharFind, Mode, Motion};
+use ratatui::layout::Position;
 use tui_textarea::{CursorMove, Scrolling};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Command {
     Quit,
     ToggleFocus,
+    /// Moves focus straight to the given pane.
+    FocusPane(Focus),
+    /// Shows only the focused pane, or all panes again.
+    ToggleZoom,
+    /// Widens the sidebar by the given percentage of the screen, or narrows it if negative.
+    ResizeSidebar(i16),
+    /// Makes the editor taller by the given percentage, taking the space from the table.
+    ResizeEditor(i16),
     ExecuteQuery,
+    CancelQuery,
+    /// Commits the transaction opened with `BEGIN`.
+    CommitTransaction,
+    RollbackTransaction,
+    ExplainQuery,
+    ExplainAnalyzeQuery,
+    ConfirmQuery,
+    OpenSnippetPicker,
+    SnippetPickerNext,
+    SnippetPickerPrevious,
+    SnippetPickerLoad,
+    SnippetPickerDelete,
+    SaveSnippet(String),
+    OpenConnectionSwitcher,
+    /// Shows the current connection and the version of the server it is on.
+    ShowConnectionInfo,
+    ConnectionSwitcherNext,
+    ConnectionSwitcherPrevious,
+    /// Connects to the selected saved connection, asking for its password if none is saved.
+    ConnectionSwitcherSelect,
+    PasswordPromptInput(char),
+    PasswordPromptBackspace,
+    PasswordPromptSubmit,
+    OpenCommandPalette,
+    PaletteInput(char),
+    PaletteBackspace,
+    PaletteNext,
+    PalettePrevious,
+    /// Closes the palette and runs the selected command in its place.
+    PaletteSubmit,
     ShowKeyMap,
     ClosePopup,
+    CopyPopupContent,
     KeyMapScrollUp,
     KeyMapScrollDown,
+    JsonTreeUp,
+    JsonTreeDown,
+    JsonTreeCollapse,
+    JsonTreeExpand,
+    JsonTreeToggle,
+    /// Narrows the key map to bindings whose keys or description contain the text.
+    KeyMapFilter(String),
+    KeyMapNextCategory,
+    KeyMapPreviousCategory,
+    /// Collapses or expands the selected key map category.
+    KeyMapToggleCategory,
 
     DataTablePreviousTab,
     DataTableNextTab,
@@ -17,6 +69,8 @@ pub enum Command {
     DataTablePreviousRow,
     DataTableNextHistoryRow,
     DataTablePreviousHistoryRow,
+    DataTableMessagesScrollDown,
+    DataTableMessagesScrollUp,
     DataTableScrollRight,
     DataTableScrollLeft,
     DataTableNextColor,
@@ -29,11 +83,60 @@ pub enum Command {
     DataTablePreviousColumn,
     DataTableAdjustColumnWidthIncrease,
     DataTableAdjustColumnWidthDecrease,
+    DataTableAutoFitColumns,
+    DataTableResetColumnWidths,
     DataTableCopySelectedCell,
+    DataTableInspectCell,
     DataTableCopySelectedRow,
+    /// Copies every value of the selected column, one per line.
+    DataTableCopyColumn,
+    /// Copies all results, headers included, as tab-separated values.
+    DataTableCopyAll,
+    /// Copies all results as an `INSERT` statement per row into their source table.
+    DataTableCopyAllAsInserts,
+    /// Switches the format rows are copied in: JSON, CSV, TSV or `INSERT` statements.
+    DataTableCycleCopyFormat,
     DataTableCopyQueryToEditor,
     DataTableRunSelectedHistoryQuery,
     DataTableSetTabIndex(usize),
+    /// Selects the cell, or sorts by the header, under the mouse.
+    DataTableClick(Position),
+    DataTableSearch(String),
+    DataTableFilterHistory(String),
+    DataTableSearchSubmit,
+    DataTableSearchCancel,
+    DataTableSearchNext,
+    DataTableSearchPrevious,
+    DataTableExportJson(String),
+    DataTableToggleColumnInfo,
+    DataTableToggleRowDetail,
+    /// Shows or hides how long the last results took to fetch and to lay out.
+    DataTableToggleTimings,
+    DataTableRowDetailScrollDown,
+    DataTableRowDetailScrollUp,
+    DataTableSortAscending,
+    DataTableSortDescending,
+    DataTableSetPageSize(usize),
+    DataTableIncreasePageSize,
+    DataTableDecreasePageSize,
+    /// Opens an edit of the selected cell, written back with an `UPDATE` by primary key.
+    DataTableEditCell,
+    CellEditInput(char),
+    CellEditBackspace,
+    /// Switches the edited value between `NULL` and a typed value.
+    CellEditToggleNull,
+    /// Writes the edited value back to the table.
+    CellEditSubmit,
+    /// Asks to delete the selected row, removed with a `DELETE` by primary key.
+    DataTableDeleteRow,
+    InsertFormInput(char),
+    InsertFormBackspace,
+    InsertFormNextField,
+    InsertFormPreviousField,
+    /// Switches the selected field between its default, `NULL` and an empty value.
+    InsertFormToggleNull,
+    /// Inserts the row typed into the form.
+    InsertFormSubmit,
 
     SidebarToggleSelected,
     SidebarKeyLeft,
@@ -45,6 +148,16 @@ pub enum Command {
     SidebarSelectLast,
     SidebarScrollDown(u16),
     SidebarScrollUp(u16),
+    /// Selects and toggles the node under the mouse.
+    SidebarClick(Position),
+    SidebarCountRows,
+    SidebarShowDdl,
+    /// Opens a form for inserting a row into the selected table.
+    SidebarInsertRow,
+    /// Reloads the tables of the selected database, dropping their cached details.
+    SidebarRefresh,
+    /// Narrows the tree to databases and tables whose names contain the text.
+    SidebarFilter(String),
 
     EditorInputChar(char),
     EditorInputBackspace,
@@ -54,16 +167,38 @@ pub enum Command {
     EditorDeleteLineByEnd,
     EditorCancelSelection,
     EditorPaste,
+    /// Inserts the system clipboard text, or the editor's own yank if it cannot be read.
+    EditorPasteSystem,
     EditorUndo,
     EditorRedo,
     EditorDeleteNextChar,
+    EditorDeleteWordBack,
     EditorSetMode(Mode),
     EditorScrollRelative(i16, i16),
     EditorScroll(Scrolling),
     EditorStartSelection,
     EditorCopySelection,
     EditorCutSelection,
-    EditorPerformPendingOperator,
+    /// Runs an editor command the given number of times, for a vim count prefix.
+    EditorRepeat(usize, Box<Command>),
+    /// Reformats the whole editor contents with `format_sql`.
+    EditorFormatSql,
+    EditorToggleLineNumbers,
+    /// Opens the completion list for the word before the cursor.
+    EditorComplete,
+    EditorCompletionNext,
+    EditorCompletionPrevious,
+    /// Replaces the word before the cursor with the selected suggestion.
+    EditorCompletionAccept,
+    EditorCompletionCancel,
+    /// Moves to the `count`-th occurrence of a character in the line, as `f`/`t` do.
+    EditorFindChar(CharFind, usize),
+    /// Moves to the bracket matching the one under or after the cursor, as `%` does.
+    EditorJumpToMatchingBracket,
+    /// Applies a vim operator (`d`, `c` or `y`) over a motion repeated `count` times.
+    EditorApplyOperator(char, Motion, usize),
+    /// Applies a vim operator to `count` whole lines, as in `dd` or `3yy`.
+    EditorApplyLineOperator(char, usize),
 
     NoOp,
 }
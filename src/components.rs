@@ -1,2 +1,5 @@
+pub mod command_palette;
+pub mod insert_form;
+pub mod json_tree;
 pub mod popup;
 pub mod tabs;
@@ -0,0 +1,158 @@
+use crate::command::Command;
+use crate::components::popup::centered_rect;
+use crate::key_bindings::{command_by_name, palette_commands};
+use crate::style::theme;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph, StatefulWidget, Widget},
+};
+
+/// A popup listing every bindable command, narrowed by fuzzy matching what is typed.
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub query: String,
+    /// Index into the current matches.
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    /// Names and descriptions of the commands matching the query, best match first.
+    pub fn matches(&self) -> Vec<(&'static str, &'static str)> {
+        let mut scored = palette_commands()
+            .into_iter()
+            .filter_map(|(name, description)| {
+                let score =
+                    fuzzy_score(&self.query, description).max(fuzzy_score(&self.query, name))?;
+                Some((score, (name, description)))
+            })
+            .collect::<Vec<_>>();
+        // Stable, so equally good matches keep the order of the list.
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    pub fn input(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn next(&mut self) {
+        let count = self.matches().len();
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let count = self.matches().len();
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+
+    pub fn selected_command(&self) -> Option<Command> {
+        let (name, _) = self.matches().get(self.selected).copied()?;
+        command_by_name(name)
+    }
+}
+
+impl Widget for &CommandPalette {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default()
+            .bg(theme::current().background)
+            .fg(theme::current().text);
+        let popup_area = centered_rect(60, 60, area);
+        let block = Block::default()
+            .title("Commands")
+            .borders(Borders::ALL)
+            .style(style);
+        let [input_area, list_area] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+            .areas(block.inner(popup_area));
+
+        Clear.render(popup_area, buf);
+        block.render(popup_area, buf);
+
+        let accent = Style::default().fg(theme::current().border_focused);
+        Paragraph::new(Line::from(vec![
+            Span::styled("> ", accent),
+            Span::raw(self.query.clone()),
+            Span::styled("█", accent),
+        ]))
+        .render(input_area, buf);
+
+        let items = self.matches().into_iter().map(|(name, description)| {
+            Line::from(vec![
+                Span::raw(format!("{}  ", description)),
+                Span::styled(name, Style::default().fg(theme::current().null)),
+            ])
+        });
+        let list = List::new(items).highlight_style(accent.add_modifier(Modifier::BOLD));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(list, list_area, buf, &mut state);
+    }
+}
+
+/// Scores `text` against `query` when every query character appears in it in order,
+/// ignoring case. Runs of consecutive characters and matches at the start of a word
+/// score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut previous_match: Option<usize> = None;
+    let mut chars = text.chars().enumerate();
+    let mut before = ' ';
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        loop {
+            let (index, c) = chars.next()?;
+            let word_start = !before.is_alphanumeric();
+            before = c;
+            if c.to_lowercase().eq(std::iter::once(wanted)) {
+                score += 1;
+                if word_start {
+                    score += 3;
+                }
+                if previous_match.is_some_and(|previous| previous + 1 == index) {
+                    score += 5;
+                }
+                previous_match = Some(index);
+                break;
+            }
+        }
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "Execute query"), Some(0));
+        assert!(fuzzy_score("exq", "Execute query").is_some());
+        assert_eq!(fuzzy_score("yq", "Execute query"), None);
+        assert!(fuzzy_score("exec", "Execute query") > fuzzy_score("exqu", "Execute query"));
+    }
+
+    #[test]
+    fn test_matches_resolve_to_commands() {
+        let mut palette = CommandPalette::default();
+        assert!(
+            palette
+                .matches()
+                .iter()
+                .all(|(name, _)| command_by_name(name).is_some())
+        );
+        for c in "commit".chars() {
+            palette.input(c);
+        }
+        assert_eq!(palette.selected_command(), Some(Command::CommitTransaction));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::components::popup::centered_rect;
+use crate::crud::row_edit::RowInsert;
+use crate::database::fetch::Column;
+use crate::style::theme;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph, StatefulWidget, Widget, Wrap},
+};
+
+/// What a field of the form puts in the new row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// Left out of the `INSERT`, so that the database fills in the column's default.
+    Default,
+    Null,
+    /// The text typed, which may be empty.
+    Text(String),
+}
+
+/// A popup with one input per column of a table, inserted as a new row on submit.
+#[derive(Debug)]
+pub struct InsertForm {
+    /// The table as shown to the user.
+    pub table: String,
+    /// The table name as written in the `INSERT`.
+    target: String,
+    pub fields: Vec<(Column, FieldValue)>,
+    /// Index of the field being typed in.
+    pub selected: usize,
+    /// Why the last submit was refused.
+    pub error: Option<String>,
+}
+
+impl InsertForm {
+    pub fn new(table: String, target: String, columns: Vec<Column>) -> Self {
+        Self {
+            table,
+            target,
+            fields: columns
+                .into_iter()
+                .map(|column| (column, FieldValue::Default))
+                .collect(),
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn input(&mut self, c: char) {
+        if let Some((_, value)) = self.fields.get_mut(self.selected) {
+            match value {
+                FieldValue::Text(text) => text.push(c),
+                _ => *value = FieldValue::Text(c.to_string()),
+            }
+        }
+    }
+
+    /// Deletes the last character typed, going back to the default once none are left.
+    /// An empty value is only had by asking for it with [`Self::toggle_null`].
+    pub fn backspace(&mut self) {
+        if let Some((_, value)) = self.fields.get_mut(self.selected) {
+            if let FieldValue::Text(text) = value
+                && text.pop().is_some()
+                && !text.is_empty()
+            {
+                return;
+            }
+            *value = FieldValue::Default;
+        }
+    }
+
+    /// Cycles the selected field from its default to `NULL` to an empty value and back.
+    pub fn toggle_null(&mut self) {
+        if let Some((_, value)) = self.fields.get_mut(self.selected) {
+            *value = match value {
+                FieldValue::Default => FieldValue::Null,
+                FieldValue::Null => FieldValue::Text(String::new()),
+                FieldValue::Text(_) => FieldValue::Default,
+            };
+        }
+    }
+
+    pub fn next(&mut self) {
+        if !self.fields.is_empty() {
+            self.selected = (self.selected + 1) % self.fields.len();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let count = self.fields.len();
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+
+    /// The row to insert, leaving out fields left to their default so the database fills
+    /// them in. Fails on the first field that can't hold its value, and selects it.
+    pub fn submit(&mut self) -> Result<RowInsert, String> {
+        for (index, (column, value)) in self.fields.iter().enumerate() {
+            let problem = match value {
+                FieldValue::Default => (!column.nullable && column.default.is_none())
+                    .then(|| format!("{} is required.", column.name)),
+                FieldValue::Null => {
+                    (!column.nullable).then(|| format!("{} can't be NULL.", column.name))
+                }
+                FieldValue::Text(text) => check_value(text, &column.data_type)
+                    .err()
+                    .map(|expected| format!("{} expects {}.", column.name, expected)),
+            };
+            if let Some(problem) = problem {
+                self.selected = index;
+                return Err(problem);
+            }
+        }
+        Ok(RowInsert {
+            table: self.target.clone(),
+            values: self
+                .fields
+                .iter()
+                .filter_map(|(column, value)| {
+                    let value = match value {
+                        FieldValue::Default => return None,
+                        FieldValue::Null => None,
+                        FieldValue::Text(text) => Some(text.clone()),
+                    };
+                    Some((column.name.clone(), Some(column.data_type.clone()), value))
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Checks that `value` fits a column of `data_type`, naming what was expected otherwise.
+/// Only numbers and booleans are checked; the database has the last word on the rest.
+fn check_value(value: &str, data_type: &str) -> Result<(), &'static str> {
+    let data_type = data_type.to_lowercase();
+    let base = data_type
+        .split(['(', ' '])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    let (valid, expected) = match base {
+        "int" | "integer" | "smallint" | "bigint" | "tinyint" | "mediumint" | "int2" | "int4"
+        | "int8" | "serial" | "bigserial" | "smallserial" => {
+            (value.trim().parse::<i128>().is_ok(), "a whole number")
+        }
+        "numeric" | "decimal" | "dec" | "real" | "double" | "float" | "float4" | "float8" => {
+            (value.trim().parse::<f64>().is_ok(), "a number")
+        }
+        "boolean" | "bool" => (
+            matches!(
+                value.trim().to_lowercase().as_str(),
+                "true" | "false" | "t" | "f" | "yes" | "no" | "1" | "0"
+            ),
+            "true or false",
+        ),
+        _ => (true, ""),
+    };
+    if valid { Ok(()) } else { Err(expected) }
+}
+
+impl Widget for &InsertForm {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default()
+            .bg(theme::current().background)
+            .fg(theme::current().text);
+        let popup_area = centered_rect(70, 70, area);
+        let block = Block::default()
+            .title(format!("Insert into {}", self.table))
+            .borders(Borders::ALL)
+            .style(style);
+        let [list_area, hint_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(2)])
+            .areas(block.inner(popup_area));
+
+        Clear.render(popup_area, buf);
+        block.render(popup_area, buf);
+
+        let accent = Style::default().fg(theme::current().border_focused);
+        let dim = Style::default().fg(theme::current().null);
+        let items = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, (column, value))| {
+                let mut notes = vec![column.data_type.clone()];
+                if !column.nullable {
+                    notes.push("NOT NULL".to_string());
+                }
+                if let Some(default) = &column.default {
+                    notes.push(format!("default {}", default));
+                }
+                let mut spans = vec![
+                    Span::raw(format!("{} ", column.name)),
+                    Span::styled(format!("({}): ", notes.join(", ")), dim),
+                    match value {
+                        FieldValue::Default => Span::styled("DEFAULT", dim),
+                        FieldValue::Null => Span::styled("NULL", dim),
+                        FieldValue::Text(text) if text.is_empty() => Span::styled("''", dim),
+                        FieldValue::Text(text) => Span::raw(text.clone()),
+                    },
+                ];
+                if index == self.selected {
+                    spans.push(Span::styled("█", accent));
+                }
+                Line::from(spans)
+            });
+        let list = List::new(items).highlight_style(accent.add_modifier(Modifier::BOLD));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(list, list_area, buf, &mut state);
+
+        let hint = match &self.error {
+            Some(error) => Line::styled(error.clone(), accent.add_modifier(Modifier::BOLD)),
+            None => Line::styled(
+                "Tab/↑↓ move between fields, Ctrl+N switches a field between DEFAULT, NULL and empty, Enter inserts, Esc cancels.",
+                dim,
+            ),
+        };
+        Paragraph::new(hint)
+            .wrap(Wrap { trim: true })
+            .render(hint_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str, nullable: bool, default: Option<&str>) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable,
+            default: default.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_check_value() {
+        assert_eq!(check_value("42", "integer"), Ok(()));
+        assert_eq!(
+            check_value("4.2", "int(11) unsigned"),
+            Err("a whole number")
+        );
+        assert_eq!(check_value("4.2", "numeric(10,2)"), Ok(()));
+        assert_eq!(check_value("many", "double precision"), Err("a number"));
+        assert_eq!(check_value("maybe", "boolean"), Err("true or false"));
+        assert_eq!(check_value("anything", "interval"), Ok(()));
+        assert_eq!(check_value("anything", "point"), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_leaves_out_default_fields() {
+        let mut form = InsertForm::new(
+            "users".to_string(),
+            "\"users\"".to_string(),
+            vec![
+                column("id", "integer", false, Some("identity")),
+                column("name", "text", false, None),
+                column("age", "integer", true, None),
+            ],
+        );
+        form.selected = 2;
+        assert_eq!(form.submit(), Err("name is required.".to_string()));
+        assert_eq!(form.selected, 1);
+
+        "Ada".chars().for_each(|c| form.input(c));
+        form.next();
+        form.input('x');
+        assert_eq!(
+            form.submit(),
+            Err("age expects a whole number.".to_string())
+        );
+        form.backspace();
+
+        let insert = form.submit().unwrap();
+        assert_eq!(
+            insert.values,
+            vec![(
+                "name".to_string(),
+                Some("text".to_string()),
+                Some("Ada".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_fields_switch_between_default_null_and_empty() {
+        let mut form = InsertForm::new(
+            "notes".to_string(),
+            "\"notes\"".to_string(),
+            vec![
+                column("id", "integer", false, None),
+                column("body", "text", true, None),
+            ],
+        );
+        form.toggle_null();
+        assert_eq!(form.submit(), Err("id can't be NULL.".to_string()));
+        form.input('1');
+        form.next();
+        form.toggle_null();
+        assert_eq!(form.fields[1].1, FieldValue::Null);
+        assert_eq!(
+            form.submit().unwrap().values[1],
+            ("body".to_string(), Some("text".to_string()), None)
+        );
+
+        form.toggle_null();
+        assert_eq!(
+            form.submit().unwrap().values[1],
+            (
+                "body".to_string(),
+                Some("text".to_string()),
+                Some(String::new())
+            )
+        );
+        form.backspace();
+        assert_eq!(form.fields[1].1, FieldValue::Default);
+        assert_eq!(form.submit().unwrap().values.len(), 1);
+    }
+}
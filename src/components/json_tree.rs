@@ -0,0 +1,134 @@
+use crate::components::popup::centered_rect;
+use crate::style::theme;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Scrollbar, ScrollbarOrientation},
+};
+use serde_json::Value;
+use tui_tree_widget::{Tree, TreeItem, TreeState};
+
+/// A collapsible view of a JSON object or array, shown when inspecting a JSON cell.
+pub struct JsonTree {
+    title: String,
+    items: Vec<TreeItem<'static, String>>,
+    pub state: TreeState<String>,
+}
+
+impl JsonTree {
+    /// Builds the tree for `text`, or `None` unless it is a JSON object or array.
+    pub fn parse(title: String, text: &str) -> Option<Self> {
+        let value = serde_json::from_str::<Value>(text).ok()?;
+        let items = children(&value)?;
+        let mut state = TreeState::default();
+        if let Some(first) = items.first() {
+            state.select(vec![first.identifier().clone()]);
+        }
+        Some(Self {
+            title,
+            items,
+            state,
+        })
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let popup_area = centered_rect(80, 80, area);
+        let widget = Tree::new(&self.items)
+            .expect("JSON keys and indexes are unique among siblings")
+            .block(
+                Block::default()
+                    .title(self.title.as_str())
+                    .borders(Borders::ALL)
+                    .style(
+                        Style::default()
+                            .bg(theme::current().background)
+                            .fg(theme::current().text),
+                    ),
+            )
+            .experimental_scrollbar(Some(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .track_symbol(None)
+                    .end_symbol(None),
+            ))
+            .highlight_style(
+                Style::default()
+                    .bg(theme::current().selection_bg)
+                    .fg(theme::current().selection_fg)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_stateful_widget(widget, popup_area, &mut self.state);
+    }
+}
+
+/// Tree items for the members of an object or the elements of an array, each identified
+/// by its key or index.
+fn children(value: &Value) -> Option<Vec<TreeItem<'static, String>>> {
+    let entries: Vec<(String, &Value)> = match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| (key.clone(), value))
+            .collect(),
+        Value::Array(values) => values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (index.to_string(), value))
+            .collect(),
+        _ => return None,
+    };
+    Some(
+        entries
+            .into_iter()
+            .map(|(key, value)| {
+                let label = format!("{}: {}", key, summary(value));
+                match children(value) {
+                    Some(nested) => TreeItem::new(key, label, nested)
+                        .expect("JSON keys and indexes are unique among siblings"),
+                    None => TreeItem::new_leaf(key, label),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// A one-line description of a value: scalars in full, containers by their size.
+fn summary(value: &Value) -> String {
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    match value {
+        Value::Object(map) => format!("{{{} key{}}}", map.len(), plural(map.len())),
+        Value::Array(values) => format!("[{} item{}]", values.len(), plural(values.len())),
+        scalar => scalar.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_only_builds_containers() {
+        assert!(JsonTree::parse("col".to_string(), "42").is_none());
+        assert!(JsonTree::parse("col".to_string(), "not json").is_none());
+
+        let tree = JsonTree::parse(
+            "col".to_string(),
+            r#"{"name": "Ada", "tags": ["a", "b"], "address": {}}"#,
+        )
+        .unwrap();
+        assert_eq!(tree.items.len(), 3);
+        let tags = tree.items.iter().find(|item| item.identifier() == "tags");
+        assert_eq!(tags.map(|item| item.children().len()), Some(2));
+        assert_eq!(tree.state.selected(), [tree.items[0].identifier().clone()]);
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(summary(&serde_json::json!({"a": 1})), "{1 key}");
+        assert_eq!(summary(&serde_json::json!([1, 2])), "[2 items]");
+        assert_eq!(summary(&serde_json::json!("text")), "\"text\"");
+        assert_eq!(summary(&Value::Null), "null");
+    }
+}
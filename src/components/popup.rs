@@ -1,10 +1,11 @@
+use crate::style::theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Margin, Rect},
-    style::{Color, Style},
+    style::Style,
     text::Text,
     widgets::{
         Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
-        StatefulWidget, Widget,
+        StatefulWidget, Widget, Wrap,
     },
 };
 
@@ -13,6 +14,7 @@ pub struct Popup<'a> {
     content: Text<'a>,
     scroll: u16,
     scrollbar_state: &'a mut ScrollbarState,
+    wrap: bool,
 }
 
 impl<'a> Popup<'a> {
@@ -27,8 +29,15 @@ impl<'a> Popup<'a> {
             content,
             scroll,
             scrollbar_state,
+            wrap: false,
         }
     }
+
+    /// Wraps long lines instead of truncating them at the popup edge.
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
 }
 
 impl Widget for Popup<'_> {
@@ -36,13 +45,20 @@ impl Widget for Popup<'_> {
         let block = Block::default()
             .title(self.title)
             .borders(Borders::ALL)
-            .style(Style::default().bg(Color::Black).fg(Color::White));
+            .style(
+                Style::default()
+                    .bg(theme::current().background)
+                    .fg(theme::current().text),
+            );
 
         let popup_area = centered_rect(70, 70, area);
 
-        let paragraph = Paragraph::new(self.content.clone())
+        let mut paragraph = Paragraph::new(self.content.clone())
             .block(block)
             .scroll((self.scroll, 0));
+        if self.wrap {
+            paragraph = paragraph.wrap(Wrap { trim: false });
+        }
 
         // Render the clear widget first to clear the area
         Clear.render(popup_area, buf);
@@ -63,7 +79,7 @@ impl Widget for Popup<'_> {
     }
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -5,7 +5,7 @@ use ratatui::{
     widgets::Tabs,
 };
 
-use crate::style::theme::{COLOR_BLACK, COLOR_FOCUS};
+use crate::style::theme;
 
 // --- Reusable StatefulTabs Component ---
 /// A component to manage and render tabs.
@@ -69,8 +69,8 @@ impl<'a> StatefulTabs<'a> {
             .select(self.index)
             .highlight_style(
                 Style::default()
-                    .fg(COLOR_FOCUS)
-                    .bg(COLOR_BLACK)
+                    .fg(theme::current().border_focused)
+                    .bg(theme::current().background)
                     .add_modifier(Modifier::BOLD)
                     .add_modifier(Modifier::UNDERLINED),
             )
@@ -1,12 +1,24 @@
+use super::mysql::MySqlExecutor;
 use super::postgres::PostgresExecutor;
-use crate::database::pool::DbPool;
+use super::result_set::{CellValue, ColumnInfo, ResultSet};
+use super::row_edit::{CellUpdate, RowDelete, RowInsert};
+use super::sqlite::SqliteExecutor;
+use crate::database::connector::DatabaseType;
+use crate::database::fetch::fetch_table_details;
+use crate::database::pool::{DbConnection, DbPool, DbSession};
 
 use crate::state::{QueryHistoryEntry, add_to_history, update_query_stats};
 use crate::utils::query_timer::query_timer;
-use crate::utils::query_type::Query;
+use crate::utils::query_type::{
+    Query, TransactionStatement, has_returning_clause, leading_keyword, paged_table_sql,
+    split_table_name, whole_table_select,
+};
+use crate::utils::statement_splitter::split_statements;
 use async_trait::async_trait;
 use chrono::Utc;
-use sqlx::{Column, Row, postgres::PgRow};
+use futures::{TryStreamExt, stream::BoxStream};
+use sqlx::postgres::{PgDatabaseError, PgErrorPosition};
+use sqlx::{Column, Row, TypeInfo};
 use std::time::Duration;
 
 #[allow(dead_code)]
@@ -19,94 +31,314 @@ pub struct DataMeta {
 pub enum ExecutionResult {
     Affected {
         rows: usize,
+        last_insert_id: Option<i64>,
         message: String,
     },
     Data {
-        headers: Vec<String>,
-        rows: Vec<sqlx::postgres::PgRow>,
+        result_set: ResultSet,
         meta: DataMeta,
     },
 }
 
+/// Rows written by a statement, plus the generated key of the last inserted row if known.
+pub struct AffectedRows {
+    pub rows: u64,
+    pub last_insert_id: Option<i64>,
+}
+
+impl From<u64> for AffectedRows {
+    fn from(rows: u64) -> Self {
+        Self {
+            rows,
+            last_insert_id: None,
+        }
+    }
+}
+
 #[async_trait]
 pub trait DatabaseExecutor: Send + Sync {
     type Row: Row + Send + Sync;
 
-    async fn fetch(&self, query: &str) -> Result<Vec<Self::Row>, sqlx::Error>;
-    async fn insert(&self, query: &str) -> Result<u64, sqlx::Error>;
-    async fn update(&self, query: &str) -> Result<u64, sqlx::Error>;
-    async fn delete(&self, query: &str) -> Result<u64, sqlx::Error>;
-    #[allow(dead_code)]
-    fn get_value_as_string(&self, row: &Self::Row, index: usize) -> String;
+    /// Fetches at most `limit` rows, dropping the rest of the stream unread.
+    async fn fetch(&mut self, query: &str, limit: usize) -> Result<Vec<Self::Row>, sqlx::Error>;
+    /// Runs any statement that does not return rows, such as DDL, and reports rows affected.
+    /// It is sent as raw SQL, since raw SQL skips statement preparation, which some DDL
+    /// statements do not support.
+    async fn execute(&mut self, query: &str) -> Result<u64, sqlx::Error>;
+    async fn insert(&mut self, query: &str) -> Result<AffectedRows, sqlx::Error>;
+    async fn update(&mut self, query: &str) -> Result<u64, sqlx::Error>;
+    async fn delete(&mut self, query: &str) -> Result<u64, sqlx::Error>;
+    /// Runs a statement with `params` bound, in order, to its placeholders; `None` binds
+    /// `NULL`.
+    async fn update_with_params(
+        &mut self,
+        query: &str,
+        params: &[Option<String>],
+    ) -> Result<u64, sqlx::Error>;
+    fn get_cell_value(&self, row: &Self::Row, index: usize) -> CellValue;
+
+    /// Reports for each result column whether it may be `NULL`, if the driver knows.
+    async fn column_nullability(&mut self, _query: &str) -> Vec<Option<bool>> {
+        Vec::new()
+    }
+
+    fn to_result_set(&self, rows: &[Self::Row]) -> ResultSet {
+        let headers: Vec<String> = rows
+            .first()
+            .map(|row| row.columns().iter().map(|c| c.name().to_string()).collect())
+            .unwrap_or_default();
+
+        let columns = rows
+            .first()
+            .map(|row| {
+                row.columns()
+                    .iter()
+                    .map(|c| ColumnInfo {
+                        type_name: c.type_info().name().to_string(),
+                        nullable: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let rows = rows
+            .iter()
+            .map(|row| {
+                (0..headers.len())
+                    .map(|i| self.get_cell_value(row, i))
+                    .collect()
+            })
+            .collect();
+
+        ResultSet {
+            columns,
+            ..ResultSet::new(headers, rows)
+        }
+    }
 }
 
-pub fn create_executor(pool: &DbPool) -> impl DatabaseExecutor<Row = PgRow> {
-    match pool {
-        DbPool::Postgres(pg_pool) => PostgresExecutor::new(pg_pool.clone()),
-        DbPool::MySQL(_) => todo!(),
-        DbPool::SQLite(_) => todo!(),
+/// Collects rows from a query stream until `limit` is reached.
+pub(crate) async fn collect_rows<R>(
+    mut stream: BoxStream<'_, Result<R, sqlx::Error>>,
+    limit: usize,
+) -> Result<Vec<R>, sqlx::Error> {
+    let mut rows = Vec::new();
+    while rows.len() < limit
+        && let Some(row) = stream.try_next().await?
+    {
+        rows.push(row);
     }
+    Ok(rows)
 }
 
-fn format_affected_result(query_type: &str, rows: usize, elapsed: Duration) -> ExecutionResult {
-    let message = format!(
-        "{} {} rows affected.\nQuery completed in {} msec.",
+fn affected_message(
+    query_type: &str,
+    rows: usize,
+    last_insert_id: Option<i64>,
+    elapsed: Duration,
+) -> String {
+    let last_insert = last_insert_id
+        .map(|id| format!("\nLast insert id: {}.", id))
+        .unwrap_or_default();
+    format!(
+        "{} {} rows affected.{}\nQuery completed in {} msec.",
         query_type,
         rows,
+        last_insert,
         elapsed.as_millis()
-    );
-    ExecutionResult::Affected { rows, message }
+    )
+}
+
+/// A failed statement of a script, described along with the statements run before it.
+#[derive(Debug)]
+pub struct ScriptError {
+    pub error: sqlx::Error,
+    /// Character offset of the failed statement within the script.
+    pub statement_start: usize,
+    /// What went wrong, ready to show in the Messages tab.
+    pub message: String,
+}
+
+impl ScriptError {
+    /// Character offset within the script that the database reported the error at.
+    ///
+    /// Only Postgres reports one, counting from 1 within the failed statement.
+    pub fn position(&self) -> Option<usize> {
+        let sqlx::Error::Database(db_err) = &self.error else {
+            return None;
+        };
+        match db_err.try_downcast_ref::<PgDatabaseError>()?.position()? {
+            PgErrorPosition::Original(position) => {
+                Some(self.statement_start + position.saturating_sub(1))
+            }
+            PgErrorPosition::Internal { .. } => None,
+        }
+    }
+}
+
+/// Describes a failed query on separate lines: the server's message, its error code and,
+/// where the database reports them, the detail, hint, position and affected objects.
+pub fn describe_query_error(err: &sqlx::Error) -> String {
+    let sqlx::Error::Database(db_err) = err else {
+        return err.to_string();
+    };
+
+    let mut lines = vec![db_err.message().to_string()];
+    if let Some(code) = db_err.code() {
+        lines.push(format!("Code: {}", code));
+    }
+    if let Some(pg_err) = db_err.try_downcast_ref::<PgDatabaseError>() {
+        if let Some(detail) = pg_err.detail() {
+            lines.push(format!("Detail: {}", detail));
+        }
+        if let Some(hint) = pg_err.hint() {
+            lines.push(format!("Hint: {}", hint));
+        }
+        match pg_err.position() {
+            Some(PgErrorPosition::Original(position)) => {
+                lines.push(format!("Position: character {}", position));
+            }
+            Some(PgErrorPosition::Internal { position, query }) => {
+                lines.push(format!(
+                    "Position: character {} of internal query: {}",
+                    position, query
+                ));
+            }
+            None => {}
+        }
+        if let Some(context) = pg_err.r#where() {
+            lines.push(format!("Where: {}", context));
+        }
+        if let Some(column) = pg_err.column() {
+            lines.push(format!("Column: {}", column));
+        }
+    }
+    if let Some(table) = db_err.table() {
+        lines.push(format!("Table: {}", table));
+    }
+    if let Some(constraint) = db_err.constraint() {
+        lines.push(format!("Constraint: {}", constraint));
+    }
+    lines.join("\n")
 }
 
-async fn run_affected_query<Fut>(
+fn format_affected_result(
+    query_type: &str,
+    rows: usize,
+    last_insert_id: Option<i64>,
+    elapsed: Duration,
+) -> ExecutionResult {
+    let message = affected_message(query_type, rows, last_insert_id, elapsed);
+    ExecutionResult::Affected {
+        rows,
+        last_insert_id,
+        message,
+    }
+}
+
+async fn run_affected_query<Fut, T>(
     fut: Fut,
-    query_type: &'static str,
+    query_type: &str,
 ) -> Result<ExecutionResult, sqlx::Error>
 where
-    Fut: std::future::Future<Output = Result<u64, sqlx::Error>>,
+    Fut: std::future::Future<Output = Result<T, sqlx::Error>>,
+    T: Into<AffectedRows>,
 {
     let (result, elapsed) = query_timer(fut).await;
-    let rows = result? as usize;
+    let affected: AffectedRows = result?.into();
+    let rows = affected.rows as usize;
     update_query_stats(rows, elapsed).await;
-    Ok(format_affected_result(query_type, rows, elapsed))
+    Ok(format_affected_result(
+        query_type,
+        rows,
+        affected.last_insert_id,
+        elapsed,
+    ))
 }
 
-pub async fn execute_query(
-    pool: &DbPool,
+/// Fetches at most `max_rows` rows of a statement, or all of them when it is 0, marking
+/// the result truncated when there were more.
+async fn fetch_result_set<E: DatabaseExecutor>(
+    executor: &mut E,
     sql: &str,
-    db_name: Option<String>,
-) -> Result<ExecutionResult, sqlx::Error> {
-    let executor = create_executor(pool);
-    let query_start_time = Utc::now();
-    let connection_name = Some(pool.get_type().to_string());
+    max_rows: usize,
+) -> Result<(ResultSet, Duration), sqlx::Error> {
+    let limit = if max_rows == 0 { usize::MAX } else { max_rows };
+    // One extra row tells us whether the result was cut off.
+    let (rows_result, elapsed) = query_timer(executor.fetch(sql, limit.saturating_add(1))).await;
+    let mut rows = rows_result?;
+    let truncated = rows.len() > limit;
+    rows.truncate(limit);
 
-    let result = match Query::from_sql(sql) {
-        Query::SELECT => {
-            let (rows_result, elapsed) = query_timer(executor.fetch(sql)).await;
-            let rows = rows_result?;
-            let row_count = rows.len();
+    update_query_stats(rows.len(), elapsed).await;
 
-            update_query_stats(row_count, elapsed).await;
+    let mut result_set = executor.to_result_set(&rows);
+    result_set.truncated = truncated;
+    if !result_set.columns.is_empty() {
+        let nullability = executor.column_nullability(sql).await;
+        for (column, nullable) in result_set.columns.iter_mut().zip(nullability) {
+            column.nullable = nullable;
+        }
+    }
+    Ok((result_set, elapsed))
+}
 
-            let message = format!(
+async fn run_query<E: DatabaseExecutor>(
+    executor: &mut E,
+    db_type: DatabaseType,
+    sql: &str,
+    max_rows: usize,
+) -> Result<ExecutionResult, sqlx::Error> {
+    match Query::from_sql(sql, db_type) {
+        Query::SELECT => {
+            let (result_set, elapsed) = fetch_result_set(executor, sql, max_rows).await?;
+            let row_count = result_set.rows.len();
+            let mut message = format!(
                 "Successfully run. Total query runtime: {} ms.\n{} rows fetched.",
                 elapsed.as_millis(),
                 row_count,
             );
+            if result_set.truncated {
+                message.push_str(&format!("\nResults truncated to {} rows.", row_count));
+            }
 
-            let headers = if let Some(first_row) = rows.first() {
-                first_row
-                    .columns()
-                    .iter()
-                    .map(|c| c.name().to_string())
-                    .collect()
+            Ok(ExecutionResult::Data {
+                result_set,
+                meta: DataMeta {
+                    rows: row_count,
+                    message,
+                },
+            })
+        }
+
+        // The rows of a `RETURNING` clause are shown like those of a `SELECT`.
+        query @ (Query::INSERT | Query::UPDATE | Query::DELETE)
+            if has_returning_clause(sql, db_type) =>
+        {
+            let (result_set, elapsed) = fetch_result_set(executor, sql, max_rows).await?;
+            let row_count = result_set.rows.len();
+            // Without an implicit last insert id on Postgres, an `INSERT` takes it from the
+            // first column returned, when that is a number.
+            let last_insert_id = match (query, result_set.rows.last().and_then(|row| row.first())) {
+                (Query::INSERT, Some(CellValue::Int(id))) => Some(*id),
+                _ => None,
+            };
+            let keyword = leading_keyword(sql).unwrap_or_default();
+            // Past the fetch limit, the rows left unread were changed too.
+            let message = if result_set.truncated {
+                format!(
+                    "{} more than {} rows affected.\nResults truncated to {} rows.\nQuery completed in {} msec.",
+                    keyword,
+                    row_count,
+                    row_count,
+                    elapsed.as_millis()
+                )
             } else {
-                Vec::new()
+                affected_message(&keyword, row_count, last_insert_id, elapsed)
             };
 
             Ok(ExecutionResult::Data {
-                headers,
-                rows,
+                result_set,
                 meta: DataMeta {
                     rows: row_count,
                     message,
@@ -117,16 +349,158 @@ pub async fn execute_query(
         Query::INSERT => run_affected_query(executor.insert(sql), "INSERT").await,
         Query::UPDATE => run_affected_query(executor.update(sql), "UPDATE").await,
         Query::DELETE => run_affected_query(executor.delete(sql), "DELETE").await,
+        Query::OTHER => {
+            let keyword = leading_keyword(sql).unwrap_or_default();
+            run_affected_query(executor.execute(sql), &keyword).await
+        }
 
         Query::UNKNOWN => Err(sqlx::Error::Protocol("Unsupported query".into())),
+    }
+}
+
+/// Runs a statement on the session connection.
+async fn run_on_connection(
+    connection: &mut DbConnection,
+    sql: &str,
+    max_rows: usize,
+) -> Result<ExecutionResult, sqlx::Error> {
+    match connection {
+        DbConnection::Postgres(conn) => {
+            run_query(
+                &mut PostgresExecutor::new(conn),
+                DatabaseType::PostgreSQL,
+                sql,
+                max_rows,
+            )
+            .await
+        }
+        DbConnection::MySQL(conn) => {
+            run_query(
+                &mut MySqlExecutor::new(conn),
+                DatabaseType::MySQL,
+                sql,
+                max_rows,
+            )
+            .await
+        }
+        DbConnection::SQLite(conn) => {
+            run_query(
+                &mut SqliteExecutor::new(conn),
+                DatabaseType::SQLite,
+                sql,
+                max_rows,
+            )
+            .await
+        }
+    }
+}
+
+/// Times a transaction control step and reports it like a statement that affected no rows.
+async fn run_transaction_control<Fut>(
+    fut: Fut,
+    message: &str,
+) -> Result<ExecutionResult, sqlx::Error>
+where
+    Fut: std::future::Future<Output = Result<(), sqlx::Error>>,
+{
+    let (result, elapsed) = query_timer(fut).await;
+    result?;
+    update_query_stats(0, elapsed).await;
+    Ok(ExecutionResult::Affected {
+        rows: 0,
+        last_insert_id: None,
+        message: format!(
+            "{}\nQuery completed in {} msec.",
+            message,
+            elapsed.as_millis()
+        ),
+    })
+}
+
+/// The session connection, opened if there is none yet.
+///
+/// Outside a transaction nothing is lost by replacing a connection that was dropped while
+/// idle, so the statement runs instead of failing on the dead connection.
+async fn live_session<'a>(
+    pool: &DbPool,
+    session: &'a mut Option<DbSession>,
+) -> Result<&'a mut DbSession, sqlx::Error> {
+    if let Some(existing) = session.as_mut()
+        && !existing.in_transaction()
+        && !existing.is_alive().await
+    {
+        *session = None;
+    }
+    match session {
+        Some(session) => Ok(session),
+        None => Ok(session.insert(pool.session().await?)),
+    }
+}
+
+/// Runs a statement in the session, opening the session connection if there is none yet.
+///
+/// `BEGIN`, `COMMIT` and `ROLLBACK` go through the session's transaction manager so that
+/// it knows whether a transaction is open. Without an open transaction, `COMMIT` and
+/// `ROLLBACK` are sent to the database as they are.
+async fn run_in_session(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    sql: &str,
+    max_rows: usize,
+) -> Result<ExecutionResult, sqlx::Error> {
+    let session = live_session(pool, session).await?;
+    let result = match TransactionStatement::from_sql(sql, pool.get_type()) {
+        Some(TransactionStatement::Begin) if session.in_transaction() => {
+            Err(sqlx::Error::InvalidArgument(
+                "A transaction is already open. Commit or roll it back first.".to_string(),
+            ))
+        }
+        Some(TransactionStatement::Begin) => run_transaction_control(
+            session.begin(sql),
+            "Transaction started. Statements run inside it until it is committed or rolled back.",
+        )
+        .await,
+        Some(TransactionStatement::Commit) if session.in_transaction() => {
+            run_transaction_control(session.commit(), "Transaction committed.").await
+        }
+        Some(TransactionStatement::Rollback) if session.in_transaction() => {
+            run_transaction_control(session.rollback(), "Transaction rolled back.").await
+        }
+        _ => run_on_connection(&mut session.connection, sql, max_rows).await,
     };
+    session.mark_used();
+    result
+}
+
+/// Runs a statement in the session and records it in the history.
+pub async fn execute_query(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    sql: &str,
+    db_name: Option<String>,
+    max_rows: usize,
+) -> Result<ExecutionResult, sqlx::Error> {
+    let query_start_time = Utc::now();
+    let result = run_in_session(pool, session, sql, max_rows).await;
+    record_history(pool, sql, db_name, query_start_time, &result).await;
+    result
+}
 
+/// Adds a statement that started at `query_start_time` to the history with its outcome.
+async fn record_history(
+    pool: &DbPool,
+    sql: &str,
+    db_name: Option<String>,
+    query_start_time: chrono::DateTime<Utc>,
+    result: &Result<ExecutionResult, sqlx::Error>,
+) {
+    let connection_name = Some(pool.get_type().to_string());
     let execution_time = Utc::now()
         .signed_duration_since(query_start_time)
         .to_std()
         .unwrap_or_default();
 
-    let history_entry = match &result {
+    let history_entry = match result {
         Ok(res) => {
             let (success, rows_affected) = match res {
                 ExecutionResult::Data { meta, .. } => (true, meta.rows),
@@ -152,6 +526,215 @@ pub async fn execute_query(
     };
 
     add_to_history(history_entry).await;
+}
 
-    result
+/// Writes an edited cell back to its table in the session, recording the `UPDATE` in the
+/// history.
+pub async fn update_cell(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    update: &CellUpdate,
+    db_name: Option<String>,
+) -> Result<ExecutionResult, ScriptError> {
+    let (sql, params) = update.to_sql(pool.get_type());
+    run_with_params(pool, session, &sql, &params, "UPDATE", db_name).await
+}
+
+/// Deletes a row of a table in the session, recording the `DELETE` in the history.
+pub async fn delete_row(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    delete: &RowDelete,
+    db_name: Option<String>,
+) -> Result<ExecutionResult, ScriptError> {
+    let (sql, params) = delete.to_sql(pool.get_type());
+    run_with_params(pool, session, &sql, &params, "DELETE", db_name).await
+}
+
+/// Inserts a row into a table in the session, recording the `INSERT` in the history.
+pub async fn insert_row(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    insert: &RowInsert,
+    db_name: Option<String>,
+) -> Result<ExecutionResult, ScriptError> {
+    let (sql, params) = insert.to_sql(pool.get_type());
+    run_with_params(pool, session, &sql, &params, "INSERT", db_name).await
+}
+
+/// Runs a statement that binds `params` as text in the session, opening one if needed.
+async fn run_with_params(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    sql: &str,
+    params: &[Option<String>],
+    keyword: &str,
+    db_name: Option<String>,
+) -> Result<ExecutionResult, ScriptError> {
+    let query_start_time = Utc::now();
+    let result = async {
+        let session = live_session(pool, session).await?;
+        let affected = match &mut session.connection {
+            DbConnection::Postgres(conn) => {
+                PostgresExecutor::new(conn)
+                    .update_with_params(sql, params)
+                    .await
+            }
+            DbConnection::MySQL(conn) => {
+                MySqlExecutor::new(conn)
+                    .update_with_params(sql, params)
+                    .await
+            }
+            DbConnection::SQLite(conn) => {
+                SqliteExecutor::new(conn)
+                    .update_with_params(sql, params)
+                    .await
+            }
+        };
+        session.mark_used();
+        run_affected_query(std::future::ready(affected), keyword).await
+    }
+    .await;
+    record_history(pool, sql, db_name, query_start_time, &result).await;
+    result.map_err(|error| ScriptError {
+        message: describe_query_error(&error),
+        statement_start: 0,
+        error,
+    })
+}
+
+/// Fetches the `page_size` rows of a `SELECT * FROM table` query that follow the first
+/// `offset`, leaving the rest of the table on the server. One extra row is requested, so
+/// `truncated` tells whether another page follows. Pages are ordered by the table's
+/// primary key, so that they neither repeat nor skip rows.
+///
+/// Only the first page is recorded in the history, as `query` itself.
+pub async fn fetch_page(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    query: &str,
+    offset: usize,
+    page_size: usize,
+    db_name: Option<String>,
+) -> Result<ExecutionResult, ScriptError> {
+    let script_error = |error: sqlx::Error| ScriptError {
+        message: describe_query_error(&error),
+        statement_start: 0,
+        error,
+    };
+    let table = whole_table_select(query).ok_or_else(|| {
+        script_error(sqlx::Error::InvalidArgument(
+            "Only SELECT * FROM a table can be fetched a page at a time.".to_string(),
+        ))
+    })?;
+
+    let (schema, table_name) = split_table_name(table);
+    // Without the key, e.g. for a view, pages are still ordered by the first column.
+    let key = fetch_table_details(pool, schema.as_deref(), &table_name)
+        .await
+        .ok()
+        .and_then(|metadata| metadata.primary_key())
+        .unwrap_or_default();
+
+    let query_start_time = Utc::now();
+    let sql = paged_table_sql(
+        pool.get_type(),
+        table,
+        &key,
+        page_size.saturating_add(1),
+        offset,
+    );
+    let result = run_in_session(pool, session, &sql, page_size).await;
+    if offset == 0 {
+        record_history(pool, query, db_name, query_start_time, &result).await;
+    }
+    result.map_err(script_error)
+}
+
+/// Executes every statement of a script in order, stopping at the first failure.
+///
+/// Each statement's outcome is collected into the returned message, and the rows of the
+/// last row-returning statement are kept for display.
+pub async fn execute_script(
+    pool: &DbPool,
+    session: &mut Option<DbSession>,
+    sql: &str,
+    db_name: Option<String>,
+    max_rows: usize,
+) -> Result<ExecutionResult, ScriptError> {
+    // Statements are slices of `sql`, so their offsets locate errors within the script.
+    let statement_start = |statement: &str| {
+        sql[..statement.as_ptr() as usize - sql.as_ptr() as usize]
+            .chars()
+            .count()
+    };
+
+    let statements = split_statements(sql, pool.get_type());
+    if statements.len() <= 1 {
+        let statement = statements.first().copied().unwrap_or(sql);
+        return execute_query(pool, session, statement, db_name, max_rows)
+            .await
+            .map_err(|error| ScriptError {
+                message: describe_query_error(&error),
+                statement_start: statement_start(statement),
+                error,
+            });
+    }
+
+    let total = statements.len();
+    let mut messages = Vec::with_capacity(total);
+    let mut last_data = None;
+    let mut affected_rows = 0;
+    let mut last_insert_id = None;
+
+    for (index, statement) in statements.into_iter().enumerate() {
+        let number = index + 1;
+        match execute_query(pool, session, statement, db_name.clone(), max_rows).await {
+            Ok(ExecutionResult::Data { result_set, meta }) => {
+                messages.push(format!("[{number}/{total}] {}", meta.message));
+                last_data = Some((result_set, meta.rows));
+            }
+            Ok(ExecutionResult::Affected {
+                rows,
+                last_insert_id: inserted_id,
+                message,
+            }) => {
+                messages.push(format!("[{number}/{total}] {message}"));
+                affected_rows += rows;
+                last_insert_id = inserted_id.or(last_insert_id);
+            }
+            Err(error) => {
+                let message = if index == 0 {
+                    describe_query_error(&error)
+                } else {
+                    messages.push(format!(
+                        "[{number}/{total}] Failed: {}",
+                        describe_query_error(&error)
+                    ));
+                    format!(
+                        "statement {number} of {total} failed.\n{}",
+                        messages.join("\n")
+                    )
+                };
+                return Err(ScriptError {
+                    error,
+                    statement_start: statement_start(statement),
+                    message,
+                });
+            }
+        }
+    }
+
+    let message = messages.join("\n");
+    Ok(match last_data {
+        Some((result_set, rows)) => ExecutionResult::Data {
+            result_set,
+            meta: DataMeta { rows, message },
+        },
+        None => ExecutionResult::Affected {
+            rows: affected_rows,
+            last_insert_id,
+            message,
+        },
+    })
 }
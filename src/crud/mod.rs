@@ -1,2 +1,6 @@
 pub mod executor;
+pub mod mysql;
 pub mod postgres;
+pub mod result_set;
+pub mod row_edit;
+pub mod sqlite;
@@ -1,96 +1,459 @@
-use super::executor::DatabaseExecutor;
+use super::executor::{AffectedRows, DatabaseExecutor, collect_rows};
+use super::result_set::CellValue;
 use async_trait::async_trait;
-use hex;
 use serde_json::Value;
 use sqlx::{
-    PgPool, Row,
-    postgres::PgRow,
+    Column, Decode, Executor, PgConnection, Postgres, Row, Type, TypeInfo, ValueRef,
+    error::BoxDynError,
+    postgres::{
+        PgHasArrayType, PgRow, PgTypeInfo, PgTypeKind, PgValueFormat, PgValueRef,
+        types::{PgInterval, PgMoney, PgTimeTz},
+    },
     types::{Json, Uuid, chrono},
 };
 
-pub struct PostgresExecutor {
-    pool: PgPool,
+pub struct PostgresExecutor<'c> {
+    conn: &'c mut PgConnection,
 }
 
-impl PostgresExecutor {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+impl<'c> PostgresExecutor<'c> {
+    pub fn new(conn: &'c mut PgConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl DatabaseExecutor for PostgresExecutor<'_> {
+    type Row = PgRow;
+
+    async fn fetch(&mut self, query: &str, limit: usize) -> Result<Vec<PgRow>, sqlx::Error> {
+        collect_rows(sqlx::query(query).fetch(&mut *self.conn), limit).await
     }
 
-    async fn execute_query(&self, query: &str) -> Result<u64, sqlx::Error> {
-        Ok(sqlx::query(query)
-            .execute(&self.pool)
+    async fn execute(&mut self, query: &str) -> Result<u64, sqlx::Error> {
+        Ok(self
+            .conn
+            .execute(sqlx::raw_sql(query))
             .await?
             .rows_affected())
     }
-}
 
-#[async_trait]
-impl DatabaseExecutor for PostgresExecutor {
-    type Row = PgRow;
+    async fn column_nullability(&mut self, query: &str) -> Vec<Option<bool>> {
+        match (&mut *self.conn).describe(query).await {
+            Ok(describe) => (0..describe.columns().len())
+                .map(|i| describe.nullable(i))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
 
-    async fn fetch(&self, query: &str) -> Result<Vec<PgRow>, sqlx::Error> {
-        let rows = sqlx::query(query).fetch_all(&self.pool).await?;
-        Ok(rows)
+    async fn insert(&mut self, query: &str) -> Result<AffectedRows, sqlx::Error> {
+        // Postgres has no implicit last insert id; it is only known through `RETURNING`,
+        // whose rows are fetched instead.
+        self.execute(query).await.map(AffectedRows::from)
     }
 
-    async fn insert(&self, query: &str) -> Result<u64, sqlx::Error> {
-        self.execute_query(query).await
+    async fn update(&mut self, query: &str) -> Result<u64, sqlx::Error> {
+        self.execute(query).await
     }
 
-    async fn update(&self, query: &str) -> Result<u64, sqlx::Error> {
-        self.execute_query(query).await
+    async fn delete(&mut self, query: &str) -> Result<u64, sqlx::Error> {
+        self.execute(query).await
     }
 
-    async fn delete(&self, query: &str) -> Result<u64, sqlx::Error> {
-        self.execute_query(query).await
+    async fn update_with_params(
+        &mut self,
+        query: &str,
+        params: &[Option<String>],
+    ) -> Result<u64, sqlx::Error> {
+        let mut query = sqlx::query(query);
+        for param in params {
+            query = query.bind(param.as_deref());
+        }
+        Ok(query.execute(&mut *self.conn).await?.rows_affected())
     }
 
-    fn get_value_as_string(&self, row: &PgRow, index: usize) -> String {
-        macro_rules! try_get_string {
-            ($($type:ty),*) => {
+    fn get_cell_value(&self, row: &PgRow, index: usize) -> CellValue {
+        let Some(column) = row.columns().get(index) else {
+            return CellValue::Null;
+        };
+        // A domain's values are those of the type it is based on, so they decode as that type.
+        let mut value_type = column.type_info().clone();
+        while let PgTypeKind::Domain(base) = value_type.kind() {
+            value_type = base.clone();
+        }
+        macro_rules! try_get_cell {
+            ($map:expr => $($type:ty),*) => {
                 $(
-                    if let Ok(val) = row.try_get::<$type, _>(index) {
-                        return val.to_string();
+                    if <$type as Type<Postgres>>::compatible(&value_type)
+                        && let Ok(val) = row.try_get_unchecked::<Option<$type>, _>(index)
+                    {
+                        return val.map_or(CellValue::Null, $map);
                     }
                 )*
             };
         }
 
-        try_get_string!(
-            String,
-            &str,
-            i16,
-            i32,
-            i64,
-            f32,
-            f64,
-            bool,
+        try_get_cell!(CellValue::Text => String);
+        try_get_cell!(|v| CellValue::Int(v.into()) => i16, i32);
+        try_get_cell!(CellValue::Int => i64);
+        try_get_cell!(|v| CellValue::Float(v.into()) => f32);
+        try_get_cell!(CellValue::Float => f64);
+        try_get_cell!(CellValue::Bool => bool);
+        try_get_cell!(
+            |v| CellValue::Text(v.to_string()) =>
             Uuid,
             chrono::NaiveDate,
             chrono::NaiveDateTime,
             chrono::NaiveTime,
             chrono::DateTime<chrono::Utc>
         );
+        try_get_cell!(|v| CellValue::Text(interval_text(&v)) => PgInterval);
+        try_get_cell!(
+            |v: PgTimeTz| CellValue::Text(format!("{}{}", v.time, v.offset)) =>
+            PgTimeTz
+        );
+        try_get_cell!(CellValue::Json => Value);
+        try_get_cell!(|Json(v)| CellValue::Json(v) => Json<Value>);
+        try_get_cell!(CellValue::Bytes => Vec<u8>);
+        try_get_cell!(|Numeric(v)| CellValue::Text(v) => Numeric);
+        try_get_cell!(|EnumLabel(v)| CellValue::Text(v) => EnumLabel);
+        try_get_cell!(|v: PgMoney| CellValue::Text(money_text(v.0)) => PgMoney);
+        try_get_cell!(
+            |v| CellValue::Text(array_literal(v)) =>
+            Vec<Option<i16>>,
+            Vec<Option<i32>>,
+            Vec<Option<i64>>,
+            Vec<Option<f32>>,
+            Vec<Option<f64>>,
+            Vec<Option<bool>>,
+            Vec<Option<String>>,
+            Vec<Option<Uuid>>,
+            Vec<Option<Numeric>>,
+            Vec<Option<EnumLabel>>,
+            Vec<Option<chrono::NaiveDate>>,
+            Vec<Option<chrono::DateTime<chrono::Utc>>>
+        );
 
-        if let Ok(val) = row.try_get::<Value, _>(index) {
-            return match serde_json::to_string(&val) {
-                Ok(s) => s,
-                Err(e) => format!("[json-error: {}]", e),
-            };
+        // Values of other types only show when they arrive as text.
+        match row.try_get_raw(index) {
+            Ok(value) if value.is_null() => CellValue::Null,
+            Ok(value) if value.format() == PgValueFormat::Text => {
+                CellValue::Text(value.as_str().unwrap_or_default().to_string())
+            }
+            _ => CellValue::Text(String::new()),
         }
+    }
+}
 
-        if let Ok(Json(val)) = row.try_get::<Json<Value>, _>(index) {
-            return match serde_json::to_string(&val) {
-                Ok(s) => s,
-                Err(e) => format!("[json-error: {}]", e),
-            };
+/// A `NUMERIC` value in its exact decimal form, decoded without a decimal crate.
+struct Numeric(String);
+
+impl std::fmt::Display for Numeric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Type<Postgres> for Numeric {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("NUMERIC")
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        ty.name() == "NUMERIC"
+    }
+}
+
+impl PgHasArrayType for Numeric {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_numeric")
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        ty.name() == "NUMERIC[]"
+    }
+}
+
+impl Decode<'_, Postgres> for Numeric {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        match value.format() {
+            PgValueFormat::Text => Ok(Numeric(value.as_str()?.to_string())),
+            PgValueFormat::Binary => Ok(Numeric(numeric_text(value.as_bytes()?)?)),
         }
+    }
+}
+
+/// The label of a user-defined enum value, which Postgres sends as plain text.
+struct EnumLabel(String);
+
+impl std::fmt::Display for EnumLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
-        if let Ok(val) = row.try_get::<Vec<u8>, _>(index) {
-            return hex::encode(val);
+impl Type<Postgres> for EnumLabel {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("text")
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        matches!(ty.kind(), PgTypeKind::Enum(_))
+    }
+}
+
+impl PgHasArrayType for EnumLabel {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_text")
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        matches!(ty.kind(), PgTypeKind::Array(element) if Self::compatible(element))
+    }
+}
+
+impl Decode<'_, Postgres> for EnumLabel {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        Ok(EnumLabel(value.as_str()?.to_string()))
+    }
+}
+
+/// Formats `NUMERIC` in its binary wire format: a digit count, the weight of the first
+/// digit, a sign and the display scale, then the digits in base 10000.
+fn numeric_text(bytes: &[u8]) -> Result<String, BoxDynError> {
+    let word = |index: usize| -> Result<i16, BoxDynError> {
+        let bytes = bytes
+            .get(index * 2..index * 2 + 2)
+            .ok_or("NUMERIC value is cut short")?;
+        Ok(i16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+    let digit_count = word(0)?.max(0) as usize;
+    let weight = word(1)? as i64;
+    let sign = word(2)? as u16;
+    let scale = word(3)?.max(0) as usize;
+    let digits = (0..digit_count)
+        .map(|index| word(4 + index))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+    // Digits past the stored ones are zero.
+    let digit = |position: i64| {
+        usize::try_from(position)
+            .ok()
+            .and_then(|position| digits.get(position))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let mut text = String::new();
+    if sign == 0x4000 {
+        text.push('-');
+    }
+    if weight < 0 {
+        text.push('0');
+    } else {
+        text.push_str(&digit(0).to_string());
+        for position in 1..=weight {
+            text.push_str(&format!("{:04}", digit(position)));
         }
+    }
+    if scale > 0 {
+        let fraction = (1..=scale.div_ceil(4) as i64)
+            .map(|place| format!("{:04}", digit(weight + place)))
+            .collect::<String>();
+        text.push('.');
+        text.push_str(&fraction[..scale]);
+    }
+    Ok(text)
+}
+
+/// Formats `money`, stored as a count of cents, with the two decimals of most locales.
+fn money_text(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+}
 
-        "".to_string()
+/// Formats an interval the way Postgres prints it by default, e.g.
+/// `1 year 2 mons -3 days +04:05:06.5`. Once a field is negative, positive fields after
+/// it carry a `+`.
+fn interval_text(interval: &PgInterval) -> String {
+    let mut parts = Vec::new();
+    let mut negative_seen = false;
+    let mut sign = |negative: bool| {
+        let sign = match (negative, negative_seen) {
+            (true, _) => "-",
+            (false, true) => "+",
+            (false, false) => "",
+        };
+        negative_seen |= negative;
+        sign
+    };
+    let fields = [
+        (i64::from(interval.months / 12), "year"),
+        (i64::from(interval.months % 12), "mon"),
+        (i64::from(interval.days), "day"),
+    ];
+    for (count, name) in fields {
+        if count != 0 {
+            let plural = if count == 1 { "" } else { "s" };
+            let sign = sign(count < 0);
+            parts.push(format!(
+                "{}{} {}{}",
+                sign,
+                count.unsigned_abs(),
+                name,
+                plural
+            ));
+        }
+    }
+    if interval.microseconds != 0 || parts.is_empty() {
+        let sign = sign(interval.microseconds < 0);
+        let micros = interval.microseconds.unsigned_abs();
+        let seconds = micros / 1_000_000;
+        let mut time = format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        let fraction = micros % 1_000_000;
+        if fraction != 0 {
+            time.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+    parts.join(" ")
+}
+
+/// Writes a one-dimensional array the way Postgres prints it, e.g. `{1,NULL,"a b"}`.
+fn array_literal<T: ToString>(values: Vec<Option<T>>) -> String {
+    let elements = values
+        .into_iter()
+        .map(|value| match value {
+            None => "NULL".to_string(),
+            Some(value) => {
+                let text = value.to_string();
+                let needs_quotes = text.is_empty()
+                    || text.eq_ignore_ascii_case("NULL")
+                    || text
+                        .chars()
+                        .any(|c| c.is_whitespace() || matches!(c, '{' | '}' | ',' | '"' | '\\'));
+                if needs_quotes {
+                    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+                } else {
+                    text
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", elements.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The binary form of a `NUMERIC` with the given header and base-10000 digits.
+    fn numeric(weight: i16, sign: u16, scale: i16, digits: &[i16]) -> Vec<u8> {
+        [digits.len() as i16, weight, sign as i16, scale]
+            .iter()
+            .chain(digits)
+            .flat_map(|word| word.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_numeric_text() {
+        let text = |bytes: Vec<u8>| numeric_text(&bytes).unwrap();
+        assert_eq!(text(numeric(1, 0, 2, &[1, 2345, 6700])), "12345.67");
+        assert_eq!(text(numeric(0, 0x4000, 3, &[42, 5000])), "-42.500");
+        assert_eq!(text(numeric(-1, 0, 5, &[12])), "0.00120");
+        assert_eq!(text(numeric(2, 0, 0, &[7])), "700000000");
+        assert_eq!(text(numeric(0, 0, 0, &[])), "0");
+        assert_eq!(text(numeric(0, 0xC000, 0, &[])), "NaN");
+        assert!(numeric_text(&[0, 1]).is_err());
+        assert_eq!(money_text(-123456), "-1234.56");
+        assert_eq!(money_text(5), "0.05");
+    }
+
+    #[test]
+    fn test_interval_text() {
+        let interval = |months, days, microseconds| {
+            interval_text(&PgInterval {
+                months,
+                days,
+                microseconds,
+            })
+        };
+        assert_eq!(interval(0, 0, 0), "00:00:00");
+        assert_eq!(interval(14, 1, 0), "1 year 2 mons 1 day");
+        assert_eq!(interval(0, -3, 14_706_500_000), "-3 days +04:05:06.5");
+        assert_eq!(interval(1, -1, 0), "1 mon -1 days");
+        assert_eq!(interval(-1, 0, -3_600_000_000), "-1 mons -01:00:00");
+        assert_eq!(interval(0, 0, 360_000_000_000), "100:00:00");
+    }
+
+    #[test]
+    fn test_array_literal() {
+        assert_eq!(array_literal(vec![Some(1), None, Some(3)]), "{1,NULL,3}");
+        assert_eq!(array_literal::<i32>(Vec::new()), "{}");
+        assert_eq!(
+            array_literal(vec![
+                Some("plain"),
+                Some("a b"),
+                Some(""),
+                Some("say \"hi\"")
+            ]),
+            r#"{plain,"a b","","say \"hi\""}"#
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres server in DATABASE_URL"]
+    async fn test_domain_values_decode_as_their_base_type() {
+        use sqlx::Connection;
+
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let mut conn = PgConnection::connect(&url).await.unwrap();
+        // The transaction is rolled back when dropped, taking the domains with it.
+        let mut tx = conn.begin().await.unwrap();
+        tx.execute(
+            "CREATE DOMAIN positive AS integer CHECK (VALUE > 0);
+             CREATE DOMAIN small_positive AS positive CHECK (VALUE < 10);
+             CREATE DOMAIN label AS text;
+             CREATE DOMAIN price AS numeric(10, 2);",
+        )
+        .await
+        .unwrap();
+
+        let mut executor = PostgresExecutor::new(&mut tx);
+        let rows = executor
+            .fetch(
+                "SELECT 5::positive, 3::small_positive, 'x'::label, 1.5::price, NULL::positive",
+                1,
+            )
+            .await
+            .unwrap();
+        let values = (0..5)
+            .map(|index| executor.get_cell_value(&rows[0], index))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                CellValue::Int(5),
+                CellValue::Int(3),
+                CellValue::Text("x".to_string()),
+                CellValue::Text("1.50".to_string()),
+                CellValue::Null
+            ]
+        );
     }
 }
@@ -0,0 +1,285 @@
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A single decoded cell, independent of the database driver that produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    Json(Value),
+    Bytes(Vec<u8>),
+}
+
+impl CellValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, CellValue::Null)
+    }
+
+    /// Converts the cell into a typed JSON value, keeping numbers and booleans unquoted.
+    pub fn to_json(&self) -> Value {
+        match self {
+            CellValue::Text(s) => Value::String(s.clone()),
+            CellValue::Int(i) => Value::from(*i),
+            CellValue::Float(n) => serde_json::Number::from_f64(*n)
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            CellValue::Bool(b) => Value::Bool(*b),
+            CellValue::Null => Value::Null,
+            CellValue::Json(val) => val.clone(),
+            CellValue::Bytes(bytes) => Value::String(hex::encode(bytes)),
+        }
+    }
+
+    /// Orders cells for sorting a column: `NULL` first, then booleans, numbers, text, JSON
+    /// and bytes. Integers, floats and text holding a number all compare by value, with
+    /// NaN after every other number. Unlike comparing floats or strings that may be
+    /// numbers, this is a total order, so a column of mixed values still sorts
+    /// consistently.
+    pub fn sort_cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+
+    fn sort_key(&self) -> SortKey<'_> {
+        match self {
+            CellValue::Null => SortKey::Null,
+            CellValue::Bool(b) => SortKey::Bool(*b),
+            CellValue::Int(i) => SortKey::Number(Number::Int(*i)),
+            CellValue::Float(n) => SortKey::Number(Number::Float(*n)),
+            CellValue::Text(s) => match (s.trim().parse::<i64>(), s.trim().parse::<f64>()) {
+                (Ok(i), _) => SortKey::Number(Number::Int(i)),
+                (_, Ok(n)) => SortKey::Number(Number::Float(n)),
+                _ => SortKey::Text(s),
+            },
+            CellValue::Json(val) => SortKey::Json(val.to_string()),
+            CellValue::Bytes(bytes) => SortKey::Bytes(bytes),
+        }
+    }
+}
+
+/// What a cell sorts by; variants sort in the order they are declared.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey<'a> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    Text(&'a str),
+    Json(String),
+    Bytes(&'a [u8]),
+}
+
+/// An integer or a float, compared exactly by value, with all NaNs equal and greatest.
+#[derive(Clone, Copy)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Ord for Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (*self, *other) {
+            (Number::Int(a), Number::Int(b)) => a.cmp(&b),
+            (Number::Float(a), Number::Float(b)) => match (a.is_nan(), b.is_nan()) {
+                (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (nan_a, nan_b) => nan_a.cmp(&nan_b),
+            },
+            (Number::Int(a), Number::Float(b)) => compare_int_float(a, b),
+            (Number::Float(a), Number::Int(b)) => compare_int_float(b, a).reverse(),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Number {}
+
+/// Compares an integer with a float without rounding the integer to the nearest float.
+fn compare_int_float(int: i64, float: f64) -> Ordering {
+    // i64 spans [-2^63, 2^63), both ends exact as floats.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() || float >= LIMIT {
+        return Ordering::Less;
+    }
+    if float < -LIMIT {
+        return Ordering::Greater;
+    }
+    let whole = float.trunc();
+    int.cmp(&(whole as i64))
+        .then_with(|| 0.0.partial_cmp(&(float - whole)).unwrap_or(Ordering::Equal))
+}
+
+impl fmt::Display for CellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellValue::Text(s) => write!(f, "{s}"),
+            CellValue::Int(i) => write!(f, "{i}"),
+            CellValue::Float(n) => write!(f, "{n}"),
+            CellValue::Bool(b) => write!(f, "{b}"),
+            CellValue::Null => Ok(()),
+            CellValue::Json(val) => match serde_json::to_string(val) {
+                Ok(s) => write!(f, "{s}"),
+                Err(e) => write!(f, "[json-error: {}]", e),
+            },
+            CellValue::Bytes(bytes) => write!(f, "{}", hex::encode(bytes)),
+        }
+    }
+}
+
+/// SQL type information for a result column.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnInfo {
+    pub type_name: String,
+    /// Whether the column may hold `NULL`, when the driver can tell.
+    pub nullable: Option<bool>,
+}
+
+/// Column headers and decoded rows of a query result.
+#[derive(Debug, Clone, Default)]
+pub struct ResultSet {
+    pub headers: Vec<String>,
+    /// Type information for each header, when known.
+    pub columns: Vec<ColumnInfo>,
+    pub rows: Vec<Vec<CellValue>>,
+    /// Whether more rows were available than the fetch limit allowed.
+    pub truncated: bool,
+}
+
+impl ResultSet {
+    pub fn new(headers: Vec<String>, rows: Vec<Vec<CellValue>>) -> Self {
+        Self {
+            headers,
+            columns: Vec::new(),
+            rows,
+            truncated: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> CellValue {
+        CellValue::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_sort_cmp_compares_numbers_by_value() {
+        assert_eq!(text("9").sort_cmp(&text("10")), Ordering::Less);
+        assert_eq!(text("-1.5").sort_cmp(&text("-2")), Ordering::Greater);
+        assert_eq!(text("3").sort_cmp(&text("3.0")), Ordering::Equal);
+        assert_eq!(
+            CellValue::Int(3).sort_cmp(&CellValue::Float(2.5)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            CellValue::Int(i64::MAX).sort_cmp(&CellValue::Float(9.223372036854776e18)),
+            Ordering::Less
+        );
+        assert_eq!(
+            CellValue::Float(-0.0).sort_cmp(&CellValue::Int(0)),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn test_sort_cmp_compares_text_lexicographically() {
+        assert_eq!(text("apple").sort_cmp(&text("banana")), Ordering::Less);
+        assert_eq!(text("10").sort_cmp(&text("abc")), Ordering::Less);
+        assert_eq!(text("").sort_cmp(&text("a")), Ordering::Less);
+    }
+
+    #[test]
+    fn test_sort_cmp_is_a_total_order_over_mixed_values() {
+        let mut cells = vec![
+            text("b"),
+            CellValue::Float(f64::NAN),
+            CellValue::Bytes(vec![1]),
+            CellValue::Int(2),
+            CellValue::Null,
+            text("1e1"),
+            CellValue::Float(f64::NEG_INFINITY),
+            CellValue::Bool(true),
+            text("NaN"),
+            CellValue::Json(serde_json::json!({"a": 1})),
+            text("1f"),
+            CellValue::Float(2.5),
+        ];
+        for a in &cells {
+            assert_eq!(a.sort_cmp(a), Ordering::Equal);
+            for b in &cells {
+                assert_eq!(a.sort_cmp(b), b.sort_cmp(a).reverse());
+                for c in &cells {
+                    if a.sort_cmp(b) != Ordering::Greater && b.sort_cmp(c) != Ordering::Greater {
+                        assert_ne!(a.sort_cmp(c), Ordering::Greater);
+                    }
+                }
+            }
+        }
+
+        cells.sort_by(CellValue::sort_cmp);
+        let sorted: Vec<String> = cells.iter().map(CellValue::to_string).collect();
+        assert_eq!(
+            sorted,
+            [
+                "",
+                "true",
+                "-inf",
+                "2",
+                "2.5",
+                "1e1",
+                "NaN",
+                "NaN",
+                "1f",
+                "b",
+                "{\"a\":1}",
+                "01"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cell_value_display() {
+        assert_eq!(CellValue::Text("abc".to_string()).to_string(), "abc");
+        assert_eq!(CellValue::Int(42).to_string(), "42");
+        assert_eq!(CellValue::Float(1.5).to_string(), "1.5");
+        assert_eq!(CellValue::Bool(true).to_string(), "true");
+        assert_eq!(CellValue::Null.to_string(), "");
+        assert_eq!(CellValue::Bytes(vec![0xde, 0xad]).to_string(), "dead");
+        assert_eq!(
+            CellValue::Json(serde_json::json!({"a": 1})).to_string(),
+            r#"{"a":1}"#
+        );
+    }
+
+    #[test]
+    fn test_cell_value_to_json() {
+        assert_eq!(CellValue::Int(7).to_json(), serde_json::json!(7));
+        assert_eq!(CellValue::Bool(false).to_json(), serde_json::json!(false));
+        assert_eq!(CellValue::Null.to_json(), Value::Null);
+        assert_eq!(CellValue::Float(f64::NAN).to_json(), Value::Null);
+        assert_eq!(
+            CellValue::Text("x".to_string()).to_json(),
+            serde_json::json!("x")
+        );
+    }
+
+    #[test]
+    fn test_cell_value_is_null() {
+        assert!(CellValue::Null.is_null());
+        assert!(!CellValue::Text(String::new()).is_null());
+    }
+}
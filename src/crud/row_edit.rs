@@ -0,0 +1,308 @@
+use super::result_set::CellValue;
+use crate::database::connector::{DatabaseType, quote_ident};
+use std::borrow::Cow;
+
+/// A primary key column of the row being edited, with its value in that row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyColumn {
+    pub name: String,
+    /// SQL type of the column as reported with the results, if known.
+    pub type_name: Option<String>,
+    pub value: CellValue,
+}
+
+/// A new value for one cell of a table, written back with an `UPDATE` of the row that
+/// has the cell's primary key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellUpdate {
+    /// The table as written in the query the results came from.
+    pub table: String,
+    pub column: String,
+    pub type_name: Option<String>,
+    /// The value being typed, starting from the current one; `None` sets the cell to
+    /// `NULL`.
+    pub value: Option<String>,
+    pub original: CellValue,
+    pub key: Vec<KeyColumn>,
+    /// Row and column of the cell within the displayed results.
+    pub position: (usize, usize),
+}
+
+impl CellUpdate {
+    /// The `UPDATE` statement and its parameters, in placeholder order. Values are always
+    /// bound as text rather than spliced into the statement; Postgres casts them back to
+    /// the column's type, while MySQL and SQLite convert them on their own.
+    pub fn to_sql(&self, db_type: DatabaseType) -> (String, Vec<Option<String>>) {
+        let set = format!(
+            "{} = {}",
+            quote_ident(db_type, &self.column),
+            placeholder(db_type, 1, &self.type_name)
+        );
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {}",
+            self.table,
+            set,
+            key_conditions(db_type, &self.key, 2)
+        );
+        let params = std::iter::once(self.value.clone())
+            .chain(key_params(&self.key))
+            .collect();
+        (sql, params)
+    }
+
+    /// Names the row being edited by its key, e.g. `id = 7`.
+    pub fn describe_row(&self) -> String {
+        describe_key(&self.key)
+    }
+}
+
+/// A row of a table to remove with a `DELETE` of the row that has its primary key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowDelete {
+    /// The table as written in the query the results came from.
+    pub table: String,
+    pub key: Vec<KeyColumn>,
+    /// Index of the row within the displayed results.
+    pub row: usize,
+}
+
+impl RowDelete {
+    /// The `DELETE` statement and its parameters, bound the same way as for [`CellUpdate`].
+    pub fn to_sql(&self, db_type: DatabaseType) -> (String, Vec<Option<String>>) {
+        let sql = format!(
+            "DELETE FROM {} WHERE {}",
+            self.table,
+            key_conditions(db_type, &self.key, 1)
+        );
+        (sql, key_params(&self.key).collect())
+    }
+
+    /// Names the row being deleted by its key, e.g. `id = 7`.
+    pub fn describe_row(&self) -> String {
+        describe_key(&self.key)
+    }
+}
+
+/// A new row for a table, written with an `INSERT` of the columns given a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowInsert {
+    /// The table name, quoted and qualified with its schema if it has one.
+    pub table: String,
+    /// Columns given a value, with their SQL type and the value typed for them, or `None`
+    /// for `NULL`.
+    pub values: Vec<(String, Option<String>, Option<String>)>,
+}
+
+impl RowInsert {
+    /// The `INSERT` statement and its parameters, bound the same way as for [`CellUpdate`].
+    /// Without any values, the row is made of the defaults alone.
+    pub fn to_sql(&self, db_type: DatabaseType) -> (String, Vec<Option<String>>) {
+        if self.values.is_empty() {
+            let sql = match db_type {
+                DatabaseType::MySQL => format!("INSERT INTO {} () VALUES ()", self.table),
+                DatabaseType::PostgreSQL | DatabaseType::SQLite => {
+                    format!("INSERT INTO {} DEFAULT VALUES", self.table)
+                }
+            };
+            return (sql, Vec::new());
+        }
+        let columns = self
+            .values
+            .iter()
+            .map(|(name, _, _)| quote_ident(db_type, name))
+            .collect::<Vec<_>>();
+        let placeholders = self
+            .values
+            .iter()
+            .enumerate()
+            .map(|(index, (_, type_name, _))| placeholder(db_type, index + 1, type_name))
+            .collect::<Vec<_>>();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            self.table,
+            columns.join(", "),
+            placeholders.join(", ")
+        );
+        let params = self
+            .values
+            .iter()
+            .map(|(_, _, value)| value.clone())
+            .collect();
+        (sql, params)
+    }
+}
+
+/// The placeholder for parameter `number`, cast to `type_name` on Postgres.
+fn placeholder(db_type: DatabaseType, number: usize, type_name: &Option<String>) -> String {
+    match (db_type, type_name) {
+        (DatabaseType::PostgreSQL, Some(type_name)) => {
+            format!("CAST(${} AS {})", number, cast_type(type_name))
+        }
+        (DatabaseType::PostgreSQL, None) => format!("${}", number),
+        (DatabaseType::MySQL | DatabaseType::SQLite, _) => "?".to_string(),
+    }
+}
+
+/// `type_name` as written in a `CAST`. Type names such as `INT4`, `numeric(10,2)`,
+/// `character varying` or `text[]` are used as they are; anything else is quoted, so that
+/// a type name can never add to the statement.
+fn cast_type(type_name: &str) -> Cow<'_, str> {
+    let plain = type_name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ' ' | '(' | ')' | ',' | '[' | ']'));
+    let quoted = type_name.len() > 2
+        && type_name.starts_with('"')
+        && type_name.ends_with('"')
+        && !type_name[1..type_name.len() - 1].contains('"');
+    if plain || quoted {
+        Cow::Borrowed(type_name)
+    } else {
+        Cow::Owned(quote_ident(DatabaseType::PostgreSQL, type_name))
+    }
+}
+
+/// The `WHERE` conditions matching `key`, numbering its placeholders from `first`.
+fn key_conditions(db_type: DatabaseType, key: &[KeyColumn], first: usize) -> String {
+    key.iter()
+        .enumerate()
+        .map(|(index, column)| {
+            format!(
+                "{} = {}",
+                quote_ident(db_type, &column.name),
+                placeholder(db_type, first + index, &column.type_name)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn key_params(key: &[KeyColumn]) -> impl Iterator<Item = Option<String>> + '_ {
+    key.iter().map(|column| Some(column.value.to_string()))
+}
+
+fn describe_key(key: &[KeyColumn]) -> String {
+    key.iter()
+        .map(|column| format!("{} = {}", column.name, column.value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update() -> CellUpdate {
+        CellUpdate {
+            table: "public.users".to_string(),
+            column: "name".to_string(),
+            type_name: Some("TEXT".to_string()),
+            value: Some("O'Brien".to_string()),
+            original: CellValue::Text("Obrien".to_string()),
+            key: vec![
+                KeyColumn {
+                    name: "org_id".to_string(),
+                    type_name: Some("INT4".to_string()),
+                    value: CellValue::Int(3),
+                },
+                KeyColumn {
+                    name: "id".to_string(),
+                    type_name: Some("UUID".to_string()),
+                    value: CellValue::Text("5f0c".to_string()),
+                },
+            ],
+            position: (0, 1),
+        }
+    }
+
+    #[test]
+    fn test_binds_values_instead_of_splicing_them() {
+        let expected_params = vec![
+            Some("O'Brien".to_string()),
+            Some("3".to_string()),
+            Some("5f0c".to_string()),
+        ];
+        assert_eq!(
+            update().to_sql(DatabaseType::PostgreSQL),
+            (
+                "UPDATE public.users SET \"name\" = CAST($1 AS TEXT) WHERE \"org_id\" = CAST($2 AS INT4) AND \"id\" = CAST($3 AS UUID)".to_string(),
+                expected_params.clone()
+            )
+        );
+        assert_eq!(
+            update().to_sql(DatabaseType::MySQL),
+            (
+                "UPDATE public.users SET `name` = ? WHERE `org_id` = ? AND `id` = ?".to_string(),
+                expected_params
+            )
+        );
+        assert_eq!(update().describe_row(), "org_id = 3, id = 5f0c");
+    }
+
+    #[test]
+    fn test_sets_null_and_quotes_odd_type_names() {
+        let mut update = update();
+        update.value = None;
+        update.type_name = Some("mood\" AS text); DROP TABLE users; --".to_string());
+        update.key.truncate(1);
+        assert_eq!(
+            update.to_sql(DatabaseType::PostgreSQL),
+            (
+                "UPDATE public.users SET \"name\" = CAST($1 AS \"mood\"\" AS text); DROP TABLE users; --\") WHERE \"org_id\" = CAST($2 AS INT4)".to_string(),
+                vec![None, Some("3".to_string())]
+            )
+        );
+        assert_eq!(cast_type("character varying(20)"), "character varying(20)");
+        assert_eq!(cast_type("INT4[]"), "INT4[]");
+        assert_eq!(cast_type("\"CHAR\""), "\"CHAR\"");
+    }
+
+    #[test]
+    fn test_insert_binds_the_given_columns() {
+        let insert = RowInsert {
+            table: "\"public\".\"users\"".to_string(),
+            values: vec![
+                (
+                    "id".to_string(),
+                    Some("integer".to_string()),
+                    Some("7".to_string()),
+                ),
+                ("name".to_string(), Some("text".to_string()), None),
+            ],
+        };
+        assert_eq!(
+            insert.to_sql(DatabaseType::PostgreSQL),
+            (
+                "INSERT INTO \"public\".\"users\" (\"id\", \"name\") VALUES (CAST($1 AS integer), CAST($2 AS text))".to_string(),
+                vec![Some("7".to_string()), None]
+            )
+        );
+        let defaults = RowInsert {
+            table: "`users`".to_string(),
+            values: Vec::new(),
+        };
+        assert_eq!(
+            defaults.to_sql(DatabaseType::MySQL).0,
+            "INSERT INTO `users` () VALUES ()"
+        );
+    }
+
+    #[test]
+    fn test_delete_matches_the_whole_key() {
+        let delete = RowDelete {
+            table: "users".to_string(),
+            key: update().key,
+            row: 0,
+        };
+        assert_eq!(
+            delete.to_sql(DatabaseType::PostgreSQL),
+            (
+                "DELETE FROM users WHERE \"org_id\" = CAST($1 AS INT4) AND \"id\" = CAST($2 AS UUID)".to_string(),
+                vec![Some("3".to_string()), Some("5f0c".to_string())]
+            )
+        );
+        assert_eq!(
+            delete.to_sql(DatabaseType::SQLite).0,
+            "DELETE FROM users WHERE \"org_id\" = ? AND \"id\" = ?"
+        );
+    }
+}
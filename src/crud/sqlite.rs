@@ -0,0 +1,104 @@
+use super::executor::{AffectedRows, DatabaseExecutor, collect_rows};
+use super::result_set::CellValue;
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::{
+    Executor, Row, SqliteConnection,
+    sqlite::SqliteRow,
+    types::{Json, chrono},
+};
+
+pub struct SqliteExecutor<'c> {
+    conn: &'c mut SqliteConnection,
+}
+
+impl<'c> SqliteExecutor<'c> {
+    pub fn new(conn: &'c mut SqliteConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl DatabaseExecutor for SqliteExecutor<'_> {
+    type Row = SqliteRow;
+
+    async fn fetch(&mut self, query: &str, limit: usize) -> Result<Vec<SqliteRow>, sqlx::Error> {
+        collect_rows(sqlx::query(query).fetch(&mut *self.conn), limit).await
+    }
+
+    async fn execute(&mut self, query: &str) -> Result<u64, sqlx::Error> {
+        Ok(self
+            .conn
+            .execute(sqlx::raw_sql(query))
+            .await?
+            .rows_affected())
+    }
+
+    async fn column_nullability(&mut self, query: &str) -> Vec<Option<bool>> {
+        match (&mut *self.conn).describe(query).await {
+            Ok(describe) => (0..describe.columns().len())
+                .map(|i| describe.nullable(i))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    async fn insert(&mut self, query: &str) -> Result<AffectedRows, sqlx::Error> {
+        let result = self.conn.execute(sqlx::raw_sql(query)).await?;
+        let rows = result.rows_affected();
+        // The rowid is left over from an earlier insert when nothing was written.
+        let last_insert_id = (rows > 0).then(|| result.last_insert_rowid());
+        Ok(AffectedRows {
+            rows,
+            last_insert_id,
+        })
+    }
+
+    async fn update(&mut self, query: &str) -> Result<u64, sqlx::Error> {
+        self.execute(query).await
+    }
+
+    async fn delete(&mut self, query: &str) -> Result<u64, sqlx::Error> {
+        self.execute(query).await
+    }
+
+    async fn update_with_params(
+        &mut self,
+        query: &str,
+        params: &[Option<String>],
+    ) -> Result<u64, sqlx::Error> {
+        let mut query = sqlx::query(query);
+        for param in params {
+            query = query.bind(param.as_deref());
+        }
+        Ok(query.execute(&mut *self.conn).await?.rows_affected())
+    }
+
+    fn get_cell_value(&self, row: &SqliteRow, index: usize) -> CellValue {
+        macro_rules! try_get_cell {
+            ($map:expr => $($type:ty),*) => {
+                $(
+                    if let Ok(val) = row.try_get::<Option<$type>, _>(index) {
+                        return val.map_or(CellValue::Null, $map);
+                    }
+                )*
+            };
+        }
+
+        try_get_cell!(CellValue::Text => String);
+        try_get_cell!(CellValue::Int => i64);
+        try_get_cell!(CellValue::Float => f64);
+        try_get_cell!(CellValue::Bool => bool);
+        try_get_cell!(
+            |v| CellValue::Text(v.to_string()) =>
+            chrono::NaiveDate,
+            chrono::NaiveDateTime,
+            chrono::NaiveTime,
+            chrono::DateTime<chrono::Utc>
+        );
+        try_get_cell!(|Json(v)| CellValue::Json(v) => Json<Value>);
+        try_get_cell!(CellValue::Bytes => Vec<u8>);
+
+        CellValue::Text(String::new())
+    }
+}
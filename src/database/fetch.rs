@@ -1,3 +1,4 @@
+use super::connector::{DatabaseType, quote_ident};
 use super::pool::DbPool;
 use color_eyre::eyre::Result;
 use ratatui::text::Text;
@@ -9,18 +10,102 @@ use tui_tree_widget::TreeItem;
 pub struct Database {
     pub name: String,
     pub tables: Vec<Table>,
+    /// Whether the table list is being fetched in the background.
+    pub loading: bool,
+}
+
+/// What kind of relation a sidebar entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableKind {
+    #[default]
+    Table,
+    View,
+    MaterializedView,
+    ForeignTable,
+}
+
+impl TableKind {
+    const ALL: [TableKind; 4] = [
+        TableKind::Table,
+        TableKind::View,
+        TableKind::MaterializedView,
+        TableKind::ForeignTable,
+    ];
+
+    /// Maps a Postgres `pg_class.relkind` code.
+    fn from_pg_relkind(relkind: &str) -> Self {
+        match relkind {
+            "v" => TableKind::View,
+            "m" => TableKind::MaterializedView,
+            "f" => TableKind::ForeignTable,
+            _ => TableKind::Table,
+        }
+    }
+
+    fn group_label(self) -> &'static str {
+        match self {
+            TableKind::Table => "Tables",
+            TableKind::View => "Views",
+            TableKind::MaterializedView => "Materialized Views",
+            TableKind::ForeignTable => "Foreign Tables",
+        }
+    }
+
+    fn group_id(self) -> &'static str {
+        match self {
+            TableKind::Table => "tables",
+            TableKind::View => "views",
+            TableKind::MaterializedView => "matviews",
+            TableKind::ForeignTable => "foreign",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Table {
     pub name: String,
+    /// Schema the table lives in, for databases that have schemas.
+    pub schema: Option<String>,
+    pub kind: TableKind,
     pub metadata: Option<TableMetadata>,
+    /// Whether the table details are being fetched in the background.
+    pub loading: bool,
+    /// Row count from an explicit `COUNT(*)`, once one has been run.
+    pub exact_row_count: Option<i64>,
+    /// Whether an exact row count is being fetched in the background.
+    pub counting: bool,
+}
+
+/// How many rows a table holds, and how much that number can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowCount {
+    /// The database keeps no cheap statistic for this table.
+    Unknown,
+    /// Planner statistics, which may lag behind the real count.
+    Estimate(i64),
+    Exact(i64),
+}
+
+impl RowCount {
+    /// Describes the count for a sidebar label, or `None` when nothing is known.
+    pub fn label(self) -> Option<String> {
+        let plural = |n: i64| if n == 1 { "" } else { "s" };
+        match self {
+            RowCount::Unknown => None,
+            RowCount::Estimate(n) => Some(format!("~{} row{}, estimate", n, plural(n))),
+            RowCount::Exact(n) => Some(format!("{} row{}, exact", n, plural(n))),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Column {
     pub name: String,
     pub data_type: String,
+    /// Whether the column accepts `NULL`.
+    pub nullable: bool,
+    /// The default expression, or how the database generates the value.
+    pub default: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -29,15 +114,24 @@ pub struct TableMetadata {
     pub name: String,
     pub columns: Vec<Column>,
     pub constraints: Vec<String>,
+    /// Columns of the primary key in key order, empty when the table has none.
+    pub primary_key: Vec<String>,
     pub indexes: Vec<String>,
     pub rls_policies: Vec<String>,
     pub rules: Vec<String>,
     pub triggers: Vec<String>,
-    pub row_count: i64,
+    pub row_count: RowCount,
     pub estimated_size: String,
     pub table_type: String,
 }
 
+impl TableMetadata {
+    /// Columns of the primary key, if the table has one.
+    pub fn primary_key(&self) -> Option<Vec<String>> {
+        (!self.primary_key.is_empty()).then(|| self.primary_key.clone())
+    }
+}
+
 pub trait Displayable {
     fn to_string(&self) -> String;
     fn name(&self) -> String;
@@ -64,16 +158,28 @@ impl Displayable for String {
 #[async_trait::async_trait]
 pub trait MetadataFetcher: Send + Sync {
     async fn fetch_tables(&self) -> Result<Vec<Table>>;
-    async fn fetch_table_metadata(&self, table_name: &str) -> Result<TableMetadata>;
+    async fn fetch_table_metadata(
+        &self,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableMetadata>;
     async fn fetch_databases(&self) -> Result<Vec<String>>;
 }
 
 #[async_trait::async_trait]
 impl MetadataFetcher for PgPool {
     async fn fetch_tables(&self) -> Result<Vec<Table>> {
+        let schemas = get_pg_schemas(self).await?;
         let rows = sqlx::query(
-            "SELECT table_name FROM information_schema.tables WHERE table_schema = 'public' ORDER BY table_name ASC",
+            r#"
+                SELECT n.nspname AS table_schema, c.relname AS table_name, c.relkind::text AS relkind
+                FROM pg_class c
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE n.nspname = ANY($1) AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                ORDER BY n.nspname ASC, c.relname ASC
+            "#,
         )
+        .bind(&schemas)
         .fetch_all(self)
         .await?;
 
@@ -81,12 +187,22 @@ impl MetadataFetcher for PgPool {
             .into_iter()
             .map(|row| Table {
                 name: row.get("table_name"),
+                schema: Some(row.get("table_schema")),
+                kind: TableKind::from_pg_relkind(row.get("relkind")),
                 metadata: None,
+                loading: false,
+                exact_row_count: None,
+                counting: false,
             })
             .collect())
     }
 
-    async fn fetch_table_metadata(&self, table_name: &str) -> Result<TableMetadata> {
+    async fn fetch_table_metadata(
+        &self,
+        schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableMetadata> {
+        let schema = schema.unwrap_or("public");
         let row = sqlx::query(
             r#"
                 SELECT
@@ -98,6 +214,7 @@ impl MetadataFetcher for PgPool {
                     pg_size_pretty(pg_total_relation_size(c.oid)) AS total_size,
                     CASE c.relkind
                         WHEN 'r' THEN 'table'
+                        WHEN 'p' THEN 'table'
                         WHEN 'v' THEN 'view'
                         WHEN 'm' THEN 'materialized view'
                         WHEN 'f' THEN 'foreign table'
@@ -105,29 +222,31 @@ impl MetadataFetcher for PgPool {
                     END AS table_type
                 FROM pg_class c
                 JOIN pg_namespace n ON n.oid = c.relnamespace
-                WHERE n.nspname = 'public' AND c.relkind IN ('r', 'v', 'm', 'f') AND c.relname = $1
+                WHERE n.nspname = $2 AND c.relkind IN ('r', 'p', 'v', 'm', 'f') AND c.relname = $1
             "#,
         )
         .bind(table_name)
+        .bind(schema)
         .fetch_one(self)
         .await?;
 
         let table_name: String = row.get("table_name");
-        let row_count: i64 = row.get("row_estimate");
+        let row_count = RowCount::Estimate(row.get("row_estimate"));
         let estimated_size: String = row.get("total_size");
         let table_type: String = row.get("table_type");
 
-        let columns = get_pg_columns(self, &table_name).await?;
-        let constraints = get_pg_constraints(self, &table_name).await?;
-        let indexes = get_pg_indexes(self, &table_name).await?;
-        let rls_policies = get_pg_rls_policies(self, &table_name).await?;
-        let rules = get_pg_rules(self, &table_name).await?;
-        let triggers = get_pg_triggers(self, &table_name).await?;
+        let columns = get_pg_columns(self, schema, &table_name).await?;
+        let (constraints, primary_key) = get_pg_constraints(self, schema, &table_name).await?;
+        let indexes = get_pg_indexes(self, schema, &table_name).await?;
+        let rls_policies = get_pg_rls_policies(self, schema, &table_name).await?;
+        let rules = get_pg_rules(self, schema, &table_name).await?;
+        let triggers = get_pg_triggers(self, schema, &table_name).await?;
 
         Ok(TableMetadata {
             name: table_name,
             columns,
             constraints,
+            primary_key,
             indexes,
             rls_policies,
             rules,
@@ -149,26 +268,42 @@ impl MetadataFetcher for PgPool {
 #[async_trait::async_trait]
 impl MetadataFetcher for MySqlPool {
     async fn fetch_tables(&self) -> Result<Vec<Table>> {
-        let rows = sqlx::query("SHOW TABLES ORDER BY table_name ASC")
-            .fetch_all(self)
-            .await?;
-        Ok(rows
+        let rows = sqlx::query("SHOW FULL TABLES").fetch_all(self).await?;
+        let mut tables: Vec<Table> = rows
             .into_iter()
             .map(|row| Table {
                 name: row.get(0),
+                schema: None,
+                kind: match row.get::<String, _>(1).as_str() {
+                    "VIEW" => TableKind::View,
+                    _ => TableKind::Table,
+                },
                 metadata: None,
+                loading: false,
+                exact_row_count: None,
+                counting: false,
             })
-            .collect())
+            .collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tables)
     }
 
-    async fn fetch_table_metadata(&self, table_name: &str) -> Result<TableMetadata> {
+    async fn fetch_table_metadata(
+        &self,
+        _schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableMetadata> {
         let row = sqlx::query("SHOW TABLE STATUS WHERE Name = ?")
             .bind(table_name)
             .fetch_one(self)
             .await?;
 
         let table_name: String = row.get("Name");
-        let row_count: i64 = row.try_get("Rows").unwrap_or(0);
+        // InnoDB only keeps an approximate count, and views report none at all.
+        let row_count = match row.try_get::<Option<i64>, _>("Rows") {
+            Ok(Some(rows)) => RowCount::Estimate(rows),
+            _ => RowCount::Unknown,
+        };
         let estimated_size: String = {
             let data_length: i64 = row.try_get("Data_length").unwrap_or(0);
             let index_length: i64 = row.try_get("Index_length").unwrap_or(0);
@@ -176,15 +311,32 @@ impl MetadataFetcher for MySqlPool {
         };
         let table_type: String = row.try_get("Comment").unwrap_or("".to_string());
 
-        let columns = sqlx::query(&format!("SHOW COLUMNS FROM `{}`", table_name))
-            .fetch_all(self)
-            .await?
-            .into_iter()
-            .map(|r| Column {
+        let columns = sqlx::query(&format!(
+            "SHOW COLUMNS FROM {}",
+            quote_ident(DatabaseType::MySQL, &table_name)
+        ))
+        .fetch_all(self)
+        .await?
+        .into_iter()
+        .map(|r| {
+            let extra: String = r.try_get("Extra").unwrap_or_default();
+            Column {
                 name: r.get("Field"),
                 data_type: r.get("Type"),
-            })
-            .collect();
+                nullable: r
+                    .try_get::<String, _>("Null")
+                    .is_ok_and(|null| null == "YES"),
+                default: r
+                    .try_get::<Option<String>, _>("Default")
+                    .ok()
+                    .flatten()
+                    .or_else(|| extra.contains("auto_increment").then_some(extra)),
+            }
+        })
+        .collect();
+
+        let (constraints, primary_key) = get_mysql_constraints(self, &table_name).await?;
+        let indexes = get_mysql_indexes(self, &table_name).await?;
 
         let triggers = sqlx::query("SHOW TRIGGERS WHERE `Table` = ?")
             .bind(&table_name)
@@ -197,8 +349,9 @@ impl MetadataFetcher for MySqlPool {
         Ok(TableMetadata {
             name: table_name,
             columns,
-            constraints: vec![],
-            indexes: vec![],
+            constraints,
+            primary_key,
+            indexes,
             rls_policies: vec![],
             rules: vec![],
             triggers,
@@ -225,34 +378,62 @@ impl MetadataFetcher for MySqlPool {
 impl MetadataFetcher for SqlitePool {
     async fn fetch_tables(&self) -> Result<Vec<Table>> {
         let rows =
-            sqlx::query("SELECT name FROM sqlite_master WHERE type='table' ORDER BY name ASC")
+            sqlx::query("SELECT name, type FROM sqlite_master WHERE type IN ('table', 'view') ORDER BY name ASC")
                 .fetch_all(self)
                 .await?;
         Ok(rows
             .into_iter()
             .map(|row| Table {
                 name: row.get("name"),
+                schema: None,
+                kind: match row.get::<String, _>("type").as_str() {
+                    "view" => TableKind::View,
+                    _ => TableKind::Table,
+                },
                 metadata: None,
+                loading: false,
+                exact_row_count: None,
+                counting: false,
             })
             .collect())
     }
 
-    async fn fetch_table_metadata(&self, table_name: &str) -> Result<TableMetadata> {
-        let columns_rows = sqlx::query(&format!("PRAGMA table_info('{}')", table_name))
-            .fetch_all(self)
-            .await?;
+    async fn fetch_table_metadata(
+        &self,
+        _schema: Option<&str>,
+        table_name: &str,
+    ) -> Result<TableMetadata> {
+        let columns_rows = sqlx::query(&format!(
+            "PRAGMA table_info({})",
+            quote_ident(DatabaseType::SQLite, table_name)
+        ))
+        .fetch_all(self)
+        .await?;
         let columns = columns_rows
             .iter()
             .map(|r| Column {
                 name: r.get("name"),
                 data_type: r.get("type"),
+                nullable: r.get::<i64, _>("notnull") == 0,
+                default: r.get("dflt_value"),
             })
             .collect();
 
-        let indexes_rows = sqlx::query(&format!("PRAGMA index_list('{}')", table_name))
-            .fetch_all(self)
-            .await?;
-        let indexes = indexes_rows.iter().map(|r| r.get("name")).collect();
+        let mut key_positions: Vec<(i64, String)> = columns_rows
+            .iter()
+            .map(|r| (r.get::<i64, _>("pk"), r.get::<String, _>("name")))
+            .filter(|(position, _)| *position > 0)
+            .collect();
+        key_positions.sort();
+        let primary_key: Vec<String> = key_positions.into_iter().map(|(_, name)| name).collect();
+        let mut constraints = Vec::new();
+        if !primary_key.is_empty() {
+            constraints.push(describe_constraint(None, "PRIMARY KEY", &primary_key, None));
+        }
+
+        let (indexes, unique_keys) = get_sqlite_indexes(self, table_name).await?;
+        constraints.extend(unique_keys);
+        constraints.extend(get_sqlite_foreign_keys(self, table_name).await?);
 
         let triggers_rows =
             sqlx::query("SELECT name FROM sqlite_master WHERE type='trigger' AND tbl_name=?")
@@ -264,12 +445,13 @@ impl MetadataFetcher for SqlitePool {
         Ok(TableMetadata {
             name: table_name.to_string(),
             columns,
-            constraints: vec![],
+            constraints,
+            primary_key,
             indexes,
             rls_policies: vec![],
             rules: vec![],
             triggers,
-            row_count: 0,
+            row_count: RowCount::Unknown,
             estimated_size: "N/A".to_string(),
             table_type: "table".to_string(),
         })
@@ -288,11 +470,67 @@ pub async fn fetch_tables(pool: &DbPool) -> Result<Vec<Table>> {
     }
 }
 
-pub async fn fetch_table_details(pool: &DbPool, table_name: &str) -> Result<TableMetadata> {
+pub async fn fetch_table_details(
+    pool: &DbPool,
+    schema: Option<&str>,
+    table_name: &str,
+) -> Result<TableMetadata> {
     match pool {
-        DbPool::Postgres(pg) => pg.fetch_table_metadata(table_name).await,
-        DbPool::MySQL(mysql) => mysql.fetch_table_metadata(table_name).await,
-        DbPool::SQLite(sqlite) => sqlite.fetch_table_metadata(table_name).await,
+        DbPool::Postgres(pg) => pg.fetch_table_metadata(schema, table_name).await,
+        DbPool::MySQL(mysql) => mysql.fetch_table_metadata(schema, table_name).await,
+        DbPool::SQLite(sqlite) => sqlite.fetch_table_metadata(schema, table_name).await,
+    }
+}
+
+/// Counts a table's rows exactly with `SELECT COUNT(*)`, which may scan the whole table.
+pub async fn count_rows(pool: &DbPool, schema: Option<&str>, table_name: &str) -> Result<i64> {
+    let quote = |name: &str| quote_ident(pool.get_type(), name);
+    let target = match schema {
+        Some(schema) => format!("{}.{}", quote(schema), quote(table_name)),
+        None => quote(table_name),
+    };
+    let sql = format!("SELECT COUNT(*) FROM {}", target);
+
+    let count = match pool {
+        DbPool::Postgres(pg) => sqlx::query_scalar(&sql).fetch_one(pg).await?,
+        DbPool::MySQL(mysql) => sqlx::query_scalar(&sql).fetch_one(mysql).await?,
+        DbPool::SQLite(sqlite) => sqlx::query_scalar(&sql).fetch_one(sqlite).await?,
+    };
+    Ok(count)
+}
+
+/// Returns the statement that creates a table or view, as the database reports it.
+///
+/// MySQL and SQLite keep the original DDL. Postgres has no equivalent for tables, so the
+/// statement is assembled from the catalog.
+pub async fn fetch_table_ddl(pool: &DbPool, table: &Table) -> Result<String> {
+    match pool {
+        DbPool::Postgres(pg) => {
+            get_pg_ddl(pg, table.schema.as_deref().unwrap_or("public"), table).await
+        }
+        DbPool::MySQL(mysql) => {
+            let row = sqlx::query(&format!(
+                "SHOW CREATE TABLE {}",
+                quote_ident(DatabaseType::MySQL, &table.name)
+            ))
+            .fetch_one(mysql)
+            .await?;
+            Ok(row.try_get(1)?)
+        }
+        DbPool::SQLite(sqlite) => {
+            // The table first, followed by its indexes and triggers.
+            let statements: Vec<String> = sqlx::query_scalar(
+                "SELECT sql FROM sqlite_master WHERE tbl_name = ? AND sql IS NOT NULL ORDER BY type NOT IN ('table', 'view'), name",
+            )
+            .bind(&table.name)
+            .fetch_all(sqlite)
+            .await?;
+            Ok(statements
+                .iter()
+                .map(|sql| format!("{};", sql))
+                .collect::<Vec<_>>()
+                .join("\n\n"))
+        }
     }
 }
 
@@ -304,11 +542,42 @@ pub async fn fetch_databases(pool: &DbPool) -> Result<Vec<String>> {
     }
 }
 
-async fn get_pg_columns(pool: &PgPool, table: &str) -> sqlx::Result<Vec<Column>> {
+/// Returns the version the server reports, such as `PostgreSQL 16.2 on x86_64-pc-linux-gnu`
+/// or `8.0.36`.
+pub async fn fetch_server_version(pool: &DbPool) -> Result<String> {
+    let sql = server_version_query(pool.get_type());
+    let version = match pool {
+        DbPool::Postgres(pg) => sqlx::query_scalar(sql).fetch_one(pg).await?,
+        DbPool::MySQL(mysql) => sqlx::query_scalar(sql).fetch_one(mysql).await?,
+        DbPool::SQLite(sqlite) => sqlx::query_scalar(sql).fetch_one(sqlite).await?,
+    };
+    Ok(version)
+}
+
+fn server_version_query(db_type: DatabaseType) -> &'static str {
+    match db_type {
+        DatabaseType::PostgreSQL => "SELECT version()",
+        DatabaseType::MySQL => "SELECT VERSION()",
+        DatabaseType::SQLite => "SELECT sqlite_version()",
+    }
+}
+
+/// Lists user schemas, leaving out the system catalogs.
+async fn get_pg_schemas(pool: &PgPool) -> sqlx::Result<Vec<String>> {
     let rows = sqlx::query(
-        "SELECT column_name, data_type FROM information_schema.columns WHERE table_schema = 'public' AND table_name = $1",
+        "SELECT schema_name FROM information_schema.schemata WHERE schema_name NOT IN ('information_schema', 'pg_catalog', 'pg_toast') AND schema_name NOT LIKE 'pg_temp_%' AND schema_name NOT LIKE 'pg_toast_temp_%' ORDER BY schema_name ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| r.get("schema_name")).collect())
+}
+
+async fn get_pg_columns(pool: &PgPool, schema: &str, table: &str) -> sqlx::Result<Vec<Column>> {
+    let rows = sqlx::query(
+        "SELECT column_name, CASE WHEN data_type IN ('USER-DEFINED', 'ARRAY') THEN udt_name::text ELSE data_type END AS data_type, is_nullable = 'YES' AS nullable, column_default, is_identity = 'YES' AS identity FROM information_schema.columns WHERE table_schema = $2 AND table_name = $1 ORDER BY ordinal_position",
     )
     .bind(table)
+    .bind(schema)
     .fetch_all(pool)
     .await?;
     Ok(rows
@@ -316,52 +585,414 @@ async fn get_pg_columns(pool: &PgPool, table: &str) -> sqlx::Result<Vec<Column>>
         .map(|r| Column {
             name: r.get("column_name"),
             data_type: r.get("data_type"),
+            nullable: r.get("nullable"),
+            default: r
+                .get::<Option<String>, _>("column_default")
+                .or_else(|| r.get::<bool, _>("identity").then(|| "identity".to_string())),
         })
         .collect())
 }
 
-async fn get_pg_constraints(pool: &PgPool, table: &str) -> sqlx::Result<Vec<String>> {
+/// Describes the table's key constraints, and returns the columns of its primary key.
+async fn get_pg_constraints(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+) -> sqlx::Result<(Vec<String>, Vec<String>)> {
     let rows = sqlx::query(
-        "SELECT constraint_name FROM information_schema.table_constraints WHERE table_name = $1 AND constraint_type != 'CHECK'",
+        r#"
+            SELECT
+                c.conname::text AS name,
+                CASE c.contype
+                    WHEN 'p' THEN 'PRIMARY KEY'
+                    WHEN 'u' THEN 'UNIQUE'
+                    WHEN 'f' THEN 'FOREIGN KEY'
+                    ELSE 'EXCLUDE'
+                END AS kind,
+                ARRAY(
+                    SELECT a.attname::text
+                    FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, position)
+                    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+                    ORDER BY k.position
+                ) AS columns,
+                r.relname::text AS referenced_table,
+                ARRAY(
+                    SELECT a.attname::text
+                    FROM unnest(c.confkey) WITH ORDINALITY AS k(attnum, position)
+                    JOIN pg_attribute a ON a.attrelid = c.confrelid AND a.attnum = k.attnum
+                    ORDER BY k.position
+                ) AS referenced_columns
+            FROM pg_constraint c
+            JOIN pg_class t ON t.oid = c.conrelid
+            JOIN pg_namespace n ON n.oid = t.relnamespace
+            LEFT JOIN pg_class r ON r.oid = c.confrelid
+            WHERE n.nspname = $2 AND t.relname = $1 AND c.contype IN ('p', 'u', 'f', 'x')
+            ORDER BY c.contype = 'f', c.conname
+        "#,
     )
     .bind(table)
+    .bind(schema)
     .fetch_all(pool)
     .await?;
-    Ok(rows.into_iter().map(|r| r.get("constraint_name")).collect())
+    let mut primary_key = Vec::new();
+    let constraints = rows
+        .into_iter()
+        .map(|r| {
+            let name: String = r.get("name");
+            let kind: String = r.get("kind");
+            let columns: Vec<String> = r.get("columns");
+            let referenced_table: Option<String> = r.get("referenced_table");
+            let referenced_columns: Vec<String> = r.get("referenced_columns");
+            let description = describe_constraint(
+                Some(&name),
+                &kind,
+                &columns,
+                referenced_table
+                    .as_deref()
+                    .map(|table| (table, referenced_columns.as_slice())),
+            );
+            if kind == "PRIMARY KEY" {
+                primary_key = columns;
+            }
+            description
+        })
+        .collect();
+    Ok((constraints, primary_key))
 }
 
-async fn get_pg_indexes(pool: &PgPool, table: &str) -> sqlx::Result<Vec<String>> {
-    let rows = sqlx::query("SELECT indexname FROM pg_indexes WHERE tablename = $1")
-        .bind(table)
-        .fetch_all(pool)
-        .await?;
+async fn get_pg_indexes(pool: &PgPool, schema: &str, table: &str) -> sqlx::Result<Vec<String>> {
+    let rows =
+        sqlx::query("SELECT indexname FROM pg_indexes WHERE schemaname = $2 AND tablename = $1")
+            .bind(table)
+            .bind(schema)
+            .fetch_all(pool)
+            .await?;
     Ok(rows.into_iter().map(|r| r.get("indexname")).collect())
 }
 
-async fn get_pg_rls_policies(pool: &PgPool, table: &str) -> sqlx::Result<Vec<String>> {
-    let rows = sqlx::query("SELECT policyname FROM pg_policies WHERE tablename = $1")
-        .bind(table)
-        .fetch_all(pool)
-        .await?;
+async fn get_pg_rls_policies(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+) -> sqlx::Result<Vec<String>> {
+    let rows =
+        sqlx::query("SELECT policyname FROM pg_policies WHERE schemaname = $2 AND tablename = $1")
+            .bind(table)
+            .bind(schema)
+            .fetch_all(pool)
+            .await?;
     Ok(rows.into_iter().map(|r| r.get("policyname")).collect())
 }
 
-async fn get_pg_rules(pool: &PgPool, table: &str) -> sqlx::Result<Vec<String>> {
-    let rows = sqlx::query("SELECT rulename FROM pg_rules WHERE tablename = $1")
-        .bind(table)
-        .fetch_all(pool)
-        .await?;
+async fn get_pg_rules(pool: &PgPool, schema: &str, table: &str) -> sqlx::Result<Vec<String>> {
+    let rows =
+        sqlx::query("SELECT rulename FROM pg_rules WHERE schemaname = $2 AND tablename = $1")
+            .bind(table)
+            .bind(schema)
+            .fetch_all(pool)
+            .await?;
     Ok(rows.into_iter().map(|r| r.get("rulename")).collect())
 }
 
-async fn get_pg_triggers(pool: &PgPool, table: &str) -> sqlx::Result<Vec<String>> {
-    let rows = sqlx::query("SELECT tgname FROM pg_trigger JOIN pg_class ON tgrelid = pg_class.oid WHERE relname = $1 AND NOT tgisinternal")
+async fn get_pg_triggers(pool: &PgPool, schema: &str, table: &str) -> sqlx::Result<Vec<String>> {
+    let rows = sqlx::query("SELECT tgname FROM pg_trigger JOIN pg_class ON tgrelid = pg_class.oid JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace WHERE relname = $1 AND nspname = $2 AND NOT tgisinternal")
         .bind(table)
+        .bind(schema)
         .fetch_all(pool)
         .await?;
     Ok(rows.into_iter().map(|r| r.get("tgname")).collect())
 }
 
+async fn get_pg_ddl(pool: &PgPool, schema: &str, table: &Table) -> Result<String> {
+    let qualified = format!(
+        "{}.{}",
+        quote_ident(DatabaseType::PostgreSQL, schema),
+        quote_ident(DatabaseType::PostgreSQL, &table.name)
+    );
+
+    if matches!(table.kind, TableKind::View | TableKind::MaterializedView) {
+        let definition: String = sqlx::query_scalar("SELECT pg_get_viewdef($1::regclass, true)")
+            .bind(&qualified)
+            .fetch_one(pool)
+            .await?;
+        let kind = match table.kind {
+            TableKind::MaterializedView => "MATERIALIZED VIEW",
+            _ => "VIEW",
+        };
+        return Ok(format!(
+            "CREATE {} {} AS\n{}",
+            kind,
+            qualified,
+            definition.trim_end()
+        ));
+    }
+
+    let columns = sqlx::query(
+        "SELECT a.attname::text AS name, format_type(a.atttypid, a.atttypmod) AS data_type, a.attnotnull AS not_null, pg_get_expr(d.adbin, d.adrelid) AS default_value FROM pg_attribute a LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum WHERE a.attrelid = $1::regclass AND a.attnum > 0 AND NOT a.attisdropped ORDER BY a.attnum",
+    )
+    .bind(&qualified)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|r| {
+        let mut definition = format!(
+            "{} {}",
+            quote_ident(DatabaseType::PostgreSQL, r.get("name")),
+            r.get::<String, _>("data_type")
+        );
+        if let Some(default) = r.get::<Option<String>, _>("default_value") {
+            definition.push_str(&format!(" DEFAULT {}", default));
+        }
+        if r.get("not_null") {
+            definition.push_str(" NOT NULL");
+        }
+        definition
+    })
+    .collect::<Vec<_>>();
+
+    // Newer servers also record NOT NULL as constraints; those are already on the columns.
+    let constraints = sqlx::query(
+        "SELECT conname::text AS name, pg_get_constraintdef(oid) AS definition FROM pg_constraint WHERE conrelid = $1::regclass AND contype <> 'n' ORDER BY contype = 'f', conname",
+    )
+    .bind(&qualified)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|r| {
+        format!(
+            "CONSTRAINT {} {}",
+            quote_ident(DatabaseType::PostgreSQL, r.get("name")),
+            r.get::<String, _>("definition")
+        )
+    })
+    .collect::<Vec<_>>();
+
+    let indexes: Vec<String> = sqlx::query_scalar(
+        "SELECT pg_get_indexdef(i.indexrelid) FROM pg_index i WHERE i.indrelid = $1::regclass AND NOT EXISTS (SELECT 1 FROM pg_constraint c WHERE c.conindid = i.indexrelid) ORDER BY i.indexrelid",
+    )
+    .bind(&qualified)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(assemble_create_table(
+        &qualified,
+        &columns,
+        &constraints,
+        &indexes,
+    ))
+}
+
+/// Builds a `CREATE TABLE` statement from column and constraint definitions, followed by
+/// the statements creating its other indexes.
+fn assemble_create_table(
+    qualified_name: &str,
+    columns: &[String],
+    constraints: &[String],
+    indexes: &[String],
+) -> String {
+    let body = columns
+        .iter()
+        .chain(constraints)
+        .map(|definition| format!("    {}", definition))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    let mut ddl = format!("CREATE TABLE {} (\n{}\n);", qualified_name, body);
+    for index in indexes {
+        ddl.push_str(&format!("\n\n{};", index));
+    }
+    ddl
+}
+
+/// Describes a key constraint, e.g. `fk_user: FOREIGN KEY (user_id) → users (id)`.
+fn describe_constraint(
+    name: Option<&str>,
+    kind: &str,
+    columns: &[String],
+    reference: Option<(&str, &[String])>,
+) -> String {
+    let mut description = match name {
+        Some(name) => format!("{}: {} ({})", name, kind, columns.join(", ")),
+        None => format!("{} ({})", kind, columns.join(", ")),
+    };
+    match reference {
+        Some((table, [])) => description.push_str(&format!(" → {}", table)),
+        Some((table, columns)) => {
+            description.push_str(&format!(" → {} ({})", table, columns.join(", ")))
+        }
+        None => {}
+    }
+    description
+}
+
+/// Describes an index by name and indexed columns, e.g. `idx_email UNIQUE (email)`.
+fn describe_index(name: &str, unique: bool, columns: &[String]) -> String {
+    let unique = if unique { " UNIQUE" } else { "" };
+    format!("{}{} ({})", name, unique, columns.join(", "))
+}
+
+/// Groups `SHOW INDEX` rows, one per indexed column in index order, into descriptions.
+fn group_mysql_indexes(rows: Vec<(String, bool, String)>) -> Vec<String> {
+    let mut indexes: Vec<(String, bool, Vec<String>)> = Vec::new();
+    for (name, unique, column) in rows {
+        match indexes.last_mut() {
+            Some((last, _, columns)) if *last == name => columns.push(column),
+            _ => indexes.push((name, unique, vec![column])),
+        }
+    }
+    indexes
+        .iter()
+        .map(|(name, unique, columns)| describe_index(name, *unique, columns))
+        .collect()
+}
+
+async fn get_mysql_indexes(pool: &MySqlPool, table: &str) -> sqlx::Result<Vec<String>> {
+    let rows = sqlx::query(&format!(
+        "SHOW INDEX FROM {}",
+        quote_ident(DatabaseType::MySQL, table)
+    ))
+    .fetch_all(pool)
+    .await?;
+    Ok(group_mysql_indexes(
+        rows.iter()
+            .map(|r| {
+                let non_unique = r
+                    .try_get::<i32, _>("Non_unique")
+                    .map(i64::from)
+                    .or_else(|_| r.try_get::<i64, _>("Non_unique"))
+                    .unwrap_or(1);
+                // Functional key parts have no column name.
+                let column = r
+                    .try_get::<Option<String>, _>("Column_name")
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| "<expression>".to_string());
+                (r.get("Key_name"), non_unique == 0, column)
+            })
+            .collect(),
+    ))
+}
+
+/// Describes the table's key constraints, and returns the columns of its primary key.
+async fn get_mysql_constraints(
+    pool: &MySqlPool,
+    table: &str,
+) -> sqlx::Result<(Vec<String>, Vec<String>)> {
+    let rows = sqlx::query(
+        "SELECT CAST(tc.CONSTRAINT_NAME AS CHAR) AS name, CAST(tc.CONSTRAINT_TYPE AS CHAR) AS kind, CAST(GROUP_CONCAT(kcu.COLUMN_NAME ORDER BY kcu.ORDINAL_POSITION SEPARATOR '\\0') AS CHAR) AS columns, CAST(MAX(kcu.REFERENCED_TABLE_NAME) AS CHAR) AS referenced_table, CAST(GROUP_CONCAT(kcu.REFERENCED_COLUMN_NAME ORDER BY kcu.ORDINAL_POSITION SEPARATOR '\\0') AS CHAR) AS referenced_columns FROM information_schema.TABLE_CONSTRAINTS tc LEFT JOIN information_schema.KEY_COLUMN_USAGE kcu ON kcu.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA AND kcu.CONSTRAINT_NAME = tc.CONSTRAINT_NAME AND kcu.TABLE_NAME = tc.TABLE_NAME WHERE tc.TABLE_SCHEMA = DATABASE() AND tc.TABLE_NAME = ? AND tc.CONSTRAINT_TYPE != 'CHECK' GROUP BY tc.CONSTRAINT_NAME, tc.CONSTRAINT_TYPE ORDER BY tc.CONSTRAINT_NAME",
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    // Column names are joined with NUL, which unlike a comma can't appear in a name.
+    let split = |list: Option<String>| -> Vec<String> {
+        list.map(|list| list.split('\0').map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    let mut primary_key = Vec::new();
+    let constraints = rows
+        .into_iter()
+        .map(|r| {
+            let name: String = r.get("name");
+            let kind: String = r.get("kind");
+            let columns = split(r.get("columns"));
+            let referenced_table: Option<String> = r.get("referenced_table");
+            let referenced_columns = split(r.get("referenced_columns"));
+            let description = describe_constraint(
+                Some(&name),
+                &kind,
+                &columns,
+                referenced_table
+                    .as_deref()
+                    .map(|table| (table, referenced_columns.as_slice())),
+            );
+            if kind == "PRIMARY KEY" {
+                primary_key = columns;
+            }
+            description
+        })
+        .collect();
+    Ok((constraints, primary_key))
+}
+
+/// Returns the table's index descriptions together with its `UNIQUE` constraints, which
+/// SQLite implements as automatically created indexes.
+async fn get_sqlite_indexes(
+    pool: &SqlitePool,
+    table: &str,
+) -> sqlx::Result<(Vec<String>, Vec<String>)> {
+    let index_rows = sqlx::query(&format!(
+        "PRAGMA index_list({})",
+        quote_ident(DatabaseType::SQLite, table)
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let mut indexes = Vec::new();
+    let mut unique_keys = Vec::new();
+    for row in index_rows {
+        let name: String = row.get("name");
+        let unique = row.get::<i64, _>("unique") != 0;
+        let origin: String = row.get("origin");
+
+        let columns: Vec<String> = sqlx::query(&format!(
+            "PRAGMA index_info({})",
+            quote_ident(DatabaseType::SQLite, &name)
+        ))
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|r| {
+            r.get::<Option<String>, _>("name")
+                .unwrap_or_else(|| "<expression>".to_string())
+        })
+        .collect();
+
+        if origin == "u" {
+            unique_keys.push(describe_constraint(None, "UNIQUE", &columns, None));
+        }
+        indexes.push(describe_index(&name, unique, &columns));
+    }
+    Ok((indexes, unique_keys))
+}
+
+async fn get_sqlite_foreign_keys(pool: &SqlitePool, table: &str) -> sqlx::Result<Vec<String>> {
+    let rows = sqlx::query(&format!(
+        "PRAGMA foreign_key_list({})",
+        quote_ident(DatabaseType::SQLite, table)
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    // One row per column, grouped by the key's id and ordered by `seq`.
+    let mut keys: Vec<(i64, String, Vec<String>, Vec<String>)> = Vec::new();
+    for row in rows {
+        let id: i64 = row.get("id");
+        let from: String = row.get("from");
+        // Without explicit columns the key references the parent's primary key.
+        let to: Option<String> = row.get("to");
+        match keys.last_mut() {
+            Some((last_id, _, from_columns, to_columns)) if *last_id == id => {
+                from_columns.push(from);
+                to_columns.extend(to);
+            }
+            _ => keys.push((id, row.get("table"), vec![from], to.into_iter().collect())),
+        }
+    }
+
+    Ok(keys
+        .iter()
+        .map(|(_, parent, from_columns, to_columns)| {
+            describe_constraint(
+                None,
+                "FOREIGN KEY",
+                from_columns,
+                Some((parent, to_columns)),
+            )
+        })
+        .collect())
+}
+
 pub fn build_category_node<T: Displayable>(
     parent: &str,
     label: &str,
@@ -383,58 +1014,289 @@ pub fn build_category_node<T: Displayable>(
     }
 }
 
+fn loading_node(parent: &str) -> TreeItem<'static, String> {
+    TreeItem::new_leaf(format!("{}_loading", parent), "Loading…".to_string())
+}
+
+/// Tree identifier of a table node, unique within its database.
+pub fn table_node_id(db_name: &str, table: &Table) -> String {
+    match &table.schema {
+        Some(schema) => format!("tbl_{}_{}.{}", db_name, schema, table.name),
+        None => format!("tbl_{}_{}", db_name, table.name),
+    }
+}
+
+fn table_to_tree_item(db_name: &str, table: &Table) -> TreeItem<'static, String> {
+    let table_id = table_node_id(db_name, table);
+    if let Some(metadata) = &table.metadata {
+        let children = vec![
+            build_category_node(&table_id, "Columns", &metadata.columns),
+            build_category_node(&table_id, "Constraints", &metadata.constraints),
+            build_category_node(&table_id, "Indexes", &metadata.indexes),
+            build_category_node(&table_id, "RLS Policies", &metadata.rls_policies),
+            build_category_node(&table_id, "Rules", &metadata.rules),
+            build_category_node(&table_id, "Triggers", &metadata.triggers),
+        ];
+        TreeItem::new(
+            table_id.clone(),
+            Text::from(table_label(&metadata.name, table)),
+            children,
+        )
+        .unwrap()
+    } else if table.loading {
+        TreeItem::new(
+            table_id.clone(),
+            table_label(&table.name, table),
+            vec![loading_node(&table_id)],
+        )
+        .unwrap()
+    } else {
+        TreeItem::new_leaf(table_id, table_label(&table.name, table))
+    }
+}
+
+/// Labels a table node with the best row count known for it.
+fn table_label(name: &str, table: &Table) -> String {
+    if table.counting {
+        return format!("{} (counting…)", name);
+    }
+    let row_count = match (table.exact_row_count, &table.metadata) {
+        (Some(rows), _) => RowCount::Exact(rows),
+        (None, Some(metadata)) => metadata.row_count,
+        (None, None) => RowCount::Unknown,
+    };
+    match row_count.label() {
+        Some(label) => format!("{} ({})", name, label),
+        None => name.to_string(),
+    }
+}
+
+/// Builds one node per relation kind under `parent_id`. "Tables" is always present; the
+/// other kinds only appear when there is something to show.
+fn table_group_nodes(
+    parent_id: &str,
+    db_name: &str,
+    tables: &[&Table],
+) -> Vec<TreeItem<'static, String>> {
+    TableKind::ALL
+        .into_iter()
+        .filter_map(|kind| {
+            let nodes = tables
+                .iter()
+                .filter(|table| table.kind == kind)
+                .map(|table| table_to_tree_item(db_name, table))
+                .collect::<Vec<_>>();
+            if nodes.is_empty() && kind != TableKind::Table {
+                return None;
+            }
+            let label = format!("{} ({})", kind.group_label(), nodes.len());
+            Some(TreeItem::new(format!("{}_{}", parent_id, kind.group_id()), label, nodes).unwrap())
+        })
+        .collect()
+}
+
+/// The databases and tables whose names contain `filter`, ignoring case. A matching
+/// database keeps all of its tables; otherwise only its matching tables are kept, and
+/// databases without any are left out. Databases still loading are kept as they are.
+pub fn filter_databases(databases: &[Database], filter: &str) -> Vec<Database> {
+    if filter.is_empty() {
+        return databases.to_vec();
+    }
+    let filter = filter.to_lowercase();
+    databases
+        .iter()
+        .filter_map(|db| {
+            if db.loading || db.name.to_lowercase().contains(&filter) {
+                return Some(db.clone());
+            }
+            let tables = db
+                .tables
+                .iter()
+                .filter(|table| table.name.to_lowercase().contains(&filter))
+                .cloned()
+                .collect::<Vec<_>>();
+            (!tables.is_empty()).then(|| Database {
+                name: db.name.clone(),
+                tables,
+                loading: false,
+            })
+        })
+        .collect()
+}
+
 pub fn metadata_to_tree_items(databases: &[Database]) -> Vec<TreeItem<'static, String>> {
     databases
         .iter()
         .map(|db| {
             let db_id = format!("db_{}", db.name);
-            let tables_node = {
-                let table_nodes = db
-                    .tables
-                    .iter()
-                    .map(|table| {
-                        let table_id = format!("tbl_{}_{}", &db.name, &table.name);
-                        if let Some(metadata) = &table.metadata {
-                            let children = vec![
-                                build_category_node(&table_id, "Columns", &metadata.columns),
-                                build_category_node(
-                                    &table_id,
-                                    "Constraints",
-                                    &metadata.constraints,
-                                ),
-                                build_category_node(&table_id, "Indexes", &metadata.indexes),
-                                build_category_node(
-                                    &table_id,
-                                    "RLS Policies",
-                                    &metadata.rls_policies,
-                                ),
-                                build_category_node(&table_id, "Rules", &metadata.rules),
-                                build_category_node(&table_id, "Triggers", &metadata.triggers),
-                            ];
-                            TreeItem::new(
-                                table_id.clone(),
-                                Text::from(format!(
-                                    "{} ({} row{})",
-                                    metadata.name,
-                                    metadata.row_count,
-                                    if metadata.row_count == 1 { "" } else { "s" }
-                                )),
-                                children,
-                            )
-                            .unwrap()
-                        } else {
-                            TreeItem::new_leaf(table_id.clone(), table.name.clone())
-                        }
+            if db.loading {
+                let loading = loading_node(&db_id);
+                return TreeItem::new(db_id, db.name.clone(), vec![loading]).unwrap();
+            }
+
+            let has_schemas = db.tables.iter().any(|table| table.schema.is_some());
+            let children = if has_schemas {
+                let mut schemas: Vec<&str> = Vec::new();
+                for table in &db.tables {
+                    let schema = table.schema.as_deref().unwrap_or_default();
+                    if !schemas.contains(&schema) {
+                        schemas.push(schema);
+                    }
+                }
+                schemas
+                    .into_iter()
+                    .map(|schema| {
+                        let schema_id = format!("schema_{}_{}", db.name, schema);
+                        let tables = db
+                            .tables
+                            .iter()
+                            .filter(|table| table.schema.as_deref().unwrap_or_default() == schema)
+                            .collect::<Vec<_>>();
+                        let children = table_group_nodes(&schema_id, &db.name, &tables);
+                        TreeItem::new(schema_id, schema.to_string(), children).unwrap()
                     })
-                    .collect::<Vec<_>>();
-                TreeItem::new(
-                    format!("{}_tables", db_id),
-                    format!("Tables ({})", db.tables.len()),
-                    table_nodes,
-                )
-                .unwrap()
+                    .collect()
+            } else {
+                let tables = db.tables.iter().collect::<Vec<_>>();
+                table_group_nodes(&db_id, &db.name, &tables)
             };
-            TreeItem::new(db_id, db.name.clone(), vec![tables_node]).unwrap()
+            TreeItem::new(db_id, db.name.clone(), children).unwrap()
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_describe_constraint() {
+        assert_eq!(
+            describe_constraint(Some("PRIMARY"), "PRIMARY KEY", &names(&["id"]), None),
+            "PRIMARY: PRIMARY KEY (id)"
+        );
+        assert_eq!(
+            describe_constraint(
+                None,
+                "FOREIGN KEY",
+                &names(&["org_id", "user_id"]),
+                Some(("members", &names(&["org", "user"])))
+            ),
+            "FOREIGN KEY (org_id, user_id) → members (org, user)"
+        );
+        assert_eq!(
+            describe_constraint(
+                None,
+                "FOREIGN KEY",
+                &names(&["user_id"]),
+                Some(("users", &[]))
+            ),
+            "FOREIGN KEY (user_id) → users"
+        );
+    }
+
+    #[test]
+    fn test_primary_key() {
+        let metadata = |primary_key: Vec<String>| TableMetadata {
+            name: "members".to_string(),
+            columns: vec![],
+            constraints: vec![],
+            primary_key,
+            indexes: vec![],
+            rls_policies: vec![],
+            rules: vec![],
+            triggers: vec![],
+            row_count: RowCount::Unknown,
+            estimated_size: String::new(),
+            table_type: String::new(),
+        };
+        // Names are kept whole, even ones that would need quoting.
+        assert_eq!(
+            metadata(names(&["org, id", "user)id"])).primary_key(),
+            Some(names(&["org, id", "user)id"]))
+        );
+        assert_eq!(metadata(vec![]).primary_key(), None);
+    }
+
+    #[test]
+    fn test_assemble_create_table() {
+        assert_eq!(
+            assemble_create_table(
+                r#""public"."users""#,
+                &names(&["id integer NOT NULL", "email text"]),
+                &names(&[r#"CONSTRAINT "users_pkey" PRIMARY KEY (id)"#]),
+                &names(&["CREATE INDEX users_email ON public.users USING btree (email)"]),
+            ),
+            r#"CREATE TABLE "public"."users" (
+    id integer NOT NULL,
+    email text,
+    CONSTRAINT "users_pkey" PRIMARY KEY (id)
+);
+
+CREATE INDEX users_email ON public.users USING btree (email);"#
+        );
+    }
+
+    #[test]
+    fn test_group_mysql_indexes() {
+        let rows = vec![
+            ("PRIMARY".to_string(), true, "id".to_string()),
+            ("idx_name".to_string(), false, "last".to_string()),
+            ("idx_name".to_string(), false, "first".to_string()),
+        ];
+        assert_eq!(
+            group_mysql_indexes(rows),
+            vec!["PRIMARY UNIQUE (id)", "idx_name (last, first)"]
+        );
+    }
+
+    #[test]
+    fn test_filter_databases() {
+        let table = |name: &str| Table {
+            name: name.to_string(),
+            schema: None,
+            kind: TableKind::Table,
+            metadata: None,
+            loading: false,
+            exact_row_count: None,
+            counting: false,
+        };
+        let databases = vec![
+            Database {
+                name: "shop".to_string(),
+                tables: vec![table("orders"), table("order_items"), table("users")],
+                loading: false,
+            },
+            Database {
+                name: "orderly".to_string(),
+                tables: vec![table("logs")],
+                loading: false,
+            },
+            Database {
+                name: "analytics".to_string(),
+                tables: vec![table("events")],
+                loading: false,
+            },
+        ];
+        let filtered = filter_databases(&databases, "ORDER");
+        let summary = filtered
+            .iter()
+            .map(|db| {
+                let tables = db.tables.iter().map(|table| table.name.as_str());
+                (db.name.as_str(), tables.collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("shop", vec!["orders", "order_items"]),
+                ("orderly", vec!["logs"])
+            ]
+        );
+        assert_eq!(filter_databases(&databases, "").len(), 3);
+    }
+}
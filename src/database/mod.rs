@@ -3,3 +3,4 @@ pub mod connector;
 pub mod db_list;
 pub mod fetch;
 pub mod pool;
+pub mod ssh_tunnel;
@@ -0,0 +1,287 @@
+//! Reaching a database through an SSH host, by running `ssh` to forward a local port.
+
+use crate::database::connector::{ConnectionDetails, DatabaseType};
+use crate::database::pool::ConnectError;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, TcpListener};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, ChildStderr, Command};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// Bytes of `ssh`'s error output kept to explain why it exited.
+const MAX_STDERR: usize = 4096;
+
+/// The SSH host, usually a bastion, that the database is reached through.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SshTunnel {
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    pub user: String,
+    /// Private key to log in with; without one, `ssh` uses the agent and its own config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_path: Option<String>,
+}
+
+/// A running `ssh` forwarding `local_port` to the database.
+struct Forward {
+    process: Child,
+    local_port: u16,
+}
+
+/// Forwards kept open for the rest of the session, so that the pools opened for each
+/// database of a connection share one `ssh` process. Keyed by the SSH host and the
+/// database address.
+static FORWARDS: Lazy<Mutex<HashMap<String, Forward>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns `details` pointed at a local port forwarded through `tunnel` to the database
+/// they describe, starting `ssh` unless a forward to it is already running.
+pub async fn forward(
+    tunnel: &SshTunnel,
+    db_type: DatabaseType,
+    mut details: ConnectionDetails,
+    timeout: Duration,
+) -> Result<ConnectionDetails, ConnectError> {
+    let target_host = details.host.as_deref().unwrap_or("localhost").to_string();
+    let target_port = details.port.unwrap_or(match db_type {
+        DatabaseType::MySQL => 3306,
+        DatabaseType::PostgreSQL | DatabaseType::SQLite => 5432,
+    });
+    let key = forward_key(tunnel, &target_host, target_port);
+
+    let mut forwards = FORWARDS.lock().await;
+    let running = match forwards.get_mut(&key) {
+        Some(forward) => matches!(forward.process.try_wait(), Ok(None)),
+        None => false,
+    };
+    if !running {
+        let forward = start_forward(tunnel, &target_host, target_port, timeout).await?;
+        forwards.insert(key.clone(), forward);
+    }
+
+    details.host = Some(Ipv4Addr::LOCALHOST.to_string());
+    details.port = Some(forwards[&key].local_port);
+    Ok(details)
+}
+
+/// Identifies a forward by the SSH login and the database address, so that the pools of
+/// every database on one server share it.
+fn forward_key(tunnel: &SshTunnel, target_host: &str, target_port: u16) -> String {
+    format!(
+        "{}@{}:{} {}:{}",
+        tunnel.user,
+        tunnel.host,
+        tunnel.port.unwrap_or(22),
+        target_host,
+        target_port
+    )
+}
+
+/// Stops every forward, so that no `ssh` process outlives the app.
+pub async fn close_tunnels() {
+    for (_, mut forward) in FORWARDS.lock().await.drain() {
+        let _ = forward.process.kill().await;
+    }
+}
+
+async fn start_forward(
+    tunnel: &SshTunnel,
+    target_host: &str,
+    target_port: u16,
+    timeout: Duration,
+) -> Result<Forward, ConnectError> {
+    let error = |message: String| ConnectError {
+        message: format!("SSH tunnel through {} failed: {}", tunnel.host, message),
+    };
+    let local_port = free_local_port().map_err(|e| error(e.to_string()))?;
+
+    let mut command = Command::new("ssh");
+    command
+        .args(ssh_args(
+            tunnel,
+            local_port,
+            target_host,
+            target_port,
+            timeout,
+        ))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut process = command
+        .spawn()
+        .map_err(|e| error(format!("could not run ssh: {}", e)))?;
+    let stderr = process.stderr.take().map(drain_stderr);
+
+    // `ssh` only listens on the local port once it has logged in.
+    let started = Instant::now();
+    loop {
+        if let Ok(Some(status)) = process.try_wait() {
+            let stderr = match stderr {
+                Some(drain) => drain.await.unwrap_or_default(),
+                None => String::new(),
+            };
+            let stderr = stderr.trim();
+            return Err(error(if stderr.is_empty() {
+                format!("ssh exited with {}", status)
+            } else {
+                stderr.to_string()
+            }));
+        }
+        if tokio::net::TcpStream::connect((Ipv4Addr::LOCALHOST, local_port))
+            .await
+            .is_ok()
+        {
+            return Ok(Forward {
+                process,
+                local_port,
+            });
+        }
+        if started.elapsed() >= timeout {
+            return Err(error(format!(
+                "timed out after {} seconds",
+                timeout.as_secs()
+            )));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// The arguments that make `ssh` forward `local_port` to the database and nothing else.
+fn ssh_args(
+    tunnel: &SshTunnel,
+    local_port: u16,
+    target_host: &str,
+    target_port: u16,
+    timeout: Duration,
+) -> Vec<String> {
+    let mut args: Vec<String> = [
+        // Only forward; there is no terminal to answer prompts, so fail instead of asking.
+        "-N",
+        "-o",
+        "BatchMode=yes",
+        "-o",
+        "ExitOnForwardFailure=yes",
+        "-o",
+        "StrictHostKeyChecking=accept-new",
+        "-o",
+    ]
+    .map(String::from)
+    .into();
+    args.push(format!("ConnectTimeout={}", timeout.as_secs().max(1)));
+    args.extend(["-o", "LogLevel=ERROR", "-L"].map(String::from));
+    // An IPv6 address goes in brackets, keeping its colons apart from those of the spec.
+    let target_host = if target_host.contains(':') {
+        format!("[{}]", target_host)
+    } else {
+        target_host.to_string()
+    };
+    args.push(format!(
+        "{}:{}:{}:{}",
+        Ipv4Addr::LOCALHOST,
+        local_port,
+        target_host,
+        target_port
+    ));
+    if let Some(port) = tunnel.port {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+    if let Some(key_path) = &tunnel.key_path {
+        args.extend(["-i".to_string(), key_path.clone()]);
+    }
+    args.push(format!("{}@{}", tunnel.user, tunnel.host));
+    args
+}
+
+/// Reads `ssh`'s error output as it comes, so that a full pipe never stalls it, keeping
+/// the start of it to report should `ssh` exit.
+fn drain_stderr(mut pipe: ChildStderr) -> JoinHandle<String> {
+    tokio::spawn(async move {
+        let mut kept = Vec::new();
+        let mut buffer = [0; 1024];
+        while let Ok(read @ 1..) = pipe.read(&mut buffer).await {
+            let room = MAX_STDERR.saturating_sub(kept.len());
+            kept.extend_from_slice(&buffer[..read.min(room)]);
+        }
+        String::from_utf8_lossy(&kept).into_owned()
+    })
+}
+
+/// A local port nothing is listening on, for `ssh` to listen on instead.
+fn free_local_port() -> std::io::Result<u16> {
+    Ok(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+        .local_addr()?
+        .port())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tunnel() -> SshTunnel {
+        SshTunnel {
+            host: "bastion.example.com".to_string(),
+            port: Some(2222),
+            user: "deploy".to_string(),
+            key_path: Some("~/.ssh/id_ed25519".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_ssh_args() {
+        let args = ssh_args(&tunnel(), 40000, "db.internal", 5432, Duration::ZERO);
+        assert_eq!(
+            args,
+            [
+                "-N",
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-o",
+                "StrictHostKeyChecking=accept-new",
+                "-o",
+                "ConnectTimeout=1",
+                "-o",
+                "LogLevel=ERROR",
+                "-L",
+                "127.0.0.1:40000:db.internal:5432",
+                "-p",
+                "2222",
+                "-i",
+                "~/.ssh/id_ed25519",
+                "deploy@bastion.example.com",
+            ]
+        );
+
+        let plain = SshTunnel {
+            port: None,
+            key_path: None,
+            ..tunnel()
+        };
+        let args = ssh_args(&plain, 40000, "db.internal", 5432, Duration::from_secs(9));
+        assert!(args.contains(&"ConnectTimeout=9".to_string()));
+        assert!(!args.contains(&"-p".to_string()) && !args.contains(&"-i".to_string()));
+
+        let args = ssh_args(&plain, 40000, "fd00::5", 5432, Duration::ZERO);
+        assert!(args.contains(&"127.0.0.1:40000:[fd00::5]:5432".to_string()));
+    }
+
+    #[test]
+    fn test_forward_key_is_shared_by_databases_of_one_server() {
+        let key = forward_key(&tunnel(), "db.internal", 5432);
+        assert_eq!(key, "deploy@bastion.example.com:2222 db.internal:5432");
+        assert_ne!(key, forward_key(&tunnel(), "db.internal", 5433));
+        let other_login = SshTunnel {
+            user: "admin".to_string(),
+            ..tunnel()
+        };
+        assert_ne!(key, forward_key(&other_login, "db.internal", 5432));
+    }
+}
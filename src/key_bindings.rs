@@ -0,0 +1,489 @@
+//! User keybindings loaded from `~/.lazydata/keys.toml`.
+//!
+//! Each section maps key chords to command names and is consulted before the built-in
+//! bindings of the same area, so a chord bound here replaces the default one:
+//!
+//! ```toml
+//! [global]
+//! "ctrl+r" = "execute_query"
+//!
+//! [table]
+//! "ctrl+d" = "next_page"
+//! "ctrl+u" = "previous_page"
+//!
+//! [sidebar]
+//! "enter" = "toggle_selected"
+//! ```
+//!
+//! A chord is an optional `ctrl+`, `alt+` or `shift+` prefix followed by a single character
+//! or a key name such as `enter`, `esc`, `space`, `up` or `f5`.
+//!
+//! The query editor uses vim-style modes by default; `editor = "emacs"` at the top of the
+//! file switches it to modeless, Emacs-style editing.
+
+use crate::app::Focus;
+use crate::command::{Command, CommandCategory};
+use crate::key_maps::{RESIZE_STEP, describe};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A key together with the modifiers held while pressing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn parse(chord: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = chord.split('+').collect();
+        // A trailing empty part means the key itself is `+`, as in "ctrl++".
+        if chord.ends_with('+') && parts.len() > 1 {
+            parts.pop();
+            parts.pop();
+            parts.push("+");
+        }
+        let (key, modifier_names) = parts
+            .split_last()
+            .ok_or_else(|| format!("empty key chord {:?}", chord))?;
+
+        for name in modifier_names {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {:?} in {:?}", name, chord)),
+            };
+        }
+
+        let code = parse_key_code(key).ok_or_else(|| format!("unknown key {:?}", chord))?;
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// A key pressed without modifiers. Shifted characters are given as the character
+    /// itself, as in `G` or `?`.
+    pub const fn key(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    pub const fn ctrl(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+
+    pub const fn alt(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::ALT,
+        }
+    }
+
+    pub const fn shift(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::SHIFT,
+        }
+    }
+
+    /// The key press of this chord, for tests to send.
+    #[cfg(test)]
+    pub fn to_event(self) -> KeyEvent {
+        KeyEvent::new(self.code, self.modifiers)
+    }
+
+    /// Whether this is a character typed without `ctrl` or `alt`, which is text in the editor.
+    pub fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && self.modifiers.is_empty()
+    }
+
+    /// Builds a chord, folding `shift` into character keys since terminals already report
+    /// the shifted character (`G` rather than `shift+g`).
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Self {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            code => Self { code, modifiers },
+        }
+    }
+}
+
+/// Formats the chord the way the key map shows it, as in `Ctrl+←` or `Shift+F6`.
+impl std::fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(event: &KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+fn parse_key_code(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let key = key.to_lowercase();
+    if let Some(number) = key.strip_prefix('f')
+        && let Ok(number) = number.parse::<u8>()
+    {
+        return (1..=12).contains(&number).then_some(KeyCode::F(number));
+    }
+
+    Some(match key.as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "space" => KeyCode::Char(' '),
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => return None,
+    })
+}
+
+/// The name of every command that can be bound in `keys.toml` or run from the command
+/// palette. Commands that open a prompt, such as search or export, and the editor's modal
+/// commands are not among them.
+pub const COMMAND_NAMES: &[(&str, Command)] = &[
+    ("execute_query", Command::ExecuteQuery),
+    ("explain_query", Command::ExplainQuery),
+    ("explain_analyze_query", Command::ExplainAnalyzeQuery),
+    ("commit_transaction", Command::CommitTransaction),
+    ("rollback_transaction", Command::RollbackTransaction),
+    ("format_sql", Command::EditorFormatSql),
+    ("toggle_line_numbers", Command::EditorToggleLineNumbers),
+    ("open_snippet_picker", Command::OpenSnippetPicker),
+    ("switch_connection", Command::OpenConnectionSwitcher),
+    ("connection_info", Command::ShowConnectionInfo),
+    ("show_key_map", Command::ShowKeyMap),
+    ("command_palette", Command::OpenCommandPalette),
+    ("toggle_focus", Command::ToggleFocus),
+    ("focus_sidebar", Command::FocusPane(Focus::Sidebar)),
+    ("focus_editor", Command::FocusPane(Focus::Editor)),
+    ("focus_table", Command::FocusPane(Focus::Table)),
+    ("toggle_zoom", Command::ToggleZoom),
+    ("grow_sidebar", Command::ResizeSidebar(RESIZE_STEP)),
+    ("shrink_sidebar", Command::ResizeSidebar(-RESIZE_STEP)),
+    ("grow_editor", Command::ResizeEditor(RESIZE_STEP)),
+    ("shrink_editor", Command::ResizeEditor(-RESIZE_STEP)),
+    ("previous_tab", Command::DataTablePreviousTab),
+    ("next_tab", Command::DataTableNextTab),
+    ("next_row", Command::DataTableNextRow),
+    ("previous_row", Command::DataTablePreviousRow),
+    ("scroll_right", Command::DataTableScrollRight),
+    ("scroll_left", Command::DataTableScrollLeft),
+    ("next_color", Command::DataTableNextColor),
+    ("previous_color", Command::DataTablePreviousColor),
+    ("next_page", Command::DataTableNextPage),
+    ("previous_page", Command::DataTablePreviousPage),
+    ("first_row", Command::DataTableJumpToFirstRow),
+    ("last_row", Command::DataTableJumpToLastRow),
+    ("next_column", Command::DataTableNextColumn),
+    ("previous_column", Command::DataTablePreviousColumn),
+    ("widen_column", Command::DataTableAdjustColumnWidthIncrease),
+    ("narrow_column", Command::DataTableAdjustColumnWidthDecrease),
+    ("auto_fit_columns", Command::DataTableAutoFitColumns),
+    ("reset_column_widths", Command::DataTableResetColumnWidths),
+    ("increase_page_size", Command::DataTableIncreasePageSize),
+    ("decrease_page_size", Command::DataTableDecreasePageSize),
+    ("inspect_cell", Command::DataTableInspectCell),
+    ("edit_cell", Command::DataTableEditCell),
+    ("delete_row", Command::DataTableDeleteRow),
+    ("copy_cell", Command::DataTableCopySelectedCell),
+    ("copy_row", Command::DataTableCopySelectedRow),
+    ("copy_column", Command::DataTableCopyColumn),
+    ("copy_all", Command::DataTableCopyAll),
+    ("copy_inserts", Command::DataTableCopyAllAsInserts),
+    ("cycle_copy_format", Command::DataTableCycleCopyFormat),
+    ("copy_query", Command::DataTableCopyQueryToEditor),
+    (
+        "run_history_query",
+        Command::DataTableRunSelectedHistoryQuery,
+    ),
+    ("toggle_column_info", Command::DataTableToggleColumnInfo),
+    ("toggle_row_detail", Command::DataTableToggleRowDetail),
+    ("toggle_timings", Command::DataTableToggleTimings),
+    ("sort_ascending", Command::DataTableSortAscending),
+    ("sort_descending", Command::DataTableSortDescending),
+    ("toggle_selected", Command::SidebarToggleSelected),
+    ("sidebar_left", Command::SidebarKeyLeft),
+    ("sidebar_right", Command::SidebarKeyRight),
+    ("sidebar_down", Command::SidebarKeyDown),
+    ("sidebar_up", Command::SidebarKeyUp),
+    ("sidebar_first", Command::SidebarSelectFirst),
+    ("sidebar_last", Command::SidebarSelectLast),
+    ("count_rows", Command::SidebarCountRows),
+    ("show_ddl", Command::SidebarShowDdl),
+    ("insert_row", Command::SidebarInsertRow),
+    ("refresh", Command::SidebarRefresh),
+    ("quit", Command::Quit),
+];
+
+/// Looks up a bindable command by the name used in `keys.toml`.
+pub fn command_by_name(name: &str) -> Option<Command> {
+    COMMAND_NAMES
+        .iter()
+        .find(|(command_name, _)| *command_name == name)
+        .map(|(_, command)| command.clone())
+}
+
+/// Names and descriptions of the commands listed in the command palette, described as in
+/// the key map.
+pub fn palette_commands() -> Vec<(&'static str, &'static str)> {
+    COMMAND_NAMES
+        .iter()
+        .filter(|(_, command)| *command != Command::OpenCommandPalette)
+        .filter_map(|(name, command)| Some((*name, describe(command)?)))
+        .collect()
+}
+
+/// Which set of keys the query editor responds to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EditorKeys {
+    /// Modal editing with normal, insert and visual modes.
+    #[default]
+    Vim,
+    /// Always inserting, with `Ctrl`/`Alt` chords for movement and editing.
+    Emacs,
+}
+
+type Bindings = HashMap<KeyChord, Command>;
+
+/// The keys file as written by the user, before chords and commands are parsed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct KeysFile {
+    editor: EditorKeys,
+    global: HashMap<String, String>,
+    table: HashMap<String, String>,
+    sidebar: HashMap<String, String>,
+}
+
+/// User bindings per area of the UI, checked before the built-in ones.
+#[derive(Debug, Default)]
+pub struct KeyBindings {
+    pub editor: EditorKeys,
+    global: Bindings,
+    table: Bindings,
+    sidebar: Bindings,
+}
+
+impl KeyBindings {
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        let file: KeysFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        Ok(Self {
+            editor: file.editor,
+            global: parse_section("global", file.global)?,
+            table: parse_section("table", file.table)?,
+            sidebar: parse_section("sidebar", file.sidebar)?,
+        })
+    }
+
+    /// The user's bindings for `category`, ordered by key, for the key map to list.
+    pub fn listed(&self, category: CommandCategory) -> Vec<(KeyChord, Command)> {
+        let bindings = match category {
+            CommandCategory::Global => &self.global,
+            CommandCategory::DataTable => &self.table,
+            CommandCategory::Sidebar => &self.sidebar,
+            CommandCategory::Editor => return Vec::new(),
+        };
+        let mut listed: Vec<_> = bindings
+            .iter()
+            .map(|(chord, command)| (*chord, command.clone()))
+            .collect();
+        listed.sort_by_key(|(chord, _)| chord.to_string());
+        listed
+    }
+
+    pub fn global(&self, key_event: &KeyEvent) -> Option<Command> {
+        self.global.get(&KeyChord::from(key_event)).cloned()
+    }
+
+    pub fn table(&self, key_event: &KeyEvent) -> Option<Command> {
+        self.table.get(&KeyChord::from(key_event)).cloned()
+    }
+
+    pub fn sidebar(&self, key_event: &KeyEvent) -> Option<Command> {
+        self.sidebar.get(&KeyChord::from(key_event)).cloned()
+    }
+}
+
+fn parse_section(section: &str, entries: HashMap<String, String>) -> Result<Bindings, String> {
+    entries
+        .into_iter()
+        .map(|(chord, name)| {
+            let chord = KeyChord::parse(&chord).map_err(|e| format!("[{}] {}", section, e))?;
+            let command = command_by_name(&name)
+                .ok_or_else(|| format!("[{}] unknown command {:?}", section, name))?;
+            Ok((chord, command))
+        })
+        .collect()
+}
+
+fn get_keys_file_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|mut path| {
+        path.push(".lazydata");
+        path.push("keys.toml");
+        path
+    })
+}
+
+/// Reads `~/.lazydata/keys.toml`, keeping only the built-in bindings if it is absent or invalid.
+pub fn load_key_bindings() -> KeyBindings {
+    let Some(path) = get_keys_file_path() else {
+        return KeyBindings::default();
+    };
+    if !path.exists() {
+        return KeyBindings::default();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(content) => KeyBindings::from_toml(&content).unwrap_or_else(|e| {
+            eprintln!("Error loading keybindings from {:?}: {}", path, e);
+            KeyBindings::default()
+        }),
+        Err(e) => {
+            eprintln!("Error reading keybindings file {:?}: {}", path, e);
+            KeyBindings::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord { code, modifiers }
+    }
+
+    #[test]
+    fn test_parse_key_chord() {
+        assert_eq!(
+            KeyChord::parse("ctrl+r"),
+            Ok(chord(KeyCode::Char('r'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("shift+g"),
+            Ok(chord(KeyCode::Char('G'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("Alt+PageDown"),
+            Ok(chord(KeyCode::PageDown, KeyModifiers::ALT))
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl++"),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("f5"),
+            Ok(chord(KeyCode::F(5), KeyModifiers::NONE))
+        );
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("f13").is_err());
+        assert!(KeyChord::parse("").is_err());
+    }
+
+    #[test]
+    fn test_display_key_chord() {
+        assert_eq!(KeyChord::ctrl(KeyCode::Left).to_string(), "Ctrl+←");
+        assert_eq!(KeyChord::shift(KeyCode::F(6)).to_string(), "Shift+F6");
+        assert_eq!(KeyChord::key(KeyCode::Char(' ')).to_string(), "Space");
+        assert_eq!(KeyChord::key(KeyCode::Char('G')).to_string(), "G");
+        assert_eq!(KeyChord::key(KeyCode::Esc).to_string(), "Esc");
+    }
+
+    #[test]
+    fn test_bindings_match_key_events() {
+        let bindings = KeyBindings::from_toml(
+            "[global]\n\"ctrl+r\" = \"execute_query\"\n[table]\n\"G\" = \"first_row\"",
+        )
+        .unwrap();
+
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(bindings.global(&ctrl_r), Some(Command::ExecuteQuery));
+        assert_eq!(bindings.table(&ctrl_r), None);
+
+        let shift_g = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(
+            bindings.table(&shift_g),
+            Some(Command::DataTableJumpToFirstRow)
+        );
+    }
+
+    #[test]
+    fn test_editor_profile() {
+        assert_eq!(KeyBindings::from_toml("").unwrap().editor, EditorKeys::Vim);
+        assert_eq!(
+            KeyBindings::from_toml("editor = \"emacs\"").unwrap().editor,
+            EditorKeys::Emacs
+        );
+        assert!(KeyBindings::from_toml("editor = \"nano\"").is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_commands_and_sections() {
+        assert!(KeyBindings::from_toml("[table]\nx = \"explode\"").is_err());
+        assert!(KeyBindings::from_toml("[editor]\nx = \"quit\"").is_err());
+    }
+
+    #[test]
+    fn test_palette_lists_every_command_with_a_key() {
+        let listed: Vec<&str> = palette_commands().iter().map(|(name, _)| *name).collect();
+        let missing: Vec<&str> = COMMAND_NAMES
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| *name != "command_palette" && !listed.contains(name))
+            .collect();
+        assert_eq!(missing, Vec::<&str>::new());
+        assert_eq!(
+            command_by_name("copy_row"),
+            Some(Command::DataTableCopySelectedRow)
+        );
+        assert_eq!(command_by_name("explode"), None);
+    }
+}
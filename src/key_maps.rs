@@ -1,9 +1,670 @@
 use crate::app::Focus;
 use crate::command::Command;
-use crate::layout::query_editor::Mode;
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use crate::key_bindings::{EditorKeys, KeyBindings, KeyChord};
+use crate::layout::data_table::{TableInput, TableInputKind, default_export_path};
+use crate::layout::query_editor::{CharFind, FindKind, Mode, Motion};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::layout::Position;
 use tui_textarea::{CursorMove, Input, Key, Scrolling};
 
+/// Percentage of the screen a pane grows or shrinks by per resize key press.
+pub const RESIZE_STEP: i16 = 5;
+
+/// Lines the sidebar or editor moves per notch of the mouse wheel.
+const MOUSE_SCROLL_LINES: u16 = 3;
+
+/// Largest repeat count the editor accepts, so that a mistyped `99999999999j` doesn't
+/// hang the UI repeating a motion.
+const MAX_EDITOR_COUNT: usize = 10_000;
+
+/// A built-in binding. The mapper and the key map guide both read these tables, so the
+/// guide always lists the keys that actually work.
+pub struct KeyMapping {
+    pub chords: &'static [KeyChord],
+    pub command: Command,
+    pub description: &'static str,
+}
+
+const fn bind(
+    chords: &'static [KeyChord],
+    command: Command,
+    description: &'static str,
+) -> KeyMapping {
+    KeyMapping {
+        chords,
+        command,
+        description,
+    }
+}
+
+const fn char_key(c: char) -> KeyChord {
+    KeyChord::key(KeyCode::Char(c))
+}
+
+/// Keys that work in every pane. Plain characters among them are typed as text in the
+/// editor instead.
+pub const GLOBAL_KEYS: &[KeyMapping] = &[
+    bind(&[char_key('q')], Command::Quit, "Quit"),
+    bind(
+        &[KeyChord::key(KeyCode::Tab)],
+        Command::ToggleFocus,
+        "Toggle focus",
+    ),
+    bind(
+        &[KeyChord::alt(KeyCode::Char('1'))],
+        Command::FocusPane(Focus::Sidebar),
+        "Focus sidebar",
+    ),
+    bind(
+        &[KeyChord::alt(KeyCode::Char('2'))],
+        Command::FocusPane(Focus::Editor),
+        "Focus editor",
+    ),
+    bind(
+        &[KeyChord::alt(KeyCode::Char('3'))],
+        Command::FocusPane(Focus::Table),
+        "Focus table",
+    ),
+    bind(
+        &[KeyChord::ctrl(KeyCode::Left)],
+        Command::ResizeSidebar(-RESIZE_STEP),
+        "Narrow the sidebar",
+    ),
+    bind(
+        &[KeyChord::ctrl(KeyCode::Right)],
+        Command::ResizeSidebar(RESIZE_STEP),
+        "Widen the sidebar",
+    ),
+    bind(
+        &[KeyChord::ctrl(KeyCode::Up)],
+        Command::ResizeEditor(-RESIZE_STEP),
+        "Shrink the editor",
+    ),
+    bind(
+        &[KeyChord::ctrl(KeyCode::Down)],
+        Command::ResizeEditor(RESIZE_STEP),
+        "Grow the editor",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(1))],
+        Command::OpenCommandPalette,
+        "Command palette (type to filter, Enter runs)",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(2))],
+        Command::ToggleZoom,
+        "Zoom focused pane / show all panes",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(3))],
+        Command::ShowConnectionInfo,
+        "Show connection and server version",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(4))],
+        Command::OpenConnectionSwitcher,
+        "Switch to another saved connection",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(5))],
+        Command::ExecuteQuery,
+        "Execute query",
+    ),
+    bind(
+        &[KeyChord::shift(KeyCode::F(6))],
+        Command::ExplainAnalyzeQuery,
+        "Explain analyze (runs the query)",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(6))],
+        Command::ExplainQuery,
+        "Explain query plan",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(7))],
+        Command::OpenSnippetPicker,
+        "Open saved snippets (Enter load, d delete)",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(8))],
+        Command::SaveSnippet(String::new()),
+        "Save editor contents as a snippet",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(9))],
+        Command::EditorToggleLineNumbers,
+        "Toggle editor line numbers",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(10))],
+        Command::CommitTransaction,
+        "Commit the open transaction",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::F(12))],
+        Command::RollbackTransaction,
+        "Roll back the open transaction",
+    ),
+    bind(
+        &[char_key('?')],
+        Command::ShowKeyMap,
+        "Show key map (/ filter, Tab/Enter collapse)",
+    ),
+];
+
+/// Keys that work while a query is running.
+pub const RUNNING_QUERY_KEYS: &[KeyMapping] = &[bind(
+    &[
+        KeyChord::key(KeyCode::Esc),
+        KeyChord::ctrl(KeyCode::Char('c')),
+    ],
+    Command::CancelQuery,
+    "Cancel running query",
+)];
+
+/// Keys of the results pane. Row movement scrolls the log or the history in the other
+/// tabs, and some keys only work in the tab their command applies to.
+pub const DATA_TABLE_KEYS: &[KeyMapping] = &[
+    bind(
+        &[char_key('[')],
+        Command::DataTablePreviousTab,
+        "Previous tab",
+    ),
+    bind(&[char_key(']')], Command::DataTableNextTab, "Next tab"),
+    bind(
+        &[char_key('1')],
+        Command::DataTableSetTabIndex(0),
+        "Results tab",
+    ),
+    bind(
+        &[char_key('2')],
+        Command::DataTableSetTabIndex(1),
+        "Messages tab",
+    ),
+    bind(
+        &[char_key('3')],
+        Command::DataTableSetTabIndex(2),
+        "History tab",
+    ),
+    bind(
+        &[char_key('j'), KeyChord::key(KeyCode::Down)],
+        Command::DataTableNextRow,
+        "Next row (scroll down in Messages)",
+    ),
+    bind(
+        &[char_key('k'), KeyChord::key(KeyCode::Up)],
+        Command::DataTablePreviousRow,
+        "Previous row (scroll up in Messages)",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::PageDown), char_key(' ')],
+        Command::DataTableNextPage,
+        "Next page",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::PageUp)],
+        Command::DataTablePreviousPage,
+        "Previous page",
+    ),
+    bind(
+        &[char_key('+')],
+        Command::DataTableIncreasePageSize,
+        "Increase page size",
+    ),
+    bind(
+        &[char_key('-')],
+        Command::DataTableDecreasePageSize,
+        "Decrease page size",
+    ),
+    bind(
+        &[char_key('P')],
+        Command::DataTableSetPageSize(0),
+        "Set page size",
+    ),
+    bind(
+        &[char_key('g')],
+        Command::DataTableJumpToFirstRow,
+        "Jump to first row",
+    ),
+    bind(
+        &[char_key('G')],
+        Command::DataTableJumpToLastRow,
+        "Jump to last row",
+    ),
+    bind(
+        &[char_key('l'), KeyChord::key(KeyCode::Right)],
+        Command::DataTableNextColumn,
+        "Next column",
+    ),
+    bind(
+        &[char_key('h'), KeyChord::key(KeyCode::Left)],
+        Command::DataTablePreviousColumn,
+        "Previous column",
+    ),
+    bind(
+        &[char_key('>')],
+        Command::DataTableScrollRight,
+        "Scroll right",
+    ),
+    bind(
+        &[char_key('<')],
+        Command::DataTableScrollLeft,
+        "Scroll left",
+    ),
+    bind(
+        &[char_key('w')],
+        Command::DataTableAdjustColumnWidthIncrease,
+        "Increase column width",
+    ),
+    bind(
+        &[char_key('W')],
+        Command::DataTableAdjustColumnWidthDecrease,
+        "Decrease column width",
+    ),
+    bind(
+        &[char_key('f')],
+        Command::DataTableAutoFitColumns,
+        "Fit columns to all rows",
+    ),
+    bind(
+        &[char_key('F')],
+        Command::DataTableResetColumnWidths,
+        "Reset column widths",
+    ),
+    bind(
+        &[char_key('s')],
+        Command::DataTableSortAscending,
+        "Sort ascending by column",
+    ),
+    bind(
+        &[char_key('S')],
+        Command::DataTableSortDescending,
+        "Sort descending by column",
+    ),
+    bind(
+        &[char_key('i')],
+        Command::DataTableToggleColumnInfo,
+        "Toggle column types panel",
+    ),
+    bind(
+        &[char_key('x')],
+        Command::DataTableToggleRowDetail,
+        "Toggle row detail view",
+    ),
+    bind(
+        &[char_key('T')],
+        Command::DataTableToggleTimings,
+        "Toggle query and render timings",
+    ),
+    bind(
+        &[char_key('J')],
+        Command::DataTableRowDetailScrollDown,
+        "Scroll row detail down",
+    ),
+    bind(
+        &[char_key('K')],
+        Command::DataTableRowDetailScrollUp,
+        "Scroll row detail up",
+    ),
+    bind(
+        &[char_key('/')],
+        Command::DataTableSearch(String::new()),
+        "Search results",
+    ),
+    bind(
+        &[char_key('/')],
+        Command::DataTableFilterHistory(String::new()),
+        "Filter query history",
+    ),
+    // Listed before the colors so that `n` and `Esc` act on an active search first.
+    bind(
+        &[char_key('n')],
+        Command::DataTableSearchNext,
+        "Next search match",
+    ),
+    bind(
+        &[char_key('N')],
+        Command::DataTableSearchPrevious,
+        "Previous search match",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::Esc)],
+        Command::DataTableSearchCancel,
+        "Clear search",
+    ),
+    bind(&[char_key('n')], Command::DataTableNextColor, "Next color"),
+    bind(
+        &[char_key('p')],
+        Command::DataTablePreviousColor,
+        "Previous color",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::Enter)],
+        Command::DataTableInspectCell,
+        "Inspect selected cell",
+    ),
+    bind(
+        &[char_key('e')],
+        Command::DataTableEditCell,
+        "Edit selected cell (UPDATE by primary key)",
+    ),
+    bind(
+        &[char_key('D')],
+        Command::DataTableDeleteRow,
+        "Delete selected row (DELETE by primary key)",
+    ),
+    bind(
+        &[char_key('y')],
+        Command::DataTableCopySelectedCell,
+        "Copy selected cell",
+    ),
+    bind(
+        &[char_key('Y')],
+        Command::DataTableCopySelectedRow,
+        "Copy selected row",
+    ),
+    bind(
+        &[char_key('c')],
+        Command::DataTableCopyColumn,
+        "Copy selected column",
+    ),
+    bind(
+        &[char_key('A')],
+        Command::DataTableCopyAll,
+        "Copy all results as TSV",
+    ),
+    bind(
+        &[char_key('I')],
+        Command::DataTableCopyAllAsInserts,
+        "Copy all results as INSERT statements",
+    ),
+    bind(
+        &[char_key('o')],
+        Command::DataTableCycleCopyFormat,
+        "Cycle row copy format (JSON, CSV, TSV, INSERT)",
+    ),
+    bind(
+        &[char_key('C')],
+        Command::DataTableCopyQueryToEditor,
+        "Copy query to editor",
+    ),
+    bind(
+        &[char_key('R')],
+        Command::DataTableRunSelectedHistoryQuery,
+        "Run selected history query",
+    ),
+    bind(
+        &[char_key('E')],
+        Command::DataTableExportJson(String::new()),
+        "Export results to JSON lines",
+    ),
+];
+
+/// Keys of the database tree.
+pub const SIDEBAR_KEYS: &[KeyMapping] = &[
+    bind(
+        &[KeyChord::key(KeyCode::Enter), char_key(' ')],
+        Command::SidebarToggleSelected,
+        "Toggle selected",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::Left)],
+        Command::SidebarKeyLeft,
+        "Collapse",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::Right)],
+        Command::SidebarKeyRight,
+        "Expand",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::Down)],
+        Command::SidebarKeyDown,
+        "Down",
+    ),
+    bind(&[KeyChord::key(KeyCode::Up)], Command::SidebarKeyUp, "Up"),
+    bind(
+        &[KeyChord::key(KeyCode::Esc)],
+        Command::SidebarDeselect,
+        "Deselect",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::Home)],
+        Command::SidebarSelectFirst,
+        "Select first",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::End)],
+        Command::SidebarSelectLast,
+        "Select last",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::PageDown)],
+        Command::SidebarScrollDown(3),
+        "Scroll down",
+    ),
+    bind(
+        &[KeyChord::key(KeyCode::PageUp)],
+        Command::SidebarScrollUp(3),
+        "Scroll up",
+    ),
+    bind(
+        &[char_key('c')],
+        Command::SidebarCountRows,
+        "Count rows of selected table",
+    ),
+    bind(
+        &[char_key('d')],
+        Command::SidebarShowDdl,
+        "Show CREATE statement of selected table",
+    ),
+    bind(
+        &[char_key('i')],
+        Command::SidebarInsertRow,
+        "Insert a row into selected table",
+    ),
+    bind(
+        &[char_key('r')],
+        Command::SidebarRefresh,
+        "Reload tables of selected database",
+    ),
+    bind(
+        &[char_key('/')],
+        Command::SidebarFilter(String::new()),
+        "Filter databases and tables by name",
+    ),
+];
+
+/// Where in the query editor a key works, as headed in the key map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorKeyMode {
+    Normal,
+    Insert,
+    Completion,
+    Visual,
+    Emacs,
+}
+
+impl EditorKeyMode {
+    pub fn heading(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal Mode",
+            Self::Insert => "Insert Mode",
+            Self::Completion => "Completion List",
+            Self::Visual => "Visual Mode",
+            Self::Emacs => "Emacs Profile",
+        }
+    }
+
+    /// The modes of an editor profile, in the order the key map lists them.
+    pub fn of_profile(profile: EditorKeys) -> &'static [Self] {
+        match profile {
+            EditorKeys::Vim => &[Self::Normal, Self::Insert, Self::Completion, Self::Visual],
+            EditorKeys::Emacs => &[Self::Emacs, Self::Completion],
+        }
+    }
+}
+
+/// A key of the query editor as the key map lists it. The editor is modal, with counts,
+/// operators and pending keys, so it is mapped in code rather than from this table; keys
+/// given with the `command` they send are checked against the mapper by a test.
+pub struct EditorKey {
+    pub mode: EditorKeyMode,
+    pub keys: &'static str,
+    pub command: Option<Command>,
+    pub description: &'static str,
+}
+
+const fn editor_key(
+    mode: EditorKeyMode,
+    keys: &'static str,
+    command: Option<Command>,
+    description: &'static str,
+) -> EditorKey {
+    EditorKey {
+        mode,
+        keys,
+        command,
+        description,
+    }
+}
+
+/// Keys of the query editor, per mode of both profiles.
+pub const EDITOR_KEYS: &[EditorKey] = {
+    use EditorKeyMode::*;
+    &[
+        editor_key(Normal, "h/j/k/l", None, "Move cursor"),
+        editor_key(Normal, "w/b", None, "Move by word"),
+        editor_key(Normal, "^/$", None, "Move to line start/end"),
+        editor_key(Normal, "gg/G", None, "Move to top/bottom"),
+        editor_key(Normal, "i/a", None, "Enter insert mode"),
+        editor_key(Normal, "o/O", None, "Insert line below/above"),
+        editor_key(Normal, "v/V", None, "Enter visual mode"),
+        editor_key(Normal, "d/c/y", None, "Delete/change/yank (operator)"),
+        editor_key(Normal, "dd/cc/yy", None, "Delete/change/yank line"),
+        editor_key(Normal, "[count]", None, "Repeat, e.g. 5j, 3dd, d2w"),
+        editor_key(Normal, "NG/Ngg/:N", None, "Go to line N"),
+        editor_key(
+            Normal,
+            "f/F/t/T{char}",
+            None,
+            "Find character in line, e.g. df(",
+        ),
+        editor_key(Normal, "%", None, "Jump to matching bracket, e.g. d%"),
+        editor_key(
+            Normal,
+            "x",
+            Some(Command::EditorDeleteNextChar),
+            "Delete character",
+        ),
+        editor_key(
+            Normal,
+            "D",
+            Some(Command::EditorDeleteLineByEnd),
+            "Delete to end of line",
+        ),
+        editor_key(Normal, "=", Some(Command::EditorFormatSql), "Format SQL"),
+        editor_key(
+            Normal,
+            "p",
+            Some(Command::EditorPasteSystem),
+            "Paste from system clipboard",
+        ),
+        editor_key(Normal, "u", Some(Command::EditorUndo), "Undo"),
+        editor_key(Normal, "Ctrl+r", Some(Command::EditorRedo), "Redo"),
+        editor_key(Insert, "Esc/Ctrl+c", None, "Enter normal mode"),
+        editor_key(
+            Insert,
+            "Tab/Ctrl+n",
+            None,
+            "Complete keyword, table or column",
+        ),
+        editor_key(
+            Insert,
+            "Ctrl+v",
+            Some(Command::EditorPasteSystem),
+            "Paste from system clipboard",
+        ),
+        editor_key(Completion, "Tab/↓/Ctrl+n", None, "Next suggestion"),
+        editor_key(
+            Completion,
+            "Shift+Tab/↑/Ctrl+p",
+            None,
+            "Previous suggestion",
+        ),
+        editor_key(
+            Completion,
+            "Enter",
+            Some(Command::EditorCompletionAccept),
+            "Accept suggestion",
+        ),
+        editor_key(
+            Completion,
+            "Esc",
+            Some(Command::EditorCompletionCancel),
+            "Close list",
+        ),
+        editor_key(Visual, "Esc/v", None, "Enter normal mode"),
+        editor_key(Visual, "d/c/y", None, "Delete/change/yank selection"),
+        editor_key(Emacs, "Ctrl+a/e", None, "Move to line start/end"),
+        editor_key(Emacs, "Ctrl+b/f/p/n", None, "Move cursor"),
+        editor_key(Emacs, "Alt+b/f", None, "Move by word"),
+        editor_key(
+            Emacs,
+            "Ctrl+k",
+            Some(Command::EditorDeleteLineByEnd),
+            "Kill to end of line",
+        ),
+        editor_key(
+            Emacs,
+            "Ctrl+w",
+            Some(Command::EditorDeleteWordBack),
+            "Delete word before cursor",
+        ),
+        editor_key(Emacs, "Ctrl+Space/Alt+w", None, "Start selection/copy it"),
+        editor_key(Emacs, "Ctrl+y", Some(Command::EditorPaste), "Paste"),
+        editor_key(Emacs, "Ctrl+/", Some(Command::EditorUndo), "Undo"),
+        editor_key(
+            Emacs,
+            "Alt+/",
+            Some(Command::EditorComplete),
+            "Complete keyword, table or column",
+        ),
+        editor_key(Emacs, "Alt+q", Some(Command::EditorFormatSql), "Format SQL"),
+    ]
+};
+
+/// What `command` does, as the key map describes it, if any built-in key sends it.
+pub fn describe(command: &Command) -> Option<&'static str> {
+    GLOBAL_KEYS
+        .iter()
+        .chain(RUNNING_QUERY_KEYS)
+        .chain(DATA_TABLE_KEYS)
+        .chain(SIDEBAR_KEYS)
+        .map(|mapping| (&mapping.command, mapping.description))
+        .chain(EDITOR_KEYS.iter().filter_map(|key| {
+            key.command
+                .as_ref()
+                .map(|command| (command, key.description))
+        }))
+        .find(|(mapped, _)| *mapped == command)
+        .map(|(_, description)| description)
+}
+
+/// The first mapping in `table` bound to `key_event` that `available` accepts.
+fn lookup(
+    table: &'static [KeyMapping],
+    key_event: &KeyEvent,
+    available: impl Fn(&KeyMapping) -> bool,
+) -> Option<&'static KeyMapping> {
+    let chord = KeyChord::from(key_event);
+    table
+        .iter()
+        .find(|mapping| mapping.chords.contains(&chord) && available(mapping))
+}
+
 pub trait KeyMapper {
     fn map_key_to_command(
         &mut self,
@@ -13,106 +674,375 @@ pub trait KeyMapper {
     ) -> Option<Command>;
 
     fn editor_mode(&self) -> Mode;
+    fn table_input(&self) -> Option<TableInput>;
     fn map_popup_key(&mut self, key_event: KeyEvent) -> Option<Command>;
+    fn map_json_tree_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_key_map_key(&mut self, key_event: KeyEvent) -> Option<Command>;
+    fn map_running_query_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_confirm_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_snippet_picker_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_connection_switcher_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_password_prompt_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_cell_edit_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_insert_form_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_command_palette_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_completion_key(&self, key_event: KeyEvent) -> Option<Command>;
+    fn map_mouse_event(
+        &self,
+        mouse_event: MouseEvent,
+        current_focus: &Focus,
+        tab_index: usize,
+    ) -> Option<Command>;
+    fn snippet_name(&self) -> Option<String>;
+    fn sidebar_filter(&self) -> Option<String>;
+    fn key_map_filter(&self) -> Option<String>;
+    fn editor_command_line(&self) -> Option<String>;
 }
 
 pub struct DefaultKeyMapper {
     editor_mode: Mode,
     editor_pending_input: Option<Input>,
+    /// Repeat count typed so far in Normal, Visual or Operator-pending mode.
+    editor_count: Option<usize>,
+    /// Count typed before the pending operator, multiplied into the motion's count.
+    operator_count: usize,
+    /// Editor command line opened with `:`, holding what has been typed after it.
+    editor_command_line: Option<String>,
+    /// An `f`/`F`/`t`/`T` motion waiting for its character, with its count.
+    editor_pending_find: Option<(FindKind, usize)>,
+    table_input: Option<TableInput>,
+    table_search_active: bool,
+    /// Name being typed for the editor contents saved as a snippet.
+    snippet_name: Option<String>,
+    /// Sidebar filter being typed after `/`.
+    sidebar_filter: Option<String>,
+    /// Key map filter being typed after `/`.
+    key_map_filter: Option<String>,
+    /// User bindings from `keys.toml`, consulted before the built-in ones.
+    bindings: KeyBindings,
 }
 
 impl DefaultKeyMapper {
     pub fn new() -> Self {
+        Self::with_bindings(KeyBindings::default())
+    }
+
+    /// The user's bindings, for the key map to list.
+    pub fn bindings(&self) -> &KeyBindings {
+        &self.bindings
+    }
+
+    pub fn with_bindings(bindings: KeyBindings) -> Self {
+        let editor_mode = match bindings.editor {
+            EditorKeys::Vim => Mode::Normal,
+            EditorKeys::Emacs => Mode::Emacs,
+        };
         Self {
-            editor_mode: Mode::Normal,
+            editor_mode,
             editor_pending_input: None,
+            editor_count: None,
+            operator_count: 1,
+            editor_command_line: None,
+            editor_pending_find: None,
+            table_input: None,
+            table_search_active: false,
+            snippet_name: None,
+            sidebar_filter: None,
+            key_map_filter: None,
+            bindings,
         }
     }
 
-    fn map_query_editor_key(&mut self, input: Input) -> Option<Command> {
+    fn map_snippet_name_key(&mut self, key: KeyCode) -> Option<Command> {
+        let name = self.snippet_name.as_mut()?;
+        match key {
+            KeyCode::Esc => {
+                self.snippet_name = None;
+                Some(Command::NoOp)
+            }
+            KeyCode::Enter => {
+                let name = self.snippet_name.take()?;
+                let name = name.trim();
+                if name.is_empty() {
+                    Some(Command::NoOp)
+                } else {
+                    Some(Command::SaveSnippet(name.to_string()))
+                }
+            }
+            KeyCode::Backspace => {
+                name.pop();
+                Some(Command::NoOp)
+            }
+            KeyCode::Char(c) => {
+                name.push(c);
+                Some(Command::NoOp)
+            }
+            _ => Some(Command::NoOp),
+        }
+    }
+
+    /// Maps keys for the modeless Emacs profile, where anything unbound is typed as text.
+    fn map_emacs_editor_key(&self, input: Input) -> Option<Command> {
+        if input.ctrl {
+            return Some(match input.key {
+                Key::Char('a') => Command::EditorMoveCursor(CursorMove::Head),
+                Key::Char('e') => Command::EditorMoveCursor(CursorMove::End),
+                Key::Char('b') => Command::EditorMoveCursor(CursorMove::Back),
+                Key::Char('f') => Command::EditorMoveCursor(CursorMove::Forward),
+                Key::Char('p') => Command::EditorMoveCursor(CursorMove::Up),
+                Key::Char('n') => Command::EditorMoveCursor(CursorMove::Down),
+                Key::Char('k') => Command::EditorDeleteLineByEnd,
+                Key::Char('w') => Command::EditorDeleteWordBack,
+                Key::Char('d') => Command::EditorDeleteNextChar,
+                Key::Char('h') => Command::EditorInputBackspace,
+                Key::Char('y') => Command::EditorPaste,
+                Key::Char('/' | '_' | 'z') => Command::EditorUndo,
+                Key::Char(' ') => Command::EditorStartSelection,
+                Key::Char('g') => Command::EditorCancelSelection,
+                Key::Char('v') => Command::EditorScroll(Scrolling::PageDown),
+                _ => Command::NoOp,
+            });
+        }
+        if input.alt {
+            return Some(match input.key {
+                Key::Char('f') => Command::EditorMoveCursor(CursorMove::WordForward),
+                Key::Char('b') => Command::EditorMoveCursor(CursorMove::WordBack),
+                Key::Char('w') => Command::EditorCopySelection,
+                Key::Char('/') => Command::EditorComplete,
+                Key::Char('q') => Command::EditorFormatSql,
+                Key::Char('v') => Command::EditorScroll(Scrolling::PageUp),
+                Key::Char('<') => Command::EditorMoveCursor(CursorMove::Top),
+                Key::Char('>') => Command::EditorMoveCursor(CursorMove::Bottom),
+                _ => Command::NoOp,
+            });
+        }
+
+        Some(match input.key {
+            Key::Esc => Command::EditorCancelSelection,
+            Key::Backspace => Command::EditorInputBackspace,
+            Key::Delete => Command::EditorInputDelete,
+            Key::Enter => Command::EditorInputEnter,
+            Key::Left => Command::EditorMoveCursor(CursorMove::Back),
+            Key::Right => Command::EditorMoveCursor(CursorMove::Forward),
+            Key::Up => Command::EditorMoveCursor(CursorMove::Up),
+            Key::Down => Command::EditorMoveCursor(CursorMove::Down),
+            Key::Home => Command::EditorMoveCursor(CursorMove::Head),
+            Key::End => Command::EditorMoveCursor(CursorMove::End),
+            Key::PageUp => Command::EditorScroll(Scrolling::PageUp),
+            Key::PageDown => Command::EditorScroll(Scrolling::PageDown),
+            Key::Char(c) => Command::EditorInputChar(c),
+            _ => Command::NoOp,
+        })
+    }
+
+    /// Motions shared by Normal, Visual and Operator-pending modes.
+    fn vim_motion(input: &Input) -> Option<CursorMove> {
+        if input.ctrl || input.alt {
+            return None;
+        }
+        Some(match input.key {
+            Key::Char('h') => CursorMove::Back,
+            Key::Char('j') => CursorMove::Down,
+            Key::Char('k') => CursorMove::Up,
+            Key::Char('l') => CursorMove::Forward,
+            Key::Char('w') => CursorMove::WordForward,
+            Key::Char('e') => CursorMove::WordEnd,
+            Key::Char('b') => CursorMove::WordBack,
+            Key::Char('^' | '0') => CursorMove::Head,
+            Key::Char('$') => CursorMove::End,
+            Key::Char('G') => CursorMove::Bottom,
+            _ => return None,
+        })
+    }
+
+    /// Wraps `command` so that it runs `count` times.
+    fn repeated(count: usize, command: Command) -> Command {
+        if count > 1 {
+            Command::EditorRepeat(count, Box::new(command))
+        } else {
+            command
+        }
+    }
+
+    /// Maps keys while the `:` command line is open. `:N` jumps to line N and `:$` to the
+    /// last line.
+    fn map_editor_command_line_key(&mut self, input: Input) -> Option<Command> {
+        let line = self.editor_command_line.as_mut()?;
+        match input.key {
+            Key::Esc => {
+                self.editor_command_line = None;
+                Some(Command::NoOp)
+            }
+            Key::Char('c') if input.ctrl => {
+                self.editor_command_line = None;
+                Some(Command::NoOp)
+            }
+            Key::Backspace => {
+                if line.pop().is_none() {
+                    self.editor_command_line = None;
+                }
+                Some(Command::NoOp)
+            }
+            Key::Enter => {
+                let line = self.editor_command_line.take()?;
+                Some(match line.trim() {
+                    "$" => Command::EditorMoveCursor(CursorMove::Bottom),
+                    target => match target.parse::<usize>() {
+                        Ok(number) => Command::EditorMoveCursor(Self::jump_to_line(number)),
+                        Err(_) => Command::NoOp,
+                    },
+                })
+            }
+            Key::Char(c) => {
+                line.push(c);
+                Some(Command::NoOp)
+            }
+            _ => Some(Command::NoOp),
+        }
+    }
+
+    /// Moves to the start of a 1-based line number, as typed after `:` or before `G`.
+    fn jump_to_line(number: usize) -> CursorMove {
+        let row = number.saturating_sub(1).min(u16::MAX as usize) as u16;
+        CursorMove::Jump(row, 0)
+    }
+
+    /// Moves by `motion`, or applies the pending operator over it.
+    fn vim_motion_command(&mut self, motion: Motion, count: usize) -> Command {
+        match self.editor_mode {
+            Mode::Operator(op) => {
+                self.editor_mode = if op == 'c' {
+                    Mode::Insert
+                } else {
+                    Mode::Normal
+                };
+                let count =
+                    (count * std::mem::replace(&mut self.operator_count, 1)).min(MAX_EDITOR_COUNT);
+                Command::EditorApplyOperator(op, motion, count)
+            }
+            _ => match motion {
+                Motion::Cursor(movement) => {
+                    Self::repeated(count, Command::EditorMoveCursor(movement))
+                }
+                Motion::Find(find) => Command::EditorFindChar(find, count),
+                Motion::MatchingBracket => Command::EditorJumpToMatchingBracket,
+            },
+        }
+    }
+
+    fn map_vim_editor_key(&mut self, input: Input) -> Option<Command> {
         if input.key == Key::Null {
             return Some(Command::NoOp);
         }
 
-        if let Some(pending) = self.editor_pending_input.take() {
-            if pending.key == Key::Char('g')
-                && !pending.ctrl
-                && input.key == Key::Char('g')
-                && !input.ctrl
-            {
-                return Some(Command::EditorMoveCursor(CursorMove::Top));
-            }
-            if let Key::Char(op @ ('y' | 'd' | 'c')) = pending.key
-                && input.key == Key::Char(op) {
-                    return match op {
-                        'y' => Some(Command::EditorCopySelection),
-                        'd' => Some(Command::EditorDeleteLineByEnd),
-                        'c' => Some(Command::EditorDeleteLineByEnd),
-                        _ => None,
+        if self.editor_command_line.is_some() {
+            return self.map_editor_command_line_key(input);
+        }
+
+        if let Some(pending) = self.editor_pending_input.take()
+            && pending.key == Key::Char('g')
+            && !pending.ctrl
+            && input.key == Key::Char('g')
+            && !input.ctrl
+        {
+            // `gg` goes to the top, or to line N when given a count as in `42gg`.
+            let motion = match self.editor_count.take() {
+                Some(number) => Self::jump_to_line(number),
+                None => CursorMove::Top,
+            };
+            return Some(self.vim_motion_command(Motion::Cursor(motion), 1));
+        }
+
+        if let Some((kind, count)) = self.editor_pending_find.take() {
+            return Some(match input.key {
+                Key::Char(target) if !input.ctrl => {
+                    self.vim_motion_command(Motion::Find(CharFind { kind, target }), count)
+                }
+                // Anything else abandons the motion, and any operator waiting on it.
+                _ => {
+                    self.editor_mode = match self.editor_mode {
+                        Mode::Operator(_) => Mode::Normal,
+                        mode => mode,
                     };
+                    self.operator_count = 1;
+                    Command::EditorSetMode(self.editor_mode)
                 }
+            });
+        }
 
-            let command_from_pending = match pending.key {
-                Key::Char(op @ ('y' | 'd' | 'c')) => {
-                    Some(Command::EditorSetMode(Mode::Operator(op)))
+        // Digits build a count for the next motion or operator; a leading 0 is a motion.
+        if matches!(
+            self.editor_mode,
+            Mode::Normal | Mode::Visual | Mode::Operator(_)
+        ) && !input.ctrl
+            && let Key::Char(c @ '0'..='9') = input.key
+            && (c != '0' || self.editor_count.is_some())
+        {
+            let digit = c as usize - '0' as usize;
+            let count = self.editor_count.unwrap_or(0);
+            self.editor_count = Some(
+                count
+                    .saturating_mul(10)
+                    .saturating_add(digit)
+                    .min(MAX_EDITOR_COUNT),
+            );
+            return Some(Command::NoOp);
+        }
+        let typed_count = self.editor_count.take();
+        let count = typed_count.unwrap_or(1);
+
+        if matches!(
+            self.editor_mode,
+            Mode::Normal | Mode::Visual | Mode::Operator(_)
+        ) {
+            // With a count, `G` jumps to that line instead of the last one.
+            if let (Some(CursorMove::Bottom), Some(number)) =
+                (Self::vim_motion(&input), typed_count)
+            {
+                let motion = Motion::Cursor(Self::jump_to_line(number));
+                return Some(self.vim_motion_command(motion, 1));
+            }
+            if let Some(motion) = Self::vim_motion(&input) {
+                return Some(self.vim_motion_command(Motion::Cursor(motion), count));
+            }
+            if !input.ctrl && !input.alt {
+                let kind = match input.key {
+                    Key::Char('f') => Some(FindKind::Forward),
+                    Key::Char('F') => Some(FindKind::Backward),
+                    Key::Char('t') => Some(FindKind::TillForward),
+                    Key::Char('T') => Some(FindKind::TillBackward),
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    self.editor_pending_find = Some((kind, count));
+                    return Some(Command::NoOp);
                 }
-                _ => None,
-            };
-            if command_from_pending.is_some() {
-                match input.key {
-                    Key::Char('h')
-                    | Key::Char('j')
-                    | Key::Char('k')
-                    | Key::Char('l')
-                    | Key::Char('w')
-                    | Key::Char('e')
-                    | Key::Char('b')
-                    | Key::Char('^')
-                    | Key::Char('$')
-                    | Key::Char('g')
-                    | Key::Char('G')
-                    | Key::PageUp
-                    | Key::PageDown => {
-                        self.editor_pending_input = Some(input);
-                        return command_from_pending;
-                    }
-                    _ => {}
+                if input.key == Key::Char('%') {
+                    return Some(self.vim_motion_command(Motion::MatchingBracket, 1));
                 }
             }
+            if input.key == Key::Char('g') && !input.ctrl {
+                self.editor_pending_input = Some(input);
+                self.editor_count = (count > 1).then_some(count);
+                return Some(Command::NoOp);
+            }
         }
 
         match self.editor_mode {
             Mode::Normal => match input.key {
-                Key::Char('h') => Some(Command::EditorMoveCursor(CursorMove::Back)),
-                Key::Char('j') => Some(Command::EditorMoveCursor(CursorMove::Down)),
-                Key::Char('k') => Some(Command::EditorMoveCursor(CursorMove::Up)),
-                Key::Char('l') => Some(Command::EditorMoveCursor(CursorMove::Forward)),
-                Key::Char('w') => Some(Command::EditorMoveCursor(CursorMove::WordForward)),
-                Key::Char('e') => {
-                    if input.ctrl {
-                        Some(Command::EditorScrollRelative(1, 0))
-                    } else {
-                        Some(Command::EditorMoveCursor(CursorMove::WordEnd))
-                    }
+                Key::Char('e') if input.ctrl => {
+                    Some(Self::repeated(count, Command::EditorScrollRelative(1, 0)))
                 }
-                Key::Char('b') => {
-                    if input.ctrl {
-                        Some(Command::EditorScroll(Scrolling::PageUp))
-                    } else {
-                        Some(Command::EditorMoveCursor(CursorMove::WordBack))
-                    }
-                }
-                Key::Char('^') => Some(Command::EditorMoveCursor(CursorMove::Head)),
-                Key::Char('$') => Some(Command::EditorMoveCursor(CursorMove::End)),
+                Key::Char('b') if input.ctrl => Some(Command::EditorScroll(Scrolling::PageUp)),
                 Key::Char('D') => Some(Command::EditorDeleteLineByEnd),
                 Key::Char('C') => {
                     self.editor_mode = Mode::Insert;
                     Some(Command::EditorDeleteLineByEnd)
                 }
-                Key::Char('p') => Some(Command::EditorPaste),
-                Key::Char('u') if !input.ctrl => Some(Command::EditorUndo),
-                Key::Char('r') if input.ctrl => Some(Command::EditorRedo),
-                Key::Char('x') => Some(Command::EditorDeleteNextChar),
+                Key::Char('p') => Some(Self::repeated(count, Command::EditorPasteSystem)),
+                Key::Char('u') if !input.ctrl => Some(Self::repeated(count, Command::EditorUndo)),
+                Key::Char('r') if input.ctrl => Some(Self::repeated(count, Command::EditorRedo)),
+                Key::Char('x') => Some(Self::repeated(count, Command::EditorDeleteNextChar)),
                 Key::Char('i') => {
                     self.editor_mode = Mode::Insert;
                     Some(Command::EditorSetMode(Mode::Insert))
@@ -137,12 +1067,19 @@ impl DefaultKeyMapper {
                     self.editor_mode = Mode::Insert;
                     Some(Command::EditorMoveCursor(CursorMove::Head))
                 }
-                Key::Char('y') if input.ctrl => Some(Command::EditorScrollRelative(-1, 0)),
+                Key::Char('y') if input.ctrl => {
+                    Some(Self::repeated(count, Command::EditorScrollRelative(-1, 0)))
+                }
                 Key::Char('d') if input.ctrl => {
                     Some(Command::EditorScroll(Scrolling::HalfPageDown))
                 }
                 Key::Char('u') if input.ctrl => Some(Command::EditorScroll(Scrolling::HalfPageUp)),
                 Key::Char('f') if input.ctrl => Some(Command::EditorScroll(Scrolling::PageDown)),
+                Key::Char(':') => {
+                    self.editor_command_line = Some(String::new());
+                    Some(Command::NoOp)
+                }
+                Key::Char('=') => Some(Command::EditorFormatSql),
                 Key::Char('v') => {
                     self.editor_mode = Mode::Visual;
                     Some(Command::EditorStartSelection)
@@ -151,19 +1088,14 @@ impl DefaultKeyMapper {
                     self.editor_mode = Mode::Visual;
                     Some(Command::EditorStartSelection)
                 }
-                Key::Char('g') => {
-                    self.editor_pending_input = Some(input);
-                    Some(Command::NoOp)
-                }
-                Key::Char('G') => Some(Command::EditorMoveCursor(CursorMove::Bottom)),
                 Key::Char(op @ ('y' | 'd' | 'c')) => {
-                    self.editor_pending_input = Some(input);
                     self.editor_mode = Mode::Operator(op);
+                    self.operator_count = count;
                     Some(Command::EditorSetMode(Mode::Operator(op)))
                 }
                 _ => Some(Command::NoOp),
             },
-            Mode::Insert => match input.key {
+            Mode::Insert | Mode::Emacs => match input.key {
                 Key::Esc => {
                     self.editor_mode = Mode::Normal;
                     Some(Command::EditorSetMode(Mode::Normal))
@@ -172,6 +1104,8 @@ impl DefaultKeyMapper {
                     self.editor_mode = Mode::Normal;
                     Some(Command::EditorSetMode(Mode::Normal))
                 }
+                Key::Char('n') if input.ctrl => Some(Command::EditorComplete),
+                Key::Char('v') if input.ctrl => Some(Command::EditorPasteSystem),
                 Key::Backspace => Some(Command::EditorInputBackspace),
                 Key::Delete => Some(Command::EditorInputDelete),
                 Key::Enter => Some(Command::EditorInputEnter),
@@ -187,20 +1121,6 @@ impl DefaultKeyMapper {
                 _ => Some(Command::NoOp),
             },
             Mode::Visual => match input.key {
-                Key::Char('h') => Some(Command::EditorMoveCursor(CursorMove::Back)),
-                Key::Char('j') => Some(Command::EditorMoveCursor(CursorMove::Down)),
-                Key::Char('k') => Some(Command::EditorMoveCursor(CursorMove::Up)),
-                Key::Char('l') => Some(Command::EditorMoveCursor(CursorMove::Forward)),
-                Key::Char('w') => Some(Command::EditorMoveCursor(CursorMove::WordForward)),
-                Key::Char('e') => Some(Command::EditorMoveCursor(CursorMove::WordEnd)),
-                Key::Char('b') => Some(Command::EditorMoveCursor(CursorMove::WordBack)),
-                Key::Char('^') => Some(Command::EditorMoveCursor(CursorMove::Head)),
-                Key::Char('$') => Some(Command::EditorMoveCursor(CursorMove::End)),
-                Key::Char('g') => {
-                    self.editor_pending_input = Some(input);
-                    Some(Command::NoOp)
-                }
-                Key::Char('G') => Some(Command::EditorMoveCursor(CursorMove::Bottom)),
                 Key::Char('y') => {
                     self.editor_mode = Mode::Normal;
                     Some(Command::EditorCopySelection)
@@ -220,111 +1140,188 @@ impl DefaultKeyMapper {
                 _ => Some(Command::NoOp),
             },
             Mode::Operator(op) => {
-                let motion_command = match input.key {
-                    Key::Char('h') => Some(Command::EditorMoveCursor(CursorMove::Back)),
-                    Key::Char('j') => Some(Command::EditorMoveCursor(CursorMove::Down)),
-                    Key::Char('k') => Some(Command::EditorMoveCursor(CursorMove::Up)),
-                    Key::Char('l') => Some(Command::EditorMoveCursor(CursorMove::Forward)),
-                    Key::Char('w') => Some(Command::EditorMoveCursor(CursorMove::WordForward)),
-                    Key::Char('e') => Some(Command::EditorMoveCursor(CursorMove::WordEnd)),
-                    Key::Char('b') => Some(Command::EditorMoveCursor(CursorMove::WordBack)),
-                    Key::Char('^') => Some(Command::EditorMoveCursor(CursorMove::Head)),
-                    Key::Char('$') => Some(Command::EditorMoveCursor(CursorMove::End)),
-                    Key::Char('g') => {
-                        self.editor_pending_input = Some(input);
-                        return Some(Command::NoOp);
-                    }
-                    Key::Char('G') => Some(Command::EditorMoveCursor(CursorMove::Bottom)),
+                let count =
+                    (count * std::mem::replace(&mut self.operator_count, 1)).min(MAX_EDITOR_COUNT);
+                match input.key {
+                    // Doubling the operator (`dd`, `yy`, `cc`) applies it to whole lines.
                     Key::Char(c) if c == op => {
+                        self.editor_mode = if op == 'c' {
+                            Mode::Insert
+                        } else {
+                            Mode::Normal
+                        };
+                        Some(Command::EditorApplyLineOperator(op, count))
+                    }
+                    _ => {
                         self.editor_mode = Mode::Normal;
-                        return Some(Command::EditorPerformPendingOperator);
+                        Some(Command::EditorSetMode(Mode::Normal))
                     }
-                    _ => None,
-                };
-
-                if motion_command.is_some() {
-                    self.editor_mode = Mode::Normal;
-                    Some(Command::EditorPerformPendingOperator)
-                } else {
-                    self.editor_mode = Mode::Normal;
-                    Some(Command::NoOp)
                 }
             }
         }
     }
 
-    fn map_data_table_key(&self, key: KeyCode, tab_index: usize) -> Option<Command> {
-        use KeyCode::*;
+    fn map_table_input_key(&mut self, key: KeyCode) -> Option<Command> {
+        let input = self.table_input.as_mut()?;
         match key {
-            Char('[') => Some(Command::DataTablePreviousTab),
-            Char(']') => Some(Command::DataTableNextTab),
-
-            Char('j') | Down => {
-                if tab_index == 2 {
-                    Some(Command::DataTableNextHistoryRow)
-                } else {
-                    Some(Command::DataTableNextRow)
+            KeyCode::Esc => {
+                let kind = input.kind;
+                self.table_input = None;
+                match kind {
+                    TableInputKind::Search => {
+                        self.table_search_active = false;
+                        Some(Command::DataTableSearchCancel)
+                    }
+                    TableInputKind::HistoryFilter => {
+                        Some(Command::DataTableFilterHistory(String::new()))
+                    }
+                    TableInputKind::ExportPath | TableInputKind::PageSize => Some(Command::NoOp),
                 }
             }
-            Char('k') | Up => {
-                if tab_index == 2 {
-                    Some(Command::DataTablePreviousHistoryRow)
-                } else {
-                    Some(Command::DataTablePreviousRow)
-                }
-            }
-            PageDown => Some(Command::DataTableNextPage),
-            PageUp => Some(Command::DataTablePreviousPage),
-            Char(' ') => Some(Command::DataTableNextPage),
-            Char('g') => Some(Command::DataTableJumpToFirstRow),
-            Char('G') => Some(Command::DataTableJumpToLastRow),
-
-            Char('>') => Some(Command::DataTableScrollRight),
-            Char('<') => Some(Command::DataTableScrollLeft),
-            Char('l') | Right => Some(Command::DataTableNextColumn),
-            Char('h') | Left => Some(Command::DataTablePreviousColumn),
-            Char('w') => Some(Command::DataTableAdjustColumnWidthIncrease),
-            Char('W') => Some(Command::DataTableAdjustColumnWidthDecrease),
-
-            Char('n') => Some(Command::DataTableNextColor),
-            Char('p') => Some(Command::DataTablePreviousColor),
-
-            Char('y') => Some(Command::DataTableCopySelectedCell),
-            Char('Y') => Some(Command::DataTableCopySelectedRow),
-            Char('C') => Some(Command::DataTableCopyQueryToEditor),
-            Char('R') => Some(Command::DataTableRunSelectedHistoryQuery),
-
-            Char(c) if c.is_ascii_digit() => {
-                if let Some(digit) = c.to_digit(10) {
-                    if digit > 0 {
-                        Some(Command::DataTableSetTabIndex((digit as usize) - 1))
-                    } else {
-                        None
+            KeyCode::Enter => {
+                let TableInput { kind, value } = self.table_input.take()?;
+                match kind {
+                    TableInputKind::Search => {
+                        self.table_search_active = !value.is_empty();
+                        Some(Command::DataTableSearchSubmit)
                     }
-                } else {
-                    None
+                    TableInputKind::ExportPath => Some(Command::DataTableExportJson(value)),
+                    TableInputKind::HistoryFilter => Some(Command::NoOp),
+                    TableInputKind::PageSize => match value.parse::<usize>() {
+                        Ok(size) if size > 0 => Some(Command::DataTableSetPageSize(size)),
+                        _ => Some(Command::NoOp),
+                    },
                 }
             }
-            _ => None,
+            KeyCode::Backspace => {
+                input.value.pop();
+                Some(input.changed_command())
+            }
+            KeyCode::Char(c) if input.accepts(c) => {
+                input.value.push(c);
+                Some(input.changed_command())
+            }
+            _ => Some(Command::NoOp),
+        }
+    }
+
+    /// Whether a data table command applies in the current tab and search state.
+    fn data_table_key_available(&self, command: &Command, tab_index: usize) -> bool {
+        match command {
+            Command::DataTableSearchNext
+            | Command::DataTableSearchPrevious
+            | Command::DataTableSearchCancel => self.table_search_active,
+            Command::DataTableSearch(_)
+            | Command::DataTableExportJson(_)
+            | Command::DataTableAutoFitColumns
+            | Command::DataTableResetColumnWidths
+            | Command::DataTableToggleColumnInfo
+            | Command::DataTableToggleRowDetail
+            | Command::DataTableRowDetailScrollDown
+            | Command::DataTableRowDetailScrollUp
+            | Command::DataTableInspectCell
+            | Command::DataTableEditCell
+            | Command::DataTableDeleteRow => tab_index == 0,
+            Command::DataTableFilterHistory(_) => tab_index == 2,
+            _ => true,
+        }
+    }
+
+    /// Opens the prompt of a binding that asks for text before it runs, returning the
+    /// command to run meanwhile.
+    fn open_prompt(&mut self, command: Command) -> Command {
+        match command {
+            Command::DataTableSearch(_) => {
+                self.table_input = Some(TableInput::new(TableInputKind::Search, ""));
+                command
+            }
+            Command::DataTableFilterHistory(_) => {
+                self.table_input = Some(TableInput::new(TableInputKind::HistoryFilter, ""));
+                command
+            }
+            Command::DataTableExportJson(_) => {
+                self.table_input = Some(TableInput::new(
+                    TableInputKind::ExportPath,
+                    &default_export_path(),
+                ));
+                Command::NoOp
+            }
+            Command::DataTableSetPageSize(_) => {
+                self.table_input = Some(TableInput::new(TableInputKind::PageSize, ""));
+                Command::NoOp
+            }
+            Command::DataTableSearchCancel => {
+                self.table_search_active = false;
+                command
+            }
+            Command::SaveSnippet(_) => {
+                self.snippet_name = Some(String::new());
+                Command::NoOp
+            }
+            Command::SidebarFilter(_) => {
+                self.sidebar_filter = Some(String::new());
+                command
+            }
+            command => command,
         }
     }
 
-    fn map_sidebar_key(&self, key: KeyCode) -> Option<Command> {
-        use KeyCode::*;
+    fn map_data_table_key(&mut self, key_event: &KeyEvent, tab_index: usize) -> Option<Command> {
+        let command = self
+            .bindings
+            .table(key_event)
+            .or_else(|| {
+                lookup(DATA_TABLE_KEYS, key_event, |mapping| {
+                    self.data_table_key_available(&mapping.command, tab_index)
+                })
+                .map(|mapping| mapping.command.clone())
+            })
+            // Digits past the named tabs select later ones, should there be any.
+            .or_else(|| match key_event.code {
+                KeyCode::Char(c @ '1'..='9') if key_event.modifiers.is_empty() => {
+                    Some(Command::DataTableSetTabIndex(c as usize - '1' as usize))
+                }
+                _ => None,
+            })?;
+        // Row movement in the other tabs scrolls the log or the history instead.
+        Some(match (command, tab_index) {
+            (Command::DataTableNextRow, 1) => Command::DataTableMessagesScrollDown,
+            (Command::DataTablePreviousRow, 1) => Command::DataTableMessagesScrollUp,
+            (Command::DataTableNextRow, 2) => Command::DataTableNextHistoryRow,
+            (Command::DataTablePreviousRow, 2) => Command::DataTablePreviousHistoryRow,
+            (command, _) => self.open_prompt(command),
+        })
+    }
+
+    fn map_sidebar_filter_key(&mut self, key: KeyCode) -> Option<Command> {
+        let filter = self.sidebar_filter.as_mut()?;
         match key {
-            Char('\n') | Char(' ') => Some(Command::SidebarToggleSelected),
-            Left => Some(Command::SidebarKeyLeft),
-            Right => Some(Command::SidebarKeyRight),
-            Down => Some(Command::SidebarKeyDown),
-            Up => Some(Command::SidebarKeyUp),
-            Esc => Some(Command::SidebarDeselect),
-            Home => Some(Command::SidebarSelectFirst),
-            End => Some(Command::SidebarSelectLast),
-            PageDown => Some(Command::SidebarScrollDown(3)),
-            PageUp => Some(Command::SidebarScrollUp(3)),
-            _ => None,
+            KeyCode::Esc => {
+                self.sidebar_filter = None;
+                Some(Command::SidebarFilter(String::new()))
+            }
+            KeyCode::Enter => {
+                self.sidebar_filter = None;
+                Some(Command::NoOp)
+            }
+            KeyCode::Backspace => {
+                filter.pop();
+                Some(Command::SidebarFilter(filter.clone()))
+            }
+            KeyCode::Char(c) => {
+                filter.push(c);
+                Some(Command::SidebarFilter(filter.clone()))
+            }
+            _ => Some(Command::NoOp),
         }
     }
+
+    fn map_sidebar_key(&mut self, key_event: &KeyEvent) -> Option<Command> {
+        let command = self.bindings.sidebar(key_event).or_else(|| {
+            lookup(SIDEBAR_KEYS, key_event, |_| true).map(|mapping| mapping.command.clone())
+        })?;
+        Some(self.open_prompt(command))
+    }
 }
 
 impl KeyMapper for DefaultKeyMapper {
@@ -338,25 +1335,52 @@ impl KeyMapper for DefaultKeyMapper {
             return None;
         }
 
-        let command = match key_event.code {
-            KeyCode::Char('q') => Some(Command::Quit),
-            KeyCode::Char('?') => Some(Command::ShowKeyMap),
-            KeyCode::Tab => Some(Command::ToggleFocus),
-            KeyCode::F(5) => Some(Command::ExecuteQuery),
-            _ => None,
-        };
+        if self.snippet_name.is_some() {
+            return self.map_snippet_name_key(key_event.code);
+        }
+
+        if *current_focus == Focus::Table && self.table_input.is_some() {
+            return self.map_table_input_key(key_event.code);
+        }
+
+        if *current_focus == Focus::Sidebar && self.sidebar_filter.is_some() {
+            return self.map_sidebar_filter_key(key_event.code);
+        }
+
+        // While text is being typed into the editor, `q` and `?` are just characters.
+        let typing = *current_focus == Focus::Editor
+            && (matches!(self.editor_mode, Mode::Insert | Mode::Emacs)
+                || self.editor_command_line.is_some());
+
+        // Tab completes in vim Insert mode; Emacs has no other mode to leave the editor from.
+        let completing = *current_focus == Focus::Editor
+            && self.editor_mode == Mode::Insert
+            && self.editor_command_line.is_none();
 
-        if command.is_some() {
-            return command;
+        if completing && key_event.code == KeyCode::Tab {
+            return Some(Command::EditorComplete);
+        }
+
+        let chord = KeyChord::from(&key_event);
+        if !(typing && chord.is_text())
+            && let Some(command) = self.bindings.global(&key_event)
+        {
+            return Some(command);
+        }
+        if let Some(mapping) = lookup(GLOBAL_KEYS, &key_event, |_| !(typing && chord.is_text())) {
+            return Some(self.open_prompt(mapping.command.clone()));
         }
 
         match current_focus {
             Focus::Editor => {
                 let input = Input::from(key_event);
-                self.map_query_editor_key(input)
+                match self.bindings.editor {
+                    EditorKeys::Vim => self.map_vim_editor_key(input),
+                    EditorKeys::Emacs => self.map_emacs_editor_key(input),
+                }
             }
-            Focus::Table => self.map_data_table_key(key_event.code, tab_index),
-            Focus::Sidebar => self.map_sidebar_key(key_event.code),
+            Focus::Table => self.map_data_table_key(&key_event, tab_index),
+            Focus::Sidebar => self.map_sidebar_key(&key_event),
         }
     }
 
@@ -369,11 +1393,678 @@ impl KeyMapper for DefaultKeyMapper {
             KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('?') => Some(Command::ClosePopup),
             KeyCode::Char('k') | KeyCode::Up => Some(Command::KeyMapScrollUp),
             KeyCode::Char('j') | KeyCode::Down => Some(Command::KeyMapScrollDown),
+            KeyCode::Char('y') => Some(Command::CopyPopupContent),
+            _ => None,
+        }
+    }
+
+    fn map_json_tree_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(Command::ClosePopup),
+            KeyCode::Char('k') | KeyCode::Up => Some(Command::JsonTreeUp),
+            KeyCode::Char('j') | KeyCode::Down => Some(Command::JsonTreeDown),
+            KeyCode::Char('h') | KeyCode::Left => Some(Command::JsonTreeCollapse),
+            KeyCode::Char('l') | KeyCode::Right => Some(Command::JsonTreeExpand),
+            KeyCode::Enter | KeyCode::Char(' ') => Some(Command::JsonTreeToggle),
+            KeyCode::Char('y') => Some(Command::CopyPopupContent),
+            _ => None,
+        }
+    }
+
+    fn map_key_map_key(&mut self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        if let Some(filter) = self.key_map_filter.as_mut() {
+            return match key_event.code {
+                KeyCode::Esc => {
+                    self.key_map_filter = None;
+                    Some(Command::KeyMapFilter(String::new()))
+                }
+                KeyCode::Enter => {
+                    self.key_map_filter = None;
+                    Some(Command::NoOp)
+                }
+                KeyCode::Backspace => {
+                    filter.pop();
+                    Some(Command::KeyMapFilter(filter.clone()))
+                }
+                KeyCode::Char(c) => {
+                    filter.push(c);
+                    Some(Command::KeyMapFilter(filter.clone()))
+                }
+                _ => Some(Command::NoOp),
+            };
+        }
+
+        match key_event.code {
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('?') => Some(Command::ClosePopup),
+            KeyCode::Char('k') | KeyCode::Up => Some(Command::KeyMapScrollUp),
+            KeyCode::Char('j') | KeyCode::Down => Some(Command::KeyMapScrollDown),
+            KeyCode::Char('/') => {
+                self.key_map_filter = Some(String::new());
+                Some(Command::KeyMapFilter(String::new()))
+            }
+            KeyCode::Tab => Some(Command::KeyMapNextCategory),
+            KeyCode::BackTab => Some(Command::KeyMapPreviousCategory),
+            KeyCode::Enter | KeyCode::Char(' ') => Some(Command::KeyMapToggleCategory),
+            _ => None,
+        }
+    }
+
+    fn map_running_query_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        lookup(RUNNING_QUERY_KEYS, &key_event, |_| true).map(|mapping| mapping.command.clone())
+    }
+
+    fn map_confirm_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(Command::ConfirmQuery),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') | KeyCode::Esc => {
+                Some(Command::ClosePopup)
+            }
+            _ => None,
+        }
+    }
+
+    fn map_snippet_picker_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Command::SnippetPickerNext),
+            KeyCode::Char('k') | KeyCode::Up => Some(Command::SnippetPickerPrevious),
+            KeyCode::Enter => Some(Command::SnippetPickerLoad),
+            KeyCode::Char('d') | KeyCode::Delete => Some(Command::SnippetPickerDelete),
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::F(7) => Some(Command::ClosePopup),
+            _ => None,
+        }
+    }
+
+    fn map_connection_switcher_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => Some(Command::ConnectionSwitcherNext),
+            KeyCode::Char('k') | KeyCode::Up => Some(Command::ConnectionSwitcherPrevious),
+            KeyCode::Enter => Some(Command::ConnectionSwitcherSelect),
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::F(4) => Some(Command::ClosePopup),
+            _ => None,
+        }
+    }
+
+    /// Keys while a password is typed; every character, including `q`, is input.
+    fn map_password_prompt_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Esc => Some(Command::ClosePopup),
+            KeyCode::Enter => Some(Command::PasswordPromptSubmit),
+            KeyCode::Backspace => Some(Command::PasswordPromptBackspace),
+            KeyCode::Char(c) => Some(Command::PasswordPromptInput(c)),
+            _ => None,
+        }
+    }
+
+    fn map_cell_edit_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Esc => Some(Command::ClosePopup),
+            KeyCode::Enter => Some(Command::CellEditSubmit),
+            KeyCode::Backspace => Some(Command::CellEditBackspace),
+            KeyCode::Char('n') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Command::CellEditToggleNull)
+            }
+            KeyCode::Char(c) => Some(Command::CellEditInput(c)),
+            _ => None,
+        }
+    }
+
+    fn map_insert_form_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        match key_event.code {
+            KeyCode::Esc => Some(Command::ClosePopup),
+            KeyCode::Enter => Some(Command::InsertFormSubmit),
+            KeyCode::Down | KeyCode::Tab => Some(Command::InsertFormNextField),
+            KeyCode::Up | KeyCode::BackTab => Some(Command::InsertFormPreviousField),
+            KeyCode::Backspace => Some(Command::InsertFormBackspace),
+            KeyCode::Char('n') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Command::InsertFormToggleNull)
+            }
+            KeyCode::Char(c) => Some(Command::InsertFormInput(c)),
+            _ => None,
+        }
+    }
+
+    fn map_command_palette_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            KeyCode::Esc | KeyCode::F(1) => Some(Command::ClosePopup),
+            KeyCode::Enter => Some(Command::PaletteSubmit),
+            KeyCode::Down | KeyCode::Tab => Some(Command::PaletteNext),
+            KeyCode::Up | KeyCode::BackTab => Some(Command::PalettePrevious),
+            KeyCode::Char('n') if ctrl => Some(Command::PaletteNext),
+            KeyCode::Char('p') if ctrl => Some(Command::PalettePrevious),
+            KeyCode::Backspace => Some(Command::PaletteBackspace),
+            KeyCode::Char(c) if !ctrl => Some(Command::PaletteInput(c)),
             _ => None,
         }
     }
 
+    /// Keys for the open completion list. Anything else returns `None`, closing the list
+    /// and going on to the editor as usual.
+    fn map_completion_key(&self, key_event: KeyEvent) -> Option<Command> {
+        if key_event.kind != KeyEventKind::Press {
+            return None;
+        }
+
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            KeyCode::Down | KeyCode::Tab => Some(Command::EditorCompletionNext),
+            KeyCode::Char('n') if ctrl => Some(Command::EditorCompletionNext),
+            KeyCode::Up | KeyCode::BackTab => Some(Command::EditorCompletionPrevious),
+            KeyCode::Char('p') if ctrl => Some(Command::EditorCompletionPrevious),
+            KeyCode::Enter => Some(Command::EditorCompletionAccept),
+            KeyCode::Esc => Some(Command::EditorCompletionCancel),
+            _ => None,
+        }
+    }
+
+    fn snippet_name(&self) -> Option<String> {
+        self.snippet_name.clone()
+    }
+
+    fn editor_command_line(&self) -> Option<String> {
+        self.editor_command_line.clone()
+    }
+
+    fn sidebar_filter(&self) -> Option<String> {
+        self.sidebar_filter.clone()
+    }
+
+    fn key_map_filter(&self) -> Option<String> {
+        self.key_map_filter.clone()
+    }
+
+    /// Maps a click in the focused pane, which the caller focuses first, or a wheel notch,
+    /// which scrolls the focused pane the way the arrow keys would.
+    fn map_mouse_event(
+        &self,
+        mouse_event: MouseEvent,
+        current_focus: &Focus,
+        tab_index: usize,
+    ) -> Option<Command> {
+        let position = Position::new(mouse_event.column, mouse_event.row);
+        let down = match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                return match current_focus {
+                    Focus::Sidebar => Some(Command::SidebarClick(position)),
+                    Focus::Table => Some(Command::DataTableClick(position)),
+                    Focus::Editor => None,
+                };
+            }
+            MouseEventKind::ScrollDown => true,
+            MouseEventKind::ScrollUp => false,
+            _ => return None,
+        };
+        let lines = MOUSE_SCROLL_LINES as i16;
+        Some(match (current_focus, tab_index, down) {
+            (Focus::Sidebar, _, true) => Command::SidebarScrollDown(MOUSE_SCROLL_LINES),
+            (Focus::Sidebar, _, false) => Command::SidebarScrollUp(MOUSE_SCROLL_LINES),
+            (Focus::Editor, _, true) => Command::EditorScroll(Scrolling::Delta {
+                rows: lines,
+                cols: 0,
+            }),
+            (Focus::Editor, _, false) => Command::EditorScroll(Scrolling::Delta {
+                rows: -lines,
+                cols: 0,
+            }),
+            (Focus::Table, 1, true) => Command::DataTableMessagesScrollDown,
+            (Focus::Table, 1, false) => Command::DataTableMessagesScrollUp,
+            (Focus::Table, 2, true) => Command::DataTableNextHistoryRow,
+            (Focus::Table, 2, false) => Command::DataTablePreviousHistoryRow,
+            (Focus::Table, _, true) => Command::DataTableNextRow,
+            (Focus::Table, _, false) => Command::DataTablePreviousRow,
+        })
+    }
+
     fn editor_mode(&self) -> Mode {
         self.editor_mode
     }
+
+    fn table_input(&self) -> Option<TableInput> {
+        self.table_input.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_bindings::KeyBindings;
+
+    fn press(mapper: &mut DefaultKeyMapper, code: KeyCode, modifiers: KeyModifiers) -> Command {
+        mapper
+            .map_key_to_command(KeyEvent::new(code, modifiers), &Focus::Editor, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_user_global_keys_type_text_in_the_editor() {
+        let bindings = KeyBindings::from_toml("[global]\n\"x\" = \"execute_query\"").unwrap();
+        let mut mapper = DefaultKeyMapper::with_bindings(bindings);
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('x'), KeyModifiers::NONE),
+            Command::ExecuteQuery
+        );
+        press(&mut mapper, KeyCode::Char('i'), KeyModifiers::NONE);
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('x'), KeyModifiers::NONE),
+            Command::EditorInputChar('x')
+        );
+    }
+
+    #[test]
+    fn test_emacs_profile_edits_without_modes() {
+        let bindings = KeyBindings::from_toml("editor = \"emacs\"").unwrap();
+        let mut mapper = DefaultKeyMapper::with_bindings(bindings);
+        assert_eq!(mapper.editor_mode(), Mode::Emacs);
+
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('q'), KeyModifiers::NONE),
+            Command::EditorInputChar('q')
+        );
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('a'), KeyModifiers::CONTROL),
+            Command::EditorMoveCursor(CursorMove::Head)
+        );
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('w'), KeyModifiers::CONTROL),
+            Command::EditorDeleteWordBack
+        );
+        press(&mut mapper, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(mapper.editor_mode(), Mode::Emacs);
+    }
+
+    fn type_keys(mapper: &mut DefaultKeyMapper, keys: &str) -> Command {
+        keys.chars()
+            .map(|c| press(mapper, KeyCode::Char(c), KeyModifiers::NONE))
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_editor_keys_send_the_listed_commands() {
+        for key in EDITOR_KEYS {
+            let Some(command) = &key.command else {
+                continue;
+            };
+            let event = KeyChord::parse(key.keys).unwrap().to_event();
+            let mapped = match key.mode {
+                EditorKeyMode::Completion => DefaultKeyMapper::new().map_completion_key(event),
+                EditorKeyMode::Emacs => {
+                    let bindings = KeyBindings::from_toml("editor = \"emacs\"").unwrap();
+                    DefaultKeyMapper::with_bindings(bindings).map_key_to_command(
+                        event,
+                        &Focus::Editor,
+                        0,
+                    )
+                }
+                mode => {
+                    let mut mapper = DefaultKeyMapper::new();
+                    match mode {
+                        EditorKeyMode::Insert => type_keys(&mut mapper, "i"),
+                        EditorKeyMode::Visual => type_keys(&mut mapper, "v"),
+                        _ => Command::NoOp,
+                    };
+                    mapper.map_key_to_command(event, &Focus::Editor, 0)
+                }
+            };
+            assert_eq!(mapped.as_ref(), Some(command), "{}", key.keys);
+        }
+    }
+
+    #[test]
+    fn test_digits_select_any_tab() {
+        let mut mapper = DefaultKeyMapper::new();
+        let mut press_in_table = |c| {
+            mapper.map_key_to_command(
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+                &Focus::Table,
+                0,
+            )
+        };
+        assert_eq!(press_in_table('2'), Some(Command::DataTableSetTabIndex(1)));
+        assert_eq!(press_in_table('9'), Some(Command::DataTableSetTabIndex(8)));
+    }
+
+    #[test]
+    fn test_count_prefixes() {
+        let mut mapper = DefaultKeyMapper::new();
+        assert_eq!(
+            type_keys(&mut mapper, "12j"),
+            Command::EditorRepeat(12, Box::new(Command::EditorMoveCursor(CursorMove::Down)))
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "w"),
+            Command::EditorMoveCursor(CursorMove::WordForward)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "0"),
+            Command::EditorMoveCursor(CursorMove::Head)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "2dd"),
+            Command::EditorApplyLineOperator('d', 2)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "2d3w"),
+            Command::EditorApplyOperator('d', Motion::Cursor(CursorMove::WordForward), 6)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "99999999999j"),
+            Command::EditorRepeat(
+                MAX_EDITOR_COUNT,
+                Box::new(Command::EditorMoveCursor(CursorMove::Down))
+            )
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "9999d9999w"),
+            Command::EditorApplyOperator(
+                'd',
+                Motion::Cursor(CursorMove::WordForward),
+                MAX_EDITOR_COUNT
+            )
+        );
+        assert_eq!(mapper.editor_mode(), Mode::Normal);
+        assert_eq!(
+            type_keys(&mut mapper, "cgg"),
+            Command::EditorApplyOperator('c', Motion::Cursor(CursorMove::Top), 1)
+        );
+        assert_eq!(mapper.editor_mode(), Mode::Insert);
+    }
+
+    #[test]
+    fn test_line_jumps() {
+        let mut mapper = DefaultKeyMapper::new();
+        assert_eq!(
+            type_keys(&mut mapper, "42G"),
+            Command::EditorMoveCursor(CursorMove::Jump(41, 0))
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "G"),
+            Command::EditorMoveCursor(CursorMove::Bottom)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "7gg"),
+            Command::EditorMoveCursor(CursorMove::Jump(6, 0))
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "d3G"),
+            Command::EditorApplyOperator('d', Motion::Cursor(CursorMove::Jump(2, 0)), 1)
+        );
+
+        type_keys(&mut mapper, ":12q");
+        assert_eq!(mapper.editor_command_line().as_deref(), Some("12q"));
+        press(&mut mapper, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(
+            press(&mut mapper, KeyCode::Enter, KeyModifiers::NONE),
+            Command::EditorMoveCursor(CursorMove::Jump(11, 0))
+        );
+        assert_eq!(mapper.editor_command_line(), None);
+
+        type_keys(&mut mapper, ":");
+        press(&mut mapper, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(mapper.editor_command_line(), None);
+        assert_eq!(mapper.editor_mode(), Mode::Normal);
+    }
+
+    #[test]
+    fn test_find_char_motions() {
+        let mut mapper = DefaultKeyMapper::new();
+        let find = |kind, target| CharFind { kind, target };
+        assert_eq!(
+            type_keys(&mut mapper, "2f,"),
+            Command::EditorFindChar(find(FindKind::Forward, ','), 2)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "T("),
+            Command::EditorFindChar(find(FindKind::TillBackward, '('), 1)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "df("),
+            Command::EditorApplyOperator('d', Motion::Find(find(FindKind::Forward, '(')), 1)
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "2ct)"),
+            Command::EditorApplyOperator('c', Motion::Find(find(FindKind::TillForward, ')')), 2)
+        );
+        assert_eq!(mapper.editor_mode(), Mode::Insert);
+    }
+
+    #[test]
+    fn test_tab_completes_only_in_insert_mode() {
+        let mut mapper = DefaultKeyMapper::new();
+        assert_eq!(
+            press(&mut mapper, KeyCode::Tab, KeyModifiers::NONE),
+            Command::ToggleFocus
+        );
+        type_keys(&mut mapper, "i");
+        assert_eq!(
+            press(&mut mapper, KeyCode::Tab, KeyModifiers::NONE),
+            Command::EditorComplete
+        );
+    }
+
+    #[test]
+    fn test_alt_number_focuses_pane_while_typing() {
+        let mut mapper = DefaultKeyMapper::new();
+        type_keys(&mut mapper, "i");
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('3'), KeyModifiers::ALT),
+            Command::FocusPane(Focus::Table)
+        );
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('3'), KeyModifiers::NONE),
+            Command::EditorInputChar('3')
+        );
+    }
+
+    #[test]
+    fn test_mouse_wheel_scrolls_focused_pane() {
+        let mapper = DefaultKeyMapper::new();
+        let mouse = |kind| MouseEvent {
+            kind,
+            column: 10,
+            row: 5,
+            modifiers: KeyModifiers::NONE,
+        };
+        let wheel_down = mouse(MouseEventKind::ScrollDown);
+        assert_eq!(
+            mapper.map_mouse_event(wheel_down, &Focus::Table, 2),
+            Some(Command::DataTableNextHistoryRow)
+        );
+        assert_eq!(
+            mapper.map_mouse_event(wheel_down, &Focus::Sidebar, 0),
+            Some(Command::SidebarScrollDown(MOUSE_SCROLL_LINES))
+        );
+        assert_eq!(
+            mapper.map_mouse_event(
+                mouse(MouseEventKind::Down(MouseButton::Left)),
+                &Focus::Table,
+                0
+            ),
+            Some(Command::DataTableClick(Position::new(10, 5)))
+        );
+        assert_eq!(
+            mapper.map_mouse_event(mouse(MouseEventKind::Moved), &Focus::Table, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_key_map_filter_takes_typed_keys() {
+        let mut mapper = DefaultKeyMapper::new();
+        let mut key = |code| mapper.map_key_map_key(KeyEvent::new(code, KeyModifiers::NONE));
+        assert_eq!(key(KeyCode::Char('q')), Some(Command::ClosePopup));
+        assert_eq!(
+            key(KeyCode::Char('/')),
+            Some(Command::KeyMapFilter(String::new()))
+        );
+        assert_eq!(
+            key(KeyCode::Char('q')),
+            Some(Command::KeyMapFilter("q".to_string()))
+        );
+        assert_eq!(key(KeyCode::Enter), Some(Command::NoOp));
+        assert_eq!(key(KeyCode::Enter), Some(Command::KeyMapToggleCategory));
+    }
+
+    #[test]
+    fn test_matching_bracket_motion() {
+        let mut mapper = DefaultKeyMapper::new();
+        assert_eq!(
+            type_keys(&mut mapper, "%"),
+            Command::EditorJumpToMatchingBracket
+        );
+        assert_eq!(
+            type_keys(&mut mapper, "y%"),
+            Command::EditorApplyOperator('y', Motion::MatchingBracket, 1)
+        );
+    }
+
+    #[test]
+    fn test_paste_uses_system_clipboard() {
+        let mut mapper = DefaultKeyMapper::new();
+        assert_eq!(
+            type_keys(&mut mapper, "2p"),
+            Command::EditorRepeat(2, Box::new(Command::EditorPasteSystem))
+        );
+        type_keys(&mut mapper, "i");
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('v'), KeyModifiers::CONTROL),
+            Command::EditorPasteSystem
+        );
+    }
+
+    #[test]
+    fn test_password_prompt_types_every_character() {
+        let mapper = DefaultKeyMapper::new();
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            mapper.map_password_prompt_key(key(KeyCode::Char('q'))),
+            Some(Command::PasswordPromptInput('q'))
+        );
+        assert_eq!(
+            mapper.map_password_prompt_key(key(KeyCode::Enter)),
+            Some(Command::PasswordPromptSubmit)
+        );
+        assert_eq!(
+            mapper.map_password_prompt_key(key(KeyCode::Esc)),
+            Some(Command::ClosePopup)
+        );
+        assert_eq!(
+            mapper.map_connection_switcher_key(key(KeyCode::Char('q'))),
+            Some(Command::ClosePopup)
+        );
+        assert_eq!(
+            mapper.map_cell_edit_key(key(KeyCode::Char('q'))),
+            Some(Command::CellEditInput('q'))
+        );
+        assert_eq!(
+            mapper.map_cell_edit_key(key(KeyCode::Enter)),
+            Some(Command::CellEditSubmit)
+        );
+        assert_eq!(
+            mapper.map_cell_edit_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL)),
+            Some(Command::CellEditToggleNull)
+        );
+        assert_eq!(
+            mapper.map_insert_form_key(key(KeyCode::Char('q'))),
+            Some(Command::InsertFormInput('q'))
+        );
+        assert_eq!(
+            mapper.map_insert_form_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL)),
+            Some(Command::InsertFormToggleNull)
+        );
+    }
+
+    #[test]
+    fn test_count_resets_on_other_keys() {
+        let mut mapper = DefaultKeyMapper::new();
+        type_keys(&mut mapper, "5v");
+        assert_eq!(
+            type_keys(&mut mapper, "l"),
+            Command::EditorMoveCursor(CursorMove::Forward)
+        );
+        press(&mut mapper, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(mapper.editor_mode(), Mode::Normal);
+        assert_eq!(
+            type_keys(&mut mapper, "3dzj"),
+            Command::EditorMoveCursor(CursorMove::Down)
+        );
+    }
+
+    #[test]
+    fn test_vim_profile_quits_only_outside_insert_mode() {
+        let mut mapper = DefaultKeyMapper::new();
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('i'), KeyModifiers::NONE),
+            Command::EditorSetMode(Mode::Insert)
+        );
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('q'), KeyModifiers::NONE),
+            Command::EditorInputChar('q')
+        );
+        press(&mut mapper, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(
+            press(&mut mapper, KeyCode::Char('q'), KeyModifiers::NONE),
+            Command::Quit
+        );
+    }
+
+    #[test]
+    fn test_sidebar_filter_keys() {
+        let mut mapper = DefaultKeyMapper::new();
+        let mut sidebar_key = |code| {
+            mapper
+                .map_key_to_command(KeyEvent::new(code, KeyModifiers::NONE), &Focus::Sidebar, 0)
+                .unwrap()
+        };
+        assert_eq!(
+            sidebar_key(KeyCode::Char('/')),
+            Command::SidebarFilter(String::new())
+        );
+        assert_eq!(
+            sidebar_key(KeyCode::Char('q')),
+            Command::SidebarFilter("q".to_string())
+        );
+        assert_eq!(sidebar_key(KeyCode::Enter), Command::NoOp);
+        assert_eq!(sidebar_key(KeyCode::Char('r')), Command::SidebarRefresh);
+    }
 }
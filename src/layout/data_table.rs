@@ -1,23 +1,29 @@
 use crate::app::Focus;
 use crate::command::Command;
 use crate::components::tabs::StatefulTabs;
+use crate::crud::result_set::{CellValue, ColumnInfo, ResultSet};
+use crate::crud::row_edit::{CellUpdate, KeyColumn, RowDelete, RowInsert};
+use crate::database::connector::DatabaseType;
 use crate::state::QueryHistoryEntry;
-use crate::style::theme::COLOR_BLOCK_BG;
+use crate::style::theme;
 use crate::style::{DefaultStyle, StyleProvider};
-use arboard::Clipboard;
-use ratatui::layout::{Alignment, Constraint, Direction, Layout, Margin, Rect};
+use crate::utils::clipboard::copy_text;
+use crate::utils::copy_format::{CopyFormat, NOT_SINGLE_TABLE, format_rows};
+use chrono::{DateTime, Local};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Margin, Position, Rect};
 use ratatui::style::palette::tailwind;
 use ratatui::style::{Color, Modifier, Style, Stylize};
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{
     Block, Borders, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-    ScrollbarState, Table, TableState, Tabs,
+    ScrollbarState, Table, TableState, Tabs, Wrap,
 };
 use ratatui::{Frame, symbols};
-use serde_json::Value;
-use sqlx::{Row as SqlxRow, postgres::PgRow, types::Json};
-use std::collections::HashMap;
-use std::time::Duration;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthStr;
 
 const PALETTES: [tailwind::Palette; 4] = [
@@ -28,6 +34,37 @@ const PALETTES: [tailwind::Palette; 4] = [
 ];
 
 const ITEM_HEIGHT: usize = 1;
+/// Width of the leading row-number column.
+const NUMBERING_COLUMN_WIDTH: u16 = 4;
+
+/// Placeholder rendered for SQL NULL so it can't be confused with an empty string.
+const NULL_TEXT: &str = "NULL";
+
+/// Characters of a value shown in a cell before it is cut off with an ellipsis.
+pub const DEFAULT_MAX_CELL_LENGTH: usize = 200;
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Oldest entries are dropped from the Messages log beyond this many.
+const MAX_MESSAGES: usize = 500;
+
+/// How long a toast stays over the table footer before it clears.
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+/// Page sizes cycled through with the increase/decrease page size commands.
+const PAGE_SIZES: [usize; 4] = [25, 50, 100, 500];
+
+/// A status message kept in the Messages tab log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageEntry {
+    pub timestamp: DateTime<Local>,
+    /// The statement that was running when the message was raised, if any.
+    pub query: Option<String>,
+    /// How long the statement ran, for messages reporting its outcome.
+    pub elapsed: Option<Duration>,
+    pub text: String,
+}
 
 struct TableColors {
     buffer_bg: Color,
@@ -40,12 +77,13 @@ struct TableColors {
 }
 
 impl TableColors {
-    const fn new(color: &tailwind::Palette) -> Self {
+    fn new(color: &tailwind::Palette) -> Self {
+        let theme = theme::current();
         Self {
-            buffer_bg: tailwind::SLATE.c950,
-            header_bg: color.c900,
-            header_fg: tailwind::SLATE.c200,
-            row_fg: tailwind::SLATE.c200,
+            buffer_bg: theme.table_bg,
+            header_bg: theme.header_bg.unwrap_or(color.c900),
+            header_fg: theme.header_fg.unwrap_or(tailwind::SLATE.c200),
+            row_fg: theme.table_fg,
             selected_row_style_fg: color.c400,
             selected_column_style_fg: color.c400,
             selected_cell_style_fg: color.c600,
@@ -57,21 +95,181 @@ pub struct DataTable<'a> {
     state: TableState,
     pub history_table_state: TableState,
     pub headers: Vec<String>,
-    pub rows: Vec<PgRow>,
+    pub columns: Vec<ColumnInfo>,
+    pub rows: Vec<Vec<CellValue>>,
     pub query_history: Vec<QueryHistoryEntry>,
+    /// Substring the Query History tab is filtered by; empty shows everything.
+    pub history_filter: String,
     pub column_widths: Vec<u16>,
     pub min_column_widths: Vec<u16>,
+    /// Widths the user set by hand for the current connection, by column name.
+    pub saved_column_widths: HashMap<String, u16>,
     vertical_scroll_state: ScrollbarState,
     horizontal_scroll_state: ScrollbarState,
     horizontal_scroll: usize,
     colors: TableColors,
     color_index: usize,
     pub tabs: StatefulTabs<'a>,
+    /// The latest message, also the last entry of `messages` unless it was cleared.
     pub status_message: Option<String>,
+    messages: Vec<MessageEntry>,
+    /// Line the Messages tab is scrolled to, or `None` to follow the newest message.
+    messages_scroll: Option<u16>,
+    /// Furthest the Messages tab could scroll when last drawn.
+    messages_max_scroll: u16,
+    /// When the current `status_message` was raised as a toast, if it was.
+    toast_shown_at: Option<Instant>,
     pub elapsed: Duration,
+    /// Whether the footer breaks the time down into the query and laying out its results.
+    pub show_timings: bool,
+    /// Time spent preparing the latest results for display and drawing them the first time.
+    render_time: Duration,
+    /// Whether the latest results have yet to be drawn, which counts toward `render_time`.
+    first_draw_pending: bool,
+    /// Whether the displayed rows were cut off at the fetch limit.
+    pub truncated: bool,
+    /// Characters shown of each value, or 0 for no limit. Copying and inspecting a cell
+    /// still use the whole value.
+    pub max_cell_length: usize,
+    /// How `DataTableCopySelectedRow` writes the row.
+    pub copy_format: CopyFormat,
+    /// The table the results were selected from when they are plain columns of it, named
+    /// in rows copied as `INSERT`s.
+    pub source_table: Option<String>,
+    /// The database the results came from, whose quoting copied `INSERT`s use.
+    pub db_type: Option<DatabaseType>,
     page_size: usize,
     pub current_page: usize,
+    /// The page of a table being browsed on the server, if the rows are one.
+    pub server_page: Option<ServerPage>,
     pub loading_state: LoadingState,
+    loading_started: Option<Instant>,
+    pub search: TableSearch,
+    pub input: Option<TableInput>,
+    /// Active sort as `(data column, direction)`.
+    pub sort: Option<(usize, SortDirection)>,
+    /// Whether the column type panel is shown beside the data output.
+    pub show_column_info: bool,
+    /// Whether the selected row is shown as a vertical list of column/value pairs.
+    pub show_row_detail: bool,
+    row_detail_scroll: u16,
+    /// Where the data grid or history table was drawn on the last frame, for mouse clicks.
+    rendered_area: Rect,
+    /// Screen `(x, width)` of each column drawn in the data grid, the row number first.
+    rendered_columns: Vec<(u16, u16)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableInputKind {
+    Search,
+    ExportPath,
+    PageSize,
+    HistoryFilter,
+}
+
+/// A single-line prompt shown in the table footer while the user is typing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableInput {
+    pub kind: TableInputKind,
+    pub value: String,
+}
+
+impl TableInput {
+    pub fn new(kind: TableInputKind, value: &str) -> Self {
+        Self {
+            kind,
+            value: value.to_string(),
+        }
+    }
+
+    /// The command emitted whenever the prompt's value changes.
+    pub fn changed_command(&self) -> Command {
+        match self.kind {
+            TableInputKind::Search => Command::DataTableSearch(self.value.clone()),
+            TableInputKind::HistoryFilter => Command::DataTableFilterHistory(self.value.clone()),
+            TableInputKind::ExportPath | TableInputKind::PageSize => Command::NoOp,
+        }
+    }
+
+    /// Whether `c` may be typed into this prompt.
+    pub fn accepts(&self, c: char) -> bool {
+        match self.kind {
+            TableInputKind::PageSize => c.is_ascii_digit(),
+            TableInputKind::Search | TableInputKind::ExportPath | TableInputKind::HistoryFilter => {
+                true
+            }
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self.kind {
+            TableInputKind::Search => "/",
+            TableInputKind::ExportPath => "Export to: ",
+            TableInputKind::PageSize => "Page size: ",
+            TableInputKind::HistoryFilter => "Filter history: ",
+        }
+    }
+}
+
+pub fn default_export_path() -> String {
+    format!(
+        "lazydata_export_{}.jsonl",
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    fn reversed(self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+
+    fn indicator(self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "▲",
+            SortDirection::Descending => "▼",
+        }
+    }
+}
+
+/// Cuts `value` down to `max_length` characters, the last being an ellipsis, unless
+/// `max_length` is 0.
+fn truncate_for_display(value: String, max_length: usize) -> String {
+    if max_length == 0 || value.chars().count() <= max_length {
+        return value;
+    }
+    let mut truncated: String = value.chars().take(max_length - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Incremental search state for the data output tab.
+#[derive(Default)]
+pub struct TableSearch {
+    pub query: String,
+    /// Matching cells as `(absolute_row, column)` pairs, in row-major order.
+    pub matches: Vec<(usize, usize)>,
+    pub current: Option<usize>,
+}
+
+/// A page of a whole table fetched from the server with `LIMIT`/`OFFSET`, shown instead
+/// of a slice of rows already fetched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerPage {
+    /// The `SELECT * FROM table` query being paged through.
+    pub query: String,
+    /// Rows of the table before this page.
+    pub offset: usize,
+    /// Whether the server has rows after this page.
+    pub has_more: bool,
 }
 
 pub enum LoadingState {
@@ -81,17 +279,20 @@ pub enum LoadingState {
 }
 
 impl<'a> DataTable<'a> {
-    pub fn new(
-        headers: Vec<String>,
-        rows: Vec<PgRow>,
-        query_history: Vec<QueryHistoryEntry>,
-    ) -> Self {
+    pub fn new(result_set: ResultSet, query_history: Vec<QueryHistoryEntry>) -> Self {
+        let ResultSet {
+            headers,
+            columns,
+            rows,
+            ..
+        } = result_set;
         let mut tabs = StatefulTabs::new(vec!["Data Output", "Messages", "Query History"]);
         if rows.is_empty() {
             tabs.set_index(1);
         }
 
-        let (column_widths, min_column_widths) = Self::calculate_column_widths(&headers, &rows);
+        let (column_widths, min_column_widths) =
+            Self::calculate_column_widths(&headers, &rows, DEFAULT_MAX_CELL_LENGTH);
 
         Self {
             state: TableState::default().with_selected(if rows.is_empty() {
@@ -101,89 +302,117 @@ impl<'a> DataTable<'a> {
             }),
             history_table_state: TableState::default(),
             vertical_scroll_state: ScrollbarState::new(
-                (rows.len().min(100).saturating_sub(1)) * ITEM_HEIGHT,
-            ),
-            horizontal_scroll_state: ScrollbarState::new(
-                column_widths.iter().sum::<u16>().saturating_sub(1) as usize,
+                (rows.len().min(DEFAULT_PAGE_SIZE).saturating_sub(1)) * ITEM_HEIGHT,
             ),
+            horizontal_scroll_state: ScrollbarState::new(column_widths.len()),
             colors: TableColors::new(&PALETTES[0]),
             color_index: 0,
             horizontal_scroll: 0,
             headers,
+            columns,
             rows,
             query_history,
+            history_filter: String::new(),
             column_widths,
             min_column_widths,
+            saved_column_widths: HashMap::new(),
             tabs,
             status_message: None,
+            messages: Vec::new(),
+            messages_scroll: None,
+            messages_max_scroll: 0,
+            toast_shown_at: None,
             elapsed: Duration::ZERO,
-            page_size: 100,
+            show_timings: false,
+            max_cell_length: DEFAULT_MAX_CELL_LENGTH,
+            copy_format: CopyFormat::default(),
+            source_table: None,
+            db_type: None,
+            render_time: Duration::ZERO,
+            first_draw_pending: false,
+            truncated: false,
+            page_size: DEFAULT_PAGE_SIZE,
             current_page: 0,
+            server_page: None,
             loading_state: LoadingState::Idle,
+            loading_started: None,
+            search: TableSearch::default(),
+            input: None,
+            sort: None,
+            show_column_info: false,
+            show_row_detail: false,
+            row_detail_scroll: 0,
+            rendered_area: Rect::default(),
+            rendered_columns: Vec::new(),
         }
     }
 
-    fn calculate_column_widths(headers: &[String], rows: &[PgRow]) -> (Vec<u16>, Vec<u16>) {
+    fn calculate_column_widths(
+        headers: &[String],
+        rows: &[Vec<CellValue>],
+        max_cell_length: usize,
+    ) -> (Vec<u16>, Vec<u16>) {
+        let sample_size = 100;
+        let final_widths =
+            Self::fit_column_widths(headers, rows.iter().take(sample_size), max_cell_length);
+        (final_widths.clone(), final_widths)
+    }
+
+    /// Widths that fit the headers and every value of `rows` as displayed, plus padding.
+    fn fit_column_widths<'r>(
+        headers: &[String],
+        rows: impl Iterator<Item = &'r Vec<CellValue>>,
+        max_cell_length: usize,
+    ) -> Vec<u16> {
         let mut widths: Vec<u16> = headers.iter().map(|h| h.width() as u16).collect();
 
-        let sample_size = 100;
-        for row in rows.iter().take(std::cmp::min(rows.len(), sample_size)) {
+        for row in rows {
             for (col_idx, col_width) in widths.iter_mut().enumerate() {
-                let val = Self::get_value_as_string(row, col_idx);
-                *col_width = (*col_width).max(val.width() as u16);
+                let val_width = match row.get(col_idx) {
+                    Some(cell) if cell.is_null() => NULL_TEXT.width(),
+                    _ => truncate_for_display(
+                        Self::get_value_as_string(row, col_idx),
+                        max_cell_length,
+                    )
+                    .width(),
+                };
+                *col_width = (*col_width).max(val_width as u16);
             }
         }
 
-        let final_widths: Vec<u16> = widths.iter().map(|&w| w.saturating_add(2).max(3)).collect();
-        (final_widths.clone(), final_widths)
+        widths.iter().map(|&w| w.saturating_add(2).max(3)).collect()
     }
 
-    fn get_value_as_string(row: &PgRow, index: usize) -> String {
-        macro_rules! try_get_string {
-            ($($type:ty),*) => {
-                $(
-                    if let Ok(val) = row.try_get::<$type, _>(index) {
-                        return val.to_string();
-                    }
-                )*
-            };
-        }
-
-        try_get_string!(
-            String,
-            &str,
-            i16,
-            i32,
-            i64,
-            f32,
-            f64,
-            bool,
-            sqlx::types::Uuid,
-            sqlx::types::chrono::NaiveDate,
-            sqlx::types::chrono::NaiveDateTime,
-            sqlx::types::chrono::NaiveTime,
-            sqlx::types::chrono::DateTime<sqlx::types::chrono::Utc>
-        );
-
-        if let Ok(val) = row.try_get::<Value, _>(index) {
-            return match serde_json::to_string(&val) {
-                Ok(s) => s,
-                Err(e) => format!("[json-error: {}]", e),
-            };
+    /// Widens columns so that the values on the current page are not cut off, since the
+    /// initial widths only sample the first rows.
+    fn fit_current_page_columns(&mut self) {
+        let page_rows = self
+            .rows
+            .iter()
+            .skip(self.current_page * self.page_size)
+            .take(self.page_size);
+        let page_widths = Self::fit_column_widths(&self.headers, page_rows, self.max_cell_length);
+        for (width, page_width) in self.column_widths.iter_mut().zip(page_widths) {
+            *width = (*width).max(page_width);
         }
+    }
 
-        if let Ok(Json(val)) = row.try_get::<Json<Value>, _>(index) {
-            return match serde_json::to_string(&val) {
-                Ok(s) => s,
-                Err(e) => format!("[json-error: {}]", e),
-            };
-        }
+    /// Fits every column to the widest value in the whole result, not just the sample.
+    pub fn auto_fit_columns(&mut self) {
+        self.column_widths =
+            Self::fit_column_widths(&self.headers, self.rows.iter(), self.max_cell_length);
+    }
 
-        if let Ok(val) = row.try_get::<Vec<u8>, _>(index) {
-            return hex::encode(val);
+    /// Restores the computed widths, forgetting any hand-adjusted ones for these columns.
+    pub fn reset_column_widths(&mut self) {
+        self.column_widths = self.min_column_widths.clone();
+        for header in &self.headers {
+            self.saved_column_widths.remove(header);
         }
+    }
 
-        "".to_string()
+    fn get_value_as_string(row: &[CellValue], index: usize) -> String {
+        row.get(index).map(CellValue::to_string).unwrap_or_default()
     }
 
     pub fn handle_command(&mut self, command: Command) {
@@ -193,7 +422,15 @@ impl<'a> DataTable<'a> {
             Command::DataTableNextRow => self.next_row(),
             Command::DataTablePreviousRow => self.previous_row(),
             Command::DataTableNextHistoryRow => self.next_history_row(),
+            Command::DataTableClick(position) => self.click(position),
             Command::DataTablePreviousHistoryRow => self.previous_history_row(),
+            Command::DataTableMessagesScrollDown => {
+                let next = self.messages_scroll_position() + 1;
+                self.messages_scroll = (next < self.messages_max_scroll).then_some(next);
+            }
+            Command::DataTableMessagesScrollUp => {
+                self.messages_scroll = Some(self.messages_scroll_position().saturating_sub(1));
+            }
             Command::DataTableScrollRight => self.scroll_right(),
             Command::DataTableScrollLeft => self.scroll_left(),
             Command::DataTableNextColor => self.next_color(),
@@ -208,39 +445,257 @@ impl<'a> DataTable<'a> {
             Command::DataTablePreviousColumn => self.previous_column(),
             Command::DataTableAdjustColumnWidthIncrease => self.adjust_column_width(1),
             Command::DataTableAdjustColumnWidthDecrease => self.adjust_column_width(-1),
+            Command::DataTableAutoFitColumns => self.auto_fit_columns(),
+            Command::DataTableResetColumnWidths => self.reset_column_widths(),
             Command::DataTableCopySelectedCell => {
-                if let Some(content) = self.copy_selected_cell() {
-                    self.status_message = Some(format!("Copied: {}", content));
+                if let Some(content) = self.selected_cell_text() {
+                    self.copy_to_clipboard(&content, format!("Copied: {}", content));
                 }
             }
+            Command::DataTableCopySelectedRow
+                if self.copy_format == CopyFormat::Insert && self.source_table.is_none() =>
+            {
+                self.set_status(NOT_SINGLE_TABLE.to_string());
+            }
             Command::DataTableCopySelectedRow => {
-                if let Some(content) = self.copy_selected_row() {
-                    self.status_message = Some(format!("Copied row: {}", content));
+                if let Some(content) = self.selected_row_copy() {
+                    let label = self.copy_format.label();
+                    self.copy_to_clipboard(&content, format!("Copied row as {}", label));
+                }
+            }
+            Command::DataTableCopyAllAsInserts if !self.rows.is_empty() => {
+                match self.results_inserts() {
+                    Some(content) => self.copy_to_clipboard(
+                        &content,
+                        format!("Copied {} INSERT statements", self.rows.len()),
+                    ),
+                    None => self.set_status(NOT_SINGLE_TABLE.to_string()),
+                }
+            }
+            Command::DataTableCycleCopyFormat => {
+                self.copy_format = self.copy_format.next();
+                self.set_status(format!("Rows now copy as {}.", self.copy_format.label()));
+            }
+            Command::DataTableCopyColumn => {
+                if let Some(column) = self.selected_data_column() {
+                    let content = self.column_text(column);
+                    self.copy_to_clipboard(
+                        &content,
+                        format!(
+                            "Copied {} values of {}",
+                            self.rows.len(),
+                            self.headers[column]
+                        ),
+                    );
                 }
             }
+            Command::DataTableCopyAll if !self.headers.is_empty() => {
+                let content = self.results_tsv();
+                self.copy_to_clipboard(&content, format!("Copied {} rows as TSV", self.rows.len()));
+            }
             Command::DataTableCopyQueryToEditor => {
-                if let Some(query) = self.copy_selected_query_to_editor() {
-                    self.status_message = Some(format!("Copied query: {}", query));
+                if let Some(query) = self.get_selected_history_query() {
+                    self.copy_to_clipboard(&query, format!("Copied query: {}", query));
                 }
             }
             Command::DataTableRunSelectedHistoryQuery => {
                 if let Some(query) = self.get_selected_history_query() {
-                    self.status_message = Some(format!("Running query: {}", query));
+                    self.set_status(format!("Running query: {}", query));
                 }
             }
-            Command::DataTableSetTabIndex(idx) => {
-                if idx < self.tabs.titles.len() {
-                    self.tabs.set_index(idx);
+            Command::DataTableSetTabIndex(idx) => self.tabs.set_index(idx),
+            Command::DataTableFilterHistory(filter) => self.filter_history(filter),
+            Command::DataTableSearch(query) => {
+                self.tabs.set_index(0);
+                self.search_in_table(&query);
+            }
+            Command::DataTableSearchSubmit if !self.search.query.is_empty() => {
+                self.set_status(format!(
+                    "{} matches for \"{}\"{}",
+                    self.search.matches.len(),
+                    self.search.query,
+                    self.page_only_note()
+                ));
+            }
+            Command::DataTableSearchCancel => self.search = TableSearch::default(),
+            Command::DataTableSearchNext => self.search_next(),
+            Command::DataTableSearchPrevious => self.search_previous(),
+            Command::DataTableToggleColumnInfo => {
+                self.show_column_info = !self.show_column_info;
+            }
+            Command::DataTableToggleTimings => {
+                self.show_timings = !self.show_timings;
+            }
+            Command::DataTableToggleRowDetail => {
+                self.show_row_detail = !self.show_row_detail;
+                self.row_detail_scroll = 0;
+            }
+            Command::DataTableRowDetailScrollDown if self.show_row_detail => {
+                self.row_detail_scroll = self.row_detail_scroll.saturating_add(1);
+            }
+            Command::DataTableRowDetailScrollUp if self.show_row_detail => {
+                self.row_detail_scroll = self.row_detail_scroll.saturating_sub(1);
+            }
+            Command::DataTableSortAscending => {
+                self.sort_by_selected_column(SortDirection::Ascending);
+                self.note_page_only("Sorted");
+            }
+            Command::DataTableSortDescending => {
+                self.sort_by_selected_column(SortDirection::Descending);
+                self.note_page_only("Sorted");
+            }
+            Command::DataTableSetPageSize(size) => self.set_page_size(size),
+            Command::DataTableIncreasePageSize => {
+                if let Some(&size) = PAGE_SIZES.iter().find(|&&size| size > self.page_size) {
+                    self.set_page_size(size);
                 }
             }
+            Command::DataTableDecreasePageSize => {
+                if let Some(&size) = PAGE_SIZES.iter().rev().find(|&&size| size < self.page_size) {
+                    self.set_page_size(size);
+                }
+            }
+            Command::DataTableExportJson(path) => {
+                self.show_toast(match self.export_json(&path) {
+                    Ok(count) => format!(
+                        "Exported {} rows to {}{}",
+                        count,
+                        path,
+                        self.page_only_note()
+                    ),
+                    Err(e) => format!("Failed to export to {}: {}", path, e),
+                });
+            }
             _ => {}
         }
     }
 
+    /// A note that sorting, searching and exporting only see the rows fetched, added to
+    /// their messages when a page of a larger table is shown.
+    fn page_only_note(&self) -> &'static str {
+        match &self.server_page {
+            Some(page) if page.has_more || page.offset > 0 => {
+                " (this page only; the rest of the table stays on the server)"
+            }
+            _ => "",
+        }
+    }
+
+    /// Tells that `action` only covered this page, when a page of a larger table is shown.
+    fn note_page_only(&mut self, action: &str) {
+        let note = self.page_only_note();
+        if !note.is_empty() {
+            self.show_toast(format!("{}{}", action, note));
+        }
+    }
+
+    /// Sets the status message and appends it to the Messages log.
+    pub fn set_status(&mut self, message: String) {
+        self.log_message(None, message);
+    }
+
+    /// Sets the status message reporting the outcome of `query`, logging it along with
+    /// the statement and how long it ran.
+    pub fn set_query_status(&mut self, query: &str, elapsed: Duration, message: String) {
+        self.log_message(Some((query, elapsed)), message);
+    }
+
+    fn log_message(&mut self, query: Option<(&str, Duration)>, message: String) {
+        self.messages.push(MessageEntry {
+            timestamp: Local::now(),
+            query: query.map(|(query, _)| query.to_string()),
+            elapsed: query.map(|(_, elapsed)| elapsed),
+            text: message.clone(),
+        });
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+        self.status_message = Some(message);
+    }
+
+    /// Sets the status message and also shows it over the footer for a few seconds, so it
+    /// is visible without switching to the Messages tab.
+    pub fn show_toast(&mut self, message: String) {
+        self.set_status(message);
+        self.toast_shown_at = Some(Instant::now());
+    }
+
+    fn messages_scroll_position(&self) -> u16 {
+        self.messages_scroll
+            .unwrap_or(self.messages_max_scroll)
+            .min(self.messages_max_scroll)
+    }
+
+    /// The Messages log as text, oldest first, each entry headed by its time and statement.
+    fn messages_text(&self) -> Text<'static> {
+        let heading_style = Style::default().fg(theme::current().border_focused);
+        let mut lines = Vec::new();
+        for (i, entry) in self.messages.iter().enumerate() {
+            if i > 0 {
+                lines.push(Line::default());
+            }
+            let time = entry.timestamp.format("[%H:%M:%S]").to_string();
+            let query = entry
+                .query
+                .as_deref()
+                .map(|query| query.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default();
+            let mut heading = vec![
+                Span::styled(time, heading_style),
+                Span::raw(" "),
+                Span::styled(query, heading_style.add_modifier(Modifier::BOLD)),
+            ];
+            if let Some(elapsed) = entry.elapsed {
+                heading.push(Span::styled(
+                    format!(" ({} ms)", elapsed.as_millis()),
+                    heading_style,
+                ));
+            }
+            lines.push(Line::from(heading));
+            lines.extend(entry.text.lines().map(|line| Line::raw(line.to_string())));
+        }
+        Text::from(lines)
+    }
+
+    fn render_messages(&mut self, frame: &mut Frame, area: Rect, style: &DefaultStyle) {
+        let text = self.messages_text();
+        let visible = area.height.saturating_sub(2) as usize;
+        self.messages_max_scroll = text.height().saturating_sub(visible) as u16;
+        let scroll = self.messages_scroll_position();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(style.border_style(Focus::Table))
+            .style(style.block_style());
+        let mut scrollbar_state =
+            ScrollbarState::new(self.messages_max_scroll as usize).position(scroll as usize);
+        frame.render_widget(Paragraph::new(text).block(block).scroll((scroll, 0)), area);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(Margin::new(0, 1)),
+            &mut scrollbar_state,
+        );
+    }
+
+    /// The toast still on screen, if one was raised less than `TOAST_DURATION` ago.
+    fn active_toast(&self) -> Option<&str> {
+        let shown_at = self.toast_shown_at?;
+        if shown_at.elapsed() >= TOAST_DURATION {
+            return None;
+        }
+        self.status_message.as_deref()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.rows.is_empty()
     }
 
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
     pub fn total_pages(&self) -> usize {
         if self.rows.is_empty() {
             return 1;
@@ -291,10 +746,35 @@ impl<'a> DataTable<'a> {
         self.vertical_scroll_state = self.vertical_scroll_state.position(i * ITEM_HEIGHT);
     }
 
+    /// History entries matching the current filter, newest first.
+    fn visible_history(&self) -> Vec<&QueryHistoryEntry> {
+        let filter = self.history_filter.to_lowercase();
+        self.query_history
+            .iter()
+            .rev()
+            .filter(|entry| filter.is_empty() || entry.query.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    pub fn filter_history(&mut self, filter: String) {
+        self.history_filter = filter;
+        self.tabs.set_index(2);
+        let selected = if self.visible_history().is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.history_table_state.select(selected);
+    }
+
     pub fn next_history_row(&mut self) {
+        let len = self.visible_history().len();
+        if len == 0 {
+            return;
+        }
         let i = match self.history_table_state.selected() {
             Some(i) => {
-                if i >= self.query_history.len() - 1 {
+                if i >= len - 1 {
                     0
                 } else {
                     i + 1
@@ -306,10 +786,14 @@ impl<'a> DataTable<'a> {
     }
 
     pub fn previous_history_row(&mut self) {
+        let len = self.visible_history().len();
+        if len == 0 {
+            return;
+        }
         let i = match self.history_table_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.query_history.len() - 1
+                    len - 1
                 } else {
                     i - 1
                 }
@@ -320,10 +804,16 @@ impl<'a> DataTable<'a> {
     }
 
     pub fn next_column(&mut self) {
+        if self.headers.is_empty() {
+            return;
+        }
         self.state.select_next_column();
     }
 
     pub fn previous_column(&mut self) {
+        if self.headers.is_empty() {
+            return;
+        }
         self.state.select_previous_column();
     }
 
@@ -348,6 +838,7 @@ impl<'a> DataTable<'a> {
     pub fn next_page(&mut self) {
         if self.current_page < self.total_pages().saturating_sub(1) {
             self.current_page += 1;
+            self.fit_current_page_columns();
             self.state.select(Some(0));
             self.vertical_scroll_state = ScrollbarState::new(
                 (self.get_current_page_rows().len().saturating_sub(1)) * ITEM_HEIGHT,
@@ -359,6 +850,7 @@ impl<'a> DataTable<'a> {
     pub fn previous_page(&mut self) {
         if self.current_page > 0 {
             self.current_page = self.current_page.saturating_sub(1);
+            self.fit_current_page_columns();
             self.state.select(Some(0));
             self.vertical_scroll_state = ScrollbarState::new(
                 (self.get_current_page_rows().len().saturating_sub(1)) * ITEM_HEIGHT,
@@ -367,6 +859,22 @@ impl<'a> DataTable<'a> {
         }
     }
 
+    /// Changes the number of rows per page, keeping the selected row in view.
+    pub fn set_page_size(&mut self, page_size: usize) {
+        let page_size = page_size.max(1);
+        let selected_absolute_row =
+            self.current_page * self.page_size + self.state.selected().unwrap_or(0);
+
+        self.page_size = page_size;
+        self.set_status(format!("Page size set to {} rows.", page_size));
+
+        if self.is_empty() {
+            self.current_page = 0;
+            return;
+        }
+        self.jump_to_absolute_row(selected_absolute_row);
+    }
+
     pub fn next_color(&mut self) {
         self.color_index = (self.color_index + 1) % PALETTES.len();
     }
@@ -390,6 +898,7 @@ impl<'a> DataTable<'a> {
 
         let target_page = target_absolute_row / self.page_size;
         self.current_page = target_page; // Update current page
+        self.fit_current_page_columns();
 
         let row_on_page = target_absolute_row % self.page_size;
         self.state.select(Some(row_on_page)); // Select row on the *new* page
@@ -403,7 +912,54 @@ impl<'a> DataTable<'a> {
             .position(row_on_page * ITEM_HEIGHT);
     }
 
-    #[allow(dead_code)]
+    /// Selects the cell or history entry drawn at `position`. Clicking a column header sorts
+    /// by that column, and clicking it again flips the direction.
+    fn click(&mut self, position: Position) {
+        let inner = self.rendered_area.inner(Margin::new(1, 1));
+        if !inner.contains(position) {
+            return;
+        }
+        let clicked_row = (position.y - inner.y) as usize;
+        match self.tabs.index {
+            0 => {
+                let column = self
+                    .rendered_columns
+                    .iter()
+                    .position(|&(x, width)| (x..x + width).contains(&position.x))
+                    .filter(|&column| column > 0);
+                if clicked_row == 0 {
+                    if column.is_some() {
+                        self.state.select_column(column);
+                        self.sort_by_selected_column(SortDirection::Ascending);
+                    }
+                    return;
+                }
+                let page_start = self.current_page * self.page_size;
+                let page_rows = self
+                    .rows
+                    .len()
+                    .saturating_sub(page_start)
+                    .min(self.page_size);
+                let row = self.state.offset() + clicked_row - 1;
+                if row < page_rows {
+                    self.state.select(Some(row));
+                    self.vertical_scroll_state =
+                        self.vertical_scroll_state.position(row * ITEM_HEIGHT);
+                    if column.is_some() {
+                        self.state.select_column(column);
+                    }
+                }
+            }
+            2 if clicked_row > 0 => {
+                let row = self.history_table_state.offset() + clicked_row - 1;
+                if row < self.visible_history().len() {
+                    self.history_table_state.select(Some(row));
+                }
+            }
+            _ => {}
+        }
+    }
+
     pub fn jump_to_column(&mut self, col: usize) {
         if col < self.headers.len() {
             self.horizontal_scroll = col;
@@ -411,36 +967,260 @@ impl<'a> DataTable<'a> {
         }
     }
 
-    #[allow(dead_code)]
+    /// Finds every cell containing `query` (case-insensitive) and jumps to the first one.
     pub fn search_in_table(&mut self, query: &str) -> Option<(usize, usize)> {
-        for (row_idx, row) in self.rows.iter().enumerate() {
-            for (col_idx, _col) in row.columns().iter().enumerate() {
-                let cell_value = Self::get_value_as_string(row, col_idx);
-                if cell_value.to_lowercase().contains(&query.to_lowercase()) {
-                    let page_row_idx = row_idx % self.page_size;
-                    let target_page = row_idx / self.page_size;
-
-                    self.current_page = target_page; // Set current page
-                    self.state.select(Some(page_row_idx)); // Select row on the target page
-
-                    // Update vertical scroll state for the *new* page and its position
-                    self.vertical_scroll_state = ScrollbarState::new(
-                        (self.get_current_page_rows().len().saturating_sub(1)) * ITEM_HEIGHT,
-                    );
-                    self.vertical_scroll_state = self
-                        .vertical_scroll_state
-                        .position(page_row_idx * ITEM_HEIGHT);
+        self.search.query = query.to_string();
+        self.search.matches = self.find_matches(query);
+        self.search.current = None;
+        self.select_search_match(0)
+    }
 
-                    self.horizontal_scroll = col_idx; // Scroll to the found column
-                    self.horizontal_scroll_state = self.horizontal_scroll_state.position(col_idx);
-                    return Some((page_row_idx, col_idx));
+    fn find_matches(&self, query: &str) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        if query.is_empty() {
+            return matches;
+        }
+
+        let needle = query.to_lowercase();
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            for (col_idx, cell) in row.iter().enumerate() {
+                if cell.to_string().to_lowercase().contains(&needle) {
+                    matches.push((row_idx, col_idx));
                 }
             }
         }
-        None
+        matches
+    }
+
+    fn select_search_match(&mut self, index: usize) -> Option<(usize, usize)> {
+        let (row_idx, col_idx) = *self.search.matches.get(index)?;
+        self.search.current = Some(index);
+        self.jump_to_absolute_row(row_idx);
+        self.jump_to_column(col_idx);
+        // Column 0 is the row-number column, so the first visible data column is 1.
+        self.state.select_column(Some(1));
+        Some((row_idx, col_idx))
+    }
+
+    pub fn search_next(&mut self) {
+        let total = self.search.matches.len();
+        if total == 0 {
+            return;
+        }
+        let next = self.search.current.map_or(0, |i| (i + 1) % total);
+        self.select_search_match(next);
+    }
+
+    pub fn search_previous(&mut self) {
+        let total = self.search.matches.len();
+        if total == 0 {
+            return;
+        }
+        let previous = self
+            .search
+            .current
+            .map_or(total - 1, |i| (i + total - 1) % total);
+        self.select_search_match(previous);
+    }
+
+    /// Returns the index into `headers` of the selected column, ignoring the row-number column.
+    fn selected_data_column(&self) -> Option<usize> {
+        let col_idx = self.state.selected_column()?;
+        if col_idx == 0 {
+            return None;
+        }
+        let data_col = col_idx - 1 + self.horizontal_scroll;
+        (data_col < self.headers.len()).then_some(data_col)
+    }
+
+    /// The row within all results and the data column of the selected cell.
+    pub fn selected_position(&self) -> Option<(usize, usize)> {
+        let column = self.selected_data_column()?;
+        let row = self.current_page * self.page_size + self.state.selected()?;
+        (row < self.rows.len()).then_some((row, column))
+    }
+
+    /// Prepares an edit of the cell at `position`, a row of `table` identified by its
+    /// `key` columns. Returns `None` when the results don't include every key column.
+    pub fn cell_update(
+        &self,
+        table: &str,
+        position: (usize, usize),
+        key: &[String],
+    ) -> Option<CellUpdate> {
+        let (row, column) = position;
+        let key = self.row_key(row, key)?;
+        let original = self.rows.get(row)?.get(column)?.clone();
+        Some(CellUpdate {
+            table: table.to_string(),
+            column: self.headers.get(column)?.clone(),
+            type_name: self.column_type_name(column),
+            value: (!original.is_null()).then(|| original.to_string()),
+            original,
+            key,
+            position,
+        })
+    }
+
+    /// Prepares the deletion of `row`, a row of `table` identified by its `key` columns.
+    /// Returns `None` when the results don't include every key column.
+    pub fn row_delete(&self, table: &str, row: usize, key: &[String]) -> Option<RowDelete> {
+        Some(RowDelete {
+            table: table.to_string(),
+            key: self.row_key(row, key)?,
+            row,
+        })
+    }
+
+    /// The values of the `key` columns in `row`, if the results include them all.
+    fn row_key(&self, row: usize, key: &[String]) -> Option<Vec<KeyColumn>> {
+        let values = self.rows.get(row)?;
+        key.iter()
+            .map(|name| {
+                let index = self.headers.iter().position(|header| header == name)?;
+                Some(KeyColumn {
+                    name: name.clone(),
+                    type_name: self.column_type_name(index),
+                    value: values.get(index)?.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn column_type_name(&self, index: usize) -> Option<String> {
+        self.columns
+            .get(index)
+            .map(|info| info.type_name.clone())
+            .filter(|name| !name.is_empty())
     }
 
-    pub fn copy_selected_cell(&self) -> Option<String> {
+    /// Shows the outcome of writing an edited cell back, updating the cell if its row was
+    /// found.
+    pub fn finish_cell_update(&mut self, update: &CellUpdate, rows_affected: usize) {
+        self.loading_state = LoadingState::Idle;
+        let (row, column) = update.position;
+        if rows_affected == 0 {
+            self.set_status(format!(
+                "No row of {} has {} any more; nothing was updated.",
+                update.table,
+                update.describe_row()
+            ));
+            return;
+        }
+        if let Some(cell) = self.rows.get_mut(row).and_then(|row| row.get_mut(column)) {
+            *cell = match &update.value {
+                Some(value) => CellValue::Text(value.clone()),
+                None => CellValue::Null,
+            };
+        }
+        self.set_status(format!(
+            "Updated {} of the {} row where {}.",
+            update.column,
+            update.table,
+            update.describe_row()
+        ));
+    }
+
+    /// Shows the outcome of deleting a row, removing it from the results if it was found.
+    pub fn finish_row_delete(&mut self, delete: &RowDelete, rows_affected: usize) {
+        self.loading_state = LoadingState::Idle;
+        if rows_affected == 0 {
+            self.set_status(format!(
+                "No row of {} has {} any more; nothing was deleted.",
+                delete.table,
+                delete.describe_row()
+            ));
+            return;
+        }
+        if delete.row < self.rows.len() {
+            self.rows.remove(delete.row);
+            self.search.matches = self.find_matches(&self.search.query);
+            self.search.current = None;
+            if self.rows.is_empty() {
+                self.current_page = 0;
+                self.state.select(None);
+            } else {
+                self.jump_to_absolute_row(delete.row);
+            }
+        }
+        self.set_status(format!(
+            "Deleted the {} row where {}.",
+            delete.table,
+            delete.describe_row()
+        ));
+    }
+
+    /// Shows the outcome of inserting a row, leaving the results as they were.
+    pub fn finish_row_insert(&mut self, insert: &RowInsert, rows_affected: usize) {
+        self.loading_state = LoadingState::Idle;
+        self.set_status(format!(
+            "Inserted {} row{} into {}.",
+            rows_affected,
+            if rows_affected == 1 { "" } else { "s" },
+            insert.table
+        ));
+    }
+
+    /// Sorts all rows by the selected column. Repeating the same sort flips its direction.
+    pub fn sort_by_selected_column(&mut self, direction: SortDirection) {
+        let Some(col) = self.selected_data_column() else {
+            return;
+        };
+
+        let direction = match self.sort {
+            Some((sorted_col, current)) if sorted_col == col && current == direction => {
+                direction.reversed()
+            }
+            _ => direction,
+        };
+
+        self.rows.sort_by(|a, b| {
+            let ordering = a
+                .get(col)
+                .unwrap_or(&CellValue::Null)
+                .sort_cmp(b.get(col).unwrap_or(&CellValue::Null));
+            match direction {
+                SortDirection::Ascending => ordering,
+                SortDirection::Descending => ordering.reverse(),
+            }
+        });
+        self.sort = Some((col, direction));
+
+        self.search.matches = self.find_matches(&self.search.query);
+        self.search.current = None;
+        self.jump_to_absolute_row(0);
+    }
+
+    /// Returns the selected cell's column name and its full, pretty-printed contents.
+    pub fn selected_cell_preview(&self) -> Option<(String, String)> {
+        let col = self.selected_data_column()?;
+        let absolute_row_idx = self.current_page * self.page_size + self.state.selected()?;
+        let cell = self.rows.get(absolute_row_idx)?.get(col)?;
+
+        let content = match cell {
+            CellValue::Null => NULL_TEXT.to_string(),
+            CellValue::Json(val) => {
+                serde_json::to_string_pretty(val).unwrap_or_else(|_| cell.to_string())
+            }
+            CellValue::Text(text) => match serde_json::from_str::<Value>(text) {
+                Ok(val @ (Value::Object(_) | Value::Array(_))) => {
+                    serde_json::to_string_pretty(&val).unwrap_or_else(|_| text.clone())
+                }
+                _ => text.clone(),
+            },
+            _ => cell.to_string(),
+        };
+
+        Some((self.headers[col].clone(), content))
+    }
+
+    /// Copies `text`, toasting `success` or, when the clipboard fails, where the text went.
+    fn copy_to_clipboard(&mut self, text: &str, success: String) {
+        self.show_toast(copy_text(text).map_or_else(|message| message, |()| success));
+    }
+
+    /// The selected cell as copied: its value, or the row number in the numbering column.
+    pub fn selected_cell_text(&self) -> Option<String> {
         let content = match (self.state.selected(), self.state.selected_column()) {
             (Some(row_idx_on_page), Some(col_idx)) => {
                 let absolute_row_idx = self.current_page * self.page_size + row_idx_on_page;
@@ -449,7 +1229,7 @@ impl<'a> DataTable<'a> {
 
                 if col_idx == 0 {
                     (absolute_row_idx + 1).to_string()
-                } else if adjusted_col < row.columns().len() {
+                } else if adjusted_col < row.len() {
                     Self::get_value_as_string(row, adjusted_col)
                 } else {
                     return None;
@@ -458,89 +1238,98 @@ impl<'a> DataTable<'a> {
             _ => return None,
         };
 
-        if let Ok(mut clipboard) = Clipboard::new() {
-            let _ = clipboard.set_text(&content);
-        }
-
         Some(content)
     }
 
-    pub fn copy_selected_row(&self) -> Option<String> {
-        let selected_row_index_on_page = self.state.selected()?;
-        let absolute_selected_row_index =
-            self.current_page * self.page_size + selected_row_index_on_page;
-
-        let headers = &self.headers;
-        let row_data = self.rows.get(absolute_selected_row_index)?;
+    /// The selected row in the chosen copy format.
+    pub fn selected_row_copy(&self) -> Option<String> {
+        let row = self.current_page * self.page_size + self.state.selected()?;
+        let row = self.rows.get(row)?;
+        Some(self.format_rows(self.copy_format, std::slice::from_ref(row)))
+    }
 
-        let mut row_as_json_object: HashMap<String, Value> = HashMap::new();
-        for (i, header) in headers.iter().enumerate() {
-            let cell_value = Self::get_value_as_string(row_data, i);
-            let json_value = if cell_value.eq_ignore_ascii_case("null")
-                || cell_value.eq_ignore_ascii_case("[null]")
-            {
-                Value::Null
-            } else {
-                Value::String(cell_value)
-            };
-            row_as_json_object.insert(header.clone(), json_value);
-        }
+    fn format_rows(&self, format: CopyFormat, rows: &[Vec<CellValue>]) -> String {
+        format_rows(
+            format,
+            &self.headers,
+            rows,
+            self.source_table.as_deref(),
+            self.db_type.unwrap_or(DatabaseType::PostgreSQL),
+        )
+    }
 
-        let json_string = serde_json::to_string_pretty(&row_as_json_object)
-            .map_err(|e| eprintln!("Error: Failed to serialize row data to JSON: {}", e))
-            .ok()?;
+    /// Every value of `column`, one per line, with NULL left empty.
+    fn column_text(&self, column: usize) -> String {
+        self.rows
+            .iter()
+            .map(|row| Self::get_value_as_string(row, column))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-        if let Ok(mut clipboard) = Clipboard::new() {
-            if let Err(e) = clipboard.set_text(&json_string) {
-                eprintln!("Warning: Could not set clipboard text: {}", e);
-            }
-        } else {
-            eprintln!("Warning: Could not access clipboard.");
-        }
+    /// The headers and every row as tab-separated values, ready to paste into a
+    /// spreadsheet.
+    fn results_tsv(&self) -> String {
+        self.format_rows(CopyFormat::Tsv, &self.rows)
+    }
 
-        Some(json_string)
+    /// An `INSERT` per row into the table the results were selected from, or `None` when
+    /// they aren't plain columns of a single table.
+    fn results_inserts(&self) -> Option<String> {
+        self.source_table.as_ref()?;
+        Some(self.format_rows(CopyFormat::Insert, &self.rows))
     }
 
-    pub fn copy_selected_query_to_editor(&self) -> Option<String> {
-        if let Some(selected) = self.history_table_state.selected() {
-            let query = self
-                .query_history
+    /// Writes every row as a JSON object on its own line (NDJSON) and returns the row count.
+    pub fn export_json(&self, path: &str) -> std::io::Result<usize> {
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .map(|home| home.join(rest))
+                .unwrap_or_else(|| rest.into()),
+            None => path.into(),
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        for row in &self.rows {
+            let object: Map<String, Value> = self
+                .headers
                 .iter()
-                .rev()
-                .nth(selected)
-                .unwrap()
-                .query
-                .clone();
-            if let Ok(mut clipboard) = Clipboard::new() {
-                let _ = clipboard.set_text(query.clone());
-            }
-            Some(query)
-        } else {
-            None
+                .zip(row)
+                .map(|(header, cell)| (header.clone(), cell.to_json()))
+                .collect();
+            serde_json::to_writer(&mut writer, &object)?;
+            writer.write_all(b"\n")?;
         }
+        writer.flush()?;
+
+        Ok(self.rows.len())
     }
 
     pub fn get_selected_history_query(&self) -> Option<String> {
-        if let Some(selected) = self.history_table_state.selected() {
-            let query = self
-                .query_history
-                .iter()
-                .rev()
-                .nth(selected)
-                .unwrap()
-                .query
-                .clone();
-            Some(query)
-        } else {
-            None
-        }
+        let selected = self.history_table_state.selected()?;
+        self.visible_history()
+            .get(selected)
+            .map(|entry| entry.query.clone())
     }
 
     pub fn adjust_column_width(&mut self, delta: i16) {
-        if let Some(col) = self.state.selected_column() {
-            self.column_widths[col] = (self.column_widths[col] as i16 + delta)
-                .max(self.min_column_widths[col] as i16)
-                as u16;
+        if let Some(col) = self.selected_data_column()
+            && let Some(column_width) = self.column_widths.get_mut(col)
+        {
+            let min_width = self.min_column_widths.get(col).copied().unwrap_or(3);
+            let width = (*column_width as i16 + delta).max(min_width as i16) as u16;
+            *column_width = width;
+            self.saved_column_widths
+                .insert(self.headers[col].clone(), width);
+        }
+    }
+
+    /// Gives columns the user has sized before their remembered width, narrower or wider.
+    fn apply_saved_column_widths(&mut self) {
+        for (header, width) in self.headers.iter().zip(self.column_widths.iter_mut()) {
+            if let Some(&saved) = self.saved_column_widths.get(header) {
+                *width = saved.max(3);
+            }
         }
     }
 
@@ -555,11 +1344,12 @@ impl<'a> DataTable<'a> {
             .alignment(Alignment::Center)
     }
 
-    fn create_padded_cell_text(content: &str) -> Text<'_> {
+    fn create_padded_cell_text(content: String) -> Text<'static> {
         Text::from(Line::raw(content))
     }
 
     pub fn draw(&mut self, frame: &mut Frame, area: Rect, current_focus: &Focus) {
+        let draw_started = self.first_draw_pending.then(Instant::now);
         // Optimization: Create DefaultStyle once for this `draw` call
         let app_style = DefaultStyle {
             focus: current_focus.clone(),
@@ -576,23 +1366,99 @@ impl<'a> DataTable<'a> {
         let tab_area = main_layout[0];
         let content_area = main_layout[1];
         let query_info_area = main_layout[2];
+        // Only what is drawn below this frame can be clicked.
+        self.rendered_area = Rect::default();
+
+        let base_style = Style::default().bg(theme::current().background);
+        let total_rows_str = if let Some(page) = &self.server_page {
+            format!(
+                "Rows: {}-{}",
+                page.offset + 1.min(self.rows.len()),
+                page.offset + self.rows.len()
+            )
+        } else if self.truncated {
+            format!("Total Rows: {} (truncated)", self.rows.len())
+        } else {
+            format!("Total Rows: {}", self.rows.len())
+        };
+        let query_done_str = if self.show_timings {
+            format!(
+                "Query: {} ms, render: {} ms",
+                self.elapsed.as_millis(),
+                self.render_time.as_millis()
+            )
+        } else {
+            format!("Query Complete: {} ms", self.elapsed.as_millis())
+        };
+        let pagination_info_str = match &self.server_page {
+            // The server isn't asked for a count, which could take long on a huge table.
+            Some(page) => {
+                let number = page.offset / self.page_size + 1;
+                if page.has_more {
+                    format!("Page: {}/? (server)", number)
+                } else {
+                    format!("Page: {}/{} (server)", number, number)
+                }
+            }
+            None => format!("Page: {}/{}", self.current_page + 1, self.total_pages()),
+        };
 
-        let base_style = Style::default().bg(COLOR_BLOCK_BG);
-        let total_rows_str = format!("Total Rows: {}", self.rows.len());
-        let query_done_str = format!("Query Complete: {} ms", self.elapsed.as_millis());
-        let pagination_info_str = format!("Page: {}/{}", self.current_page + 1, self.total_pages());
+        let mut info_segments = vec![total_rows_str, query_done_str, pagination_info_str];
+        if !self.history_filter.is_empty() {
+            info_segments.push(format!(
+                "History \"{}\": {}/{}",
+                self.history_filter,
+                self.visible_history().len(),
+                self.query_history.len()
+            ));
+        }
+        if !self.search.query.is_empty() {
+            let position = self.search.current.map_or(0, |i| i + 1);
+            info_segments.push(format!(
+                "Search \"{}\": {}/{}",
+                self.search.query,
+                position,
+                self.search.matches.len()
+            ));
+        }
 
-        let tab_lines = [total_rows_str, query_done_str, pagination_info_str]
+        let tab_lines = info_segments
             .iter()
             .map(|text| Line::from(Span::styled(text.clone(), base_style)))
             .collect::<Vec<_>>();
 
-        let query_info_tabs = Tabs::new(tab_lines)
-            .select(0)
-            .highlight_style(base_style)
-            .divider(symbols::line::VERTICAL)
+        if let Some(input) = &self.input {
+            let prompt = Paragraph::new(Line::from(vec![
+                Span::styled(
+                    input.label(),
+                    base_style.fg(theme::current().border_focused),
+                ),
+                Span::styled(input.value.clone(), base_style),
+                Span::styled("█", base_style.fg(theme::current().border_focused)),
+            ]))
+            .style(app_style.block_style());
+            frame.render_widget(prompt, query_info_area);
+        } else if let Some(toast) = self.active_toast() {
+            // The footer is a single line, so multi-line messages such as a copied row are
+            // flattened; the Messages tab keeps the original.
+            let flattened = toast.split_whitespace().collect::<Vec<_>>().join(" ");
+            let toast_widget = Paragraph::new(Line::from(Span::styled(
+                flattened,
+                base_style
+                    .fg(theme::current().border_focused)
+                    .add_modifier(Modifier::BOLD),
+            )))
             .style(app_style.block_style());
-        frame.render_widget(query_info_tabs, query_info_area);
+            frame.render_widget(toast_widget, query_info_area);
+        } else {
+            self.toast_shown_at = None;
+            let query_info_tabs = Tabs::new(tab_lines)
+                .select(0)
+                .highlight_style(base_style)
+                .divider(symbols::line::VERTICAL)
+                .style(app_style.block_style());
+            frame.render_widget(query_info_tabs, query_info_area);
+        }
 
         let tabs_widget = self
             .tabs
@@ -610,14 +1476,30 @@ impl<'a> DataTable<'a> {
                             let message = "No data output. Execute a query to get output";
                             let status_widget = self.build_status_paragraph(message, &app_style);
                             frame.render_widget(status_widget, content_area);
+                        } else if self.show_row_detail {
+                            self.render_row_detail(frame, content_area, &app_style);
+                        } else if self.show_column_info {
+                            let [table_area, info_area] = Layout::default()
+                                .direction(Direction::Horizontal)
+                                .constraints([Constraint::Min(0), Constraint::Length(44)])
+                                .areas(content_area);
+                            self.render_table(frame, table_area, current_focus);
+                            self.render_scrollbar(frame, table_area);
+                            self.render_column_info(frame, info_area, &app_style);
                         } else {
                             self.render_table(frame, content_area, current_focus);
                             self.render_scrollbar(frame, content_area);
                         }
                     }
                     LoadingState::Loading => {
-                        let loading_widget =
-                            self.build_status_paragraph("Loading data...", &app_style);
+                        let elapsed = self.loading_elapsed();
+                        let frame_idx = (elapsed.as_millis() / 100) as usize % SPINNER.len();
+                        let message = format!(
+                            "{} Loading data... ({:.1}s)",
+                            SPINNER[frame_idx],
+                            elapsed.as_secs_f64()
+                        );
+                        let loading_widget = self.build_status_paragraph(&message, &app_style);
                         frame.render_widget(loading_widget, content_area);
                     }
                     LoadingState::Error(ref err_msg) => {
@@ -628,19 +1510,19 @@ impl<'a> DataTable<'a> {
                 }
             }
             1 => {
-                let messages_block = Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(app_style.border_style(Focus::Table))
-                    .style(app_style.block_style());
-                let message = self.status_message.clone().unwrap_or("".to_string());
-                let messages_paragraph = Paragraph::new(message).block(messages_block);
-                frame.render_widget(messages_paragraph, content_area);
+                self.render_messages(frame, content_area, &app_style);
             }
             2 => {
                 self.render_history_table(frame, content_area, current_focus);
             }
             _ => {}
         }
+
+        // The footer above shows the total from the next frame on.
+        if let Some(started) = draw_started {
+            self.render_time += started.elapsed();
+            self.first_draw_pending = false;
+        }
     }
 
     fn render_table(&mut self, frame: &mut Frame, area: Rect, current_focus: &Focus) {
@@ -656,7 +1538,12 @@ impl<'a> DataTable<'a> {
         let data_column_widths = &self.column_widths;
         let data_headers = &self.headers;
 
-        let owned_current_page_rows: Vec<Vec<String>> = self.get_current_page_rows();
+        let page_start = current_page * page_size;
+        let page_end = (page_start + page_size).min(self.rows.len());
+        let current_page_rows = &self.rows[page_start..page_end];
+        let server_offset = self.server_page.as_ref().map_or(0, |page| page.offset);
+        let null_style = table_widget_style.null_style();
+        let max_cell_length = self.max_cell_length;
 
         let header_style = Style::default().fg(colors.header_fg).bg(colors.header_bg);
         let selected_row_style = Style::default()
@@ -666,20 +1553,31 @@ impl<'a> DataTable<'a> {
         let selected_cell_style = Style::default()
             .add_modifier(Modifier::REVERSED)
             .fg(colors.selected_cell_style_fg);
+        let search_match_style = Style::default()
+            .fg(tailwind::SLATE.c950)
+            .bg(tailwind::YELLOW.c300);
+        let current_match_style = Style::default()
+            .fg(tailwind::SLATE.c950)
+            .bg(tailwind::ORANGE.c400)
+            .add_modifier(Modifier::BOLD);
+
+        let current_match = self
+            .search
+            .current
+            .and_then(|i| self.search.matches.get(i))
+            .copied();
+        let page_matches: HashSet<(usize, usize)> = self
+            .search
+            .matches
+            .iter()
+            .filter(|(row, _)| (page_start..page_end).contains(row))
+            .copied()
+            .collect();
 
-        let numbering_col_width = 4;
-        let mut visible_columns = 0;
-        let mut total_width_of_visible_data_columns = 0;
+        let numbering_col_width = NUMBERING_COLUMN_WIDTH;
+        let visible_columns = self.visible_column_count(area.width);
         let available_width = area.width.saturating_sub(1);
 
-        for &width in data_column_widths.iter().skip(horizontal_scroll) {
-            if numbering_col_width + total_width_of_visible_data_columns + width > available_width {
-                break;
-            }
-            total_width_of_visible_data_columns += width;
-            visible_columns += 1;
-        }
-
         let mut adjusted_widths = Vec::with_capacity(visible_columns + 1);
         adjusted_widths.push(Constraint::Length(numbering_col_width));
 
@@ -699,11 +1597,18 @@ impl<'a> DataTable<'a> {
             }
         }
 
-        let visible_headers: Vec<&str> = data_headers
+        let sort = self.sort;
+        let visible_headers: Vec<String> = data_headers
             .iter()
+            .enumerate()
             .skip(horizontal_scroll)
             .take(visible_columns)
-            .map(|s| s.as_str())
+            .map(|(col_idx, header)| match sort {
+                Some((sorted_col, direction)) if sorted_col == col_idx => {
+                    format!("{} {}", header, direction.indicator())
+                }
+                _ => header.clone(),
+            })
             .collect();
 
         let header = std::iter::once(Cell::from("#"))
@@ -712,15 +1617,34 @@ impl<'a> DataTable<'a> {
             .style(header_style)
             .height(1);
 
-        let rows = owned_current_page_rows.iter().enumerate().map(|(i, row)| {
-            let absolute_row_number = current_page * page_size + i + 1;
+        let rows = current_page_rows.iter().enumerate().map(|(i, row)| {
+            let absolute_row_number = server_offset + current_page * page_size + i + 1;
             let number_cell = Cell::from(Text::from(format!("{}", absolute_row_number)));
 
+            let absolute_row_idx = page_start + i;
             let data_cells = row
                 .iter()
+                .enumerate()
                 .skip(horizontal_scroll)
                 .take(visible_columns)
-                .map(|text| Cell::from(Self::create_padded_cell_text(text.as_str())));
+                .map(|(col_idx, value)| {
+                    let cell = if value.is_null() {
+                        Cell::from(Span::styled(NULL_TEXT, null_style))
+                    } else {
+                        Cell::from(Self::create_padded_cell_text(truncate_for_display(
+                            value.to_string(),
+                            max_cell_length,
+                        )))
+                    };
+                    let position = (absolute_row_idx, col_idx);
+                    if current_match == Some(position) {
+                        cell.style(current_match_style)
+                    } else if page_matches.contains(&position) {
+                        cell.style(search_match_style)
+                    } else {
+                        cell
+                    }
+                });
 
             Row::new(std::iter::once(number_cell).chain(data_cells))
                 .style(Style::new().fg(colors.row_fg))
@@ -728,6 +1652,19 @@ impl<'a> DataTable<'a> {
         });
 
         let bar = " █ ";
+        let [_, columns_area] = Layout::horizontal([
+            Constraint::Length(bar.chars().count() as u16),
+            Constraint::Fill(0),
+        ])
+        .areas(area.inner(Margin::new(1, 1)));
+        self.rendered_columns = Layout::horizontal(adjusted_widths.clone())
+            .spacing(1)
+            .split(columns_area)
+            .iter()
+            .map(|rect| (rect.x, rect.width))
+            .collect();
+        self.rendered_area = area;
+
         let t = Table::new(rows, adjusted_widths)
             .header(header)
             .row_highlight_style(selected_row_style)
@@ -746,6 +1683,107 @@ impl<'a> DataTable<'a> {
         frame.render_stateful_widget(t, area, &mut self.state);
     }
 
+    /// Lists each result column with its SQL type and nullability.
+    fn render_column_info(&self, frame: &mut Frame, area: Rect, style: &DefaultStyle) {
+        let header_style = Style::default()
+            .fg(self.colors.header_fg)
+            .bg(self.colors.header_bg);
+        let header = ["Column", "Type", "Null"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style);
+
+        let rows = self.headers.iter().enumerate().map(|(i, name)| {
+            let info = self.columns.get(i);
+            let type_name = info.map_or("?", |c| c.type_name.as_str());
+            let nullable = match info.and_then(|c| c.nullable) {
+                Some(true) => "YES",
+                Some(false) => "NO",
+                None => "?",
+            };
+            Row::new(vec![
+                Cell::from(name.clone()),
+                Cell::from(type_name.to_string()),
+                Cell::from(nullable),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(45),
+                Constraint::Percentage(40),
+                Constraint::Percentage(15),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::default()
+                .title("Columns")
+                .borders(Borders::ALL)
+                .border_style(style.border_style(Focus::Table))
+                .style(style.block_style()),
+        );
+        frame.render_widget(table, area);
+    }
+
+    /// Renders the selected row one column per line, like psql's expanded display.
+    fn render_row_detail(&mut self, frame: &mut Frame, area: Rect, style: &DefaultStyle) {
+        let absolute_row_idx =
+            self.current_page * self.page_size + self.state.selected().unwrap_or(0);
+        let Some(row) = self.rows.get(absolute_row_idx) else {
+            return;
+        };
+
+        let key_width = self.headers.iter().map(|h| h.width()).max().unwrap_or(0);
+        let key_style = Style::default()
+            .fg(theme::current().border_focused)
+            .add_modifier(Modifier::BOLD);
+        let mut lines = Vec::new();
+        for (i, header) in self.headers.iter().enumerate() {
+            let key = Span::styled(format!("{:<key_width$} │ ", header), key_style);
+            match row.get(i) {
+                Some(cell) if !cell.is_null() => {
+                    let value = cell.to_string();
+                    let mut value_lines = value.lines();
+                    let first = value_lines.next().unwrap_or_default().to_string();
+                    lines.push(Line::from(vec![key, Span::raw(first)]));
+                    // Continuation lines of multi-line values stay aligned with the value.
+                    for rest in value_lines {
+                        lines.push(Line::from(vec![
+                            Span::styled(format!("{:<key_width$} │ ", ""), key_style),
+                            Span::raw(rest.to_string()),
+                        ]));
+                    }
+                }
+                _ => lines.push(Line::from(vec![
+                    key,
+                    Span::styled(NULL_TEXT, style.null_style()),
+                ])),
+            }
+        }
+
+        self.row_detail_scroll = self
+            .row_detail_scroll
+            .min(lines.len().saturating_sub(1) as u16);
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(format!(
+                        "Row {} of {}",
+                        absolute_row_idx + 1,
+                        self.rows.len()
+                    ))
+                    .borders(Borders::ALL)
+                    .border_style(style.border_style(Focus::Table))
+                    .style(style.block_style()),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((self.row_detail_scroll, 0));
+        frame.render_widget(paragraph, area);
+    }
+
     fn render_history_table(&mut self, frame: &mut Frame, area: Rect, current_focus: &Focus) {
         let history_widget_style = DefaultStyle {
             focus: current_focus.clone(),
@@ -765,7 +1803,7 @@ impl<'a> DataTable<'a> {
             .style(header_style)
             .height(1);
 
-        let rows = self.query_history.iter().rev().map(|entry| {
+        let rows = self.visible_history().into_iter().map(|entry| {
             let query = entry.query.clone();
             let timestamp = entry.timestamp.to_string();
             let status = if entry.success { "OK" } else { "Error" };
@@ -800,14 +1838,36 @@ impl<'a> DataTable<'a> {
         )
         .row_highlight_style(selected_row_style);
 
+        self.rendered_area = area;
         frame.render_stateful_widget(table, area, &mut self.history_table_state);
     }
 
+    /// Number of data columns, starting at the horizontal scroll offset, that fit in `width`.
+    fn visible_column_count(&self, width: u16) -> usize {
+        let available_width = width.saturating_sub(1);
+        let mut used_width = NUMBERING_COLUMN_WIDTH;
+        self.column_widths
+            .iter()
+            .skip(self.horizontal_scroll)
+            .take_while(|&&column_width| {
+                used_width = used_width.saturating_add(column_width);
+                used_width <= available_width
+            })
+            .count()
+    }
+
     fn render_scrollbar(&mut self, frame: &mut Frame, area: Rect) {
         if self.is_empty() {
             return;
         }
 
+        // Horizontal scrolling moves by whole columns, so the scrollbar counts columns too.
+        self.horizontal_scroll_state = self
+            .horizontal_scroll_state
+            .content_length(self.column_widths.len())
+            .viewport_content_length(self.visible_column_count(area.width))
+            .position(self.horizontal_scroll);
+
         self.vertical_scroll_state = self
             .vertical_scroll_state
             .content_length(self.get_current_page_rows().len().saturating_sub(1) * ITEM_HEIGHT);
@@ -841,26 +1901,36 @@ impl<'a> DataTable<'a> {
     pub fn start_loading(&mut self) {
         self.tabs.set_index(0);
         self.loading_state = LoadingState::Loading;
+        self.loading_started = Some(Instant::now());
     }
 
-    pub fn finish_loading(&mut self, headers: Vec<String>, rows: Vec<PgRow>, elapsed: Duration) {
-        self.headers = headers;
-        self.rows = rows;
+    pub fn finish_loading(&mut self, result_set: ResultSet, elapsed: Duration) {
+        let prepare_started = Instant::now();
+        self.headers = result_set.headers;
+        self.columns = result_set.columns;
+        self.rows = result_set.rows;
+        self.truncated = result_set.truncated;
+        self.server_page = None;
         self.elapsed = elapsed;
         self.loading_state = LoadingState::Idle;
         self.status_message = Some(format!("Query complete in {} ms.", elapsed.as_millis()));
 
         let (column_widths, min_column_widths) =
-            Self::calculate_column_widths(&self.headers, &self.rows);
+            Self::calculate_column_widths(&self.headers, &self.rows, self.max_cell_length);
         self.column_widths = column_widths;
         self.min_column_widths = min_column_widths;
+        self.apply_saved_column_widths();
+
+        self.search.matches = self.find_matches(&self.search.query);
+        self.search.current = None;
+        self.sort = None;
 
         self.state =
             TableState::default().with_selected(if self.is_empty() { None } else { Some(0) });
-        self.vertical_scroll_state =
-            ScrollbarState::new((self.rows.len().min(100).saturating_sub(1)) * ITEM_HEIGHT);
-        self.horizontal_scroll_state =
-            ScrollbarState::new(self.column_widths.iter().sum::<u16>().saturating_sub(1) as usize);
+        self.vertical_scroll_state = ScrollbarState::new(
+            (self.rows.len().min(self.page_size).saturating_sub(1)) * ITEM_HEIGHT,
+        );
+        self.horizontal_scroll_state = ScrollbarState::new(self.column_widths.len());
         self.current_page = 0;
 
         if self.is_empty() {
@@ -868,11 +1938,278 @@ impl<'a> DataTable<'a> {
         } else {
             self.tabs.set_index(0);
         }
+        self.render_time = prepare_started.elapsed();
+        self.first_draw_pending = true;
+    }
+
+    /// How long the query being loaded has run so far.
+    fn loading_elapsed(&self) -> Duration {
+        self.loading_started
+            .map(|started| started.elapsed())
+            .unwrap_or_default()
+    }
+
+    pub fn cancel_loading(&mut self, query: &str) {
+        let elapsed = self.loading_elapsed();
+        self.loading_state = LoadingState::Idle;
+        self.loading_started = None;
+        self.set_query_status(query, elapsed, "Query cancelled.".to_string());
+        self.tabs.set_index(1);
     }
 
     pub fn set_error_state(&mut self, message: String) {
         self.loading_state = LoadingState::Error(message.clone());
-        self.status_message = Some(format!("Error: {}", message));
+        self.set_status(format!("Error: {}", message));
+        self.tabs.set_index(1);
+    }
+
+    /// Shows that `query` failed, logging the error along with the statement.
+    pub fn set_query_error(&mut self, query: &str, message: String) {
+        let elapsed = self.loading_elapsed();
+        self.loading_state = LoadingState::Error(message.clone());
+        self.set_query_status(query, elapsed, format!("Error: {}", message));
         self.tabs.set_index(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_column_widths_are_restored() {
+        let mut table = DataTable::new(ResultSet::default(), Vec::new());
+        table.saved_column_widths.insert("note".to_string(), 8);
+        table.saved_column_widths.insert("id".to_string(), 1);
+        let long_note = CellValue::Text("a note far wider than eight".to_string());
+        table.finish_loading(
+            ResultSet::new(
+                vec!["id".to_string(), "note".to_string()],
+                vec![vec![CellValue::Int(1), long_note]],
+            ),
+            Duration::ZERO,
+        );
+        assert_eq!(table.column_widths, [3, 8]);
+    }
+
+    #[test]
+    fn test_long_values_are_cut_off_for_display_only() {
+        assert_eq!(truncate_for_display("short".to_string(), 5), "short");
+        assert_eq!(truncate_for_display("héllo world".to_string(), 5), "héll…");
+        assert_eq!(
+            truncate_for_display("héllo world".to_string(), 0),
+            "héllo world"
+        );
+
+        let long = "x".repeat(50_000);
+        let mut table = DataTable::new(
+            ResultSet::new(
+                vec!["body".to_string()],
+                vec![vec![CellValue::Text(long.clone())]],
+            ),
+            Vec::new(),
+        );
+        assert_eq!(
+            table.column_widths,
+            vec![DEFAULT_MAX_CELL_LENGTH as u16 + 2]
+        );
+        table.state.select_cell(Some((0, 1)));
+        assert_eq!(table.selected_cell_text(), Some(long));
+    }
+
+    #[test]
+    fn test_copying_columns_and_results() {
+        let mut table = DataTable::new(
+            ResultSet::new(
+                vec!["id".to_string(), "note".to_string()],
+                vec![
+                    vec![CellValue::Int(1), CellValue::Text("tab\there".to_string())],
+                    vec![CellValue::Int(2), CellValue::Null],
+                    vec![CellValue::Int(3), CellValue::Text("say \"hi\"".to_string())],
+                ],
+            ),
+            Vec::new(),
+        );
+        assert_eq!(table.column_text(0), "1\n2\n3");
+        assert_eq!(
+            table.results_tsv(),
+            "id\tnote\n1\t\"tab\there\"\n2\t\n3\t\"say \"\"hi\"\"\""
+        );
+
+        assert_eq!(table.results_inserts(), None);
+        table.source_table = Some("notes".to_string());
+        table.db_type = Some(DatabaseType::MySQL);
+        assert_eq!(
+            table.results_inserts().unwrap().lines().nth(1),
+            Some("INSERT INTO notes (`id`, `note`) VALUES (2, NULL);")
+        );
+    }
+
+    fn history_entry(query: &str) -> QueryHistoryEntry {
+        QueryHistoryEntry {
+            query: query.to_string(),
+            connection_name: None,
+            timestamp: chrono::Utc::now(),
+            success: true,
+            rows_affected: 0,
+            execution_time: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_navigating_empty_history_is_a_no_op() {
+        let mut table = DataTable::new(ResultSet::default(), Vec::new());
+        table.handle_command(Command::DataTablePreviousHistoryRow);
+        table.handle_command(Command::DataTableNextHistoryRow);
+        assert_eq!(table.history_table_state.selected(), None);
+        assert_eq!(table.get_selected_history_query(), None);
+    }
+
+    #[test]
+    fn test_commands_on_empty_result_are_no_ops() {
+        let results = [
+            ResultSet::default(),
+            ResultSet::new(vec!["id".to_string(), "name".to_string()], Vec::new()),
+        ];
+        for result in results {
+            let mut table = DataTable::new(result, Vec::new());
+            for command in [
+                Command::DataTableNextRow,
+                Command::DataTablePreviousRow,
+                Command::DataTableNextColumn,
+                Command::DataTableNextColumn,
+                Command::DataTableNextColumn,
+                Command::DataTablePreviousColumn,
+                Command::DataTableScrollRight,
+                Command::DataTableScrollLeft,
+                Command::DataTableNextPage,
+                Command::DataTablePreviousPage,
+                Command::DataTableJumpToFirstRow,
+                Command::DataTableJumpToLastRow,
+                Command::DataTableAdjustColumnWidthIncrease,
+                Command::DataTableAdjustColumnWidthDecrease,
+                Command::DataTableAutoFitColumns,
+                Command::DataTableResetColumnWidths,
+                Command::DataTableSortAscending,
+                Command::DataTableSortDescending,
+                Command::DataTableClick(Position::new(5, 3)),
+                Command::DataTableSearch("x".to_string()),
+                Command::DataTableSearchNext,
+                Command::DataTableSearchPrevious,
+                Command::DataTableIncreasePageSize,
+                Command::DataTableDecreasePageSize,
+            ] {
+                table.handle_command(command);
+            }
+            assert_eq!(table.state.selected(), None);
+            assert_eq!(table.current_page, 0);
+            assert_eq!(table.selected_cell_text(), None);
+            assert_eq!(table.selected_row_copy(), None);
+            assert_eq!(table.selected_cell_preview(), None);
+        }
+    }
+
+    #[test]
+    fn test_deleted_rows_leave_the_results() {
+        let rows = (1..=2)
+            .map(|id| vec![CellValue::Int(id), CellValue::Text(format!("user {}", id))])
+            .collect();
+        let result = ResultSet::new(vec!["id".to_string(), "name".to_string()], rows);
+        let mut table = DataTable::new(result, Vec::new());
+        let key = ["id".to_string()];
+        assert_eq!(table.row_delete("users", 0, &["missing".to_string()]), None);
+
+        let delete = table.row_delete("users", 1, &key).unwrap();
+        assert_eq!(delete.describe_row(), "id = 2");
+        table.finish_row_delete(&delete, 0);
+        assert_eq!(table.rows.len(), 2);
+        table.finish_row_delete(&delete, 1);
+        assert_eq!(table.rows.len(), 1);
+        assert_eq!(table.state.selected(), Some(0));
+
+        let delete = table.row_delete("users", 0, &key).unwrap();
+        table.finish_row_delete(&delete, 1);
+        assert!(table.rows.is_empty());
+        assert_eq!(table.state.selected(), None);
+    }
+
+    #[test]
+    fn test_toast_expires_after_duration() {
+        let mut table = DataTable::new(ResultSet::default(), Vec::new());
+        assert_eq!(table.active_toast(), None);
+
+        table.show_toast("Copied: 42".to_string());
+        assert_eq!(table.active_toast(), Some("Copied: 42"));
+
+        table.toast_shown_at = Instant::now().checked_sub(TOAST_DURATION);
+        assert_eq!(table.active_toast(), None);
+        assert_eq!(table.status_message.as_deref(), Some("Copied: 42"));
+    }
+
+    #[test]
+    fn test_messages_log_keeps_each_run() {
+        let mut table = DataTable::new(ResultSet::default(), Vec::new());
+        table.start_loading();
+        table.set_status("Connecting to local...".to_string());
+        table.set_query_status(
+            "SELECT 1",
+            Duration::from_millis(12),
+            "1 rows fetched.".to_string(),
+        );
+        table.set_status("Page size set to 50 rows.".to_string());
+        table.start_loading();
+        table.set_query_error("SELECT oops", "syntax error".to_string());
+
+        let logged = table
+            .messages
+            .iter()
+            .map(|entry| (entry.query.as_deref(), entry.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            logged,
+            vec![
+                (None, "Connecting to local..."),
+                (Some("SELECT 1"), "1 rows fetched."),
+                (None, "Page size set to 50 rows."),
+                (Some("SELECT oops"), "Error: syntax error"),
+            ]
+        );
+        assert_eq!(table.messages[1].elapsed, Some(Duration::from_millis(12)));
+        assert_eq!(table.messages[2].elapsed, None);
+        assert_eq!(table.status_message.as_deref(), Some("Error: syntax error"));
+    }
+
+    #[test]
+    fn test_messages_scroll_follows_newest_until_scrolled_up() {
+        let mut table = DataTable::new(ResultSet::default(), Vec::new());
+        table.messages_max_scroll = 3;
+        assert_eq!(table.messages_scroll_position(), 3);
+
+        table.handle_command(Command::DataTableMessagesScrollUp);
+        assert_eq!(table.messages_scroll, Some(2));
+        table.handle_command(Command::DataTableMessagesScrollDown);
+        table.handle_command(Command::DataTableMessagesScrollDown);
+        assert_eq!(table.messages_scroll, None);
+    }
+
+    #[test]
+    fn test_history_selection_matches_newest_first_display() {
+        let history = vec![history_entry("SELECT 1"), history_entry("SELECT 2")];
+        let mut table = DataTable::new(ResultSet::default(), history);
+        table.handle_command(Command::DataTableNextHistoryRow);
+        assert_eq!(
+            table.get_selected_history_query().as_deref(),
+            Some("SELECT 2")
+        );
+        table.handle_command(Command::DataTableNextHistoryRow);
+        assert_eq!(
+            table.get_selected_history_query().as_deref(),
+            Some("SELECT 1")
+        );
+        table.handle_command(Command::DataTableNextHistoryRow);
+        assert_eq!(
+            table.get_selected_history_query().as_deref(),
+            Some("SELECT 2")
+        );
+    }
+}
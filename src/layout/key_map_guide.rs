@@ -1,162 +1,324 @@
 use crate::command::CommandCategory;
+use crate::key_bindings::KeyBindings;
+use crate::key_maps::{
+    DATA_TABLE_KEYS, EDITOR_KEYS, EditorKeyMode, GLOBAL_KEYS, KeyMapping, RUNNING_QUERY_KEYS,
+    SIDEBAR_KEYS, describe,
+};
+use crate::style::theme;
 use ratatui::style::{Color, Style, Stylize};
 use ratatui::text::{Line, Span, Text};
 
-pub fn get_key_map_guide() -> Text<'static> {
+/// A binding as `(keys, description)`. An entry without a description is a heading, such
+/// as "Insert Mode", for the entries after it.
+type KeyMapEntry = (String, &'static str);
+
+/// The bindings of one category, in display order.
+pub struct KeyMapSection {
+    pub category: CommandCategory,
+    pub entries: Vec<KeyMapEntry>,
+}
+
+impl KeyMapSection {
+    /// Entries whose keys or description contain `filter`, ignoring case, each under its
+    /// heading. An empty filter keeps everything.
+    fn visible_entries(&self, filter: &str) -> Vec<KeyMapEntry> {
+        if filter.is_empty() {
+            return self.entries.clone();
+        }
+        let filter = filter.to_lowercase();
+        let mut visible = Vec::new();
+        let mut heading = None;
+        for (key, description) in &self.entries {
+            if description.is_empty() {
+                heading = Some((key.clone(), *description));
+            } else if key.to_lowercase().contains(&filter)
+                || description.to_lowercase().contains(&filter)
+            {
+                visible.extend(heading.take());
+                visible.push((key.clone(), *description));
+            }
+        }
+        visible
+    }
+}
+
+/// The sections of the key map, with the editor profile and the extra bindings chosen in
+/// `keys.toml`.
+pub fn key_map_sections(bindings: &KeyBindings) -> Vec<KeyMapSection> {
+    CommandCategory::help_command_categories()
+        .into_iter()
+        .map(|category| {
+            let mut entries = match category {
+                CommandCategory::Global => get_global_keymaps(),
+                CommandCategory::DataTable => get_data_table_keymaps(),
+                CommandCategory::Sidebar => get_sidebar_keymaps(),
+                CommandCategory::Editor => get_editor_keymaps(bindings),
+            };
+            let user_bindings = bindings.listed(category);
+            if !user_bindings.is_empty() {
+                entries.push(("From keys.toml".to_string(), ""));
+                entries.extend(user_bindings.into_iter().map(|(chord, command)| {
+                    let description = describe(&command).unwrap_or("Bound in keys.toml");
+                    (format!("  {}", chord), description)
+                }));
+            }
+            KeyMapSection { category, entries }
+        })
+        .collect()
+}
+
+/// What the key map popup shows: a filter typed after `/` and the collapsed categories.
+#[derive(Debug, Default)]
+pub struct KeyMapView {
+    pub filter: String,
+    /// Whether the filter is still being typed.
+    pub filter_editing: bool,
+    pub collapsed: Vec<CommandCategory>,
+    /// Index of the category that `Enter` collapses or expands.
+    pub selected: usize,
+}
+
+impl KeyMapView {
+    pub fn next_category(&mut self) {
+        let count = CommandCategory::help_command_categories().len();
+        self.selected = (self.selected + 1) % count;
+    }
+
+    pub fn previous_category(&mut self) {
+        let count = CommandCategory::help_command_categories().len();
+        self.selected = (self.selected + count - 1) % count;
+    }
+
+    pub fn toggle_selected_category(&mut self) {
+        let category = CommandCategory::help_command_categories()[self.selected];
+        if let Some(index) = self.collapsed.iter().position(|&c| c == category) {
+            self.collapsed.remove(index);
+        } else {
+            self.collapsed.push(category);
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match (self.filter.is_empty(), self.filter_editing) {
+            (true, false) => "Key Maps".to_string(),
+            (_, true) => format!("Key Maps /{}█", self.filter),
+            (false, false) => format!("Key Maps /{}", self.filter),
+        }
+    }
+}
+
+/// Renders the key map in two columns per category. While filtering, only matching
+/// entries are shown, collapsed categories included, and categories without any are left
+/// out.
+pub fn get_key_map_guide(view: &KeyMapView, bindings: &KeyBindings) -> Text<'static> {
     let mut text = Text::default();
-    const COLUMN_WIDTH: usize = 38;
-    const COLUMN_GAP: usize = 4;
 
-    for category in CommandCategory::help_command_categories() {
-        text.push_line(Span::styled(category.to_string(), Style::default().bold()));
-        let keymaps = match category {
-            CommandCategory::Global => get_global_keymaps(),
-            CommandCategory::DataTable => get_data_table_keymaps(),
-            CommandCategory::Sidebar => get_sidebar_keymaps(),
-            CommandCategory::Editor => get_editor_keymaps(),
-        };
+    for (index, section) in key_map_sections(bindings).into_iter().enumerate() {
+        let entries = section.visible_entries(&view.filter);
+        if !view.filter.is_empty() && entries.is_empty() {
+            continue;
+        }
 
-        let max_key_len = if category == CommandCategory::Editor {
-            keymaps
-                .iter()
-                .filter(|(key, _)| !key.contains(" Mode"))
-                .map(|(key, _)| key.len())
-                .max()
-                .unwrap_or(0)
+        let collapsed = view.filter.is_empty() && view.collapsed.contains(&section.category);
+        let heading = if collapsed {
+            let count = entries.iter().filter(|(_, desc)| !desc.is_empty()).count();
+            format!("▸ {} ({})", section.category, count)
         } else {
-            keymaps.iter().map(|(key, _)| key.len()).max().unwrap_or(0)
+            format!("▾ {}", section.category)
         };
-        let key_col_width = max_key_len + 2;
-
-        let mut i = 0;
-        while i < keymaps.len() {
-            let mut line_spans = Vec::new();
-
-            if category == CommandCategory::Editor && keymaps[i].0.contains(" Mode") {
-                text.push_line(Line::from(vec![Span::styled(
-                    format!(
-                        "  {:<width$}",
-                        keymaps[i].0,
-                        width = COLUMN_WIDTH * 2 + COLUMN_GAP - 2
-                    ),
-                    Style::default().fg(Color::White),
-                )]));
-                i += 1;
-                continue;
-            }
+        let mut heading_style = Style::default().bold();
+        if index == view.selected {
+            heading_style = heading_style.fg(theme::current().border_focused);
+        }
+        text.push_line(Span::styled(heading, heading_style));
 
-            let (key_l, desc_l) = keymaps[i];
-            let key_span_l = Span::styled(
-                format!("  {:<width$}", key_l, width = key_col_width),
+        if !collapsed {
+            push_entries(&mut text, &entries);
+        }
+        text.push_line("");
+    }
+
+    if text.lines.is_empty() {
+        text.push_line(format!("No key bindings match \"{}\".", view.filter));
+    }
+    text
+}
+
+/// Lays out `entries` two to a line, with headings on lines of their own.
+fn push_entries(text: &mut Text<'static>, entries: &[KeyMapEntry]) {
+    const COLUMN_WIDTH: usize = 38;
+    const COLUMN_GAP: usize = 4;
+
+    let max_key_len = entries
+        .iter()
+        .filter(|(_, description)| !description.is_empty())
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let key_col_width = max_key_len + 2;
+    let entry_spans = |key: &str, description: &str| {
+        [
+            Span::styled(
+                format!("  {:<width$}", key, width = key_col_width),
                 Style::default().fg(Color::Cyan),
-            );
-            let desc_span_l = Span::raw(format!(
+            ),
+            Span::raw(format!(
                 "{:<width$}",
-                desc_l,
+                description,
                 width = COLUMN_WIDTH - key_col_width
-            ));
-            line_spans.push(key_span_l);
-            line_spans.push(desc_span_l);
-
-            line_spans.push(Span::raw(" ".repeat(COLUMN_GAP)));
-
-            if let Some((key_r, desc_r)) = keymaps.get(i + 1) {
-                if category == CommandCategory::Editor && key_r.contains(" Mode") {
-                    text.push_line(Line::from(line_spans));
-                    i += 1;
-                    continue;
-                }
-
-                let key_span_r = Span::styled(
-                    format!("  {:<width$}", key_r, width = key_col_width),
-                    Style::default().fg(Color::Cyan),
-                );
-                let desc_span_r = Span::raw(format!(
-                    "{:<width$}",
-                    desc_r,
-                    width = COLUMN_WIDTH - key_col_width
-                ));
-                line_spans.push(key_span_r);
-                line_spans.push(desc_span_r);
-            }
+            )),
+        ]
+    };
 
-            text.push_line(Line::from(line_spans));
-            i += 2;
+    let mut i = 0;
+    while i < entries.len() {
+        let (key_l, desc_l) = &entries[i];
+        if desc_l.is_empty() {
+            text.push_line(Line::from(vec![Span::styled(
+                format!(
+                    "  {:<width$}",
+                    key_l,
+                    width = COLUMN_WIDTH * 2 + COLUMN_GAP - 2
+                ),
+                Style::default().fg(theme::current().text),
+            )]));
+            i += 1;
+            continue;
         }
-        text.push_line("");
+
+        let mut line_spans = Vec::new();
+        line_spans.extend(entry_spans(key_l, desc_l));
+        line_spans.push(Span::raw(" ".repeat(COLUMN_GAP)));
+
+        match entries.get(i + 1) {
+            Some((key_r, desc_r)) if !desc_r.is_empty() => {
+                line_spans.extend(entry_spans(key_r, desc_r));
+                i += 2;
+            }
+            _ => i += 1,
+        }
+        text.push_line(Line::from(line_spans));
     }
-    text
 }
 
-fn get_global_keymaps() -> Vec<(&'static str, &'static str)> {
-    vec![
-        ("q", "Quit"),
-        ("Tab", "Toggle focus"),
-        ("F5", "Execute query"),
-        ("?", "Show key map"),
-    ]
+/// Entries for a binding table, with alternative keys joined as in `j / ↓`.
+fn table_entries(table: &[KeyMapping]) -> Vec<KeyMapEntry> {
+    table
+        .iter()
+        .map(|mapping| {
+            let keys = mapping
+                .chords
+                .iter()
+                .map(|chord| chord.to_string())
+                .collect::<Vec<_>>()
+                .join(" / ");
+            (keys, mapping.description)
+        })
+        .collect()
+}
+
+/// Entries for keys the binding tables don't describe.
+fn note_entries(notes: &[(&str, &'static str)]) -> Vec<KeyMapEntry> {
+    notes
+        .iter()
+        .map(|&(keys, description)| (keys.to_string(), description))
+        .collect()
+}
+
+fn get_global_keymaps() -> Vec<KeyMapEntry> {
+    let mut entries = table_entries(GLOBAL_KEYS);
+    entries.extend(table_entries(RUNNING_QUERY_KEYS));
+    entries.extend(note_entries(&[
+        ("Click", "Focus pane, select cell/node, sort by header"),
+        ("Mouse wheel", "Scroll focused pane"),
+        ("y (in popup)", "Copy popup contents"),
+        ("h/l (in JSON popup)", "Collapse/expand JSON node"),
+    ]));
+    entries
+}
+
+fn get_data_table_keymaps() -> Vec<KeyMapEntry> {
+    let mut entries = table_entries(DATA_TABLE_KEYS);
+    entries.extend(note_entries(&[("4-9", "Later tabs, when there are more")]));
+    entries
 }
 
-fn get_data_table_keymaps() -> Vec<(&'static str, &'static str)> {
-    vec![
-        ("[", "Previous tab"),
-        ("]", "Next tab"),
-        ("j / ↓", "Next row"),
-        ("k / ↑", "Previous row"),
-        ("PageDown / Space", "Next page"),
-        ("PageUp", "Previous page"),
-        ("g", "Jump to first row"),
-        ("G", "Jump to last row"),
-        ("l / →", "Next column"),
-        ("h / ←", "Previous column"),
-        (">", "Scroll right"),
-        ("<", "Scroll left"),
-        ("w", "Increase column width"),
-        ("W", "Decrease column width"),
-        ("n", "Next color"),
-        ("p", "Previous color"),
-        ("y", "Copy selected cell"),
-        ("Y", "Copy selected row"),
-        ("C", "Copy query to editor"),
-        ("R", "Run selected history query"),
-        ("1-9", "Set tab index"),
-    ]
+fn get_sidebar_keymaps() -> Vec<KeyMapEntry> {
+    table_entries(SIDEBAR_KEYS)
 }
 
-fn get_sidebar_keymaps() -> Vec<(&'static str, &'static str)> {
-    vec![
-        ("Enter / Space", "Toggle selected"),
-        ("←", "Collapse"),
-        ("→", "Expand"),
-        ("↓", "Down"),
-        ("↑", "Up"),
-        ("Esc", "Deselect"),
-        ("Home", "Select first"),
-        ("End", "Select last"),
-        ("PageDown", "Scroll down"),
-        ("PageUp", "Scroll up"),
-    ]
+/// The keys of the editor profile chosen in `keys.toml`, under a heading per mode.
+fn get_editor_keymaps(bindings: &KeyBindings) -> Vec<KeyMapEntry> {
+    EditorKeyMode::of_profile(bindings.editor)
+        .iter()
+        .flat_map(|&mode| {
+            std::iter::once((mode.heading().to_string(), "")).chain(
+                EDITOR_KEYS
+                    .iter()
+                    .filter(move |key| key.mode == mode)
+                    .map(|key| (format!("  {}", key.keys), key.description)),
+            )
+        })
+        .collect()
 }
 
-fn get_editor_keymaps() -> Vec<(&'static str, &'static str)> {
-    vec![
-        ("Normal Mode", ""),
-        ("  h/j/k/l", "Move cursor"),
-        ("  w/b", "Move by word"),
-        ("  ^/$", "Move to line start/end"),
-        ("  g/G", "Move to top/bottom"),
-        ("  i/a", "Enter insert mode"),
-        ("  o/O", "Insert line below/above"),
-        ("  v/V", "Enter visual mode"),
-        ("  d/c/y", "Delete/change/yank (operator)"),
-        ("  dd/cc/yy", "Delete/change/yank line"),
-        ("  p", "Paste"),
-        ("  u", "Undo"),
-        ("  Ctrl+r", "Redo"),
-        ("Insert Mode", ""),
-        ("  Esc/Ctrl+c", "Enter normal mode"),
-        ("Visual Mode", ""),
-        ("  Esc/v", "Enter normal mode"),
-        ("  d/c/y", "Delete/change/yank selection"),
-    ]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_keeps_headings_of_matches() {
+        let section = KeyMapSection {
+            category: CommandCategory::Editor,
+            entries: note_entries(&[
+                ("Normal Mode", ""),
+                ("  u", "Undo"),
+                ("  p", "Paste from system clipboard"),
+                ("Insert Mode", ""),
+                ("  Ctrl+v", "Paste from system clipboard"),
+                ("Visual Mode", ""),
+                ("  d/c/y", "Delete/change/yank selection"),
+            ]),
+        };
+        assert_eq!(
+            section.visible_entries("PASTE"),
+            note_entries(&[
+                ("Normal Mode", ""),
+                ("  p", "Paste from system clipboard"),
+                ("Insert Mode", ""),
+                ("  Ctrl+v", "Paste from system clipboard"),
+            ])
+        );
+        assert_eq!(section.visible_entries("").len(), 7);
+    }
+
+    #[test]
+    fn test_sections_list_the_mapped_keys() {
+        let bindings = KeyBindings::from_toml("[table]\n\"ctrl+d\" = \"next_page\"").unwrap();
+        let sections = key_map_sections(&bindings);
+        let has = |category, keys: &str, description| {
+            sections
+                .iter()
+                .find(|section| section.category == category)
+                .is_some_and(|section| section.entries.contains(&(keys.to_string(), description)))
+        };
+        assert!(has(
+            CommandCategory::DataTable,
+            "PageDown / Space",
+            "Next page"
+        ));
+        assert!(has(
+            CommandCategory::Sidebar,
+            "Enter / Space",
+            "Toggle selected"
+        ));
+        assert!(has(
+            CommandCategory::Global,
+            "Esc / Ctrl+c",
+            "Cancel running query"
+        ));
+        assert!(has(CommandCategory::DataTable, "  Ctrl+d", "Next page"));
+        assert!(has(CommandCategory::Editor, "  =", "Format SQL"));
+        assert!(!has(CommandCategory::Editor, "  Alt+q", "Format SQL"));
+    }
 }
@@ -1,14 +1,18 @@
 use crate::app::Focus;
 use crate::command::Command;
+use crate::style::theme;
 use crate::style::{DefaultStyle, StyleProvider};
+use crate::utils::clipboard::paste_text;
+use crate::utils::completion::Completion;
 use color_eyre::eyre::Result;
 use crossterm::event::KeyEvent;
 use ratatui::Frame;
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{Block, Borders};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListState};
 use std::fmt;
-use tui_textarea::{Input, TextArea};
+use tui_textarea::{CursorMove, Input, TextArea};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
@@ -16,6 +20,8 @@ pub enum Mode {
     Insert,
     Visual,
     Operator(char),
+    /// The modeless editor of the Emacs key profile, always inserting.
+    Emacs,
 }
 
 impl Mode {
@@ -28,6 +34,7 @@ impl Mode {
             Self::Insert => "type Esc to back to normal mode",
             Self::Visual => "type y to yank, type d to delete, type Esc to back to normal mode",
             Self::Operator(_) => "move cursor to apply operator",
+            Self::Emacs => "Ctrl+a/e line start/end, Ctrl+k kill line, Ctrl+w delete word",
         };
         let title = format!(
             "{} MODE ({}) - {}",
@@ -45,7 +52,7 @@ impl Mode {
     fn cursor_style(&self) -> Style {
         let color = match self {
             Self::Normal => Color::Reset,
-            Self::Insert => Color::LightBlue,
+            Self::Insert | Self::Emacs => Color::LightBlue,
             Self::Visual => Color::LightYellow,
             Self::Operator(_) => Color::LightGreen,
         };
@@ -60,13 +67,96 @@ impl fmt::Display for Mode {
             Self::Insert => write!(f, "INSERT"),
             Self::Visual => write!(f, "VISUAL"),
             Self::Operator(c) => write!(f, "OPERATOR({})", c),
+            Self::Emacs => write!(f, "EMACS"),
         }
     }
 }
 
+/// Which way an `f`, `F`, `t` or `T` motion searches, and whether it stops short.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindKind {
+    /// `f`: onto the next occurrence.
+    Forward,
+    /// `F`: onto the previous occurrence.
+    Backward,
+    /// `t`: just before the next occurrence.
+    TillForward,
+    /// `T`: just after the previous occurrence.
+    TillBackward,
+}
+
+/// A find-character motion within the cursor's line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharFind {
+    pub kind: FindKind,
+    pub target: char,
+}
+
+impl CharFind {
+    /// Column the motion lands on from `col`, or `None` if the character does not occur
+    /// `count` times in that direction.
+    ///
+    /// With `inclusive`, a forward motion lands one past the target so that a selection
+    /// ending there, which excludes its end, still covers the character as `df(` should.
+    fn column(&self, line: &str, col: usize, count: usize, inclusive: bool) -> Option<usize> {
+        let chars: Vec<char> = line.chars().collect();
+        let count = count.max(1);
+        match self.kind {
+            FindKind::Forward | FindKind::TillForward => {
+                let found = (col + 1..chars.len())
+                    .filter(|&i| chars[i] == self.target)
+                    .nth(count - 1)?;
+                let landing = if self.kind == FindKind::Forward {
+                    found
+                } else {
+                    found - 1
+                };
+                Some(if inclusive { landing + 1 } else { landing })
+            }
+            FindKind::Backward | FindKind::TillBackward => {
+                let skip = if self.kind == FindKind::TillBackward {
+                    1
+                } else {
+                    0
+                };
+                let found = (0..col.saturating_sub(skip))
+                    .rev()
+                    .filter(|&i| chars[i] == self.target)
+                    .nth(count - 1)?;
+                Some(if self.kind == FindKind::Backward {
+                    found
+                } else {
+                    found + 1
+                })
+            }
+        }
+    }
+}
+
+/// What an operator acts over: a textarea cursor movement or a motion the editor resolves
+/// from the text itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Cursor(CursorMove),
+    Find(CharFind),
+    /// `%`: the bracket matching the one under or after the cursor.
+    MatchingBracket,
+}
+
+/// Characters typed in pairs, with the closing character inserted after the cursor.
+const AUTO_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('\'', '\''), ('"', '"')];
+
+/// Brackets `%` jumps between.
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
 pub struct QueryEditor {
     pub mode: Mode,
     pub textarea: TextArea<'static>,
+    /// Text typed after `:` in Normal mode, shown in the block title while it is open.
+    pub command_line: Option<String>,
+    /// Suggestions for the word before the cursor, shown below it while open.
+    pub completion: Option<Completion>,
+    pub line_numbers: bool,
 }
 
 impl QueryEditor {
@@ -80,16 +170,24 @@ impl QueryEditor {
         Self {
             mode: Mode::Normal,
             textarea,
+            command_line: None,
+            completion: None,
+            line_numbers: false,
         }
     }
 
     pub fn handle_command(&mut self, command: Command, key_event: KeyEvent) {
         match command {
-            Command::EditorInputChar(_) => {
-                self.input(Input::from(key_event));
+            Command::EditorInputChar(c) => {
+                self.input_char(c);
             }
             Command::EditorInputBackspace => {
-                self.input(Input::from(key_event));
+                // Deleting the opening half of an empty pair takes the closing half too.
+                let (before, after) = self.chars_around_cursor();
+                if AUTO_PAIRS.contains(&(before.unwrap_or_default(), after.unwrap_or_default())) {
+                    self.textarea.delete_next_char();
+                }
+                self.textarea.delete_char();
             }
             Command::EditorInputDelete => {
                 self.input(Input::from(key_event));
@@ -109,6 +207,15 @@ impl QueryEditor {
             Command::EditorPaste => {
                 self.textarea.paste();
             }
+            Command::EditorPasteSystem => match paste_text() {
+                // A multi-line insert is a single edit, so one undo takes it all back.
+                Ok(text) => {
+                    self.textarea.insert_str(text);
+                }
+                Err(_) => {
+                    self.textarea.paste();
+                }
+            },
             Command::EditorUndo => {
                 self.textarea.undo();
             }
@@ -118,6 +225,9 @@ impl QueryEditor {
             Command::EditorDeleteNextChar => {
                 self.textarea.delete_next_char();
             }
+            Command::EditorDeleteWordBack => {
+                self.textarea.delete_word();
+            }
             Command::EditorSetMode(mode) => {
                 self.mode = mode;
             }
@@ -136,14 +246,191 @@ impl QueryEditor {
             Command::EditorCutSelection => {
                 self.textarea.cut();
             }
-            Command::EditorPerformPendingOperator => {
+            Command::EditorCompletionNext => {
+                if let Some(completion) = &mut self.completion {
+                    completion.next();
+                }
+            }
+            Command::EditorCompletionPrevious => {
+                if let Some(completion) = &mut self.completion {
+                    completion.previous();
+                }
+            }
+            Command::EditorCompletionAccept => {
+                if let Some(completion) = self.completion.take() {
+                    self.insert_completion(&completion);
+                }
+            }
+            Command::EditorCompletionCancel => {
+                self.completion = None;
+            }
+            Command::EditorRepeat(count, command) => {
+                for _ in 0..count {
+                    self.handle_command((*command).clone(), key_event);
+                }
+            }
+            Command::EditorFindChar(find, count) => {
+                if let Some(col) = self.find_column(find, count, false) {
+                    let (row, _) = self.textarea.cursor();
+                    self.jump_to((row, col));
+                }
+            }
+            Command::EditorJumpToMatchingBracket => {
+                if let Some(target) =
+                    matching_bracket(self.textarea.lines(), self.textarea.cursor())
+                {
+                    self.jump_to(target);
+                }
+            }
+            Command::EditorApplyOperator(op, motion, count) => {
+                let origin = self.textarea.cursor();
+                // Like vim, an operator over a motion that goes nowhere does nothing.
+                let Some((start, end)) = self.operator_range(motion, count) else {
+                    return;
+                };
                 self.textarea.cancel_selection();
-                self.mode = Mode::Normal;
+                self.jump_to(start);
+                self.textarea.start_selection();
+                self.jump_to(end);
+                self.finish_operator(op, origin);
+            }
+            Command::EditorApplyLineOperator(op, count) => {
+                let origin = self.textarea.cursor();
+                let last_row = self.textarea.lines().len().saturating_sub(1);
+                self.textarea.cancel_selection();
+                self.textarea.move_cursor(CursorMove::Head);
+                self.textarea.start_selection();
+                // Take the trailing newline too, unless the lines run to the end of the text
+                // or are being changed, which keeps an empty line to type into.
+                if origin.0 + count <= last_row && op != 'c' {
+                    for _ in 0..count {
+                        self.textarea.move_cursor(CursorMove::Down);
+                    }
+                    self.textarea.move_cursor(CursorMove::Head);
+                } else {
+                    for _ in 1..count {
+                        self.textarea.move_cursor(CursorMove::Down);
+                    }
+                    self.textarea.move_cursor(CursorMove::End);
+                }
+                self.finish_operator(op, origin);
             }
             _ => {}
         }
     }
 
+    /// Offers completions for the word before the cursor, inserting the only one directly.
+    pub fn open_completion(&mut self, schema_words: &[String]) {
+        let (row, col) = self.textarea.cursor();
+        let line = self.textarea.lines().get(row).map_or("", String::as_str);
+        self.completion = match Completion::new(line, col, schema_words) {
+            Some(completion) if completion.items.len() == 1 => {
+                self.insert_completion(&completion);
+                None
+            }
+            completion => completion,
+        };
+    }
+
+    fn insert_completion(&mut self, completion: &Completion) {
+        for _ in completion.prefix.chars() {
+            self.textarea.delete_char();
+        }
+        self.textarea.insert_str(completion.selected_item());
+    }
+
+    /// Types `c`, adding its closing pair after the cursor, or typing over the closing
+    /// character already there.
+    fn input_char(&mut self, c: char) {
+        let (before, after) = self.chars_around_cursor();
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+        if after == Some(c) && AUTO_PAIRS.iter().any(|&(_, close)| close == c) {
+            self.textarea.move_cursor(CursorMove::Forward);
+            return;
+        }
+        let pair = AUTO_PAIRS.iter().find(|&&(open, _)| open == c);
+        let paired = pair.is_some_and(|&(open, close)| {
+            // Leave quotes alone inside words, such as the apostrophe in `it's`.
+            if open == close {
+                !is_word(before) && !is_word(after)
+            } else {
+                !is_word(after)
+            }
+        });
+        self.textarea.insert_char(c);
+        if let Some(&(_, close)) = pair.filter(|_| paired) {
+            self.textarea.insert_char(close);
+            self.textarea.move_cursor(CursorMove::Back);
+        }
+    }
+
+    fn chars_around_cursor(&self) -> (Option<char>, Option<char>) {
+        let (row, col) = self.textarea.cursor();
+        let Some(line) = self.textarea.lines().get(row) else {
+            return (None, None);
+        };
+        let before = col.checked_sub(1).and_then(|i| line.chars().nth(i));
+        (before, line.chars().nth(col))
+    }
+
+    /// Start and exclusive end of the text an operator over `motion` acts on, or `None`
+    /// if the motion cannot be made.
+    fn operator_range(
+        &mut self,
+        motion: Motion,
+        count: usize,
+    ) -> Option<((usize, usize), (usize, usize))> {
+        let origin = self.textarea.cursor();
+        match motion {
+            Motion::Cursor(movement) => {
+                for _ in 0..count {
+                    self.textarea.move_cursor(movement);
+                }
+                let end = self.textarea.cursor();
+                self.jump_to(origin);
+                Some((origin, end))
+            }
+            Motion::Find(find) => {
+                let col = self.find_column(find, count, true)?;
+                Some((origin, (origin.0, col)))
+            }
+            // `%` is inclusive at both ends, whichever way the match lies.
+            Motion::MatchingBracket => {
+                let target = matching_bracket(self.textarea.lines(), origin)?;
+                if target > origin {
+                    Some((origin, (target.0, target.1 + 1)))
+                } else {
+                    Some(((origin.0, origin.1 + 1), target))
+                }
+            }
+        }
+    }
+
+    fn find_column(&self, find: CharFind, count: usize, inclusive: bool) -> Option<usize> {
+        let (row, col) = self.textarea.cursor();
+        let line = self.textarea.lines().get(row)?;
+        find.column(line, col, count, inclusive)
+    }
+
+    fn jump_to(&mut self, (row, col): (usize, usize)) {
+        let clamp = |n: usize| n.min(u16::MAX as usize) as u16;
+        self.textarea
+            .move_cursor(CursorMove::Jump(clamp(row), clamp(col)));
+    }
+
+    /// Cuts the selection for `d` and `c`, or copies it for `y` and returns to `origin`.
+    fn finish_operator(&mut self, op: char, origin: (usize, usize)) {
+        if op == 'y' {
+            self.textarea.copy();
+            self.textarea.cancel_selection();
+            self.textarea
+                .move_cursor(CursorMove::Jump(origin.0 as u16, origin.1 as u16));
+        } else {
+            self.textarea.cut();
+        }
+    }
+
     pub fn input(&mut self, input: Input) {
         self.textarea.input(input);
     }
@@ -152,6 +439,36 @@ impl QueryEditor {
         self.textarea.lines().join("\n")
     }
 
+    /// Text most recently yanked or cut in the editor.
+    pub fn yank_text(&self) -> String {
+        self.textarea.yank_text()
+    }
+
+    /// Where the text run as the query starts: the selection if there is one, else the top.
+    pub fn query_start(&self) -> (usize, usize) {
+        match self.selected_text() {
+            Some(_) => self
+                .textarea
+                .selection_range()
+                .map_or((0, 0), |(start, _)| start),
+            None => (0, 0),
+        }
+    }
+
+    /// Moves the cursor `offset` characters past `origin`, counting line breaks as one.
+    pub fn move_to_offset(&mut self, origin: (usize, usize), offset: usize) {
+        let target = offset_position(self.textarea.lines(), origin, offset);
+        self.textarea.cancel_selection();
+        self.jump_to(target);
+    }
+
+    /// Returns the currently selected text, or `None` when nothing is selected.
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.textarea.selection_range()?;
+        let text = text_in_range(self.textarea.lines(), start, end);
+        (!text.trim().is_empty()).then_some(text)
+    }
+
     pub fn set_textarea_content(
         &mut self,
         content: String,
@@ -171,9 +488,428 @@ impl QueryEditor {
         current_focus: Focus,
         connection_name: Option<String>,
     ) {
-        self.textarea
-            .set_block(self.mode.block(&current_focus, connection_name));
+        let mut block = self.mode.block(&current_focus, connection_name);
+        if let Some(command_line) = &self.command_line {
+            block = block.title(Line::from(format!(" :{}█ ", command_line)).right_aligned());
+        }
+        block = block.title_bottom(Line::from(self.position_label()).right_aligned());
+        self.textarea.set_block(block);
         self.textarea.set_cursor_style(self.mode.cursor_style());
+        if self.line_numbers {
+            self.textarea
+                .set_line_number_style(Style::default().fg(theme::current().null));
+        } else {
+            self.textarea.remove_line_number();
+        }
         frame.render_widget(&self.textarea, area);
+        self.highlight_matching_bracket(frame, area);
+        if let Some(completion) = &self.completion {
+            self.draw_completion(frame, area, completion);
+        }
+    }
+
+    /// `Ln X, Col Y` for the cursor, 1-based, with the selection size while selecting.
+    fn position_label(&self) -> String {
+        let (row, col) = self.textarea.cursor();
+        let selected = self.textarea.selection_range().map(|(start, end)| {
+            text_in_range(self.textarea.lines(), start, end)
+                .chars()
+                .count()
+        });
+        match selected {
+            Some(count) => format!(" Ln {}, Col {} ({} selected) ", row + 1, col + 1, count),
+            None => format!(" Ln {}, Col {} ", row + 1, col + 1),
+        }
+    }
+
+    /// Highlights the bracket matching the one under the cursor.
+    ///
+    /// The textarea keeps its scroll offset private, so the match is placed relative to
+    /// where the cursor was drawn, found by its reversed style.
+    fn highlight_matching_bracket(&self, frame: &mut Frame, area: Rect) {
+        let lines = self.textarea.lines();
+        let (row, col) = self.textarea.cursor();
+        let on_bracket = lines[row].chars().nth(col).is_some_and(|c| {
+            BRACKETS
+                .iter()
+                .any(|&(open, close)| c == open || c == close)
+        });
+        let Some(target) = on_bracket
+            .then(|| matching_bracket(lines, (row, col)))
+            .flatten()
+        else {
+            return;
+        };
+
+        let inner = area.inner(ratatui::layout::Margin::new(1, 1));
+        let buf = frame.buffer_mut();
+        let cursor_cell = inner.positions().find(|&position| {
+            buf.cell(position)
+                .is_some_and(|cell| cell.modifier.contains(Modifier::REVERSED))
+        });
+        let Some(cursor_cell) = cursor_cell else {
+            return;
+        };
+        let x = cursor_cell.x as i64 + target.1 as i64 - col as i64;
+        let y = cursor_cell.y as i64 + target.0 as i64 - row as i64;
+        let position = ratatui::layout::Position::new(x as u16, y as u16);
+        if x >= 0 && y >= 0 && inner.contains(position) {
+            buf[position].set_style(
+                Style::default()
+                    .bg(theme::current().selection_bg)
+                    .fg(theme::current().selection_fg)
+                    .add_modifier(Modifier::BOLD),
+            );
+        }
+    }
+
+    /// Draws the suggestion list under the cursor, kept inside the editor area.
+    ///
+    /// The textarea does not expose its scroll offset, so the list is placed from the
+    /// cursor's position in the text and pulled up to the bottom edge when that is off
+    /// screen.
+    fn draw_completion(&self, frame: &mut Frame, area: Rect, completion: &Completion) {
+        const MAX_VISIBLE: usize = 8;
+        let inner = area.inner(ratatui::layout::Margin::new(1, 1));
+        let widest = completion
+            .items
+            .iter()
+            .map(|item| item.chars().count())
+            .max();
+        let width = (widest.unwrap_or(0) as u16 + 2).min(inner.width);
+        let height = (completion.items.len().min(MAX_VISIBLE) as u16 + 2).min(inner.height);
+        if width < 3 || height < 3 {
+            return;
+        }
+
+        let (row, col) = self.textarea.cursor();
+        let word_start = col.saturating_sub(completion.prefix.chars().count()) as u16;
+        let x = inner.x + word_start.min(inner.width - width);
+        let y = inner.y + (row as u16 + 1).min(inner.height - height);
+        let list_area = Rect::new(x, y, width, height);
+
+        let list = List::new(completion.items.iter().map(String::as_str))
+            .block(Block::default().borders(Borders::ALL))
+            .style(
+                Style::default()
+                    .bg(theme::current().background)
+                    .fg(theme::current().text),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(theme::current().selection_bg)
+                    .fg(theme::current().selection_fg),
+            );
+        let mut state = ListState::default().with_selected(Some(completion.selected));
+        frame.render_widget(Clear, list_area);
+        frame.render_stateful_widget(list, list_area, &mut state);
+    }
+}
+
+/// Finds the bracket matching the first one at or after `cursor` on its line, searching
+/// across lines and skipping nested pairs.
+fn matching_bracket(lines: &[String], cursor: (usize, usize)) -> Option<(usize, usize)> {
+    let (row, col) = cursor;
+    let line: Vec<char> = lines.get(row)?.chars().collect();
+    let (start_col, (open, close), forward) = (col..line.len()).find_map(|i| {
+        BRACKETS.iter().find_map(|&(open, close)| {
+            if line[i] == open {
+                Some((i, (open, close), true))
+            } else if line[i] == close {
+                Some((i, (open, close), false))
+            } else {
+                None
+            }
+        })
+    })?;
+
+    let mut depth = 0usize;
+    let mut visit = |r: usize, i: usize, c: char| {
+        let (deeper, shallower) = if forward {
+            (open, close)
+        } else {
+            (close, open)
+        };
+        if c == deeper {
+            depth += 1;
+        } else if c == shallower {
+            depth -= 1;
+            if depth == 0 {
+                return Some((r, i));
+            }
+        }
+        None
+    };
+
+    if forward {
+        for (r, text) in lines.iter().enumerate().skip(row) {
+            let skip = if r == row { start_col } else { 0 };
+            for (i, c) in text.chars().enumerate().skip(skip) {
+                if let Some(found) = visit(r, i, c) {
+                    return Some(found);
+                }
+            }
+        }
+    } else {
+        for r in (0..=row).rev() {
+            let chars: Vec<char> = lines[r].chars().collect();
+            let end = if r == row { start_col + 1 } else { chars.len() };
+            for i in (0..end).rev() {
+                if let Some(found) = visit(r, i, chars[i]) {
+                    return Some(found);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The `(row, col)` position `offset` characters past `origin`, with each line break
+/// counting as one character, clamped to the end of the text.
+fn offset_position(lines: &[String], origin: (usize, usize), offset: usize) -> (usize, usize) {
+    let (mut row, mut col) = origin;
+    let mut remaining = offset;
+    while let Some(line) = lines.get(row) {
+        let line_len = line.chars().count();
+        if col + remaining <= line_len || row + 1 == lines.len() {
+            return (row, (col + remaining).min(line_len));
+        }
+        remaining -= line_len - col + 1;
+        row += 1;
+        col = 0;
+    }
+    origin
+}
+
+/// Extracts the text between two `(row, col)` positions, with `end` exclusive.
+fn text_in_range(lines: &[String], start: (usize, usize), end: (usize, usize)) -> String {
+    let (start_row, start_col) = start;
+    let (end_row, end_col) = end;
+
+    (start_row..=end_row.min(lines.len().saturating_sub(1)))
+        .map(|row| {
+            let line = &lines[row];
+            let from = if row == start_row { start_col } else { 0 };
+            let to = if row == end_row {
+                end_col
+            } else {
+                line.chars().count()
+            };
+            line.chars()
+                .skip(from)
+                .take(to.saturating_sub(from))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_in_range_single_line() {
+        let lines = vec!["SELECT 1; SELECT 2;".to_string()];
+        assert_eq!(text_in_range(&lines, (0, 10), (0, 19)), "SELECT 2;");
+    }
+
+    #[test]
+    fn test_text_in_range_multiple_lines() {
+        let lines = vec![
+            "SELECT *".to_string(),
+            "FROM users".to_string(),
+            "WHERE id = 1;".to_string(),
+        ];
+        assert_eq!(
+            text_in_range(&lines, (0, 7), (2, 5)),
+            "*\nFROM users\nWHERE"
+        );
+    }
+
+    fn editor_with(content: &str) -> QueryEditor {
+        let mut editor = QueryEditor::new();
+        editor.textarea = TextArea::from(content.lines().map(String::from));
+        editor
+    }
+
+    fn run(editor: &mut QueryEditor, command: Command) {
+        editor.handle_command(command, KeyEvent::from(crossterm::event::KeyCode::Null));
+    }
+
+    #[test]
+    fn test_operator_over_repeated_motion() {
+        let mut editor = editor_with("SELECT id, name FROM users");
+        run(
+            &mut editor,
+            Command::EditorApplyOperator('d', Motion::Cursor(CursorMove::WordForward), 2),
+        );
+        assert_eq!(editor.textarea_content(), ", name FROM users");
+        assert_eq!(editor.yank_text(), "SELECT id");
+    }
+
+    #[test]
+    fn test_char_find_columns() {
+        let line = "f(a, b(c))";
+        let find = |kind, target| CharFind { kind, target };
+        assert_eq!(
+            find(FindKind::Forward, '(').column(line, 0, 1, false),
+            Some(1)
+        );
+        assert_eq!(
+            find(FindKind::Forward, '(').column(line, 0, 2, false),
+            Some(6)
+        );
+        assert_eq!(
+            find(FindKind::Forward, '(').column(line, 0, 2, true),
+            Some(7)
+        );
+        assert_eq!(
+            find(FindKind::TillForward, ')').column(line, 0, 1, false),
+            Some(7)
+        );
+        assert_eq!(
+            find(FindKind::TillForward, ')').column(line, 7, 1, false),
+            Some(7)
+        );
+        assert_eq!(
+            find(FindKind::Backward, 'a').column(line, 9, 1, false),
+            Some(2)
+        );
+        assert_eq!(
+            find(FindKind::TillBackward, 'b').column(line, 9, 1, false),
+            Some(6)
+        );
+        assert_eq!(find(FindKind::Forward, 'z').column(line, 0, 1, false), None);
+        assert_eq!(
+            find(FindKind::Backward, 'f').column(line, 0, 1, false),
+            None
+        );
+    }
+
+    #[test]
+    fn test_operator_over_char_find() {
+        let mut editor = editor_with("count(id) AS total");
+        let find = |kind| Motion::Find(CharFind { kind, target: ')' });
+        run(
+            &mut editor,
+            Command::EditorApplyOperator('y', find(FindKind::TillForward), 1),
+        );
+        assert_eq!(editor.yank_text(), "count(id");
+        run(
+            &mut editor,
+            Command::EditorApplyOperator('d', find(FindKind::Forward), 1),
+        );
+        assert_eq!(editor.textarea_content(), " AS total");
+        run(
+            &mut editor,
+            Command::EditorApplyOperator('d', find(FindKind::Forward), 1),
+        );
+        assert_eq!(editor.textarea_content(), " AS total");
+    }
+
+    #[test]
+    fn test_completion_replaces_word_prefix() {
+        let mut editor = editor_with("SELECT * FROM use");
+        editor.textarea.move_cursor(CursorMove::End);
+        editor.open_completion(&["users".to_string()]);
+        assert_eq!(editor.completion, None);
+        assert_eq!(editor.textarea_content(), "SELECT * FROM users");
+
+        let mut editor = editor_with("SELECT * FROM users WHERE na");
+        editor.textarea.move_cursor(CursorMove::End);
+        editor.open_completion(&["name".to_string(), "nationality".to_string()]);
+        run(&mut editor, Command::EditorCompletionNext);
+        run(&mut editor, Command::EditorCompletionAccept);
+        assert_eq!(
+            editor.textarea_content(),
+            "SELECT * FROM users WHERE nationality"
+        );
+    }
+
+    #[test]
+    fn test_matching_bracket() {
+        let lines: Vec<String> = ["SELECT (a + (b", "  * c)) FROM t", "x)"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(matching_bracket(&lines, (0, 7)), Some((1, 6)));
+        assert_eq!(matching_bracket(&lines, (0, 0)), Some((1, 6)));
+        assert_eq!(matching_bracket(&lines, (1, 5)), Some((0, 12)));
+        assert_eq!(matching_bracket(&lines, (1, 7)), None);
+        assert_eq!(matching_bracket(&lines, (2, 0)), None);
+    }
+
+    #[test]
+    fn test_auto_pairs() {
+        let mut editor = editor_with("");
+        for c in "count(".chars() {
+            run(&mut editor, Command::EditorInputChar(c));
+        }
+        assert_eq!(editor.textarea_content(), "count()");
+        for c in "'it's')".chars() {
+            run(&mut editor, Command::EditorInputChar(c));
+        }
+        assert_eq!(editor.textarea_content(), "count('it's')");
+        assert_eq!(editor.textarea.cursor(), (0, 13));
+
+        let mut editor = editor_with("");
+        run(&mut editor, Command::EditorInputChar('['));
+        run(&mut editor, Command::EditorInputBackspace);
+        assert_eq!(editor.textarea_content(), "");
+    }
+
+    #[test]
+    fn test_operator_over_matching_bracket() {
+        let mut editor = editor_with("WHERE id IN (SELECT (1)) AND x");
+        editor.textarea.move_cursor(CursorMove::Jump(0, 12));
+        run(
+            &mut editor,
+            Command::EditorApplyOperator('d', Motion::MatchingBracket, 1),
+        );
+        assert_eq!(editor.textarea_content(), "WHERE id IN  AND x");
+
+        let mut editor = editor_with("f(a) b");
+        editor.textarea.move_cursor(CursorMove::Jump(0, 3));
+        run(
+            &mut editor,
+            Command::EditorApplyOperator('y', Motion::MatchingBracket, 1),
+        );
+        assert_eq!(editor.yank_text(), "(a)");
+    }
+
+    #[test]
+    fn test_position_label() {
+        let mut editor = editor_with("SELECT 1\nFROM t");
+        editor.textarea.move_cursor(CursorMove::Jump(1, 2));
+        assert_eq!(editor.position_label(), " Ln 2, Col 3 ");
+        editor.textarea.start_selection();
+        editor.textarea.move_cursor(CursorMove::End);
+        assert_eq!(editor.position_label(), " Ln 2, Col 7 (4 selected) ");
+    }
+
+    #[test]
+    fn test_offset_position() {
+        let lines: Vec<String> = ["SELECT *", "FROM users", "WHERE idd = 1"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(offset_position(&lines, (0, 0), 3), (0, 3));
+        assert_eq!(offset_position(&lines, (0, 0), 9), (1, 0));
+        assert_eq!(offset_position(&lines, (0, 0), 26), (2, 6));
+        assert_eq!(offset_position(&lines, (1, 5), 6), (2, 0));
+        assert_eq!(offset_position(&lines, (0, 0), 100), (2, 13));
+    }
+
+    #[test]
+    fn test_line_operators() {
+        let mut editor = editor_with("a\nb\nc\nd");
+        run(&mut editor, Command::EditorApplyLineOperator('d', 2));
+        assert_eq!(editor.textarea_content(), "c\nd");
+
+        run(&mut editor, Command::EditorApplyLineOperator('y', 5));
+        assert_eq!(editor.yank_text(), "c\nd");
+        assert_eq!(editor.textarea.cursor(), (0, 0));
+        assert_eq!(editor.textarea_content(), "c\nd");
     }
 }
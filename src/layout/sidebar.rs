@@ -12,6 +12,10 @@ pub struct SideBar {
     pub state: TreeState<String>,
     pub items: Vec<TreeItem<'static, String>>,
     pub focus: Focus,
+    /// Text the tree is narrowed to, empty when every node is shown.
+    pub filter: String,
+    /// Whether the filter is still being typed.
+    pub filter_editing: bool,
 }
 
 impl SideBar {
@@ -20,21 +24,30 @@ impl SideBar {
             state: TreeState::default(),
             items,
             focus,
+            filter: String::new(),
+            filter_editing: false,
         }
     }
 
-    pub fn handle_command(&mut self, command: Command) -> Option<String> {
+    /// Applies a sidebar command, returning the selected node's path when it was opened.
+    pub fn handle_command(&mut self, command: Command) -> Option<Vec<String>> {
         match command {
             Command::SidebarToggleSelected => {
                 self.state.toggle_selected();
-                return self.state.selected().first().cloned();
+                return Some(self.state.selected().to_vec());
+            }
+            Command::SidebarClick(position) => {
+                let path = self.state.rendered_at(position)?.to_vec();
+                self.state.select(path.clone());
+                self.state.toggle(path.clone());
+                return Some(path);
             }
             Command::SidebarKeyLeft => {
                 self.state.key_left();
             }
             Command::SidebarKeyRight => {
                 self.state.key_right();
-                return self.state.selected().first().cloned();
+                return Some(self.state.selected().to_vec());
             }
             Command::SidebarKeyDown => {
                 self.state.key_down();
@@ -70,6 +83,34 @@ impl SideBar {
         self.items = new_items;
     }
 
+    /// Expands every database, schema and group so filtered tables are visible.
+    pub fn open_all_groups(&mut self) {
+        fn open(
+            state: &mut TreeState<String>,
+            items: &[TreeItem<'static, String>],
+            path: &[String],
+        ) {
+            for item in items {
+                if item.identifier().starts_with("tbl_") || item.children().is_empty() {
+                    continue;
+                }
+                let mut item_path = path.to_vec();
+                item_path.push(item.identifier().clone());
+                open(state, item.children(), &item_path);
+                state.open(item_path);
+            }
+        }
+        open(&mut self.state, &self.items, &[]);
+    }
+
+    fn title(&self) -> String {
+        match (self.filter.is_empty(), self.filter_editing) {
+            (true, false) => "Databases".to_string(),
+            (_, true) => format!("Databases /{}█", self.filter),
+            (false, false) => format!("Databases /{}", self.filter),
+        }
+    }
+
     pub fn render(&mut self, frame: &mut Frame, area: Rect) {
         let style = DefaultStyle {
             focus: self.focus.clone(),
@@ -78,7 +119,7 @@ impl SideBar {
             .expect("tree item IDs must be unique")
             .block(
                 Block::bordered()
-                    .title("Databases")
+                    .title(self.title())
                     .borders(Borders::ALL)
                     .border_style(style.border_style(Focus::Sidebar))
                     .style(style.block_style()),
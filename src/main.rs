@@ -1,8 +1,10 @@
 mod app;
+mod cli;
 mod command;
 mod components;
 mod crud;
 mod database;
+mod key_bindings;
 mod key_maps;
 mod layout;
 mod state;
@@ -10,12 +12,17 @@ mod style;
 mod utils;
 
 use app::App;
+use clap::Parser;
+use cli::Cli;
 use color_eyre::eyre::Result;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
+    let cli = Cli::parse();
+    style::theme::init(style::theme::load_theme(cli.theme));
     let mut app = App::default();
-    app.init().await?;
-    Ok(())
+    let result = app.init(cli).await;
+    database::ssh_tunnel::close_tunnels().await;
+    result
 }
@@ -1,8 +1,10 @@
 use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::{self, Read, Write};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use tokio::sync::RwLock;
 
@@ -24,10 +26,104 @@ pub struct QueryStats {
     pub elapsed: Duration,
 }
 
+/// A named query saved for reuse.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub sql: String,
+}
+
+/// Column widths adjusted by hand, keyed by connection name and then column name.
+pub type ColumnWidths = HashMap<String, HashMap<String, u16>>;
+
+/// Share of the screen given to each pane, in percent.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneLayout {
+    /// Width of the sidebar; the editor and table take the rest.
+    pub sidebar_percent: u16,
+    /// Height of the editor; the table takes the rest.
+    pub editor_percent: u16,
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        Self {
+            sidebar_percent: 30,
+            editor_percent: 50,
+        }
+    }
+}
+
+impl PaneLayout {
+    const MIN_PERCENT: u16 = 10;
+    const MAX_PERCENT: u16 = 90;
+
+    pub fn resize_sidebar(&mut self, delta: i16) {
+        self.sidebar_percent = Self::resized(self.sidebar_percent, delta);
+    }
+
+    pub fn resize_editor(&mut self, delta: i16) {
+        self.editor_percent = Self::resized(self.editor_percent, delta);
+    }
+
+    /// Applies `delta`, keeping every pane at least `MIN_PERCENT` wide or tall.
+    fn resized(percent: u16, delta: i16) -> u16 {
+        (percent as i16 + delta).clamp(Self::MIN_PERCENT as i16, Self::MAX_PERCENT as i16) as u16
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct PersistedState {
+    #[serde(default)]
+    last_connection: Option<String>,
+    /// Whether the editor shows line numbers.
+    #[serde(default)]
+    line_numbers: bool,
+    #[serde(default)]
+    layout: PaneLayout,
+    /// Characters of a value shown in a result cell, as last given on the command line.
+    #[serde(default)]
+    max_cell_length: Option<usize>,
+}
+
 pub static GLOBAL_QUERY_STATS: Lazy<RwLock<Option<QueryStats>>> = Lazy::new(|| RwLock::new(None));
 pub static GLOBAL_QUERY_HISTORY: Lazy<RwLock<Vec<QueryHistoryEntry>>> =
     Lazy::new(|| RwLock::new(Vec::new()));
 
+pub const DEFAULT_HISTORY_LIMIT: usize = 1000;
+/// Maximum number of history entries kept in memory and on disk.
+static HISTORY_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_HISTORY_LIMIT);
+
+pub fn set_history_limit(limit: usize) {
+    HISTORY_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+fn history_limit() -> usize {
+    HISTORY_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Drops the oldest entries so that at most `limit` remain.
+fn trim_history(history: &mut Vec<QueryHistoryEntry>, limit: usize) {
+    if history.len() > limit {
+        history.drain(..history.len() - limit);
+    }
+}
+
+/// Appends an entry, replacing the previous one when it repeats the same query on the
+/// same connection, then enforces `limit`.
+fn push_history(history: &mut Vec<QueryHistoryEntry>, entry: QueryHistoryEntry, limit: usize) {
+    if let Some(last) = history.last_mut()
+        && last.query == entry.query
+        && last.connection_name == entry.connection_name
+    {
+        *last = entry;
+    } else {
+        history.push(entry);
+    }
+    trim_history(history, limit);
+}
+
 fn get_history_file_path() -> Option<PathBuf> {
     dirs::home_dir().map(|mut path| {
         path.push(".lazydata");
@@ -36,6 +132,212 @@ fn get_history_file_path() -> Option<PathBuf> {
     })
 }
 
+#[cfg(not(test))]
+fn get_state_file_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|mut path| {
+        path.push(".lazydata");
+        path.push("state.json");
+        path
+    })
+}
+
+/// Tests each get a state file of their own, so they never touch the user's.
+#[cfg(test)]
+fn get_state_file_path() -> Option<PathBuf> {
+    let dir = format!(
+        "lazydata-test-{}-{:?}",
+        std::process::id(),
+        std::thread::current().id()
+    );
+    Some(std::env::temp_dir().join(dir).join("state.json"))
+}
+
+fn get_snippets_file_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|mut path| {
+        path.push(".lazydata");
+        path.push("snippets.json");
+        path
+    })
+}
+
+fn get_column_widths_file_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|mut path| {
+        path.push(".lazydata");
+        path.push("column_widths.json");
+        path
+    })
+}
+
+fn get_buffer_file_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|mut path| {
+        path.push(".lazydata");
+        path.push("buffer.sql");
+        path
+    })
+}
+
+/// Reads the editor contents left from the previous session, or an empty string.
+pub fn load_buffer() -> String {
+    let Some(path) = get_buffer_file_path() else {
+        return String::new();
+    };
+    if !path.exists() {
+        return String::new();
+    }
+    std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("Error reading buffer file {:?}: {}", path, e);
+        String::new()
+    })
+}
+
+pub fn save_buffer(content: &str) -> io::Result<()> {
+    if let Some(path) = get_buffer_file_path() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+    }
+    Ok(())
+}
+
+pub fn load_column_widths() -> ColumnWidths {
+    let Some(path) = get_column_widths_file_path() else {
+        return ColumnWidths::new();
+    };
+    if !path.exists() {
+        return ColumnWidths::new();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            eprintln!("Error deserializing column widths from {:?}: {}", path, e);
+            ColumnWidths::new()
+        }),
+        Err(e) => {
+            eprintln!("Error reading column widths file {:?}: {}", path, e);
+            ColumnWidths::new()
+        }
+    }
+}
+
+pub fn save_column_widths(widths: &ColumnWidths) -> io::Result<()> {
+    if let Some(path) = get_column_widths_file_path() {
+        let json = serde_json::to_string_pretty(widths)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, json)?;
+    }
+    Ok(())
+}
+
+pub fn load_snippets() -> Vec<Snippet> {
+    let Some(path) = get_snippets_file_path() else {
+        return Vec::new();
+    };
+    if !path.exists() {
+        return Vec::new();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            eprintln!("Error deserializing snippets from {:?}: {}", path, e);
+            Vec::new()
+        }),
+        Err(e) => {
+            eprintln!("Error reading snippets file {:?}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+pub fn save_snippets(snippets: &[Snippet]) -> io::Result<()> {
+    if let Some(path) = get_snippets_file_path() {
+        let json = serde_json::to_string_pretty(snippets)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, json)?;
+    }
+    Ok(())
+}
+
+/// Adds a snippet, replacing any existing snippet with the same name.
+pub fn upsert_snippet(snippets: &mut Vec<Snippet>, snippet: Snippet) {
+    match snippets.iter_mut().find(|s| s.name == snippet.name) {
+        Some(existing) => *existing = snippet,
+        None => snippets.push(snippet),
+    }
+}
+
+fn load_state() -> PersistedState {
+    let Some(path) = get_state_file_path() else {
+        return PersistedState::default();
+    };
+    if !path.exists() {
+        return PersistedState::default();
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            eprintln!("Error deserializing state from {:?}: {}", path, e);
+            PersistedState::default()
+        }),
+        Err(e) => {
+            eprintln!("Error reading state file {:?}: {}", path, e);
+            PersistedState::default()
+        }
+    }
+}
+
+fn save_state(state: &PersistedState) -> io::Result<()> {
+    if let Some(path) = get_state_file_path() {
+        let json = serde_json::to_string_pretty(state)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, json)?;
+    }
+    Ok(())
+}
+
+pub fn save_last_connection(name: &str) -> io::Result<()> {
+    let mut state = load_state();
+    state.last_connection = Some(name.to_string());
+    save_state(&state)
+}
+
+pub fn load_last_connection() -> Option<String> {
+    load_state().last_connection
+}
+
+pub fn load_line_numbers() -> bool {
+    load_state().line_numbers
+}
+
+pub fn save_line_numbers(line_numbers: bool) -> io::Result<()> {
+    let mut state = load_state();
+    state.line_numbers = line_numbers;
+    save_state(&state)
+}
+
+pub fn load_pane_layout() -> PaneLayout {
+    load_state().layout
+}
+
+pub fn save_pane_layout(layout: PaneLayout) -> io::Result<()> {
+    let mut state = load_state();
+    state.layout = layout;
+    save_state(&state)
+}
+
+pub fn load_max_cell_length() -> Option<usize> {
+    load_state().max_cell_length
+}
+
+pub fn save_max_cell_length(max_cell_length: usize) -> io::Result<()> {
+    let mut state = load_state();
+    state.max_cell_length = Some(max_cell_length);
+    save_state(&state)
+}
+
 pub async fn save_history() -> io::Result<()> {
     if let Some(path) = get_history_file_path() {
         let history = GLOBAL_QUERY_HISTORY.read().await;
@@ -66,7 +368,8 @@ pub async fn load_history() -> io::Result<()> {
                     let mut json = String::new();
                     match file.read_to_string(&mut json) {
                         Ok(_) => match serde_json::from_str::<Vec<QueryHistoryEntry>>(&json) {
-                            Ok(history) => {
+                            Ok(mut history) => {
+                                trim_history(&mut history, history_limit());
                                 let mut global_history = GLOBAL_QUERY_HISTORY.write().await;
                                 *global_history = history;
                             }
@@ -98,7 +401,7 @@ pub async fn get_query_stats() -> Option<QueryStats> {
 
 pub async fn add_to_history(entry: QueryHistoryEntry) {
     let mut history = GLOBAL_QUERY_HISTORY.write().await;
-    history.push(entry);
+    push_history(&mut history, entry, history_limit());
 }
 
 pub async fn get_history(connection_name: Option<String>) -> Vec<QueryHistoryEntry> {
@@ -113,3 +416,84 @@ pub async fn get_history(connection_name: Option<String>) -> Vec<QueryHistoryEnt
         history.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(query: &str) -> QueryHistoryEntry {
+        QueryHistoryEntry {
+            query: query.to_string(),
+            connection_name: Some("local".to_string()),
+            timestamp: Utc::now(),
+            success: true,
+            rows_affected: 0,
+            execution_time: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_line_numbers_round_trip() {
+        assert!(!load_line_numbers());
+        save_line_numbers(true).unwrap();
+        assert!(load_line_numbers());
+        save_last_connection("prod").unwrap();
+        save_line_numbers(false).unwrap();
+        assert!(!load_line_numbers());
+        assert_eq!(load_last_connection().as_deref(), Some("prod"));
+
+        let dir = get_state_file_path().unwrap();
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_upsert_snippet_replaces_by_name() {
+        let mut snippets = Vec::new();
+        let snippet = |name: &str, sql: &str| Snippet {
+            name: name.to_string(),
+            sql: sql.to_string(),
+        };
+        upsert_snippet(&mut snippets, snippet("locks", "SELECT 1"));
+        upsert_snippet(&mut snippets, snippet("sizes", "SELECT 2"));
+        upsert_snippet(&mut snippets, snippet("locks", "SELECT 3"));
+        assert_eq!(
+            snippets,
+            [snippet("locks", "SELECT 3"), snippet("sizes", "SELECT 2")]
+        );
+    }
+
+    #[test]
+    fn test_pane_layout_resize_is_clamped() {
+        let mut layout = PaneLayout::default();
+        layout.resize_sidebar(-5);
+        assert_eq!(layout.sidebar_percent, 25);
+        layout.resize_sidebar(-50);
+        assert_eq!(layout.sidebar_percent, 10);
+        layout.resize_editor(45);
+        assert_eq!(layout.editor_percent, 90);
+        let partial: PersistedState =
+            serde_json::from_str(r#"{"layout": {"editor_percent": 70}}"#).unwrap();
+        assert_eq!(partial.layout.sidebar_percent, 30);
+        assert_eq!(partial.layout.editor_percent, 70);
+    }
+
+    #[test]
+    fn test_push_history_keeps_most_recent_entries() {
+        let mut history = Vec::new();
+        for i in 0..5 {
+            push_history(&mut history, entry(&format!("SELECT {i}")), 3);
+        }
+        let queries: Vec<_> = history.iter().map(|e| e.query.as_str()).collect();
+        assert_eq!(queries, ["SELECT 2", "SELECT 3", "SELECT 4"]);
+    }
+
+    #[test]
+    fn test_push_history_collapses_consecutive_duplicates() {
+        let mut history = Vec::new();
+        push_history(&mut history, entry("SELECT 1"), 10);
+        push_history(&mut history, entry("SELECT 1"), 10);
+        push_history(&mut history, entry("SELECT 2"), 10);
+        push_history(&mut history, entry("SELECT 1"), 10);
+        assert_eq!(history.len(), 3);
+    }
+}
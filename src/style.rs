@@ -1,23 +1,13 @@
 use crate::app::Focus;
 use ratatui::style::{Modifier, Style};
 
-/// Predefined colors for consistent style
-pub mod theme {
-    use ratatui::style::Color;
-
-    pub const COLOR_FOCUS: Color = Color::Rgb(137, 220, 235);
-    pub const COLOR_UNFOCUSED: Color = Color::Rgb(88, 91, 112);
-    pub const COLOR_BLOCK_BG: Color = Color::Rgb(30, 30, 46);
-    pub const COLOR_HIGHLIGHT_BG: Color = Color::Rgb(137, 220, 235);
-    pub const COLOR_HIGHLIGHT_FG: Color = Color::Black;
-    pub const COLOR_BLACK: Color = Color::Black;
-    pub const COLOR_WHITE: Color = Color::White;
-}
+pub mod theme;
 
 pub trait StyleProvider {
     fn border_style(&self, current: Focus) -> Style;
     fn block_style(&self) -> Style;
     fn highlight_style(&self) -> Style;
+    fn null_style(&self) -> Style;
 }
 
 pub struct DefaultStyle {
@@ -28,23 +18,29 @@ impl StyleProvider for DefaultStyle {
     fn border_style(&self, current: Focus) -> Style {
         if self.focus == current {
             Style::default()
-                .fg(theme::COLOR_FOCUS)
+                .fg(theme::current().border_focused)
                 .add_modifier(Modifier::BOLD)
         } else {
-            Style::default().fg(theme::COLOR_UNFOCUSED)
+            Style::default().fg(theme::current().border_unfocused)
         }
     }
 
     fn block_style(&self) -> Style {
-        Style::default().bg(theme::COLOR_BLOCK_BG)
+        Style::default().bg(theme::current().background)
     }
 
     fn highlight_style(&self) -> Style {
         Style::default()
-            .bg(theme::COLOR_HIGHLIGHT_BG)
-            .fg(theme::COLOR_HIGHLIGHT_FG)
+            .bg(theme::current().selection_bg)
+            .fg(theme::current().selection_fg)
             .add_modifier(Modifier::BOLD)
     }
+
+    fn null_style(&self) -> Style {
+        Style::default()
+            .fg(theme::current().null)
+            .add_modifier(Modifier::DIM | Modifier::ITALIC)
+    }
 }
 
 #[cfg(test)]
@@ -61,7 +57,7 @@ mod tests {
         assert_eq!(
             result,
             Style::default()
-                .fg(theme::COLOR_FOCUS)
+                .fg(theme::current().border_focused)
                 .add_modifier(Modifier::BOLD)
         )
     }
@@ -72,7 +68,7 @@ mod tests {
             focus: Focus::Sidebar,
         };
         let result = style.block_style();
-        assert_eq!(result, Style::default().bg(theme::COLOR_BLOCK_BG))
+        assert_eq!(result, Style::default().bg(theme::current().background))
     }
 
     #[test]
@@ -84,9 +80,23 @@ mod tests {
         assert_eq!(
             result,
             Style::default()
-                .bg(theme::COLOR_HIGHLIGHT_BG)
-                .fg(theme::COLOR_HIGHLIGHT_FG)
+                .bg(theme::current().selection_bg)
+                .fg(theme::current().selection_fg)
                 .add_modifier(Modifier::BOLD)
         )
     }
+
+    #[test]
+    fn test_null_style() {
+        let style = DefaultStyle {
+            focus: Focus::Table,
+        };
+        let result = style.null_style();
+        assert_eq!(
+            result,
+            Style::default()
+                .fg(theme::current().null)
+                .add_modifier(Modifier::DIM | Modifier::ITALIC)
+        )
+    }
 }
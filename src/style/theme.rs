@@ -0,0 +1,276 @@
+//! Colors used across the UI, overridable from `~/.lazydata/theme.toml`.
+//!
+//! The base palette is dark or light, picked with `--theme`. Every key in the file is
+//! optional; missing keys keep the base palette's color. Colors are names such as
+//! `"white"` or `"dark_gray"`, hex values like `"#1e1e2e"`, or 256-color indexes like `"236"`.
+//!
+//! ```toml
+//! border_focused = "#89dceb"
+//! border_unfocused = "#585b70"
+//! background = "#1e1e2e"
+//! text = "white"
+//! selection_bg = "#89dceb"
+//! selection_fg = "black"
+//! header_bg = "#1e3a8a"
+//! header_fg = "#e2e8f0"
+//! null = "#6c7086"
+//! table_bg = "#020617"
+//! table_fg = "#e2e8f0"
+//! ```
+
+use clap::ValueEnum;
+use ratatui::style::Color;
+use ratatui::style::palette::tailwind;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Whether the UI is drawn for a dark or a light terminal background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Appearance {
+    /// Guess from the terminal's `COLORFGBG`, assuming dark when it is not set
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+impl Appearance {
+    /// Turns `Auto` into `Dark` or `Light` based on the terminal environment.
+    pub fn resolve(self) -> Self {
+        match self {
+            Appearance::Auto => std::env::var("COLORFGBG")
+                .ok()
+                .and_then(|value| appearance_from_colorfgbg(&value))
+                .unwrap_or(Appearance::Dark),
+            appearance => appearance,
+        }
+    }
+}
+
+/// Reads the background from a `COLORFGBG` value such as `"15;0"` or `"0;default;15"`.
+///
+/// The background is the last field. Of the 16 ANSI colors, 7 (light gray) and the
+/// bright colors 9-15 count as light; 8 is dark gray.
+fn appearance_from_colorfgbg(value: &str) -> Option<Appearance> {
+    let background: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    Some(match background {
+        7 | 9..=15 => Appearance::Light,
+        _ => Appearance::Dark,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Border of the focused pane, also used to accent prompts and active items.
+    pub border_focused: Color,
+    pub border_unfocused: Color,
+    pub background: Color,
+    pub text: Color,
+    pub selection_bg: Color,
+    pub selection_fg: Color,
+    /// Data table header colors; when unset they follow the palette cycled in the table.
+    pub header_bg: Option<Color>,
+    pub header_fg: Option<Color>,
+    pub null: Color,
+    /// Background and text of the data table rows.
+    pub table_bg: Color,
+    pub table_fg: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            border_focused: Color::Rgb(137, 220, 235),
+            border_unfocused: Color::Rgb(88, 91, 112),
+            background: Color::Rgb(30, 30, 46),
+            text: Color::White,
+            selection_bg: Color::Rgb(137, 220, 235),
+            selection_fg: Color::Black,
+            header_bg: None,
+            header_fg: None,
+            null: Color::Rgb(108, 112, 134),
+            table_bg: tailwind::SLATE.c950,
+            table_fg: tailwind::SLATE.c200,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            border_focused: Color::Rgb(30, 102, 245),
+            border_unfocused: Color::Rgb(156, 160, 176),
+            background: Color::Rgb(239, 241, 245),
+            text: Color::Rgb(76, 79, 105),
+            selection_bg: Color::Rgb(30, 102, 245),
+            selection_fg: Color::White,
+            header_bg: None,
+            header_fg: None,
+            null: Color::Rgb(140, 143, 161),
+            table_bg: tailwind::SLATE.c50,
+            table_fg: tailwind::SLATE.c800,
+        }
+    }
+
+    /// The built-in palette for a resolved appearance.
+    pub fn for_appearance(appearance: Appearance) -> Self {
+        match appearance.resolve() {
+            Appearance::Light => Self::light(),
+            Appearance::Dark | Appearance::Auto => Self::dark(),
+        }
+    }
+}
+
+/// The theme file as written by the user, before its colors are parsed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    border_focused: Option<String>,
+    border_unfocused: Option<String>,
+    background: Option<String>,
+    text: Option<String>,
+    selection_bg: Option<String>,
+    selection_fg: Option<String>,
+    header_bg: Option<String>,
+    header_fg: Option<String>,
+    null: Option<String>,
+    table_bg: Option<String>,
+    table_fg: Option<String>,
+}
+
+impl Theme {
+    /// Parses a theme file, taking any key it leaves out from `defaults`.
+    pub fn from_toml(content: &str, defaults: Theme) -> Result<Self, String> {
+        let file: ThemeFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        let color = |key: &str, value: Option<String>, default: Color| match value {
+            Some(value) => parse_color(key, &value),
+            None => Ok(default),
+        };
+        let optional_color = |key: &str, value: Option<String>, default: Option<Color>| {
+            value
+                .map(|value| parse_color(key, &value))
+                .transpose()
+                .map(|color| color.or(default))
+        };
+
+        Ok(Self {
+            border_focused: color(
+                "border_focused",
+                file.border_focused,
+                defaults.border_focused,
+            )?,
+            border_unfocused: color(
+                "border_unfocused",
+                file.border_unfocused,
+                defaults.border_unfocused,
+            )?,
+            background: color("background", file.background, defaults.background)?,
+            text: color("text", file.text, defaults.text)?,
+            selection_bg: color("selection_bg", file.selection_bg, defaults.selection_bg)?,
+            selection_fg: color("selection_fg", file.selection_fg, defaults.selection_fg)?,
+            header_bg: optional_color("header_bg", file.header_bg, defaults.header_bg)?,
+            header_fg: optional_color("header_fg", file.header_fg, defaults.header_fg)?,
+            null: color("null", file.null, defaults.null)?,
+            table_bg: color("table_bg", file.table_bg, defaults.table_bg)?,
+            table_fg: color("table_fg", file.table_fg, defaults.table_fg)?,
+        })
+    }
+}
+
+fn parse_color(key: &str, value: &str) -> Result<Color, String> {
+    Color::from_str(value).map_err(|_| format!("invalid color {:?} for `{}`", value, key))
+}
+
+fn get_theme_file_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|mut path| {
+        path.push(".lazydata");
+        path.push("theme.toml");
+        path
+    })
+}
+
+/// Builds the theme for `appearance`, applying `~/.lazydata/theme.toml` on top if it exists.
+/// An unreadable or invalid file leaves the built-in palette as is.
+pub fn load_theme(appearance: Appearance) -> Theme {
+    let base = Theme::for_appearance(appearance);
+    let Some(path) = get_theme_file_path() else {
+        return base;
+    };
+    if !path.exists() {
+        return base;
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(content) => Theme::from_toml(&content, base.clone()).unwrap_or_else(|e| {
+            eprintln!("Error loading theme from {:?}: {}", path, e);
+            base
+        }),
+        Err(e) => {
+            eprintln!("Error reading theme file {:?}: {}", path, e);
+            base
+        }
+    }
+}
+
+/// Installs the theme for the rest of the session. Only the first call has any effect.
+pub fn init(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The active theme, or the default one if none was installed.
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_file_uses_defaults() {
+        assert_eq!(
+            Theme::from_toml("", Theme::light()).unwrap(),
+            Theme::light()
+        );
+    }
+
+    #[test]
+    fn test_overrides_given_colors() {
+        let theme = Theme::from_toml(
+            "border_focused = \"#ff8800\"\nnull = \"dark_gray\"\nheader_bg = \"236\"",
+            Theme::default(),
+        )
+        .unwrap();
+        assert_eq!(theme.border_focused, Color::Rgb(255, 136, 0));
+        assert_eq!(theme.null, Color::DarkGray);
+        assert_eq!(theme.header_bg, Some(Color::Indexed(236)));
+        assert_eq!(theme.background, Theme::default().background);
+        assert_eq!(theme.header_fg, None);
+    }
+
+    #[test]
+    fn test_rejects_bad_colors_and_keys() {
+        assert!(Theme::from_toml("text = \"not-a-color\"", Theme::default()).is_err());
+        assert!(Theme::from_toml("border = \"red\"", Theme::default()).is_err());
+    }
+
+    #[test]
+    fn test_appearance_from_colorfgbg() {
+        assert_eq!(appearance_from_colorfgbg("15;0"), Some(Appearance::Dark));
+        assert_eq!(appearance_from_colorfgbg("0;15"), Some(Appearance::Light));
+        assert_eq!(
+            appearance_from_colorfgbg("0;default;7"),
+            Some(Appearance::Light)
+        );
+        assert_eq!(appearance_from_colorfgbg("12;8"), Some(Appearance::Dark));
+        assert_eq!(appearance_from_colorfgbg("default;default"), None);
+        assert_eq!(appearance_from_colorfgbg(""), None);
+    }
+}
@@ -0,0 +1,99 @@
+use arboard::Clipboard;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fs::{self, OpenOptions};
+use std::io::{Write, stdout};
+use std::path::{Path, PathBuf};
+
+/// Copies `text` to the system clipboard.
+///
+/// Over SSH there is usually no clipboard to reach, so the text is then sent to the terminal
+/// emulator as an OSC 52 sequence, which most modern terminals copy to the local clipboard.
+/// Whether the terminal did can't be known, so the text is saved to
+/// `~/.lazydata/clipboard.txt` as well. The error tells where the text went, ready to show
+/// to the user.
+pub fn copy_text(text: &str) -> Result<(), String> {
+    let error = match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+
+    let sent = write_osc52(text).is_ok();
+    let Some(path) = fallback_path() else {
+        return Err(match sent {
+            true => "Sent to the terminal clipboard.".to_string(),
+            false => format!("Clipboard unavailable: {}", error),
+        });
+    };
+    Err(match (sent, save_fallback(&path, text)) {
+        (true, Ok(())) => format!(
+            "Sent to the terminal clipboard; also saved to {}.",
+            path.display()
+        ),
+        (true, Err(_)) => "Sent to the terminal clipboard.".to_string(),
+        (false, Ok(())) => format!(
+            "Clipboard unavailable: {}. Saved to {} instead.",
+            error,
+            path.display()
+        ),
+        (false, Err(write_error)) => format!(
+            "Clipboard unavailable: {}. Could not write {}: {}",
+            error,
+            path.display(),
+            write_error
+        ),
+    })
+}
+
+/// Reads text from the system clipboard, with `\r\n` line endings turned into `\n`.
+pub fn paste_text() -> Result<String, String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map(|text| text.replace("\r\n", "\n"))
+        .map_err(|e| format!("Clipboard unavailable: {}", e))
+}
+
+/// Builds the OSC 52 sequence asking the terminal to put `text` on the clipboard.
+fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+fn write_osc52(text: &str) -> std::io::Result<()> {
+    let mut stdout = stdout();
+    stdout.write_all(osc52_sequence(text).as_bytes())?;
+    stdout.flush()
+}
+
+fn fallback_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|mut path| {
+        path.push(".lazydata");
+        path.push("clipboard.txt");
+        path
+    })
+}
+
+/// Writes the copied text where only the user can read it, since it may hold query results.
+fn save_fallback(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+        assert_eq!(osc52_sequence(""), "\x1b]52;c;\x07");
+    }
+}
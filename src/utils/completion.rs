@@ -0,0 +1,164 @@
+/// Keywords offered by editor completion, alongside the table and column names loaded
+/// for the connection.
+const SQL_KEYWORDS: &[&str] = &[
+    "ALTER",
+    "AND",
+    "AS",
+    "ASC",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "COUNT",
+    "CREATE",
+    "CROSS",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DROP",
+    "ELSE",
+    "END",
+    "EXISTS",
+    "EXPLAIN",
+    "FALSE",
+    "FROM",
+    "FULL",
+    "GROUP",
+    "HAVING",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "NOT",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "RETURNING",
+    "RIGHT",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "THEN",
+    "TRUE",
+    "UNION",
+    "UPDATE",
+    "USING",
+    "VALUES",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WITH",
+];
+
+/// An open completion list for the word being typed in the editor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    /// The part of the word before the cursor, replaced when a suggestion is accepted.
+    pub prefix: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Completion {
+    /// Builds the list for the word ending at `col` in `line`, offering keywords and
+    /// `schema_words`. Returns `None` when nothing matches.
+    pub fn new(line: &str, col: usize, schema_words: &[String]) -> Option<Self> {
+        let prefix = word_prefix(line, col);
+        let items = candidates(prefix, schema_words);
+        (!items.is_empty()).then(|| Self {
+            prefix: prefix.to_string(),
+            items,
+            selected: 0,
+        })
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    pub fn selected_item(&self) -> &str {
+        &self.items[self.selected]
+    }
+}
+
+/// The identifier characters immediately before `col`, which may be empty.
+fn word_prefix(line: &str, col: usize) -> &str {
+    let end = line
+        .char_indices()
+        .nth(col)
+        .map_or(line.len(), |(index, _)| index);
+    let start = line[..end]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(end, |(index, _)| index);
+    &line[start..end]
+}
+
+/// Schema names first, then keywords, that start with `prefix` ignoring case. Keywords
+/// follow the case of the prefix so lowercase SQL stays lowercase.
+fn candidates(prefix: &str, schema_words: &[String]) -> Vec<String> {
+    let prefix_lower = prefix.to_lowercase();
+    let matches = |word: &str| {
+        let lower = word.to_lowercase();
+        lower.starts_with(&prefix_lower) && lower != prefix_lower
+    };
+    let lowercase_keywords = !prefix.is_empty() && prefix == prefix_lower;
+
+    let mut items: Vec<String> = Vec::new();
+    for word in schema_words {
+        if matches(word) && !items.contains(word) {
+            items.push(word.clone());
+        }
+    }
+    items.extend(
+        SQL_KEYWORDS
+            .iter()
+            .filter(|keyword| matches(keyword))
+            .map(|keyword| {
+                if lowercase_keywords {
+                    keyword.to_lowercase()
+                } else {
+                    keyword.to_string()
+                }
+            }),
+    );
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_prefix() {
+        assert_eq!(word_prefix("SELECT us", 9), "us");
+        assert_eq!(word_prefix("SELECT user_na FROM", 14), "user_na");
+        assert_eq!(word_prefix("WHERE a.id", 8), "");
+        assert_eq!(word_prefix("", 0), "");
+    }
+
+    #[test]
+    fn test_candidates_rank_schema_before_keywords() {
+        let schema = vec!["users".to_string(), "updated_at".to_string()];
+        assert_eq!(
+            candidates("u", &schema),
+            vec!["users", "updated_at", "union", "update", "using"]
+        );
+        assert_eq!(candidates("SEL", &schema), vec!["SELECT"]);
+        assert!(candidates("users", &schema).is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! Text produced when copying result rows, in the format chosen by the user.
+
+use crate::crud::result_set::CellValue;
+use crate::database::connector::{DatabaseType, quote_ident};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// Why results can't be copied as `INSERT`s when they aren't plain columns of one table.
+pub const NOT_SINGLE_TABLE: &str = "Only results of a SELECT of plain columns, without aliases or expressions, from a single table can be copied as INSERT statements.";
+
+/// Stands in for the table name of an `INSERT` when the results don't come from one table.
+const UNKNOWN_TABLE: &str = "<table>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CopyFormat {
+    /// An object per row, keyed by column name.
+    #[default]
+    Json,
+    /// A header line, then a line per row.
+    Csv,
+    /// Like CSV but separated by tabs, which spreadsheets take as columns when pasted.
+    Tsv,
+    /// An `INSERT` statement per row.
+    Insert,
+}
+
+impl CopyFormat {
+    pub fn next(self) -> Self {
+        match self {
+            CopyFormat::Json => CopyFormat::Csv,
+            CopyFormat::Csv => CopyFormat::Tsv,
+            CopyFormat::Tsv => CopyFormat::Insert,
+            CopyFormat::Insert => CopyFormat::Json,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyFormat::Json => "JSON",
+            CopyFormat::Csv => "CSV",
+            CopyFormat::Tsv => "TSV",
+            CopyFormat::Insert => "INSERT",
+        }
+    }
+}
+
+/// Formats `rows` under `headers`. A single row copied as JSON is an object and several
+/// are an array of them; `INSERT`s go into `table`, written as in the query, and quote
+/// their columns and values for `db_type`.
+pub fn format_rows(
+    format: CopyFormat,
+    headers: &[String],
+    rows: &[Vec<CellValue>],
+    table: Option<&str>,
+    db_type: DatabaseType,
+) -> String {
+    let values = |row: &Vec<CellValue>| {
+        (0..headers.len())
+            .map(|i| row.get(i).map(CellValue::to_string).unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+    match format {
+        CopyFormat::Json => {
+            let value = match <[Value; 1]>::try_from(json_objects(headers, rows)) {
+                Ok([object]) => object,
+                Err(objects) => Value::Array(objects),
+            };
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        }
+        CopyFormat::Csv | CopyFormat::Tsv => {
+            let (separator, field): (&str, fn(&str) -> Cow<'_, str>) = match format {
+                CopyFormat::Csv => (",", csv_field),
+                _ => ("\t", tsv_field),
+            };
+            let line = |values: Vec<String>| {
+                values
+                    .iter()
+                    .map(|value| field(value))
+                    .collect::<Vec<_>>()
+                    .join(separator)
+            };
+            std::iter::once(line(headers.to_vec()))
+                .chain(rows.iter().map(|row| line(values(row))))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        CopyFormat::Insert => {
+            let columns = headers
+                .iter()
+                .map(|header| quote_ident(db_type, header))
+                .collect::<Vec<_>>()
+                .join(", ");
+            rows.iter()
+                .map(|row| {
+                    let literals = (0..headers.len())
+                        .map(|i| sql_literal(row.get(i).unwrap_or(&CellValue::Null), db_type))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!(
+                        "INSERT INTO {} ({}) VALUES ({});",
+                        table.unwrap_or(UNKNOWN_TABLE),
+                        columns,
+                        literals
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    }
+}
+
+/// Each row as an object keyed by column name, keeping numbers, booleans and JSON values
+/// typed, and NULL as null.
+pub fn json_objects(headers: &[String], rows: &[Vec<CellValue>]) -> Vec<Value> {
+    rows.iter()
+        .map(|row| {
+            let object = headers
+                .iter()
+                .enumerate()
+                .map(|(i, header)| {
+                    let value = row.get(i).map_or(Value::Null, CellValue::to_json);
+                    (header.clone(), value)
+                })
+                .collect::<Map<_, _>>();
+            Value::Object(object)
+        })
+        .collect()
+}
+
+/// Quotes a value holding a comma, line break or quote.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '\n', '\r', '"']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Quotes a value holding a tab, line break or quote, the way spreadsheets expect when
+/// pasting.
+fn tsv_field(value: &str) -> Cow<'_, str> {
+    if value.contains(['\t', '\n', '\r', '"']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// `cell` written as a SQL literal. Numbers and booleans stay bare, and everything else
+/// becomes a string the database casts to the column's type.
+fn sql_literal(cell: &CellValue, db_type: DatabaseType) -> String {
+    match cell {
+        CellValue::Null => "NULL".to_string(),
+        CellValue::Int(i) => i.to_string(),
+        CellValue::Float(n) if n.is_finite() => n.to_string(),
+        CellValue::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        CellValue::Bytes(bytes) => match db_type {
+            DatabaseType::PostgreSQL => format!("'\\x{}'", hex::encode(bytes)),
+            DatabaseType::MySQL | DatabaseType::SQLite => format!("X'{}'", hex::encode(bytes)),
+        },
+        other => {
+            let text = other.to_string().replace('\'', "''");
+            // MySQL reads backslashes in strings as escapes.
+            match db_type {
+                DatabaseType::MySQL => format!("'{}'", text.replace('\\', "\\\\")),
+                DatabaseType::PostgreSQL | DatabaseType::SQLite => format!("'{}'", text),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> (Vec<String>, Vec<Vec<CellValue>>) {
+        (
+            vec!["id".to_string(), "name".to_string(), "note".to_string()],
+            vec![vec![
+                CellValue::Int(7),
+                CellValue::Text("O'Brien, Pat".to_string()),
+                CellValue::Null,
+            ]],
+        )
+    }
+
+    #[test]
+    fn test_formats_a_row() {
+        let (headers, rows) = sample();
+        let format =
+            |format, table| format_rows(format, &headers, &rows, table, DatabaseType::PostgreSQL);
+        assert_eq!(
+            format(CopyFormat::Json, None),
+            "{\n  \"id\": 7,\n  \"name\": \"O'Brien, Pat\",\n  \"note\": null\n}"
+        );
+        assert_eq!(
+            format(CopyFormat::Csv, None),
+            "id,name,note\n7,\"O'Brien, Pat\","
+        );
+        assert_eq!(
+            format(CopyFormat::Tsv, None),
+            "id\tname\tnote\n7\tO'Brien, Pat\t"
+        );
+        assert_eq!(
+            format(CopyFormat::Insert, Some("public.people")),
+            "INSERT INTO public.people (\"id\", \"name\", \"note\") VALUES (7, 'O''Brien, Pat', NULL);"
+        );
+    }
+
+    #[test]
+    fn test_sql_literal() {
+        let bytes = CellValue::Bytes(vec![0xde, 0xad]);
+        assert_eq!(sql_literal(&bytes, DatabaseType::PostgreSQL), "'\\xdead'");
+        assert_eq!(sql_literal(&bytes, DatabaseType::SQLite), "X'dead'");
+        let path = CellValue::Text("C:\\tmp".to_string());
+        assert_eq!(sql_literal(&path, DatabaseType::MySQL), "'C:\\\\tmp'");
+        assert_eq!(sql_literal(&path, DatabaseType::SQLite), "'C:\\tmp'");
+        assert_eq!(
+            sql_literal(&CellValue::Float(f64::NAN), DatabaseType::PostgreSQL),
+            "'NaN'"
+        );
+        assert_eq!(
+            sql_literal(&CellValue::Bool(true), DatabaseType::MySQL),
+            "TRUE"
+        );
+    }
+}
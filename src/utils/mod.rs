@@ -1,3 +1,9 @@
+pub mod clipboard;
+pub mod completion;
+pub mod copy_format;
 pub mod highlighter;
 pub mod query_timer;
 pub mod query_type;
+pub mod spinner;
+pub mod sql_format;
+pub mod statement_splitter;
@@ -1,22 +1,776 @@
+use crate::database::connector::{DatabaseType, quote_ident};
+use crate::utils::statement_splitter::strip_comments_and_literals;
+
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Query {
     SELECT,
     INSERT,
     UPDATE,
     DELETE,
+    /// Any other statement, e.g. DDL such as `CREATE` or `DROP`, run without fetching rows.
+    OTHER,
     UNKNOWN,
 }
 
 impl Query {
-    pub fn from_sql(sql: &str) -> Self {
-        let trimmed = sql.trim_start().to_uppercase();
-        match trimmed.split_whitespace().next() {
-            Some("SELECT") => Query::SELECT,
+    pub fn from_sql(sql: &str, db_type: DatabaseType) -> Self {
+        let sql = strip_leading_comments(sql).to_uppercase();
+        match sql.split_whitespace().next() {
+            Some("SELECT" | "EXPLAIN" | "SHOW" | "PRAGMA") => Query::SELECT,
             Some("INSERT") => Query::INSERT,
             Some("UPDATE") => Query::UPDATE,
             Some("DELETE") => Query::DELETE,
-            _ => Query::UNKNOWN,
+            Some("WITH") => Self::from_cte_body(&sql, db_type),
+            Some(_) => Query::OTHER,
+            None => Query::UNKNOWN,
+        }
+    }
+
+    /// Classifies a `WITH` query by the first statement keyword found outside the CTE
+    /// definitions, i.e. at parenthesis depth zero. Keywords in comments and literals
+    /// don't count.
+    fn from_cte_body(sql: &str, db_type: DatabaseType) -> Self {
+        top_level_words(&strip_comments_and_literals(sql, db_type))
+            .iter()
+            .find_map(|(_, word)| match word.to_ascii_uppercase().as_str() {
+                "SELECT" => Some(Query::SELECT),
+                "INSERT" => Some(Query::INSERT),
+                "UPDATE" => Some(Query::UPDATE),
+                "DELETE" => Some(Query::DELETE),
+                _ => None,
+            })
+            .unwrap_or(Query::UNKNOWN)
+    }
+}
+
+/// A statement that starts or ends a transaction.
+#[derive(Debug, PartialEq)]
+pub enum TransactionStatement {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+impl TransactionStatement {
+    /// Recognizes `BEGIN`/`START TRANSACTION`, `COMMIT`/`END` and `ROLLBACK`/`ABORT`.
+    /// Savepoint and two-phase commit statements run as ordinary statements.
+    pub fn from_sql(sql: &str, db_type: DatabaseType) -> Option<Self> {
+        let words: Vec<String> = strip_comments_and_literals(sql, db_type)
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .filter(|word| !word.is_empty())
+            .take(3)
+            .map(str::to_uppercase)
+            .collect();
+        let (keyword, rest) = words.split_first()?;
+        // `ROLLBACK [WORK] TO` goes back to a savepoint and `COMMIT PREPARED` finishes a
+        // two-phase transaction, neither of which ends the one that is open.
+        let rest = match rest {
+            [word, rest @ ..] if word == "WORK" || word == "TRANSACTION" => rest,
+            rest => rest,
+        };
+        let ends_transaction = !matches!(rest.first().map(String::as_str), Some("TO" | "PREPARED"));
+        match keyword.as_str() {
+            "BEGIN" => Some(Self::Begin),
+            "START" if words.get(1).is_some_and(|word| word == "TRANSACTION") => Some(Self::Begin),
+            "COMMIT" | "END" if ends_transaction => Some(Self::Commit),
+            "ROLLBACK" | "ABORT" if ends_transaction => Some(Self::Rollback),
+            _ => None,
+        }
+    }
+}
+
+/// Wraps a statement so that it returns its execution plan instead of its results.
+///
+/// With `analyze` the statement is actually executed to collect real timings. SQLite has
+/// no such mode, so it always gets the estimated plan.
+pub fn explain_sql(sql: &str, db_type: DatabaseType, analyze: bool) -> String {
+    let prefix = match (db_type, analyze) {
+        (DatabaseType::PostgreSQL, false) => "EXPLAIN",
+        (DatabaseType::PostgreSQL, true) => "EXPLAIN (ANALYZE, BUFFERS)",
+        (DatabaseType::MySQL, false) => "EXPLAIN",
+        (DatabaseType::MySQL, true) => "EXPLAIN ANALYZE",
+        (DatabaseType::SQLite, _) => "EXPLAIN QUERY PLAN",
+    };
+    format!("{} {}", prefix, sql.trim().trim_end_matches(';').trim_end())
+}
+
+/// The table of a plain `SELECT * FROM table`, which can be browsed a page at a time
+/// with [`paged_table_sql`]. Anything with a clause after the table name is not one.
+pub fn whole_table_select(sql: &str) -> Option<&str> {
+    let sql = strip_leading_comments(sql)
+        .trim_end()
+        .trim_end_matches(';')
+        .trim_end();
+    let mut words = sql.split_whitespace();
+    let (select, star, from, table) = (words.next()?, words.next()?, words.next()?, words.next()?);
+    let plain_name = table
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '"' | '`' | '$'));
+    (select.eq_ignore_ascii_case("SELECT")
+        && star == "*"
+        && from.eq_ignore_ascii_case("FROM")
+        && plain_name
+        && words.next().is_none())
+    .then_some(table)
+}
+
+/// The table a `SELECT` reads when it reads only that one, without joins, subqueries,
+/// grouping or set operations, so that every result row is a row of the table. Words in
+/// comments and literals don't count.
+pub fn single_table_select(sql: &str, db_type: DatabaseType) -> Option<&str> {
+    if leading_keyword(sql).as_deref() != Some("SELECT") {
+        return None;
+    }
+    let code = strip_comments_and_literals(sql, db_type);
+    let selects = code
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| word.eq_ignore_ascii_case("SELECT"))
+        .count();
+    let combines = ["JOIN", "UNION", "INTERSECT", "EXCEPT", "GROUP", "DISTINCT"]
+        .iter()
+        .any(|keyword| contains_keyword(&code, keyword));
+    if selects > 1 || combines {
+        return None;
+    }
+
+    let (from_at, from) = top_level_words(&code)
+        .into_iter()
+        .find(|(_, word)| word.eq_ignore_ascii_case("FROM"))?;
+    let after_from = from_at + from.len();
+    let start = after_from + (code[after_from..].len() - code[after_from..].trim_start().len());
+    // Quoted names keep their quotes in `code`, so a blanked-out space within one doesn't
+    // end the name; the name itself is read from `sql`.
+    let mut quoted = false;
+    let end = code[start..]
+        .char_indices()
+        .find(|&(_, c)| {
+            quoted ^= c == '"';
+            !quoted && (c.is_whitespace() || c == ',' || c == ';')
+        })
+        .map_or(code.len(), |(i, _)| start + i);
+    let table = &sql[start..end];
+    // A comma after the table, or after its alias, lists another table.
+    let lists_more = code[end..]
+        .split_whitespace()
+        .take(2)
+        .any(|word| word.starts_with(',') || word.ends_with(','));
+    let plain_name = !table.is_empty()
+        && table
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '"' | '`' | '$'));
+    (plain_name && !lists_more).then_some(table)
+}
+
+/// The table of a [`single_table_select`] that [`selects_plain_columns`], so that every
+/// result header is a column of that table and its rows can be written back to it.
+pub fn plain_table_select(sql: &str, db_type: DatabaseType) -> Option<&str> {
+    single_table_select(sql, db_type).filter(|_| selects_plain_columns(sql, db_type))
+}
+
+/// Whether a `SELECT` lists `*` or plain column names, possibly qualified, and nothing
+/// else, so that each result column is the table column its header names. Aliases and
+/// expressions rename or compute columns, so results holding them can't be written back.
+pub fn selects_plain_columns(sql: &str, db_type: DatabaseType) -> bool {
+    let code = strip_comments_and_literals(sql, db_type);
+    let words = top_level_words(&code);
+    let Some((select_at, select)) = words.first() else {
+        return false;
+    };
+    let Some((from_at, _)) = words
+        .iter()
+        .find(|(_, word)| word.eq_ignore_ascii_case("FROM"))
+    else {
+        return false;
+    };
+    select.eq_ignore_ascii_case("SELECT")
+        && sql[select_at + select.len()..*from_at]
+            .split(',')
+            .all(|item| is_column_reference(item.trim()))
+}
+
+/// Whether `item` is `*` or a column name, such as `name`, `u.name`, `"Name"` or `u.*`.
+fn is_column_reference(item: &str) -> bool {
+    const NOT_COLUMNS: [&str; 11] = [
+        "NULL",
+        "TRUE",
+        "FALSE",
+        "USER",
+        "CURRENT_USER",
+        "SESSION_USER",
+        "CURRENT_DATE",
+        "CURRENT_TIME",
+        "CURRENT_TIMESTAMP",
+        "LOCALTIME",
+        "LOCALTIMESTAMP",
+    ];
+    let mut rest = item;
+    loop {
+        let (part, after) = match rest.chars().next() {
+            Some(quote @ ('"' | '`')) => match rest[1..].find(quote) {
+                Some(end) => rest.split_at(end + 2),
+                None => return false,
+            },
+            Some('*') => rest.split_at(1),
+            Some(c) if c.is_alphabetic() || c == '_' => rest.split_at(
+                rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(rest.len()),
+            ),
+            _ => return false,
+        };
+        if NOT_COLUMNS
+            .iter()
+            .any(|word| part.eq_ignore_ascii_case(word))
+        {
+            return false;
         }
+        match after.strip_prefix('.') {
+            // Only the last part can be `*`.
+            Some(next) if part != "*" => rest = next,
+            Some(_) => return false,
+            None => return after.is_empty(),
+        }
+    }
+}
+
+/// Splits a table name as written in a query, such as `public."Orders"`, into its
+/// unquoted schema and table names.
+pub fn split_table_name(name: &str) -> (Option<String>, String) {
+    let unquote = |part: &str| part.trim_matches(|c| c == '"' || c == '`').to_string();
+    match name.rsplit_once('.') {
+        Some((schema, table)) => (Some(unquote(schema)), unquote(table)),
+        None => (None, unquote(name)),
+    }
+}
+
+/// Selects `limit` rows of `table` after skipping the first `offset`. All three supported
+/// databases accept `LIMIT ... OFFSET ...`.
+///
+/// Without an `ORDER BY` the database may return rows in any order, so pages could repeat
+/// or skip rows. They are ordered by `key`, the table's primary key, or by the first
+/// column when it has none.
+pub fn paged_table_sql(
+    db_type: DatabaseType,
+    table: &str,
+    key: &[String],
+    limit: usize,
+    offset: usize,
+) -> String {
+    let order_by = if key.is_empty() {
+        "1".to_string()
+    } else {
+        key.iter()
+            .map(|column| quote_ident(db_type, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "SELECT * FROM {} ORDER BY {} LIMIT {} OFFSET {}",
+        table, order_by, limit, offset
+    )
+}
+
+/// Whether a statement can wipe data in bulk: `DROP`, `TRUNCATE`, or an `UPDATE`/`DELETE`
+/// without a `WHERE` clause of its own. A `WHERE` in a comment, a string, a CTE or a
+/// subquery doesn't limit the rows changed, so it doesn't count.
+pub fn is_destructive(sql: &str, db_type: DatabaseType) -> bool {
+    match Query::from_sql(sql, db_type) {
+        Query::UPDATE | Query::DELETE => {
+            !top_level_words(&strip_comments_and_literals(sql, db_type))
+                .iter()
+                .any(|(_, word)| word.eq_ignore_ascii_case("WHERE"))
+        }
+        Query::OTHER => matches!(leading_keyword(sql).as_deref(), Some("DROP" | "TRUNCATE")),
+        _ => false,
+    }
+}
+
+/// Whether an `INSERT`, `UPDATE` or `DELETE` returns rows through a `RETURNING` clause of
+/// its own. One inside a CTE, a comment or a string doesn't count.
+pub fn has_returning_clause(sql: &str, db_type: DatabaseType) -> bool {
+    matches!(
+        Query::from_sql(sql, db_type),
+        Query::INSERT | Query::UPDATE | Query::DELETE
+    ) && top_level_words(&strip_comments_and_literals(sql, db_type))
+        .iter()
+        .any(|(_, word)| word.eq_ignore_ascii_case("RETURNING"))
+}
+
+/// The words of a statement outside any parentheses, with their byte offsets, leaving out
+/// CTE bodies, subqueries and argument lists. Expects comments and literals already
+/// stripped.
+fn top_level_words(code: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut word_start = None;
+    for (i, c) in code
+        .char_indices()
+        .chain(std::iter::once((code.len(), ' ')))
+    {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word_start.take()
+            && depth == 0
+        {
+            words.push((start, &code[start..i]));
+        }
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Whether a statement changes the schema, which leaves loaded table lists out of date.
+pub fn is_ddl(sql: &str) -> bool {
+    matches!(
+        leading_keyword(sql).as_deref(),
+        Some("CREATE" | "DROP" | "ALTER" | "RENAME")
+    )
+}
+
+/// Whether a statement only reads data, as required by read-only mode.
+///
+/// A `WITH` query or `EXPLAIN ANALYZE` is only read-only when no data-modifying keyword
+/// appears in it, since a CTE or analyzed statement may write. A `SELECT ... INTO` creates
+/// a table or sets variables, and `PRAGMA name = value` changes a setting, so neither is
+/// read-only either. Keywords in comments and literals don't count.
+pub fn is_read_only(sql: &str, db_type: DatabaseType) -> bool {
+    if Query::from_sql(sql, db_type) != Query::SELECT {
+        return false;
+    }
+    let code = strip_comments_and_literals(sql, db_type);
+    let writes = || {
+        ["INSERT", "UPDATE", "DELETE", "MERGE"]
+            .iter()
+            .any(|keyword| contains_keyword(&code, keyword))
+            || top_level_words(&code)
+                .iter()
+                .any(|(_, word)| word.eq_ignore_ascii_case("INTO"))
+    };
+    match leading_keyword(sql).as_deref() {
+        Some("EXPLAIN") => !contains_keyword(&code, "ANALYZE") || !writes(),
+        Some("PRAGMA") if code.contains('=') => false,
+        _ => !writes(),
+    }
+}
+
+/// Whether `keyword` appears as a whole word anywhere in the statement.
+pub fn contains_keyword(sql: &str, keyword: &str) -> bool {
+    sql.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .any(|word| word.eq_ignore_ascii_case(keyword))
+}
+
+/// Returns the upper-cased first keyword of a statement, ignoring leading comments.
+pub fn leading_keyword(sql: &str) -> Option<String> {
+    strip_leading_comments(sql)
+        .split_whitespace()
+        .next()
+        .map(str::to_uppercase)
+}
+
+/// Removes leading whitespace together with any `--` and `/* */` comments.
+fn strip_leading_comments(sql: &str) -> &str {
+    let mut rest = sql.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after);
+        } else {
+            return rest;
+        }
+        rest = rest.trim_start();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PG: DatabaseType = DatabaseType::PostgreSQL;
+
+    #[test]
+    fn test_whole_table_select() {
+        assert_eq!(whole_table_select("SELECT * FROM users"), Some("users"));
+        assert_eq!(
+            whole_table_select("-- all of it\nselect *\n  from public.\"Orders\";"),
+            Some("public.\"Orders\"")
+        );
+        assert_eq!(whole_table_select("SELECT * FROM users WHERE id = 1"), None);
+        assert_eq!(whole_table_select("SELECT id FROM users"), None);
+        assert_eq!(whole_table_select("SELECT * FROM users LIMIT 10"), None);
+        assert_eq!(whole_table_select("SELECT * FROM (SELECT 1)"), None);
+        assert_eq!(
+            paged_table_sql(DatabaseType::PostgreSQL, "users", &[], 101, 200),
+            "SELECT * FROM users ORDER BY 1 LIMIT 101 OFFSET 200"
+        );
+        assert_eq!(
+            paged_table_sql(
+                DatabaseType::MySQL,
+                "orders",
+                &["org_id".to_string(), "id".to_string()],
+                51,
+                0
+            ),
+            "SELECT * FROM orders ORDER BY `org_id`, `id` LIMIT 51 OFFSET 0"
+        );
+    }
+
+    #[test]
+    fn test_single_table_select() {
+        assert_eq!(
+            single_table_select("SELECT id, name FROM users WHERE id > 3 ORDER BY name", PG),
+            Some("users")
+        );
+        assert_eq!(
+            single_table_select("select * from public.\"Orders\" o;", PG),
+            Some("public.\"Orders\"")
+        );
+        assert_eq!(single_table_select("SELECT * FROM a, b", PG), None);
+        assert_eq!(single_table_select("SELECT * FROM a x , b y", PG), None);
+        assert_eq!(
+            single_table_select("SELECT * FROM a JOIN b ON a.id = b.id", PG),
+            None
+        );
+        assert_eq!(single_table_select("SELECT * FROM (SELECT 1) t", PG), None);
+        assert_eq!(
+            single_table_select("SELECT kind, count(*) FROM a GROUP BY kind", PG),
+            None
+        );
+        assert_eq!(single_table_select("DELETE FROM a", PG), None);
+        assert_eq!(
+            single_table_select("SELECT id, \"a from orders \" FROM users", PG),
+            Some("users")
+        );
+        assert_eq!(
+            single_table_select("SELECT 'join' AS x FROM /* orders */ users -- , b", PG),
+            Some("users")
+        );
+        assert_eq!(
+            single_table_select("SELECT extract(year FROM at) FROM events", PG),
+            Some("events")
+        );
+        assert_eq!(
+            split_table_name("public.\"Orders\""),
+            (Some("public".to_string()), "Orders".to_string())
+        );
+        assert_eq!(split_table_name("`users`"), (None, "users".to_string()));
+    }
+
+    #[test]
+    fn test_plain_table_select() {
+        assert_eq!(
+            plain_table_select("SELECT id, u.name FROM users u WHERE id > 3", PG),
+            Some("users")
+        );
+        assert_eq!(
+            plain_table_select("SELECT id AS user_id FROM users", PG),
+            None
+        );
+        assert_eq!(plain_table_select("SELECT count(*) FROM users", PG), None);
+    }
+
+    #[test]
+    fn test_selects_plain_columns() {
+        assert!(selects_plain_columns("SELECT * FROM users", PG));
+        assert!(selects_plain_columns(
+            "select id, u.name, \"Email\", u.* from users u where id > 3",
+            PG
+        ));
+        assert!(!selects_plain_columns("SELECT name AS id FROM users", PG));
+        assert!(!selects_plain_columns("SELECT name id FROM users", PG));
+        assert!(!selects_plain_columns("SELECT count(*) FROM users", PG));
+        assert!(!selects_plain_columns(
+            "SELECT id, name || 'x' FROM users",
+            PG
+        ));
+        assert!(!selects_plain_columns("SELECT 1 FROM users", PG));
+        assert!(!selects_plain_columns("SELECT NULL, id FROM users", PG));
+        assert!(!selects_plain_columns("SELECT *.id FROM users", PG));
+        assert!(!selects_plain_columns("SELECT id", PG));
+    }
+
+    #[test]
+    fn test_detects_leading_keyword() {
+        assert_eq!(Query::from_sql("SELECT 1", PG), Query::SELECT);
+        assert_eq!(
+            Query::from_sql("insert into t values (1)", PG),
+            Query::INSERT
+        );
+        assert_eq!(Query::from_sql("  Update t set a = 1", PG), Query::UPDATE);
+        assert_eq!(Query::from_sql("\n\tdelete from t", PG), Query::DELETE);
+        assert_eq!(Query::from_sql("CREATE TABLE t (id int)", PG), Query::OTHER);
+        assert_eq!(Query::from_sql("drop table t", PG), Query::OTHER);
+        assert_eq!(Query::from_sql("   ", PG), Query::UNKNOWN);
+    }
+
+    #[test]
+    fn test_row_returning_statements() {
+        assert_eq!(Query::from_sql("EXPLAIN SELECT 1", PG), Query::SELECT);
+        assert_eq!(Query::from_sql("show tables", PG), Query::SELECT);
+        assert_eq!(Query::from_sql("PRAGMA table_info(t)", PG), Query::SELECT);
+    }
+
+    #[test]
+    fn test_has_returning_clause() {
+        assert!(has_returning_clause(
+            "INSERT INTO users (name) VALUES ('Ada') RETURNING id",
+            PG
+        ));
+        assert!(has_returning_clause(
+            "delete from users where id = 1 returning *",
+            PG
+        ));
+        assert!(!has_returning_clause(
+            "INSERT INTO notes (body) VALUES ('RETURNING soon') -- RETURNING id",
+            PG
+        ));
+        assert!(!has_returning_clause(
+            "WITH gone AS (DELETE FROM a RETURNING *) INSERT INTO b SELECT * FROM gone",
+            PG
+        ));
+        assert!(!has_returning_clause("SELECT returning FROM t", PG));
+    }
+
+    #[test]
+    fn test_skips_leading_comments() {
+        assert_eq!(
+            Query::from_sql("-- fetch users\n/* all of them */ SELECT * FROM users", PG),
+            Query::SELECT
+        );
+        assert_eq!(Query::from_sql("/* unterminated", PG), Query::UNKNOWN);
+        assert_eq!(Query::from_sql("-- only a comment", PG), Query::UNKNOWN);
+    }
+
+    #[test]
+    fn test_explain_sql_per_dialect() {
+        let sql = "SELECT * FROM t;";
+        assert_eq!(
+            explain_sql(sql, DatabaseType::PostgreSQL, false),
+            "EXPLAIN SELECT * FROM t"
+        );
+        assert_eq!(
+            explain_sql(sql, DatabaseType::PostgreSQL, true),
+            "EXPLAIN (ANALYZE, BUFFERS) SELECT * FROM t"
+        );
+        assert_eq!(
+            explain_sql(sql, DatabaseType::MySQL, true),
+            "EXPLAIN ANALYZE SELECT * FROM t"
+        );
+        assert_eq!(
+            explain_sql(sql, DatabaseType::SQLite, true),
+            "EXPLAIN QUERY PLAN SELECT * FROM t"
+        );
+    }
+
+    #[test]
+    fn test_is_destructive() {
+        assert!(is_destructive("DELETE FROM users", PG));
+        assert!(is_destructive("update users set active = false", PG));
+        assert!(is_destructive("-- cleanup\nDROP TABLE users", PG));
+        assert!(is_destructive("truncate users", PG));
+        assert!(!is_destructive("DELETE FROM users WHERE id = 1", PG));
+        assert!(!is_destructive("UPDATE users SET a = 1\nwhere id = 1", PG));
+        assert!(!is_destructive("SELECT * FROM users", PG));
+        assert!(!is_destructive("CREATE TABLE t (id int)", PG));
+    }
+
+    #[test]
+    fn test_is_destructive_ignores_where_outside_the_statement() {
+        assert!(is_destructive("DELETE FROM users -- where", PG));
+        assert!(is_destructive("DELETE FROM users /* where id = 1 */", PG));
+        assert!(is_destructive("UPDATE t SET note = 'where'", PG));
+        assert!(is_destructive("UPDATE t SET \"where\" = 1", PG));
+        assert!(is_destructive(
+            "WITH old AS (SELECT id FROM t WHERE id < 5) DELETE FROM t",
+            PG
+        ));
+        assert!(is_destructive(
+            "UPDATE t SET a = (SELECT max(b) FROM u WHERE u.id = 1)",
+            PG
+        ));
+        assert!(!is_destructive(
+            "DELETE FROM t WHERE id IN (SELECT id FROM u WHERE u.gone)",
+            PG
+        ));
+        assert!(!is_destructive("UPDATE t SET note = 'a' WHERE id = 1", PG));
+    }
+
+    #[test]
+    fn test_is_destructive_follows_backslash_escapes() {
+        let sql = r"UPDATE t SET a = 'x\'', b = 'WHERE'";
+        assert!(is_destructive(sql, DatabaseType::MySQL));
+        assert!(is_destructive(r"UPDATE t SET a = E'x\'', b = 'WHERE'", PG));
+        // Without `E`, Postgres and SQLite end the first string at the backslash.
+        assert!(!is_destructive(sql, PG));
+        assert!(!is_destructive(sql, DatabaseType::SQLite));
+    }
+
+    #[test]
+    fn test_is_read_only() {
+        assert!(is_read_only("SELECT * FROM users", PG));
+        assert!(is_read_only("-- peek\nSHOW TABLES", PG));
+        assert!(is_read_only("EXPLAIN DELETE FROM users", PG));
+        assert!(is_read_only("EXPLAIN ANALYZE SELECT * FROM users", PG));
+        assert!(!is_read_only("EXPLAIN ANALYZE DELETE FROM users", PG));
+        assert!(!is_read_only("INSERT INTO users VALUES (1)", PG));
+        assert!(!is_read_only("CREATE TABLE t (id int)", PG));
+        assert!(!is_read_only(
+            "WITH gone AS (DELETE FROM users RETURNING id) SELECT * FROM gone",
+            PG
+        ));
+    }
+
+    #[test]
+    fn test_is_read_only_ignores_comments_and_literals() {
+        assert!(is_read_only(
+            "WITH a AS (SELECT 'delete' AS word) SELECT * FROM a -- update later",
+            PG
+        ));
+        assert!(is_read_only(
+            "SELECT * FROM t WHERE note = 'insert into'",
+            PG
+        ));
+        assert!(is_read_only(
+            "EXPLAIN ANALYZE SELECT * FROM t /* delete */",
+            PG
+        ));
+        assert!(!is_read_only("SELECT * INTO backup FROM users", PG));
+        assert!(!is_read_only(
+            "WITH a AS (SELECT 1 AS n) SELECT n INTO copied FROM a",
+            PG
+        ));
+        assert!(!is_read_only(
+            "EXPLAIN ANALYZE SELECT * INTO backup FROM users",
+            PG
+        ));
+        assert!(is_read_only("EXPLAIN SELECT * INTO backup FROM users", PG));
+        assert!(!is_read_only(
+            r"SELECT 'a\'', 'b' INTO OUTFILE '/tmp/x'",
+            DatabaseType::MySQL
+        ));
+    }
+
+    #[test]
+    fn test_pragma_assignments_are_not_read_only() {
+        let sqlite = DatabaseType::SQLite;
+        assert!(is_read_only("PRAGMA table_info(users)", sqlite));
+        assert!(is_read_only("PRAGMA user_version", sqlite));
+        assert!(!is_read_only("PRAGMA user_version = 5", sqlite));
+        assert!(!is_read_only("pragma main.query_only=OFF", sqlite));
+    }
+
+    #[test]
+    fn test_leading_keyword() {
+        assert_eq!(
+            leading_keyword("/* c */ truncate t").as_deref(),
+            Some("TRUNCATE")
+        );
+        assert_eq!(leading_keyword("-- nothing"), None);
+    }
+
+    #[test]
+    fn test_is_ddl() {
+        assert!(is_ddl("create table t (id int)"));
+        assert!(is_ddl("-- cleanup\nDROP TABLE t"));
+        assert!(is_ddl("ALTER TABLE t ADD COLUMN name text"));
+        assert!(is_ddl("RENAME TABLE a TO b"));
+        assert!(!is_ddl("SELECT * FROM created"));
+        assert!(!is_ddl("TRUNCATE t"));
+    }
+
+    #[test]
+    fn test_transaction_statement() {
+        use TransactionStatement::*;
+        assert_eq!(TransactionStatement::from_sql("BEGIN;", PG), Some(Begin));
+        assert_eq!(
+            TransactionStatement::from_sql("begin isolation level serializable", PG),
+            Some(Begin)
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("START TRANSACTION READ ONLY", PG),
+            Some(Begin)
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("-- done\nCOMMIT", PG),
+            Some(Commit)
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("rollback", PG),
+            Some(Rollback)
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("ROLLBACK TO SAVEPOINT before_delete", PG),
+            None
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("COMMIT PREPARED 'tx1'", PG),
+            None
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("ROLLBACK WORK TO before_delete", PG),
+            None
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("ROLLBACK TRANSACTION", PG),
+            Some(Rollback)
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("COMMIT /* not PREPARED */", PG),
+            Some(Commit)
+        );
+        assert_eq!(
+            TransactionStatement::from_sql("COMMIT AND NO CHAIN -- back to work", PG),
+            Some(Commit)
+        );
+        assert_eq!(TransactionStatement::from_sql("START SLAVE", PG), None);
+        assert_eq!(TransactionStatement::from_sql("SELECT 1", PG), None);
+    }
+
+    #[test]
+    fn test_detects_cte_statement() {
+        assert_eq!(
+            Query::from_sql("WITH recent AS (SELECT * FROM t) SELECT * FROM recent", PG),
+            Query::SELECT
+        );
+        assert_eq!(
+            Query::from_sql(
+                "with recursive a(n) as (select 1), b as (select ')' from a) select * from b",
+                PG
+            ),
+            Query::SELECT
+        );
+        assert_eq!(
+            Query::from_sql("WITH old AS (SELECT id FROM t) DELETE FROM t USING old", PG),
+            Query::DELETE
+        );
+        assert_eq!(
+            Query::from_sql(
+                "WITH moved AS (DELETE FROM a RETURNING *) INSERT INTO b SELECT * FROM moved",
+                PG
+            ),
+            Query::INSERT
+        );
+    }
+
+    #[test]
+    fn test_cte_statement_ignores_comments() {
+        assert_eq!(
+            Query::from_sql("WITH a AS (SELECT 1) -- delete later\nSELECT * FROM a", PG),
+            Query::SELECT
+        );
+        assert_eq!(
+            Query::from_sql("WITH a AS (SELECT 1) -- don't\nSELECT * FROM a", PG),
+            Query::SELECT
+        );
+        assert_eq!(
+            Query::from_sql("WITH d AS (SELECT 1) /* select */ DELETE FROM t", PG),
+            Query::DELETE
+        );
     }
 }
@@ -0,0 +1,84 @@
+use crossterm::{
+    cursor, execute,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+use std::io::{Write, stdout};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+use tokio::{task::JoinHandle, time::sleep};
+
+pub const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// A terminal spinner shown while the app waits on startup work.
+///
+/// The message can be changed while it spins, so each step reports what it is doing.
+/// Dropping the spinner stops it, but only [`Spinner::stop`] waits for the line to clear.
+pub struct Spinner {
+    message: Arc<Mutex<String>>,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    pub fn start(message: impl Into<String>) -> Self {
+        let message = Arc::new(Mutex::new(message.into()));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let task_message = message.clone();
+        let task_running = running.clone();
+        let handle = tokio::spawn(async move {
+            let mut stdout = stdout();
+            let mut i = 0;
+
+            while task_running.load(Ordering::SeqCst) {
+                let text = task_message.lock().map(|m| m.clone()).unwrap_or_default();
+                let _ = execute!(
+                    stdout,
+                    cursor::MoveToColumn(0),
+                    Clear(ClearType::CurrentLine),
+                    Print(format!("🔄 {} {}", text, FRAMES[i % FRAMES.len()])),
+                );
+                let _ = stdout.flush();
+                sleep(Duration::from_millis(100)).await;
+                i += 1;
+            }
+
+            let _ = execute!(
+                stdout,
+                cursor::MoveToColumn(0),
+                Clear(ClearType::CurrentLine),
+            );
+        });
+
+        Self {
+            message,
+            running,
+            handle: Some(handle),
+        }
+    }
+
+    /// Replaces the text shown next to the spinner.
+    pub fn set_message(&self, message: impl Into<String>) {
+        if let Ok(mut current) = self.message.lock() {
+            *current = message.into();
+        }
+    }
+
+    /// Stops the spinner and waits until its line has been cleared.
+    pub async fn stop(mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.await;
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
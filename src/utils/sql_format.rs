@@ -0,0 +1,32 @@
+use sqlformat::{FormatOptions, Indent, QueryParams};
+
+/// Pretty-prints SQL with upper-case keywords, one clause per line and two-space indents.
+pub fn format_sql(sql: &str) -> String {
+    let options = FormatOptions {
+        indent: Indent::Spaces(2),
+        uppercase: true,
+        lines_between_queries: 2,
+    };
+    sqlformat::format(sql.trim(), &QueryParams::None, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_one_line_query() {
+        assert_eq!(
+            format_sql("select id, name from users where id = 1 order by name"),
+            "SELECT\n  id,\n  name\nFROM\n  users\nWHERE\n  id = 1\nORDER BY\n  name"
+        );
+    }
+
+    #[test]
+    fn test_keeps_literals_and_separates_statements() {
+        assert_eq!(
+            format_sql("select 'a from b';select 2"),
+            "SELECT\n  'a from b';\n\nSELECT\n  2"
+        );
+    }
+}
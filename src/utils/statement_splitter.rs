@@ -0,0 +1,302 @@
+use crate::database::connector::DatabaseType;
+
+/// Splits a SQL script into its individual statements.
+///
+/// Semicolons inside string literals, quoted identifiers, dollar-quoted bodies and
+/// comments are ignored. Returned statements are trimmed, exclude the terminating
+/// semicolon, and segments holding nothing but whitespace or comments are dropped.
+/// `db_type` decides whether a backslash escapes a quote inside a literal.
+pub fn split_statements(sql: &str, db_type: DatabaseType) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut i = 0;
+
+    let mut push = |from: usize, to: usize, has_code: bool| {
+        let statement = sql[from..to].trim();
+        if has_code && !statement.is_empty() {
+            statements.push(statement);
+        }
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' => {
+                push(start, i, has_code);
+                start = i + 1;
+                has_code = false;
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = bytes[i..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(bytes.len(), |pos| i + pos + 1);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = skip_block_comment(bytes, i);
+            }
+            quote @ (b'\'' | b'"' | b'`') => {
+                has_code = true;
+                let backslash = backslash_escapes(db_type, bytes, i, quote);
+                i = skip_quoted(bytes, i, quote, backslash);
+            }
+            b'$' => {
+                has_code = true;
+                i = match dollar_tag(bytes, i) {
+                    Some(tag) => skip_dollar_quoted(sql, i, tag),
+                    None => i + 1,
+                };
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                has_code = true;
+                i += 1;
+            }
+        }
+    }
+
+    push(start, bytes.len(), has_code);
+    statements
+}
+
+/// Returns `sql` with comments and the contents of quoted literals and identifiers blanked
+/// out, so keywords can be looked for without matching text that only mentions them.
+/// Everything keeps its byte offset, so positions found in the result apply to `sql`.
+/// Literals end where `db_type` ends them, as in [`split_statements`].
+pub fn strip_comments_and_literals(sql: &str, db_type: DatabaseType) -> String {
+    let bytes = sql.as_bytes();
+    let mut code = String::with_capacity(sql.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (end, quote) = match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => (
+                bytes[i..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(bytes.len(), |pos| i + pos + 1),
+                None,
+            ),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => (skip_block_comment(bytes, i), None),
+            b'\'' => {
+                let backslash = backslash_escapes(db_type, bytes, i, b'\'');
+                (skip_quoted(bytes, i, b'\'', backslash), Some('\''))
+            }
+            quote @ (b'"' | b'`') => {
+                let backslash = backslash_escapes(db_type, bytes, i, quote);
+                (skip_quoted(bytes, i, quote, backslash), Some('"'))
+            }
+            b'$' if let Some(tag) = dollar_tag(bytes, i) => {
+                (skip_dollar_quoted(sql, i, tag), Some('\''))
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        code.push_str(&sql[copied..i]);
+        match quote {
+            Some(quote) if end - i >= 2 => {
+                code.push(quote);
+                code.push_str(&" ".repeat(end - i - 2));
+                code.push(quote);
+            }
+            _ => code.push_str(&" ".repeat(end - i)),
+        }
+        i = end;
+        copied = end;
+    }
+
+    code.push_str(&sql[copied..]);
+    code
+}
+
+/// Skips a (possibly nested) `/* */` comment starting at `start`.
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Whether a backslash escapes the next character of the literal opened at `start`: in any
+/// MySQL string, and on Postgres only in an `E'...'` escape string, since its standard
+/// strings keep `'C:\'` as written.
+fn backslash_escapes(db_type: DatabaseType, bytes: &[u8], start: usize, quote: u8) -> bool {
+    match db_type {
+        DatabaseType::MySQL => quote != b'`',
+        DatabaseType::PostgreSQL => {
+            let is_word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+            quote == b'\''
+                && start > 0
+                && bytes[start - 1].eq_ignore_ascii_case(&b'E')
+                && !(start > 1 && is_word(&bytes[start - 2]))
+        }
+        DatabaseType::SQLite => false,
+    }
+}
+
+/// Skips a quoted literal or identifier, treating a doubled quote as an escape, and with
+/// `backslash` also a quote following a backslash.
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, backslash: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if backslash && bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Returns the `$tag$` opener at `start`, if this `$` begins a dollar-quoted string.
+fn dollar_tag(bytes: &[u8], start: usize) -> Option<&[u8]> {
+    // `$` following an identifier character is part of a name, e.g. `foo$bar`.
+    if start > 0 && (bytes[start - 1].is_ascii_alphanumeric() || bytes[start - 1] == b'_') {
+        return None;
+    }
+
+    let mut end = start + 1;
+    while end < bytes.len() && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_') {
+        end += 1;
+    }
+
+    let tag_body = &bytes[start + 1..end];
+    let valid_tag = tag_body.first().is_none_or(|b| !b.is_ascii_digit());
+    (valid_tag && bytes.get(end) == Some(&b'$')).then(|| &bytes[start..=end])
+}
+
+/// Skips past the closing tag of a dollar-quoted string opened at `start`.
+fn skip_dollar_quoted(sql: &str, start: usize, tag: &[u8]) -> usize {
+    let body_start = start + tag.len();
+    let tag = std::str::from_utf8(tag).unwrap_or("$$");
+    sql[body_start..]
+        .find(tag)
+        .map_or(sql.len(), |pos| body_start + pos + tag.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splits_simple_statements() {
+        assert_eq!(
+            split_statements("SELECT 1; SELECT 2;\nSELECT 3", DatabaseType::PostgreSQL),
+            vec!["SELECT 1", "SELECT 2", "SELECT 3"]
+        );
+    }
+
+    #[test]
+    fn test_ignores_semicolons_in_literals_and_identifiers() {
+        assert_eq!(
+            split_statements(
+                r#"SELECT 'a;b', 'it''s;'; SELECT "x;y", `z;w`"#,
+                DatabaseType::MySQL
+            ),
+            vec![r#"SELECT 'a;b', 'it''s;'"#, r#"SELECT "x;y", `z;w`"#]
+        );
+    }
+
+    #[test]
+    fn test_ignores_semicolons_in_comments() {
+        let sql = "-- first; still a comment\nSELECT 1; /* a; /* nested; */ b; */ SELECT 2;\n-- trailing;";
+        assert_eq!(
+            split_statements(sql, DatabaseType::PostgreSQL),
+            vec![
+                "-- first; still a comment\nSELECT 1",
+                "/* a; /* nested; */ b; */ SELECT 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_respects_dollar_quoting() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END; $body$ LANGUAGE plpgsql; SELECT $$a;b$$, $1";
+        assert_eq!(
+            split_statements(sql, DatabaseType::PostgreSQL),
+            vec![
+                "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END; $body$ LANGUAGE plpgsql",
+                "SELECT $$a;b$$, $1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strip_comments_and_literals() {
+        assert_eq!(
+            strip_comments_and_literals(
+                "DELETE FROM t -- where\nWHERE a = 'where' /* x */ AND \"where\" = $$;$$",
+                DatabaseType::PostgreSQL
+            ),
+            "DELETE FROM t          WHERE a = '     '         AND \"     \" = '   '"
+        );
+        assert_eq!(
+            strip_comments_and_literals("SELECT 'é', 1", DatabaseType::SQLite),
+            "SELECT '  ', 1"
+        );
+    }
+
+    #[test]
+    fn test_drops_empty_statements() {
+        assert!(split_statements(" ;; \n ; -- nothing here", DatabaseType::SQLite).is_empty());
+    }
+
+    #[test]
+    fn test_backslash_escapes_follow_the_dialect() {
+        assert_eq!(
+            split_statements(
+                r#"SELECT 'it\'s; x'; SELECT "a\"; b", 'C:\\'; SELECT `a\`"#,
+                DatabaseType::MySQL
+            ),
+            vec![
+                r"SELECT 'it\'s; x'",
+                r#"SELECT "a\"; b", 'C:\\'"#,
+                r"SELECT `a\`"
+            ]
+        );
+        assert_eq!(
+            split_statements(
+                r"SELECT 'C:\'; SELECT E'it\'s; x', e'\\'",
+                DatabaseType::PostgreSQL
+            ),
+            vec![r"SELECT 'C:\'", r"SELECT E'it\'s; x', e'\\'"]
+        );
+        assert_eq!(
+            split_statements(r"SELECT 'C:\'; SELECT 1", DatabaseType::SQLite),
+            vec![r"SELECT 'C:\'", "SELECT 1"]
+        );
+        assert_eq!(
+            strip_comments_and_literals(r"SET a = 'x\'', b = 'WHERE'", DatabaseType::MySQL),
+            r"SET a = '   ', b = '     '"
+        );
+        assert_eq!(
+            strip_comments_and_literals(r"SET a = E'x\'', b = 'WHERE'", DatabaseType::PostgreSQL),
+            r"SET a = E'   ', b = '     '"
+        );
+    }
+}